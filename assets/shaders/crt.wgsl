@@ -0,0 +1,33 @@
+// Old television look over the whole picture: the tube's bulge, red and blue drifting apart
+// toward the edges, scanlines and darker corners
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct CrtMaterial {
+    resolution: vec2<f32>,
+    curvature: f32,
+    aberration: f32,
+    scanline_strength: f32,
+};
+
+@group(1) @binding(0) var<uniform> material: CrtMaterial;
+@group(1) @binding(1) var screen_texture: texture_2d<f32>;
+@group(1) @binding(2) var screen_sampler: sampler;
+
+const PI: f32 = 3.14159265;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let centered = mesh.uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + material.curvature * centered.yx * centered.yx);
+    let uv = bent * 0.5 + 0.5;
+    // past the bent edge of the tube is black, sampled anyway to keep the sampling uniform
+    let inside = f32(all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0)));
+    let shift = (uv - 0.5) * material.aberration;
+    let red = textureSample(screen_texture, screen_sampler, uv + shift).r;
+    let green = textureSample(screen_texture, screen_sampler, uv).g;
+    let blue = textureSample(screen_texture, screen_sampler, uv - shift).b;
+    let scanline = 1.0 - material.scanline_strength
+        * (0.5 + 0.5 * sin(uv.y * material.resolution.y * PI));
+    let vignette = clamp(1.0 - 0.35 * dot(centered, centered), 0.0, 1.0);
+    return vec4<f32>(vec3<f32>(red, green, blue) * scanline * vignette * inside, 1.0);
+}
@@ -0,0 +1,36 @@
+// Two glowing eyes over the head, looking the way the snake is heading, with a slow pulse in
+// their glow
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import bevy_sprite::mesh2d_view_bindings::globals
+
+struct HeadMaterial {
+    glow: vec4<f32>,
+    direction: vec2<f32>,
+    pulse_speed: f32,
+};
+
+@group(1) @binding(0) var<uniform> material: HeadMaterial;
+
+const EYE_RADIUS: f32 = 0.09;
+const GLOW_RADIUS: f32 = 0.22;
+
+fn eye(p: vec2<f32>, center: vec2<f32>, pulse: f32) -> vec4<f32> {
+    let distance = length(p - center);
+    let pupil = 1.0 - smoothstep(EYE_RADIUS * 0.4, EYE_RADIUS * 0.5, distance);
+    let eye = 1.0 - smoothstep(EYE_RADIUS, EYE_RADIUS + 0.02, distance);
+    let halo = (1.0 - smoothstep(EYE_RADIUS, GLOW_RADIUS, distance)) * 0.6 * pulse;
+    let rgb = mix(material.glow.rgb, vec3<f32>(0.0), pupil);
+    return vec4<f32>(rgb, max(eye, halo) * material.glow.a);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // from the middle of the cell, y up like the world
+    let p = vec2<f32>(mesh.uv.x - 0.5, 0.5 - mesh.uv.y);
+    let forward = normalize(material.direction);
+    let side = vec2<f32>(-forward.y, forward.x);
+    let pulse = 0.75 + 0.25 * sin(globals.time * material.pulse_speed);
+    let left = eye(p, forward * 0.18 + side * 0.2, pulse);
+    let right = eye(p, forward * 0.18 - side * 0.2, pulse);
+    return select(right, left, left.a > right.a);
+}
@@ -0,0 +1,26 @@
+// Scales drawn over the body segments: darker edges where the rows overlap and a slow shimmer
+// running along the snake. Laid out in world space so the pattern carries on between segments
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import bevy_sprite::mesh2d_view_bindings::globals
+
+struct ScalesMaterial {
+    shade: vec4<f32>,
+    cell_size: f32,
+    scales_per_cell: f32,
+    shimmer_speed: f32,
+};
+
+@group(1) @binding(0) var<uniform> material: ScalesMaterial;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let p = mesh.world_position.xy / material.cell_size * material.scales_per_cell;
+    // every other row is shifted by half a scale
+    let odd_row = fract(floor(p.y) * 0.5) * 2.0;
+    let local = fract(vec2<f32>(p.x + 0.5 * odd_row, p.y)) - vec2<f32>(0.5, 0.0);
+    let edge = smoothstep(0.42, 0.52, length(local * vec2<f32>(1.0, 1.3)));
+    let shimmer = 0.5 + 0.5 * sin(globals.time * material.shimmer_speed + p.x * 0.7 + p.y * 0.4);
+    let rgb = mix(vec3<f32>(1.0), material.shade.rgb, edge);
+    let alpha = mix(shimmer * 0.12, material.shade.a, edge);
+    return vec4<f32>(rgb, alpha);
+}
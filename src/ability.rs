@@ -0,0 +1,220 @@
+// Abilities
+// Dash: the ability action (Space, the south button or a tap) launches the snake a few cells
+// ahead, then needs to cool down
+// Boost: holding the boost action (Shift or the right trigger) doubles the tick rate while
+// stamina lasts
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::input::{Action, ActionState};
+use crate::menu::menu_closed;
+use crate::{GameOver, Player, SnakeHead, SnakeSystemSet};
+
+const DASH_CELLS: u32 = 3;
+const DASH_COOLDOWN_SECONDS: f32 = 5.0;
+const BAR_WIDTH: f32 = 120.0;
+const BAR_READY_COLOR: Color = Color::ORANGE;
+const BAR_CHARGING_COLOR: Color = Color::rgb(0.5, 0.35, 0.1);
+pub const BOOST_TICKRATE_FACTOR: f64 = 0.5;
+const STAMINA_DRAIN_PER_SECOND: f32 = 0.5;
+const STAMINA_REGEN_PER_SECOND: f32 = 0.2;
+const STAMINA_TO_RECOVER: f32 = 0.25;
+const BOOST_READY_COLOR: Color = Color::YELLOW_GREEN;
+const BOOST_EXHAUSTED_COLOR: Color = Color::rgb(0.3, 0.4, 0.15);
+
+#[derive(Resource)]
+struct DashCooldown(Timer);
+
+impl DashCooldown {
+    fn new() -> Self {
+        let mut cooldown = Timer::from_seconds(DASH_COOLDOWN_SECONDS, TimerMode::Once);
+        // ready from the start
+        cooldown.tick(cooldown.duration());
+        DashCooldown(cooldown)
+    }
+}
+
+// boost fuel, from 0 to 1
+#[derive(Resource)]
+pub struct Stamina {
+    value: f32,
+    pub boosting: bool,
+    exhausted: bool,
+}
+
+// both bars, put away at game over for the seed in the same corner
+#[derive(Component)]
+struct AbilityBars;
+
+#[derive(Component)]
+struct CooldownBar;
+
+#[derive(Component)]
+struct StaminaBar;
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    // dashing is timed in real seconds, so a ranked run only boosts
+    fn build(&self, app: &mut App) {
+        let ranked = Args::of(app).ranked();
+        app.insert_resource(DashCooldown::new())
+            .insert_resource(Stamina {
+                value: 1.0,
+                boosting: false,
+                exhausted: false,
+            })
+            .add_systems(Startup, setup_ability_bars)
+            .add_systems(
+                Update,
+                (
+                    activate_dash.run_if(menu_closed).run_if(move || !ranked),
+                    update_cooldown_bar,
+                    hide_ability_bars,
+                    (
+                        update_stamina.in_set(SnakeSystemSet::Input),
+                        update_stamina_bar,
+                    )
+                        .chain(),
+                ),
+            );
+    }
+}
+
+fn setup_ability_bars(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(12.0),
+                    left: Val::Px(12.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            AbilityBars,
+        ))
+        .with_children(|parent| {
+            spawn_bar(parent, "Boost [Shift]", BOOST_READY_COLOR, StaminaBar);
+            spawn_bar(parent, "Dash [Space]", BAR_READY_COLOR, CooldownBar);
+        });
+}
+
+fn hide_ability_bars(
+    mut game_over_event: EventReader<GameOver>,
+    mut bars_query: Query<&mut Visibility, With<AbilityBars>>,
+) {
+    if game_over_event.read().count() == 0 {
+        return;
+    }
+    for mut visibility in &mut bars_query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, label: &str, color: Color, marker: impl Component) {
+    parent.spawn(TextBundle::from_section(
+        label,
+        TextStyle {
+            font_size: 20.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ));
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(BAR_WIDTH),
+                height: Val::Px(10.0),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            border_color: Color::WHITE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                marker,
+            ));
+        });
+}
+
+fn activate_dash(
+    time: Res<Time>,
+    action_state: Res<ActionState>,
+    mut dash_cooldown: ResMut<DashCooldown>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+) {
+    dash_cooldown.0.tick(time.delta());
+    if !dash_cooldown.0.finished() || !action_state.just_pressed(Action::Ability) {
+        return;
+    }
+    if let Some((_, mut snake_head)) = snake_head_query
+        .iter_mut()
+        .find(|(player, _)| **player == Player::ONE)
+    {
+        snake_head.pending_dash = DASH_CELLS;
+        dash_cooldown.0.reset();
+    }
+}
+
+fn update_cooldown_bar(
+    dash_cooldown: Res<DashCooldown>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<CooldownBar>>,
+) {
+    for (mut style, mut background_color) in &mut bar_query {
+        style.width = Val::Percent(dash_cooldown.0.percent() * 100.0);
+        background_color.0 = if dash_cooldown.0.finished() {
+            BAR_READY_COLOR
+        } else {
+            BAR_CHARGING_COLOR
+        };
+    }
+}
+
+pub fn update_stamina(
+    time: Res<Time>,
+    action_state: Res<ActionState>,
+    mut stamina: ResMut<Stamina>,
+) {
+    let held = action_state.pressed(Action::Boost);
+    // running dry locks the boost until the bar has refilled a bit
+    if stamina.exhausted && stamina.value >= STAMINA_TO_RECOVER {
+        stamina.exhausted = false;
+    }
+    stamina.boosting = held && !stamina.exhausted && stamina.value > 0.0;
+    if stamina.boosting {
+        stamina.value = (stamina.value - STAMINA_DRAIN_PER_SECOND * time.delta_seconds()).max(0.0);
+        if stamina.value == 0.0 {
+            stamina.exhausted = true;
+        }
+    } else {
+        stamina.value = (stamina.value + STAMINA_REGEN_PER_SECOND * time.delta_seconds()).min(1.0);
+    }
+}
+
+fn update_stamina_bar(
+    stamina: Res<Stamina>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<StaminaBar>>,
+) {
+    for (mut style, mut background_color) in &mut bar_query {
+        style.width = Val::Percent(stamina.value * 100.0);
+        background_color.0 = if stamina.exhausted {
+            BOOST_EXHAUSTED_COLOR
+        } else {
+            BOOST_READY_COLOR
+        };
+    }
+}
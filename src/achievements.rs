@@ -0,0 +1,207 @@
+// Achievements
+// Milestones earned once per profile, kept in its achievements.ron: a toast shows when one is
+// earned, and unlocks.rs hands out the cosmetics tied to them
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::cheats::no_cheats_used;
+use crate::profile::Profile;
+use crate::score::{Combo, Score};
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::tween::{Easing, Tween, TweenTarget};
+use crate::{AppleEaten, GameOver, Player, SnakeHead};
+
+const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+const TOAST_SECONDS: f32 = 4.0;
+const TOAST_COLOR: Color = Color::GOLD;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Achievement {
+    FirstApple,
+    Score50,
+    Score200,
+    Combo5,
+    Length30,
+    TenRuns,
+    Konami,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 7] = [
+        Achievement::FirstApple,
+        Achievement::Score50,
+        Achievement::Score200,
+        Achievement::Combo5,
+        Achievement::Length30,
+        Achievement::TenRuns,
+        Achievement::Konami,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstApple => "First bite",
+            Achievement::Score50 => "Fifty",
+            Achievement::Score200 => "Two hundred",
+            Achievement::Combo5 => "Combo five",
+            Achievement::Length30 => "Long boi",
+            Achievement::TenRuns => "Regular",
+            Achievement::Konami => "Thirty lives",
+        }
+    }
+
+    // what it takes, shown next to whatever it unlocks
+    pub fn hint(self) -> &'static str {
+        match self {
+            Achievement::FirstApple => "eat an apple",
+            Achievement::Score50 => "score 50 in one run",
+            Achievement::Score200 => "score 200 in one run",
+            Achievement::Combo5 => "eat 5 apples in a combo",
+            Achievement::Length30 => "grow to 30 segments",
+            Achievement::TenRuns => "finish 10 runs",
+            Achievement::Konami => "enter a famous code during play",
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AchievementEarned(pub Achievement);
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Achievements {
+    earned: Vec<Achievement>,
+    runs: u32,
+    #[serde(skip)]
+    file: Option<String>, // None until a profile is picked
+}
+
+impl Achievements {
+    pub fn has(&self, achievement: Achievement) -> bool {
+        self.earned.contains(&achievement)
+    }
+
+    // true the first time, along with the toast
+    pub fn earn(
+        &mut self,
+        achievement: Achievement,
+        achievement_earned_event: &mut EventWriter<AchievementEarned>,
+    ) -> bool {
+        if self.has(achievement) {
+            return false;
+        }
+        info!(?achievement, "Achievement earned");
+        self.earned.push(achievement);
+        achievement_earned_event.send(AchievementEarned(achievement));
+        true
+    }
+
+    pub fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Err(error) = write_ron_save(file, self) {
+            warn!("Could not save achievements: {error}");
+        }
+    }
+}
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    // the demo bot plays as player one and cheats do the work, nothing either does counts
+    fn build(&self, app: &mut App) {
+        let demo = Args::of(app).has("--demo");
+        app.init_resource::<Achievements>()
+            .add_event::<AchievementEarned>()
+            .add_systems(
+                Update,
+                (
+                    load_achievements.run_if(resource_added::<Profile>()),
+                    track_achievements
+                        .run_if(move || !demo)
+                        .run_if(no_cheats_used),
+                    show_toasts,
+                )
+                    .chain(),
+            );
+    }
+}
+
+pub fn load_achievements(profile: Res<Profile>, mut achievements: ResMut<Achievements>) {
+    let file = profile.file(ACHIEVEMENTS_FILE);
+    *achievements = read_ron_save(&file).unwrap_or_default();
+    achievements.file = Some(file);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_achievements(
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut game_over_event: EventReader<GameOver>,
+    score: Res<Score>,
+    combo: Res<Combo>,
+    mut achievements: ResMut<Achievements>,
+    mut achievement_earned_event: EventWriter<AchievementEarned>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    mut counted_run: Local<bool>,
+) {
+    let ate = apple_eaten_event
+        .read()
+        .any(|event| event.player == Player::ONE);
+    let length = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+        .map_or(0, |(_, snake_head)| snake_head.segments.len());
+    // there is one run per launch, a game over repeating doesn't count it again
+    let finished =
+        game_over_event.read().count() > 0 && !std::mem::replace(&mut *counted_run, true);
+    if finished {
+        achievements.runs += 1;
+    }
+    let reached = [
+        (Achievement::FirstApple, ate),
+        (Achievement::Score50, score.0 >= 50),
+        (Achievement::Score200, score.0 >= 200),
+        (Achievement::Combo5, combo.count >= 5),
+        (Achievement::Length30, length >= 30),
+        (Achievement::TenRuns, achievements.runs >= 10),
+    ];
+    let mut changed = finished;
+    for (achievement, reached) in reached {
+        if reached && achievements.earn(achievement, &mut achievement_earned_event) {
+            changed = true;
+        }
+    }
+    if changed {
+        achievements.save();
+    }
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut achievement_earned_event: EventReader<AchievementEarned>,
+) {
+    for (index, event) in achievement_earned_event.read().enumerate() {
+        commands.spawn((
+            TextBundle::from_section(
+                format!("Achievement: {}", event.0.name()),
+                TextStyle {
+                    font_size: 28.0,
+                    color: TOAST_COLOR,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(96.0 + index as f32 * 32.0),
+                right: Val::Px(12.0),
+                ..default()
+            }),
+            Tween::new(TOAST_SECONDS, Easing::InQuad)
+                .with(TweenTarget::Color {
+                    from: TOAST_COLOR,
+                    to: TOAST_COLOR.with_a(0.0),
+                })
+                .despawn_when_done(),
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+// Bots
+// Snakes steered by a strategy instead of the keyboard: an optional CPU opponent (--versus-cpu)
+// and a demo bot playing as player one (--demo). The CPU's skill is set by reaction delay,
+// lookahead depth and mistake chance, and with --cpu-skill adaptive it is eased or hardened after
+// each death and kept in ai.ron
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::event_log::EventLogged;
+use crate::hamiltonian::Hamiltonian;
+use crate::input_source::{AddInputSource, DirectionIntents, InputSource};
+use crate::occupancy::Occupancy;
+use crate::pathfinding;
+use crate::playfield::PlayfieldMask;
+use crate::seed::RunSeed;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::theme::Theme;
+use crate::{
+    spawn_snake, Apple, Player, SnakeBody, SnakeDied, SnakeHead, SnakeSystemSet, START_LENGTH,
+};
+
+pub const CPU: Player = Player(2);
+const CPU_COLOR: Color = Color::ORANGE;
+const ADAPTIVE_FILE: &str = "ai.ron";
+const ADAPTIVE_STEP: f32 = 0.1; // skill level change per death
+const RNG_SALT: u64 = 0x5eed_c0de; // keeps the bot's rolls apart from the spawn sequence
+
+#[derive(Resource, Clone, Copy, PartialEq)]
+pub struct AiSkill {
+    pub reaction_ticks: u32, // ticks between decisions
+    pub lookahead: u32,      // cells of free room it checks for before committing to a move
+    pub mistake_chance: f32, // per decision, of picking a random turn
+}
+
+impl AiSkill {
+    pub const EASY: AiSkill = AiSkill {
+        reaction_ticks: 3,
+        lookahead: 4,
+        mistake_chance: 0.15,
+    };
+    pub const NORMAL: AiSkill = AiSkill {
+        reaction_ticks: 1,
+        lookahead: 16,
+        mistake_chance: 0.05,
+    };
+    pub const HARD: AiSkill = AiSkill {
+        reaction_ticks: 0,
+        lookahead: 64,
+        mistake_chance: 0.0,
+    };
+
+    // 0 is easy, 1 is hard
+    fn at_level(level: f32) -> AiSkill {
+        let (easy, hard) = (AiSkill::EASY, AiSkill::HARD);
+        let lerp = |from: f32, to: f32| from + (to - from) * level;
+        AiSkill {
+            reaction_ticks: lerp(easy.reaction_ticks as f32, hard.reaction_ticks as f32).round()
+                as u32,
+            lookahead: lerp(easy.lookahead as f32, hard.lookahead as f32).round() as u32,
+            mistake_chance: lerp(easy.mistake_chance, hard.mistake_chance),
+        }
+    }
+}
+
+// skill level following the player's results, saved between sessions
+#[derive(Resource, Serialize, Deserialize)]
+struct AdaptiveAi {
+    level: f32,
+}
+
+impl Default for AdaptiveAi {
+    fn default() -> Self {
+        AdaptiveAi { level: 0.5 }
+    }
+}
+
+// what a strategy gets to look at when asked for its next move
+pub struct BotView<'a> {
+    pub occupancy: &'a Occupancy,
+    pub snake_head: &'a SnakeHead,
+    pub apple: Option<&'a Apple>,
+    pub skill: &'a AiSkill,
+}
+
+impl BotView<'_> {
+    // walls, heads and bodies
+    pub fn is_blocked(&self, position: (i32, i32)) -> bool {
+        pathfinding::is_blocked(self.occupancy, position)
+    }
+}
+
+// asked once per tick, the answer becomes the snake's next direction
+pub trait Strategy: Send + Sync {
+    fn choose_direction(&mut self, view: &BotView, rng: &mut StdRng) -> Direction;
+
+    // the cells it means to move through next, as far as it can tell from here
+    fn planned_path(&self, view: &BotView, moves: usize) -> Vec<(i32, i32)> {
+        plan_toward_apple(view, moves)
+    }
+}
+
+// heads for the apple while keeping enough room to move, held back by its skill
+#[derive(Clone, Default)]
+pub struct Greedy {
+    ticks_since_decision: u32,
+}
+
+impl Strategy for Greedy {
+    fn choose_direction(&mut self, view: &BotView, rng: &mut StdRng) -> Direction {
+        let skill = view.skill;
+        if self.ticks_since_decision < skill.reaction_ticks {
+            self.ticks_since_decision += 1;
+            return view.snake_head.potential_direction;
+        }
+        self.ticks_since_decision = 0;
+        if rng.gen::<f32>() < skill.mistake_chance {
+            let candidates: Vec<Direction> = Direction::ALL
+                .into_iter()
+                .filter(|direction| *direction != view.snake_head.direction.opposite())
+                .collect();
+            return candidates[rng.gen_range(0..candidates.len())];
+        }
+        safest_toward_apple(view, skill.lookahead)
+    }
+}
+
+pub struct Bot {
+    pub player: Player,
+    pub strategy: Box<dyn Strategy>,
+}
+
+#[derive(Resource)]
+pub struct Bots {
+    pub bots: Vec<Bot>,
+    rng: StdRng,
+}
+
+// --demo-strategy <greedy|hamiltonian>
+fn strategy_from_name(name: &str, cycle: &Option<Hamiltonian>) -> Option<Box<dyn Strategy>> {
+    match name {
+        "greedy" => Some(Box::<Greedy>::default()),
+        "hamiltonian" => Some(cycle_or_greedy(cycle)),
+        _ => None,
+    }
+}
+
+// the hamiltonian bot, or the greedy one on a board it has no cycle for
+fn cycle_or_greedy(cycle: &Option<Hamiltonian>) -> Box<dyn Strategy> {
+    match cycle {
+        Some(cycle) => Box::new(cycle.clone()),
+        None => Box::<Greedy>::default(),
+    }
+}
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    // --cpu-skill <easy|normal|hard|adaptive|perfect>, perfect being the hamiltonian bot
+    fn build(&self, app: &mut App) {
+        let args = Args::of(app);
+        let cycle = app
+            .world
+            .get_resource::<PlayfieldMask>()
+            .and_then(Hamiltonian::new);
+        let cpu_skill = args.value("--cpu-skill");
+        let wants_cycle = (args.has("--demo") && args.value("--demo-strategy") != Some("greedy"))
+            || (args.has("--versus-cpu") && cpu_skill == Some("perfect"));
+        if wants_cycle && cycle.is_none() {
+            app.add_systems(Startup, refuse_cycle);
+        }
+
+        let mut bots = Vec::new();
+        if args.has("--demo") {
+            let strategy = args
+                .value("--demo-strategy")
+                .and_then(|name| strategy_from_name(name, &cycle))
+                .unwrap_or_else(|| cycle_or_greedy(&cycle));
+            bots.push(Bot {
+                player: Player::ONE,
+                strategy,
+            });
+        }
+
+        let mut skill = AiSkill::NORMAL;
+        match cpu_skill {
+            Some("adaptive") => {
+                let adaptive: AdaptiveAi = read_ron_save(ADAPTIVE_FILE).unwrap_or_default();
+                skill = AiSkill::at_level(adaptive.level);
+                app.insert_resource(adaptive)
+                    .add_systems(Update, adapt_skill);
+            }
+            Some("easy") => skill = AiSkill::EASY,
+            Some("hard" | "perfect") => skill = AiSkill::HARD,
+            _ => {}
+        }
+        if args.has("--versus-cpu") {
+            let strategy: Box<dyn Strategy> = if cpu_skill == Some("perfect") {
+                cycle_or_greedy(&cycle)
+            } else {
+                Box::<Greedy>::default()
+            };
+            bots.push(Bot {
+                player: CPU,
+                strategy,
+            });
+            app.add_systems(Startup, setup_cpu_snake)
+                .add_systems(Update, tint_cpu_snake.in_set(SnakeSystemSet::Rendering));
+        }
+
+        // reseeded from the run seed once it is known
+        app.add_input_source(Bots {
+            bots,
+            rng: StdRng::seed_from_u64(0),
+        })
+        .insert_resource(skill)
+        .add_systems(Startup, seed_bots);
+    }
+}
+
+fn refuse_cycle(mut event_logged_event: EventWriter<EventLogged>) {
+    let message = "No Hamiltonian cycle on this board, the bot plays greedy";
+    warn!("{message}");
+    event_logged_event.send(EventLogged(message.to_string()));
+}
+
+fn seed_bots(mut bots: ResMut<Bots>, seed: Res<RunSeed>) {
+    bots.rng = StdRng::seed_from_u64(seed.0 ^ RNG_SALT);
+}
+
+fn setup_cpu_snake(mut commands: Commands, theme: Res<Theme>, playfield_mask: Res<PlayfieldMask>) {
+    spawn_snake(&mut commands, &theme, &playfield_mask, CPU, START_LENGTH);
+}
+
+// the move closest to the apple among those leaving room for the whole snake, as far as it looks
+pub fn safest_toward_apple(view: &BotView, lookahead: u32) -> Direction {
+    let snake_head = view.snake_head;
+    let needed = (snake_head.segments.len() + 1)
+        .min(lookahead as usize)
+        .max(1);
+    Direction::ALL
+        .into_iter()
+        .filter(|direction| *direction != snake_head.direction.opposite())
+        .filter_map(|direction| {
+            let next = view.occupancy.wrapped(direction.step(snake_head.position));
+            if view.is_blocked(next) {
+                return None;
+            }
+            let room = pathfinding::reachable_area(view.occupancy, next, needed);
+            // around whatever is in the way, straight through when there is no way
+            let distance = view.apple.map_or(0, |apple| {
+                pathfinding::find_path(view.occupancy, next, &apple.footprint).map_or_else(
+                    || {
+                        apple
+                            .footprint
+                            .iter()
+                            .map(|cell| view.occupancy.distance(next, *cell))
+                            .min()
+                            .unwrap_or(0)
+                    },
+                    |path| path.len() as u32,
+                )
+            });
+            Some((direction, room >= needed, distance, room))
+        })
+        // safe first, then closest to the apple, then the most room
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)).then(a.3.cmp(&b.3)))
+        .map_or(snake_head.direction, |(direction, ..)| direction)
+}
+
+// the safest first move, then the shortest way on from there to the apple
+pub fn plan_toward_apple(view: &BotView, moves: usize) -> Vec<(i32, i32)> {
+    let first = view
+        .occupancy
+        .wrapped(safest_toward_apple(view, view.skill.lookahead).step(view.snake_head.position));
+    if view.is_blocked(first) {
+        return Vec::new();
+    }
+    let mut path = vec![first];
+    if let Some(rest) = view
+        .apple
+        .and_then(|apple| pathfinding::find_path(view.occupancy, first, &apple.footprint))
+    {
+        path.extend(rest);
+    }
+    path.truncate(moves);
+    path
+}
+
+impl InputSource for Bots {
+    type Param = (
+        Res<'static, AiSkill>,
+        Res<'static, Occupancy>,
+        Query<'static, 'static, &'static Apple>,
+        Query<'static, 'static, (&'static Player, &'static SnakeHead)>,
+    );
+    const PER_TICK: bool = true;
+
+    fn poll(
+        &mut self,
+        (skill, occupancy, apple_query, snake_head_query): &mut SystemParamItem<
+            '_,
+            '_,
+            Self::Param,
+        >,
+        intents: &mut DirectionIntents,
+    ) {
+        let Bots { bots, rng } = self;
+        let apple = apple_query.iter().next();
+        for (player, snake_head) in snake_head_query.iter() {
+            let Some(bot) = bots.iter_mut().find(|bot| bot.player == *player) else {
+                continue;
+            };
+            let view = BotView {
+                occupancy,
+                snake_head,
+                apple,
+                skill,
+            };
+            intents.push(*player, bot.strategy.choose_direction(&view, rng));
+        }
+    }
+}
+
+// the player losing a life eases the bot off, the bot losing one toughens it up
+fn adapt_skill(
+    time: Res<Time<Virtual>>,
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut adaptive: ResMut<AdaptiveAi>,
+    mut skill: ResMut<AiSkill>,
+) {
+    // a snake left dead on the game over screen keeps reporting it
+    if time.is_paused() {
+        snake_died_event.clear();
+        return;
+    }
+    // several collisions can report the same death in one frame
+    let mut died: Vec<Player> = snake_died_event.read().map(|event| event.player).collect();
+    died.sort_by_key(|player| player.0);
+    died.dedup();
+    let mut changed = false;
+    for player in died {
+        let step = if player == CPU {
+            ADAPTIVE_STEP
+        } else {
+            -ADAPTIVE_STEP
+        };
+        adaptive.level = (adaptive.level + step).clamp(0.0, 1.0);
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+    *skill = AiSkill::at_level(adaptive.level);
+    info!(level = adaptive.level, "CPU skill adapted");
+    if let Err(error) = write_ron_save(ADAPTIVE_FILE, &*adaptive) {
+        warn!("Could not save the CPU skill level: {error}");
+    }
+}
+
+// keeps the bot's colors apart from the player's, alpha is left to blinking and fading
+#[allow(clippy::type_complexity)]
+fn tint_cpu_snake(
+    mut sprite_query: Query<(&Player, &mut Sprite), Or<(With<SnakeHead>, With<SnakeBody>)>>,
+) {
+    for (player, mut sprite) in &mut sprite_query {
+        if *player == CPU {
+            sprite.color = CPU_COLOR.with_a(sprite.color.a());
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Apple prediction
+// Practice for speedrunners (--predict-apples): a faint outline shows where the next apple will
+// land, from the moment the current one spawns, to plan a route through both. It is rolled every
+// tick from a copy of the run's generator, so nothing drawn for the real run changes. The guess
+// holds as long as the cells stay free and no power-up or stone draws first; if the snake ends up
+// on them when the apple comes, spawn_apple rolls on past them and the outline moves to match.
+// Knowing the next apple changes the run, so it can't be ranked
+use bevy::prelude::*;
+
+use crate::items::{ItemTable, PowerUp};
+use crate::playfield::PlayfieldMask;
+use crate::seed::RunRng;
+use crate::spawn_balance::{RecentSpawns, SpawnBalance};
+use crate::theme::Theme;
+use crate::{
+    apple_blocked_positions, roll_apple, Apple, SnakeBody, SnakeHead, SnakeSystemSet,
+    PIXEL_UNIT_SIZE,
+};
+
+const MARKER_ALPHA: f32 = 0.35;
+
+// the next apple's cells and color, none while no apple is on the board
+#[derive(Resource, Default)]
+struct ApplePrediction(Option<(Vec<(i32, i32)>, Color)>);
+
+pub struct ApplePredictionPlugin;
+
+impl Plugin for ApplePredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ApplePrediction>()
+            .add_systems(
+                FixedUpdate,
+                predict_apple
+                    .in_set(SnakeSystemSet::Spawning)
+                    .after(crate::spawn_apple),
+            )
+            .add_systems(Update, draw_prediction.in_set(SnakeSystemSet::Rendering));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn predict_apple(
+    run_rng: Res<RunRng>,
+    item_table: Res<ItemTable>,
+    theme: Res<Theme>,
+    spawn_balance: Res<SpawnBalance>,
+    recent_spawns: Res<RecentSpawns>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+    power_up_query: Query<&PowerUp>,
+    apple_query: Query<(), With<Apple>>,
+    mut prediction: ResMut<ApplePrediction>,
+) {
+    if apple_query.is_empty() {
+        prediction.0 = None;
+        return;
+    }
+    // the apple on the board will have been eaten by then, so its cells count as free
+    let mut rng = run_rng.0.clone();
+    prediction.0 = roll_apple(
+        &mut rng,
+        &item_table,
+        *spawn_balance,
+        &recent_spawns,
+        &playfield_mask,
+        apple_blocked_positions(&snake_body_query, &power_up_query),
+        &snake_head_query,
+    )
+    .map(|(definition, footprint)| (footprint, definition.color.unwrap_or(theme.apple)));
+}
+
+fn draw_prediction(prediction: Res<ApplePrediction>, mut gizmos: Gizmos) {
+    let Some((footprint, color)) = &prediction.0 else {
+        return;
+    };
+    let Some(&(x, y)) = footprint.first() else {
+        return;
+    };
+    // the footprint is square and starts at its bottom-left cell
+    let size = (footprint.len() as f32).sqrt();
+    let center_offset = (size - 1.0) / 2.0;
+    let center = Vec2::new(x as f32 + center_offset, y as f32 + center_offset) * PIXEL_UNIT_SIZE;
+    let color = color.with_a(MARKER_ALPHA);
+    gizmos.rect_2d(center, 0.0, Vec2::splat(size * PIXEL_UNIT_SIZE), color);
+    gizmos.rect_2d(
+        center,
+        0.0,
+        Vec2::splat(size * PIXEL_UNIT_SIZE * 0.6),
+        color,
+    );
+}
@@ -0,0 +1,274 @@
+// Command line
+// The arguments are read once at startup into Args, which every module asks instead of reading
+// them again. Each flag the game takes is declared in FLAGS with what it does to a run: settings
+// leave the rules alone, difficulty keeps runs apart in the high score tables without changing the
+// rules, and rule flags change the game, so a ranked run can't have them. A value is whatever
+// follows its flag up to the next flag. Problems with the command line are noted as they are found,
+// before logging is set up, and logged together at startup
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlagKind {
+    Setting,    // presentation, input, saves and the tools that run without the game
+    Difficulty, // not rules, but not the same game either
+    Rules,      // a ranked run can't have it
+}
+
+// every flag the game reads, a flag missing here is reported as unknown
+const FLAGS: [(&str, FlagKind); 61] = [
+    ("--assist", FlagKind::Rules),
+    ("--battle", FlagKind::Rules),
+    ("--battle-humans", FlagKind::Rules),
+    ("--battle-rounds", FlagKind::Rules),
+    ("--bench-occupancy", FlagKind::Setting),
+    ("--board-window", FlagKind::Setting),
+    ("--check-determinism", FlagKind::Setting),
+    ("--check-ticks", FlagKind::Setting),
+    ("--corridor", FlagKind::Rules),
+    ("--cpu-skill", FlagKind::Difficulty),
+    ("--crt", FlagKind::Setting),
+    ("--day-night", FlagKind::Setting),
+    ("--demo", FlagKind::Rules),
+    ("--demo-strategy", FlagKind::Rules),
+    ("--diagonal-policy", FlagKind::Setting),
+    ("--endless", FlagKind::Rules),
+    ("--event", FlagKind::Setting),
+    ("--fancy-graphics", FlagKind::Setting),
+    ("--gravity", FlagKind::Rules),
+    ("--hints", FlagKind::Rules),
+    ("--hunger", FlagKind::Rules),
+    ("--ice-rink", FlagKind::Rules),
+    ("--lives", FlagKind::Rules),
+    ("--livesplit", FlagKind::Setting),
+    ("--lobby", FlagKind::Rules),
+    ("--log-level", FlagKind::Setting),
+    ("--low-graphics", FlagKind::Setting),
+    ("--low-power", FlagKind::Setting),
+    ("--molt", FlagKind::Rules),
+    ("--no-auto-quality", FlagKind::Setting),
+    ("--no-events", FlagKind::Setting),
+    ("--objectives", FlagKind::Rules),
+    ("--obstacles", FlagKind::Rules),
+    ("--play-inputs", FlagKind::Rules),
+    ("--playfield", FlagKind::Rules),
+    ("--predict-apples", FlagKind::Rules),
+    ("--profile", FlagKind::Setting),
+    ("--ranked", FlagKind::Setting),
+    ("--remote-play", FlagKind::Rules),
+    ("--render-replay", FlagKind::Setting),
+    ("--replay", FlagKind::Setting),
+    ("--resolution", FlagKind::Setting),
+    ("--scenarios", FlagKind::Rules),
+    ("--scoring", FlagKind::Rules),
+    ("--seed", FlagKind::Setting),
+    ("--show-path", FlagKind::Setting),
+    ("--spectate", FlagKind::Rules),
+    ("--spectator-bind", FlagKind::Setting),
+    ("--spectator-port", FlagKind::Setting),
+    ("--splits", FlagKind::Setting),
+    ("--stats-window", FlagKind::Setting),
+    ("--teams", FlagKind::Rules),
+    ("--telemetry", FlagKind::Setting),
+    ("--tick-rate", FlagKind::Rules),
+    ("--train-boards", FlagKind::Setting),
+    ("--train-ticks", FlagKind::Setting),
+    ("--tutorial", FlagKind::Setting),
+    ("--verify-replay", FlagKind::Setting),
+    ("--versus-cpu", FlagKind::Rules),
+    ("--wrap", FlagKind::Rules),
+    ("--zen", FlagKind::Rules),
+];
+
+fn kind(flag: &str) -> Option<FlagKind> {
+    FLAGS
+        .iter()
+        .find(|(name, _)| *name == flag)
+        .map(|(_, kind)| *kind)
+}
+
+fn is_flag(arg: &str) -> bool {
+    arg.starts_with("--")
+}
+
+// the command line, empty for a game embedded in another app
+#[derive(Resource, Clone, Default)]
+pub struct Args {
+    args: Vec<String>,
+    ranked: bool,
+    problems: Arc<Mutex<Vec<String>>>, // shared by the copies plugins take
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Args::parse(std::env::args().skip(1))
+    }
+
+    // without the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = Args {
+            args: args.into_iter().collect(),
+            ..default()
+        };
+        for flag in args.args.iter().filter(|arg| is_flag(arg)) {
+            if kind(flag).is_none() {
+                args.problem(format!("Unknown flag {flag}, ignoring it"));
+            }
+        }
+        if args.has("--ranked") {
+            let rule_flag = args.flags(FlagKind::Rules).next().map(str::to_string);
+            match rule_flag {
+                Some(flag) => {
+                    args.problem(format!("{flag} changes the rules, the run is not ranked"));
+                }
+                None => args.ranked = true,
+            }
+        }
+        args
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        debug_assert!(kind(flag).is_some(), "{flag} is missing from FLAGS");
+        self.args.iter().any(|arg| arg == flag)
+    }
+
+    // what follows the flag up to the next one
+    pub fn values(&self, flag: &str) -> Option<Vec<&str>> {
+        debug_assert!(kind(flag).is_some(), "{flag} is missing from FLAGS");
+        let index = self.args.iter().position(|arg| arg == flag)?;
+        Some(
+            self.args[index + 1..]
+                .iter()
+                .take_while(|arg| !is_flag(arg))
+                .map(String::as_str)
+                .collect(),
+        )
+    }
+
+    pub fn value(&self, flag: &str) -> Option<&str> {
+        self.values(flag)?.first().copied()
+    }
+
+    // the command line with only this value given to the flag, for starting the game again
+    pub fn with_value(&self, flag: &str, value: &str) -> Vec<String> {
+        debug_assert!(kind(flag).is_some(), "{flag} is missing from FLAGS");
+        let mut skipping = false;
+        let mut args: Vec<String> = self
+            .args
+            .iter()
+            .filter(|arg| {
+                if is_flag(arg) {
+                    skipping = *arg == flag;
+                }
+                !skipping
+            })
+            .cloned()
+            .collect();
+        args.extend([flag.to_string(), value.to_string()]);
+        args
+    }
+
+    // --ranked, unless another flag changes the rules
+    pub fn ranked(&self) -> bool {
+        self.ranked
+    }
+
+    // the flags of this kind that were given, in order
+    pub fn flags(&self, kind_wanted: FlagKind) -> impl Iterator<Item = &str> {
+        self.args
+            .iter()
+            .filter(move |arg| kind(arg) == Some(kind_wanted))
+            .map(String::as_str)
+    }
+
+    // for the log once it is set up, the game goes on with a default
+    pub fn problem(&self, message: String) {
+        self.problems
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(message);
+    }
+
+    pub fn take_problems(&self) -> Vec<String> {
+        std::mem::take(&mut *self.problems.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    // a plugin's copy while building, an embedded game without one gets an empty command line
+    pub fn of(app: &mut App) -> Args {
+        app.world.get_resource_or_insert_with(Args::default).clone()
+    }
+}
+
+pub struct ArgsPlugin;
+
+impl Plugin for ArgsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, log_problems);
+    }
+}
+
+fn log_problems(args: Res<Args>) {
+    for problem in args.take_problems() {
+        warn!("{problem}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn flags_are_declared_once() {
+        for (index, (name, _)) in FLAGS.iter().enumerate() {
+            assert!(is_flag(name), "{name}");
+            assert!(
+                !FLAGS[index + 1..].iter().any(|(other, _)| other == name),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn values_run_up_to_the_next_flag() {
+        let args = parse("--log-level debug trace --lives");
+        assert_eq!(args.values("--log-level"), Some(vec!["debug", "trace"]));
+        assert_eq!(args.value("--log-level"), Some("debug"));
+        assert_eq!(args.values("--lives"), Some(Vec::new()));
+        assert_eq!(args.value("--lives"), None);
+        assert_eq!(args.values("--hunger"), None);
+    }
+
+    #[test]
+    fn with_value_replaces_the_old_one() {
+        let args = parse("--lives --seed 7 --hunger");
+        assert_eq!(
+            args.with_value("--seed", "9"),
+            vec!["--lives", "--hunger", "--seed", "9"]
+        );
+        assert_eq!(parse("").with_value("--seed", "9"), vec!["--seed", "9"]);
+    }
+
+    #[test]
+    fn rule_flags_unrank_a_run() {
+        assert!(parse("--ranked --seed 7 --low-graphics").ranked());
+        let args = parse("--ranked --lives");
+        assert!(!args.ranked());
+        assert_eq!(args.take_problems().len(), 1);
+        assert!(!parse("--seed 7").ranked());
+    }
+
+    #[test]
+    fn unknown_flags_are_reported() {
+        let args = parse("--ice-rink --no-such-flag");
+        assert!(args.has("--ice-rink"));
+        assert_eq!(
+            args.take_problems(),
+            vec!["Unknown flag --no-such-flag, ignoring it".to_string()]
+        );
+    }
+}
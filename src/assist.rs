@@ -0,0 +1,131 @@
+// Assist mode
+// Optional overlay for beginners (--assist): cells player one would die in on the next tick are
+// tinted red and a faint arrow points along the shortest way to the apple
+use bevy::prelude::*;
+
+use crate::direction::Direction;
+use crate::occupancy::Occupancy;
+use crate::pathfinding;
+use crate::{Apple, PendingGrowth, Player, SnakeBody, SnakeHead, PIXEL_UNIT_SIZE};
+
+const DANGER_COLOR: Color = Color::rgba(1.0, 0.0, 0.0, 0.35);
+const ARROW_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.25);
+const ARROW_LENGTH: f32 = 2.0; // cells
+const ARROW_HEAD: f32 = 0.4; // cells
+
+// one per move the snake can make, moved onto the cells that are fatal
+#[derive(Component)]
+struct DangerCell;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_danger_cells)
+            .add_systems(Update, (highlight_danger, draw_apple_arrow));
+    }
+}
+
+fn setup_danger_cells(mut commands: Commands) {
+    for _ in 0..3 {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: DANGER_COLOR,
+                    custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DangerCell,
+        ));
+    }
+}
+
+fn highlight_danger(
+    occupancy: Res<Occupancy>,
+    pending_growth: Res<PendingGrowth>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    mut danger_query: Query<(&mut Transform, &mut Visibility), With<DangerCell>>,
+) {
+    let mut dangers = Vec::new();
+    if let Some((player, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    {
+        // the tail moves out of the way unless the snake is about to grow
+        let growing = pending_growth
+            .0
+            .get(player)
+            .is_some_and(|growth| *growth > 0);
+        let tail = snake_head
+            .segments
+            .last()
+            .and_then(|segment| snake_body_query.get(*segment).ok())
+            .map(|snake_body| snake_body.position);
+        for direction in Direction::ALL {
+            if direction == snake_head.direction.opposite() {
+                continue;
+            }
+            let next = occupancy.wrapped(direction.step(snake_head.position));
+            if pathfinding::is_blocked(&occupancy, next) && (growing || tail != Some(next)) {
+                dangers.push(next);
+            }
+        }
+    }
+    let mut dangers = dangers.into_iter();
+    for (mut transform, mut visibility) in &mut danger_query {
+        if let Some(position) = dangers.next() {
+            transform.translation = Vec3::new(
+                position.0 as f32 * PIXEL_UNIT_SIZE,
+                position.1 as f32 * PIXEL_UNIT_SIZE,
+                0.5,
+            );
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+// gizmos only last a frame, so this redraws every frame
+fn draw_apple_arrow(
+    occupancy: Res<Occupancy>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    apple_query: Query<&Apple>,
+    mut gizmos: Gizmos,
+) {
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let Some(apple) = apple_query.iter().next() else {
+        return;
+    };
+    let Some(next) = pathfinding::find_path(&occupancy, snake_head.position, &apple.footprint)
+        .and_then(|path| path.first().copied())
+    else {
+        return;
+    };
+    // off the edge rather than across the board when the way wraps around
+    let Some(next) = Direction::ALL
+        .into_iter()
+        .map(|direction| direction.step(snake_head.position))
+        .find(|position| occupancy.wrapped(*position) == next)
+    else {
+        return;
+    };
+    let cell =
+        |position: (i32, i32)| Vec2::new(position.0 as f32, position.1 as f32) * PIXEL_UNIT_SIZE;
+    let start = cell(snake_head.position);
+    let heading = (cell(next) - start).normalize_or_zero();
+    let tip = start + heading * ARROW_LENGTH * PIXEL_UNIT_SIZE;
+    let back = heading * ARROW_HEAD * PIXEL_UNIT_SIZE;
+    gizmos.line_2d(start, tip, ARROW_COLOR);
+    gizmos.line_2d(tip, tip - back + back.perp() * 0.6, ARROW_COLOR);
+    gizmos.line_2d(tip, tip - back - back.perp() * 0.6, ARROW_COLOR);
+}
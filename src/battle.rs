@@ -0,0 +1,577 @@
+// Battle royale
+// Rounds of up to four snakes (--battle): two keyboard layouts and two gamepads, with bots taking
+// any seat nobody is in. The last snake standing takes the round, everyone scores by how long
+// they lasted, and a podium ranks the totals after the final round. With --teams the seats pair
+// up two against two, sharing a score and bumping into each other instead of dying
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParamItem;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::ai::{Bot, Bots, Greedy};
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::input::ActionMap;
+use crate::input_source::{AddInputSource, DirectionIntents, InputSource};
+use crate::playfield::PlayfieldMask;
+use crate::theme::Theme;
+use crate::{
+    spawn_snake, Apple, PendingGrowth, Player, SnakeBody, SnakeDied, SnakeHead, START_LENGTH,
+};
+
+pub const PLAYERS: usize = 4;
+const DEFAULT_ROUNDS: u32 = 5;
+const DEFAULT_HUMANS: usize = 2;
+const INTERMISSION_SECONDS: f32 = 3.0;
+const PLAYER_COLORS: [Color; PLAYERS] = [
+    Color::LIME_GREEN,
+    Color::ORANGE,
+    Color::CYAN,
+    Color::FUCHSIA,
+];
+const SEAT_NAMES: [&str; PLAYERS] = ["WASD", "Arrows", "Pad 1", "Pad 2"];
+// a keyboard and a pad on each side, the second snake of a team a shade darker
+const TEAM_NAMES: [&str; 2] = ["Red", "Blue"];
+const TEAM_COLORS: [[Color; 2]; 2] = [
+    [Color::rgb(1.0, 0.35, 0.3), Color::rgb(0.75, 0.15, 0.15)],
+    [Color::rgb(0.3, 0.6, 1.0), Color::rgb(0.15, 0.35, 0.8)],
+];
+
+// present in team battles, where odd and even seats play together
+#[derive(Resource)]
+pub struct Teams;
+
+impl Teams {
+    pub fn team(player: Player) -> usize {
+        Battle::seat(player) % 2
+    }
+
+    // two different snakes on the same side
+    pub fn allies(&self, a: Player, b: Player) -> bool {
+        a != b && Teams::team(a) == Teams::team(b)
+    }
+}
+
+#[derive(Resource)]
+pub struct Battle {
+    rounds: u32,
+    round: u32,
+    humans: usize, // seats taken by people, in seat order, before bots fill the rest
+    points: [u32; PLAYERS],
+    wins: [u32; PLAYERS],
+    bots: [bool; PLAYERS], // this round
+    alive: Vec<Player>,
+    teams: bool,
+    // between rounds, in real time while the game is paused
+    intermission: Option<Timer>,
+    finished: bool,
+}
+
+impl Battle {
+    fn seat(player: Player) -> usize {
+        player.0 as usize - 1
+    }
+
+    fn players() -> impl Iterator<Item = Player> {
+        (1..=PLAYERS as u8).map(Player)
+    }
+
+    fn color(&self, player: Player) -> Color {
+        let seat = Battle::seat(player);
+        if self.teams {
+            TEAM_COLORS[Teams::team(player)][seat / 2]
+        } else {
+            PLAYER_COLORS[seat]
+        }
+    }
+
+    // a team's points are shared by both its snakes
+    fn score(&self, player: Player) -> u32 {
+        Battle::players()
+            .filter(|other| *other == player || (self.teams && Teams.allies(*other, player)))
+            .map(|other| self.points[Battle::seat(other)])
+            .sum()
+    }
+
+    // one snake left, or with teams, one side
+    fn round_over(&self) -> bool {
+        match self.alive.first() {
+            Some(first) if self.teams => self
+                .alive
+                .iter()
+                .all(|player| Teams::team(*player) == Teams::team(*first)),
+            _ => self.alive.len() <= 1,
+        }
+    }
+
+    fn winner_name(&self) -> Option<String> {
+        let winner = self.alive.first()?;
+        Some(if self.teams {
+            format!("{} team", TEAM_NAMES[Teams::team(*winner)])
+        } else {
+            format!("P{}", winner.0)
+        })
+    }
+}
+
+#[derive(Component)]
+struct BattlePanel(Player);
+
+#[derive(Component)]
+struct RoundBanner;
+
+#[derive(Component)]
+struct Podium;
+
+#[derive(Component)]
+struct PodiumText;
+
+pub struct BattlePlugin;
+
+impl Plugin for BattlePlugin {
+    // --battle-rounds <n> and --battle-humans <0-4>, seats past the humans go to bots
+    fn build(&self, app: &mut App) {
+        let args = Args::of(app);
+        let teams = args.has("--teams");
+        if teams {
+            app.insert_resource(Teams);
+        }
+        let rounds = match args.value("--battle-rounds") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                args.problem(format!(
+                    "Invalid round count {value:?}, playing {DEFAULT_ROUNDS}"
+                ));
+                DEFAULT_ROUNDS
+            }),
+            None => DEFAULT_ROUNDS,
+        };
+        let humans = match args.value("--battle-humans") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                args.problem(format!(
+                    "Invalid player count {value:?}, seating {DEFAULT_HUMANS}"
+                ));
+                DEFAULT_HUMANS
+            }),
+            None => DEFAULT_HUMANS,
+        };
+        app.insert_resource(Battle {
+            rounds: rounds.max(1),
+            round: 1,
+            humans: humans.min(PLAYERS),
+            points: [0; PLAYERS],
+            wins: [0; PLAYERS],
+            bots: [false; PLAYERS],
+            alive: Battle::players().collect(),
+            teams,
+            intermission: None,
+            finished: false,
+        })
+        .add_input_source(BattleControls {
+            pads: ActionMap::default(),
+        })
+        .add_systems(Startup, (setup_battle, setup_battle_hud))
+        .add_systems(
+            Update,
+            (
+                eliminate_snakes,
+                next_round.after(eliminate_snakes),
+                tint_battle_snakes,
+                update_battle_hud,
+                exit_podium,
+            ),
+        );
+    }
+}
+
+// the player one snake is already spawned by the main setup
+fn setup_battle(
+    mut commands: Commands,
+    mut battle: ResMut<Battle>,
+    mut bots: ResMut<Bots>,
+    gamepads: Res<Gamepads>,
+    theme: Res<Theme>,
+    playfield_mask: Res<PlayfieldMask>,
+) {
+    for player in Battle::players().skip(1) {
+        spawn_snake(&mut commands, &theme, &playfield_mask, player, START_LENGTH);
+    }
+    seat_bots(&mut battle, &mut bots, &gamepads);
+}
+
+// keyboard seats are always there, a gamepad seat needs its pad connected at the start of a round
+fn seat_bots(battle: &mut Battle, bots: &mut Bots, gamepads: &Gamepads) {
+    let connected = gamepads.iter().count();
+    for player in Battle::players() {
+        let seat = Battle::seat(player);
+        let human = seat < battle.humans && (seat < 2 || seat - 2 < connected);
+        battle.bots[seat] = !human;
+    }
+    bots.bots
+        .retain(|bot| !battle.bots[Battle::seat(bot.player)]);
+    for player in Battle::players().filter(|player| battle.bots[Battle::seat(*player)]) {
+        bots.bots.push(Bot {
+            player,
+            strategy: Box::<Greedy>::default(),
+        });
+    }
+    info!(round = battle.round, bots = ?battle.bots, "Battle seats filled");
+}
+
+fn setup_battle_hud(mut commands: Commands, battle: Res<Battle>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for player in Battle::players() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(6.0)),
+                            min_width: Val::Px(120.0),
+                            ..default()
+                        },
+                        background_color: battle.color(player).with_a(0.35).into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 18.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                            BattlePanel(player),
+                        ));
+                    });
+            }
+        });
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_text_alignment(TextAlignment::Center),
+        RoundBanner,
+    ));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(80.0),
+                    left: Val::Px(12.0),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            Podium,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Battle over - podium",
+                TextStyle {
+                    font_size: 32.0,
+                    color: Color::GOLD,
+                    ..default()
+                },
+            ));
+            parent.spawn((TextBundle::default(), PodiumText));
+            parent.spawn(TextBundle::from_section(
+                "Press Enter to exit",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+// WASD for the first seat and the arrows for the second, the pads for the other two on their
+// default bindings whatever player one rebound
+#[derive(Resource)]
+struct BattleControls {
+    pads: ActionMap,
+}
+
+impl InputSource for BattleControls {
+    type Param = (
+        EventReader<'static, 'static, KeyboardInput>,
+        Res<'static, Gamepads>,
+        Res<'static, Input<GamepadButton>>,
+        Res<'static, Axis<GamepadAxis>>,
+        Res<'static, Battle>,
+    );
+
+    fn poll(
+        &mut self,
+        (keyboard_input_events, gamepads, gamepad_buttons, gamepad_axes, battle): &mut SystemParamItem<
+            '_,
+            '_,
+            Self::Param,
+        >,
+        intents: &mut DirectionIntents,
+    ) {
+        let mut directions: [Option<Direction>; PLAYERS] = [None; PLAYERS];
+        // the last key to go down wins, like the default diagonal policy
+        for event in keyboard_input_events
+            .read()
+            .filter(|event| event.state == ButtonState::Pressed)
+        {
+            let seated = match event.key_code {
+                Some(KeyCode::W) => Some((0, Direction::Up)),
+                Some(KeyCode::S) => Some((0, Direction::Down)),
+                Some(KeyCode::A) => Some((0, Direction::Left)),
+                Some(KeyCode::D) => Some((0, Direction::Right)),
+                Some(KeyCode::Up) => Some((1, Direction::Up)),
+                Some(KeyCode::Down) => Some((1, Direction::Down)),
+                Some(KeyCode::Left) => Some((1, Direction::Left)),
+                Some(KeyCode::Right) => Some((1, Direction::Right)),
+                _ => None,
+            };
+            if let Some((seat, direction)) = seated {
+                directions[seat] = Some(direction);
+            }
+        }
+        for (index, gamepad) in gamepads.iter().take(2).enumerate() {
+            directions[2 + index] =
+                self.pads
+                    .gamepad_direction(gamepad, gamepad_buttons, gamepad_axes);
+        }
+        for player in Battle::players() {
+            let seat = Battle::seat(player);
+            if battle.bots[seat] {
+                continue;
+            }
+            if let Some(direction) = directions[seat] {
+                intents.push(player, direction);
+            }
+        }
+    }
+}
+
+// dead snakes leave the board, and the round is over once one snake or one team is left
+fn eliminate_snakes(
+    mut commands: Commands,
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut battle: ResMut<Battle>,
+    mut time: ResMut<Time<Virtual>>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+) {
+    if battle.intermission.is_some() || battle.finished {
+        snake_died_event.clear();
+        return;
+    }
+    let mut died: Vec<Player> = snake_died_event
+        .read()
+        .map(|event| event.player)
+        .filter(|player| battle.alive.contains(player))
+        .collect();
+    died.sort_by_key(|player| player.0);
+    died.dedup();
+    if died.is_empty() {
+        return;
+    }
+    // snakes going out together share a place, scoring one point for every snake out before them
+    let out_before = (PLAYERS - battle.alive.len()) as u32;
+    for player in &died {
+        battle.points[Battle::seat(*player)] += out_before;
+        battle.alive.retain(|alive| alive != player);
+        info!(player = player.0, round = battle.round, "Snake eliminated");
+    }
+    for (entity, player, snake_head) in &snake_head_query {
+        if died.contains(player) {
+            for segment in snake_head.segments.iter().chain([&entity]) {
+                commands.entity(*segment).despawn();
+            }
+        }
+    }
+    if !battle.round_over() {
+        return;
+    }
+
+    for winner in battle.alive.clone() {
+        let seat = Battle::seat(winner);
+        battle.points[seat] += (PLAYERS - 1) as u32;
+        battle.wins[seat] += 1;
+    }
+    info!(round = battle.round, winner = ?battle.winner_name(), "Round over");
+    time.pause();
+    if battle.round >= battle.rounds {
+        battle.finished = true;
+    } else {
+        battle.intermission = Some(Timer::from_seconds(INTERMISSION_SECONDS, TimerMode::Once));
+    }
+}
+
+// clears the board and puts every snake back on its row
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn next_round(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut battle: ResMut<Battle>,
+    mut bots: ResMut<Bots>,
+    mut pending_growth: ResMut<PendingGrowth>,
+    gamepads: Res<Gamepads>,
+    theme: Res<Theme>,
+    playfield_mask: Res<PlayfieldMask>,
+    board_query: Query<Entity, Or<(With<SnakeHead>, With<SnakeBody>, With<Apple>)>>,
+) {
+    let Some(intermission) = &mut battle.intermission else {
+        return;
+    };
+    if !intermission.tick(real_time.delta()).finished() {
+        return;
+    }
+    battle.intermission = None;
+    battle.round += 1;
+    battle.alive = Battle::players().collect();
+    for entity in &board_query {
+        commands.entity(entity).despawn();
+    }
+    pending_growth.0.clear();
+    for player in Battle::players() {
+        spawn_snake(&mut commands, &theme, &playfield_mask, player, START_LENGTH);
+    }
+    seat_bots(&mut battle, &mut bots, &gamepads);
+    time.unpause();
+    info!(round = battle.round, "Round started");
+}
+
+// alpha is left to blinking and fading
+#[allow(clippy::type_complexity)]
+fn tint_battle_snakes(
+    battle: Res<Battle>,
+    mut sprite_query: Query<(&Player, &mut Sprite), Or<(With<SnakeHead>, With<SnakeBody>)>>,
+) {
+    for (player, mut sprite) in &mut sprite_query {
+        sprite.color = battle.color(*player).with_a(sprite.color.a());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_battle_hud(
+    battle: Res<Battle>,
+    mut panel_query: Query<(&BattlePanel, &mut Text)>,
+    mut banner_query: Query<&mut Text, (With<RoundBanner>, Without<BattlePanel>)>,
+    mut podium_query: Query<&mut Visibility, With<Podium>>,
+    mut podium_text_query: Query<
+        &mut Text,
+        (With<PodiumText>, Without<BattlePanel>, Without<RoundBanner>),
+    >,
+) {
+    if !battle.is_changed() {
+        return;
+    }
+    for (panel, mut text) in &mut panel_query {
+        let seat = Battle::seat(panel.0);
+        let seat_name = if battle.bots[seat] {
+            "Bot"
+        } else {
+            SEAT_NAMES[seat]
+        };
+        let state = if battle.alive.contains(&panel.0) {
+            "in"
+        } else {
+            "out"
+        };
+        let team = if battle.teams {
+            format!(" {}", TEAM_NAMES[Teams::team(panel.0)])
+        } else {
+            String::new()
+        };
+        text.sections[0].value = format!(
+            "P{}{team} {seat_name}\n{} pts, {} wins\n{state}",
+            panel.0 .0,
+            battle.score(panel.0),
+            battle.wins[seat]
+        );
+    }
+    for mut text in &mut banner_query {
+        text.sections[0].value = match (&battle.intermission, battle.winner_name()) {
+            (Some(_), Some(winner)) => format!("Round {} to {winner}", battle.round),
+            (Some(_), None) => format!("Round {} is a draw", battle.round),
+            (None, _) => String::new(),
+        };
+    }
+    if !battle.finished {
+        return;
+    }
+    for mut visibility in &mut podium_query {
+        *visibility = Visibility::Inherited;
+    }
+    // most points first, round wins break ties, a team is listed under its first seat
+    let mut standings: Vec<Player> = Battle::players()
+        .filter(|player| !battle.teams || Battle::seat(*player) < 2)
+        .collect();
+    standings.sort_by_key(|player| {
+        std::cmp::Reverse((battle.score(*player), battle.wins[Battle::seat(*player)]))
+    });
+    for mut text in &mut podium_text_query {
+        text.sections = standings
+            .iter()
+            .enumerate()
+            .map(|(place, player)| {
+                let name = if battle.teams {
+                    format!("{} team", TEAM_NAMES[Teams::team(*player)])
+                } else {
+                    format!("P{}", player.0)
+                };
+                TextSection::new(
+                    format!(
+                        "{}. {name}  {} pts  {} wins\n",
+                        place + 1,
+                        battle.score(*player),
+                        battle.wins[Battle::seat(*player)]
+                    ),
+                    TextStyle {
+                        font_size: 24.0,
+                        color: battle.color(*player),
+                        ..default()
+                    },
+                )
+            })
+            .collect();
+    }
+}
+
+fn exit_podium(
+    battle: Res<Battle>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_exit_event: EventWriter<AppExit>,
+) {
+    if battle.finished && keyboard_input.just_pressed(KeyCode::Return) {
+        app_exit_event.send(AppExit);
+    }
+}
@@ -0,0 +1,88 @@
+// Bitboard
+// A set of cells over a fixed rectangle of the board, a bit per cell in u64 words a row at a
+// time. Looking a cell up is a shift and a mask, and two boards laid over the same rectangle are
+// combined or checked for overlap a word, 64 cells, at a time
+#[derive(Clone, Default)]
+pub struct Bitboard {
+    origin: (i32, i32), // the lowest cell covered
+    width: i32,
+    height: i32,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl Bitboard {
+    pub fn new(origin: (i32, i32), width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        let words_per_row = (width as usize).div_ceil(64);
+        Bitboard {
+            origin,
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height as usize],
+        }
+    }
+
+    // word and bit of a cell, None outside the rectangle
+    fn index(&self, (x, y): (i32, i32)) -> Option<(usize, u64)> {
+        let (column, row) = (x - self.origin.0, y - self.origin.1);
+        if !(0..self.width).contains(&column) || !(0..self.height).contains(&row) {
+            return None;
+        }
+        let word = row as usize * self.words_per_row + column as usize / 64;
+        Some((word, 1 << (column % 64)))
+    }
+
+    // false for a cell outside the rectangle, which isn't kept
+    pub fn insert(&mut self, position: (i32, i32)) -> bool {
+        let Some((word, bit)) = self.index(position) else {
+            return false;
+        };
+        self.words[word] |= bit;
+        true
+    }
+
+    pub fn contains(&self, position: (i32, i32)) -> bool {
+        self.get(position).unwrap_or(false)
+    }
+
+    // None outside the rectangle, where the board can't say
+    pub fn get(&self, position: (i32, i32)) -> Option<bool> {
+        self.index(position)
+            .map(|(word, bit)| self.words[word] & bit != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    // covering a new rectangle, emptied
+    pub fn reset(&mut self, origin: (i32, i32), width: i32, height: i32) {
+        if (origin, width, height) == (self.origin, self.width, self.height) {
+            self.clear();
+        } else {
+            *self = Bitboard::new(origin, width, height);
+        }
+    }
+
+    fn same_rectangle(&self, other: &Bitboard) -> bool {
+        (self.origin, self.width, self.height) == (other.origin, other.width, other.height)
+    }
+
+    // both boards over the same rectangle, as Occupancy lays them out
+    pub fn union_with(&mut self, other: &Bitboard) {
+        debug_assert!(self.same_rectangle(other));
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word |= other_word;
+        }
+    }
+
+    pub fn intersects(&self, other: &Bitboard) -> bool {
+        debug_assert!(self.same_rectangle(other));
+        self.words
+            .iter()
+            .zip(&other.words)
+            .any(|(word, other_word)| word & other_word != 0)
+    }
+}
@@ -0,0 +1,119 @@
+// Board dump
+// F10 writes the whole board to bug-reports/board-<time>.txt, an ASCII grid for reading followed
+// by the exact state as JSON, and copies it to the clipboard so a suspicious collision can be
+// pasted straight into a bug report
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::game_state::{render_ascii, AppleState, GameState, SnakeState};
+use crate::items::PowerUp;
+use crate::playfield::PlayfieldMask;
+use crate::score::Score;
+use crate::seed::RunSeed;
+use crate::storage::StoragePaths;
+use crate::{Apple, Player, SnakeBody, SnakeHead};
+
+const DUMP_DIR: &str = "bug-reports";
+
+pub struct BoardDumpPlugin;
+
+impl Plugin for BoardDumpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, dump_board);
+    }
+}
+
+// the clipboard is kept open, on some platforms its contents go away with it
+#[allow(clippy::too_many_arguments)]
+fn dump_board(
+    keyboard_input: Res<Input<KeyCode>>,
+    seed: Res<RunSeed>,
+    score: Res<Score>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    power_up_query: Query<&PowerUp>,
+    mut clipboard: Local<Option<arboard::Clipboard>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F10) {
+        return;
+    }
+    let mut snakes: Vec<SnakeState> = snake_head_query
+        .iter()
+        .map(|(player, snake_head)| SnakeState {
+            player: player.0,
+            direction: snake_head.direction,
+            potential_direction: snake_head.potential_direction,
+            cells: std::iter::once(snake_head.position)
+                .chain(
+                    snake_head
+                        .segments
+                        .iter()
+                        .filter_map(|segment| snake_body_query.get(*segment).ok())
+                        .map(|snake_body| snake_body.position),
+                )
+                .collect(),
+            path: snake_head.path.clone(),
+        })
+        .collect();
+    snakes.sort_by_key(|snake| snake.player);
+    let mut walls: Vec<(i32, i32)> = playfield_mask.walls().collect();
+    walls.sort();
+    let state = GameState {
+        seed: seed.0,
+        score: score.0,
+        half_extents: playfield_mask.half_extents(),
+        scroll: playfield_mask.scroll(),
+        wrap: playfield_mask.wrap,
+        walls,
+        snakes,
+        apples: apple_query
+            .iter()
+            .map(|apple| AppleState {
+                footprint: apple.footprint.clone(),
+                points: apple.points,
+                growth: apple.growth,
+            })
+            .collect(),
+        power_ups: power_up_query
+            .iter()
+            .map(|power_up| power_up.position)
+            .collect(),
+    };
+    let json = match serde_json::to_string_pretty(&state) {
+        Ok(json) => json,
+        Err(error) => {
+            warn!("Could not serialize the board: {error}");
+            return;
+        }
+    };
+    let dump = format!("{}\n{json}\n", render_ascii(&state));
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let file = format!("{DUMP_DIR}/board-{seconds}.txt");
+    match StoragePaths::get().write(&file, &dump) {
+        Ok(()) => info!(
+            "Board dumped to {}",
+            StoragePaths::get().path(&file).display()
+        ),
+        Err(error) => warn!("Could not write {file}: {error}"),
+    }
+    if clipboard.is_none() {
+        match arboard::Clipboard::new() {
+            Ok(opened) => *clipboard = Some(opened),
+            Err(error) => {
+                warn!("Could not open the clipboard: {error}");
+                return;
+            }
+        }
+    }
+    if let Some(clipboard) = clipboard.as_mut() {
+        if let Err(error) = clipboard.set_text(dump) {
+            warn!("Could not copy the board: {error}");
+        }
+    }
+}
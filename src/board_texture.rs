@@ -0,0 +1,177 @@
+// Board texture
+// Renders the playfield into an offscreen image as well, for a host app to put on a sprite, a UI
+// node or a window of its own. A camera of its own draws into it, fitted to the board at whatever
+// resolution was asked for, and the HUD is left out. --board-window [WxH] shows it in a second
+// window, 480x480 by default
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::view::RenderLayers;
+use bevy::window::WindowRef;
+
+use crate::args::Args;
+use crate::camera::board_size;
+use crate::playfield::PlayfieldMask;
+
+const BOARD_WINDOW_SIZE: UVec2 = UVec2::new(480, 480);
+const BOARD_WINDOW_LAYER: u8 = 2; // the texture's sprite stays out of the other cameras
+
+// the image the board is drawn into, for the host to show
+#[derive(Resource)]
+pub struct BoardTexture(pub Handle<Image>);
+
+// in pixels
+#[derive(Resource)]
+struct BoardTextureResolution(UVec2);
+
+#[derive(Component)]
+struct BoardTextureCamera;
+
+// --board-window [WxH]
+pub fn resolution_from_args(args: &Args) -> Option<UVec2> {
+    let Some(value) = args.values("--board-window")?.first().copied() else {
+        return Some(BOARD_WINDOW_SIZE);
+    };
+    let parsed = value
+        .split_once('x')
+        .and_then(|(width, height)| Some(UVec2::new(width.parse().ok()?, height.parse().ok()?)));
+    if parsed.is_none() {
+        args.problem(format!(
+            "Invalid --board-window size {value:?}, expected WxH"
+        ));
+    }
+    Some(parsed.unwrap_or(BOARD_WINDOW_SIZE))
+}
+
+pub struct BoardTexturePlugin {
+    pub resolution: UVec2,
+}
+
+impl Plugin for BoardTexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BoardTextureResolution(self.resolution.max(UVec2::ONE)))
+            .add_systems(Startup, setup_board_texture)
+            .add_systems(Update, center_texture_camera);
+    }
+}
+
+// the texture on a second window, needs the BoardTexturePlugin too
+pub struct BoardWindowPlugin;
+
+impl Plugin for BoardWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, open_board_window.after(setup_board_texture));
+    }
+}
+
+fn setup_board_texture(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    resolution: Res<BoardTextureResolution>,
+) {
+    let handle = images.add(render_target_image(resolution.0));
+
+    let board = board_size();
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(handle.clone()),
+                // drawn before the cameras that show it
+                order: -1,
+                ..default()
+            },
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::AutoMin {
+                    min_width: board.x,
+                    min_height: board.y,
+                },
+                ..default()
+            },
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        BoardTextureCamera,
+    ));
+    commands.insert_resource(BoardTexture(handle));
+}
+
+// blank to start with, cameras can draw into it and materials sample it
+pub fn render_target_image(resolution: UVec2) -> Image {
+    let size = Extent3d {
+        width: resolution.x,
+        height: resolution.y,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    // zeroed, so it shows as transparent until the first frame is drawn
+    image.resize(size);
+    image
+}
+
+// the endless corridor moves the board along under the camera
+fn center_texture_camera(
+    playfield_mask: Res<PlayfieldMask>,
+    mut camera_query: Query<&mut Transform, With<BoardTextureCamera>>,
+) {
+    if !playfield_mask.is_changed() {
+        return;
+    }
+    let center = playfield_mask.world_offset();
+    for mut transform in &mut camera_query {
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+    }
+}
+
+fn open_board_window(
+    mut commands: Commands,
+    board_texture: Res<BoardTexture>,
+    resolution: Res<BoardTextureResolution>,
+) {
+    let size = resolution.0.as_vec2();
+    let window = commands
+        .spawn(Window {
+            title: "Snake board".to_string(),
+            resolution: size.into(),
+            ..default()
+        })
+        .id();
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Entity(window)),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(BOARD_WINDOW_LAYER),
+        UiCameraConfig { show_ui: false },
+    ));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..default()
+            },
+            texture: board_texture.0.clone(),
+            ..default()
+        },
+        RenderLayers::layer(BOARD_WINDOW_LAYER),
+    ));
+}
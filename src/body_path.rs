@@ -0,0 +1,293 @@
+// Body path
+// A snake's body kept as the runs it makes from the neck back to the tail, a direction and a
+// length each, instead of a cell or an entity per segment. Memory grows with the number of turns
+// rather than the length, and cells are worked out from the runs only when asked for. The
+// headless boards use it, the game keeps a sprite per segment. Cells must follow on from each
+// other, so it can't hold a body that wraps around the board
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::direction::Direction;
+
+// how a headless board keeps a body, front first, the way the determinism check compares them
+pub trait BodyCells: Default {
+    fn from_cells(cells: impl IntoIterator<Item = (i32, i32)>) -> Self;
+    fn len(&self) -> usize;
+    fn push_front(&mut self, cell: (i32, i32));
+    fn push_back(&mut self, cell: (i32, i32));
+    fn pop_back(&mut self) -> Option<(i32, i32)>;
+    fn contains(&self, cell: (i32, i32)) -> bool;
+    fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_;
+}
+
+// a cell per segment, as the game keeps an entity per segment
+impl BodyCells for VecDeque<(i32, i32)> {
+    fn from_cells(cells: impl IntoIterator<Item = (i32, i32)>) -> Self {
+        cells.into_iter().collect()
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn push_front(&mut self, cell: (i32, i32)) {
+        VecDeque::push_front(self, cell);
+    }
+
+    fn push_back(&mut self, cell: (i32, i32)) {
+        VecDeque::push_back(self, cell);
+    }
+
+    fn pop_back(&mut self) -> Option<(i32, i32)> {
+        VecDeque::pop_back(self)
+    }
+
+    fn contains(&self, cell: (i32, i32)) -> bool {
+        VecDeque::contains(self, &cell)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        VecDeque::iter(self).copied()
+    }
+}
+
+#[derive(Component, Clone, Default, Debug)]
+pub struct BodyPath {
+    front: Option<(i32, i32)>, // behind the head, None for no body at all
+    back: (i32, i32),
+    runs: VecDeque<(Direction, u32)>, // walking from the front toward the back
+    len: usize,
+}
+
+// the way from one cell to the next one over
+fn direction_between(from: (i32, i32), to: (i32, i32)) -> Direction {
+    let direction = Direction::ALL
+        .into_iter()
+        .find(|direction| direction.step(from) == to);
+    debug_assert!(
+        direction.is_some(),
+        "{from:?} and {to:?} are not neighbours"
+    );
+    direction.unwrap_or(Direction::Right)
+}
+
+impl BodyCells for BodyPath {
+    // front first
+    fn from_cells(cells: impl IntoIterator<Item = (i32, i32)>) -> Self {
+        let mut body_path = BodyPath::default();
+        for cell in cells {
+            body_path.push_back(cell);
+        }
+        body_path
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    // the new neck, next to the old one
+    fn push_front(&mut self, cell: (i32, i32)) {
+        let Some(front) = self.front.replace(cell) else {
+            self.back = cell;
+            self.len = 1;
+            return;
+        };
+        let direction = direction_between(cell, front);
+        match self.runs.front_mut() {
+            Some((run_direction, length)) if *run_direction == direction => *length += 1,
+            _ => self.runs.push_front((direction, 1)),
+        }
+        self.len += 1;
+    }
+
+    // grows the tail by a cell next to it
+    fn push_back(&mut self, cell: (i32, i32)) {
+        if self.front.is_none() {
+            self.push_front(cell);
+            return;
+        }
+        let direction = direction_between(self.back, cell);
+        match self.runs.back_mut() {
+            Some((run_direction, length)) if *run_direction == direction => *length += 1,
+            _ => self.runs.push_back((direction, 1)),
+        }
+        self.back = cell;
+        self.len += 1;
+    }
+
+    fn pop_back(&mut self) -> Option<(i32, i32)> {
+        self.front?;
+        let tail = self.back;
+        self.len -= 1;
+        match self.runs.back_mut() {
+            None => self.front = None,
+            Some((direction, length)) => {
+                self.back = direction.opposite().step(tail);
+                *length -= 1;
+                if *length == 0 {
+                    self.runs.pop_back();
+                }
+            }
+        }
+        Some(tail)
+    }
+
+    // a run at a time, without listing the cells
+    fn contains(&self, cell: (i32, i32)) -> bool {
+        let Some(mut from) = self.front else {
+            return false;
+        };
+        if from == cell {
+            return true;
+        }
+        for (direction, length) in &self.runs {
+            let (dx, dy) = direction.delta();
+            let (ox, oy) = (cell.0 - from.0, cell.1 - from.1);
+            // how far along the run, and how far off to the side of it
+            let (along, aside) = if dx != 0 {
+                (ox * dx, oy)
+            } else {
+                (oy * dy, ox)
+            };
+            if aside == 0 && (1..=*length as i32).contains(&along) {
+                return true;
+            }
+            from = (from.0 + dx * *length as i32, from.1 + dy * *length as i32);
+        }
+        false
+    }
+
+    // front first
+    fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let front = self.front;
+        let runs = self
+            .runs
+            .iter()
+            .flat_map(|(direction, length)| std::iter::repeat_n(*direction, *length as usize));
+        front.into_iter().chain(runs.scan(front, |cell, direction| {
+            let next = direction.step((*cell)?);
+            *cell = Some(next);
+            Some(next)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same moves made on both, which must agree after every one
+    struct Both {
+        path: BodyPath,
+        cells: VecDeque<(i32, i32)>,
+    }
+
+    impl Both {
+        fn new(cells: &[(i32, i32)]) -> Self {
+            let both = Both {
+                path: BodyPath::from_cells(cells.iter().copied()),
+                cells: VecDeque::from_cells(cells.iter().copied()),
+            };
+            both.check();
+            both
+        }
+
+        fn check(&self) {
+            assert_eq!(self.path.len(), BodyCells::len(&self.cells));
+            assert_eq!(
+                self.path.iter().collect::<Vec<_>>(),
+                BodyCells::iter(&self.cells).collect::<Vec<_>>()
+            );
+            for x in -8..=8 {
+                for y in -8..=8 {
+                    assert_eq!(
+                        self.path.contains((x, y)),
+                        BodyCells::contains(&self.cells, (x, y)),
+                        "contains({x}, {y})"
+                    );
+                }
+            }
+        }
+
+        // a step of the snake, the head leaving its cell for the neck
+        fn step(&mut self, direction: Direction, grow: bool) {
+            let neck = BodyCells::iter(&self.cells).next().unwrap();
+            let cell = direction.step(neck);
+            self.path.push_front(cell);
+            BodyCells::push_front(&mut self.cells, cell);
+            if !grow {
+                assert_eq!(self.path.pop_back(), BodyCells::pop_back(&mut self.cells));
+            }
+            self.check();
+        }
+
+        fn pop_back(&mut self) {
+            assert_eq!(self.path.pop_back(), BodyCells::pop_back(&mut self.cells));
+            self.check();
+        }
+    }
+
+    #[test]
+    fn follows_turns() {
+        let mut both = Both::new(&[(0, 0), (-1, 0), (-2, 0), (-3, 0)]);
+        let turns = [
+            Direction::Up,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Down,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+            Direction::Right,
+            Direction::Right,
+        ];
+        for direction in turns {
+            both.step(direction, false);
+        }
+    }
+
+    #[test]
+    fn grows_at_both_ends() {
+        let mut both = Both::new(&[(0, 0)]);
+        for direction in [
+            Direction::Right,
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+        ] {
+            both.step(direction, true);
+        }
+        for cell in [(0, -1), (0, -2), (-1, -2)] {
+            both.path.push_back(cell);
+            BodyCells::push_back(&mut both.cells, cell);
+            both.check();
+        }
+        assert_eq!(both.path.len(), 8);
+    }
+
+    #[test]
+    fn shrinks_to_empty_and_grows_again() {
+        let mut both = Both::new(&[(2, 2), (2, 3), (1, 3), (1, 4)]);
+        for _ in 0..4 {
+            both.pop_back();
+        }
+        assert_eq!(both.path.len(), 0);
+        assert!(both.path.iter().next().is_none());
+        both.pop_back();
+
+        both.path.push_back((5, 5));
+        BodyCells::push_back(&mut both.cells, (5, 5));
+        both.check();
+        both.step(Direction::Down, true);
+        both.step(Direction::Left, false);
+    }
+
+    #[test]
+    fn starts_empty() {
+        let mut both = Both::new(&[]);
+        both.pop_back();
+        assert!(!both.path.contains((0, 0)));
+    }
+}
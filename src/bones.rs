@@ -0,0 +1,140 @@
+// Bones
+// Versus leftovers: a snake that dies while another is still going drops its body as bones, food
+// worth a point and a segment each to whoever gets there first
+use bevy::prelude::*;
+
+use crate::lives::Lives;
+use crate::{
+    game_over, grow_snake_body, move_snake, AppleEaten, Player, SnakeBody, SnakeDied, SnakeHead,
+    SnakeSystemSet, PIXEL_UNIT_SIZE,
+};
+
+const BONE_COLOR: Color = Color::rgb(0.95, 0.92, 0.8);
+const BONE_SIZE: f32 = 0.5; // of a cell
+const BONE_POINTS: u32 = 1;
+const BONE_GROWTH: u32 = 1;
+
+#[derive(Component)]
+pub struct Bone {
+    pub position: (i32, i32),
+}
+
+pub struct BonesPlugin;
+
+impl Plugin for BonesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, drop_bones.before(game_over))
+            .add_systems(
+                FixedUpdate,
+                eat_bones
+                    .in_set(SnakeSystemSet::Movement)
+                    .after(move_snake)
+                    .before(grow_snake_body),
+            );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn drop_bones(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut snake_died_event: EventReader<SnakeDied>,
+    lives: Option<Res<Lives>>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+) {
+    // a snake left dead on the game over screen keeps reporting it
+    if time.is_paused() {
+        snake_died_event.clear();
+        return;
+    }
+    // several collisions can report the same death in one frame
+    let mut died: Vec<Player> = snake_died_event.read().map(|event| event.player).collect();
+    died.sort_by_key(|player| player.0);
+    died.dedup();
+    if !snake_head_query
+        .iter()
+        .any(|(_, player, _)| !died.contains(player))
+    {
+        return;
+    }
+
+    for (entity, player, snake_head) in &snake_head_query {
+        if !died.contains(player) {
+            continue;
+        }
+        // without lives player one dying ends the run, there is nobody left to eat them
+        if lives.is_none() && *player == Player::ONE {
+            continue;
+        }
+        info!(
+            player = player.0,
+            bones = snake_head.segments.len(),
+            "Bones dropped"
+        );
+        for segment in &snake_head.segments {
+            let Ok(snake_body) = snake_body_query.get(*segment) else {
+                continue;
+            };
+            let bone = Bone {
+                position: snake_body.position,
+            };
+            if lives.is_some() {
+                // the respawn clears the old body, so the bones are copies of it
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: bone_sprite(),
+                        transform: Transform::from_xyz(
+                            bone.position.0 as f32 * PIXEL_UNIT_SIZE,
+                            bone.position.1 as f32 * PIXEL_UNIT_SIZE,
+                            0.0,
+                        ),
+                        ..default()
+                    },
+                    bone,
+                ));
+            } else {
+                // the snake is out for good, its segments stay where they are as bones
+                commands
+                    .entity(*segment)
+                    .remove::<(SnakeBody, Player)>()
+                    .insert((bone_sprite(), bone));
+            }
+        }
+        if lives.is_none() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn bone_sprite() -> Sprite {
+    Sprite {
+        color: BONE_COLOR,
+        custom_size: Some(Vec2::splat(PIXEL_UNIT_SIZE * BONE_SIZE)),
+        ..default()
+    }
+}
+
+fn eat_bones(
+    mut commands: Commands,
+    mut apple_eaten_event: EventWriter<AppleEaten>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    bone_query: Query<(Entity, &Bone)>,
+) {
+    for (entity, bone) in &bone_query {
+        let Some((player, _)) = snake_head_query
+            .iter()
+            .find(|(_, snake_head)| snake_head.path.contains(&bone.position))
+        else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        apple_eaten_event.send(AppleEaten {
+            player: *player,
+            growth: BONE_GROWTH,
+            points: BONE_POINTS,
+            effects: Vec::new(),
+            position: bone.position,
+        });
+    }
+}
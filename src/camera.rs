@@ -0,0 +1,140 @@
+// Camera
+// Follows the snake when the board does not fit in the window, and after game over becomes a
+// free photo mode camera panned by dragging and zoomed with the wheel
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::playfield::PlayfieldMask;
+use crate::{GameOver, Player, SnakeHead, PIXEL_UNIT_SIZE, PLAYFIELD};
+
+const FOLLOW_SPEED: f32 = 6.0; // fraction of the remaining distance covered per second
+const ZOOM_STEP: f32 = 0.1; // per wheel line
+const PIXELS_PER_LINE: f32 = 100.0; // for touchpads that scroll in pixels
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+
+#[derive(Component)]
+pub struct MainCamera;
+
+// present once the run is over and the camera is free
+#[derive(Resource)]
+struct PhotoMode;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                follow_snake.run_if(not(resource_exists::<PhotoMode>())),
+                enter_photo_mode,
+                photo_camera.run_if(resource_exists::<PhotoMode>()),
+            ),
+        );
+    }
+}
+
+pub fn board_size() -> Vec2 {
+    Vec2::new(
+        PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE,
+        PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE,
+    )
+}
+
+pub fn board_exceeds_window(window: &Window) -> bool {
+    let board_size = board_size();
+    board_size.x > window.width() || board_size.y > window.height()
+}
+
+#[allow(clippy::type_complexity)]
+fn follow_snake(
+    time: Res<Time>,
+    playfield_mask: Res<PlayfieldMask>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    snake_head_query: Query<(&Player, &Transform), (With<SnakeHead>, Without<MainCamera>)>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let (Ok(window), Ok(mut camera_transform)) =
+        (window_query.get_single(), camera_query.get_single_mut())
+    else {
+        return;
+    };
+    let center = playfield_mask.world_offset();
+    let head = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+        .map(|(_, transform)| transform.translation.truncate())
+        .unwrap_or(center);
+
+    // stop at the board edges, and stay centered along axes that fit
+    let slack = ((board_size() - Vec2::new(window.width(), window.height())) / 2.0).max(Vec2::ZERO);
+    let target = center + (head - center).clamp(-slack, slack);
+    let current = camera_transform.translation.truncate();
+    let next = current.lerp(target, (FOLLOW_SPEED * time.delta_seconds()).min(1.0));
+    camera_transform.translation.x = next.x;
+    camera_transform.translation.y = next.y;
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    mut game_over_event: EventReader<GameOver>,
+    photo_mode: Option<Res<PhotoMode>>,
+) {
+    if game_over_event.read().count() == 0 || photo_mode.is_some() {
+        return;
+    }
+    info!("Photo mode");
+    commands.insert_resource(PhotoMode);
+    commands.spawn(
+        TextBundle::from_section(
+            "Drag to pan, scroll to zoom",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        // bottom center, the corners are taken by the seed, hunger and objectives
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            justify_self: JustifySelf::Center,
+            ..default()
+        }),
+    );
+}
+
+// driven by mouse input only, game time is paused by now
+fn photo_camera(
+    mut pressed_on_ui: Local<bool>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion_event: EventReader<MouseMotion>,
+    mut mouse_wheel_event: EventReader<MouseWheel>,
+    interaction_query: Query<&Interaction>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let dragged: Vec2 = mouse_motion_event.read().map(|event| event.delta).sum();
+    // a press on a button or other clickable ui is its own, not the start of a drag
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        *pressed_on_ui = interaction_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+    }
+    if mouse_buttons.pressed(MouseButton::Left) && !*pressed_on_ui {
+        // screen y points down, world y up
+        camera_transform.translation.x -= dragged.x * projection.scale;
+        camera_transform.translation.y += dragged.y * projection.scale;
+    }
+    for event in mouse_wheel_event.read() {
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        };
+        projection.scale = (projection.scale * (1.0 - ZOOM_STEP * lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
@@ -0,0 +1,253 @@
+// Chat
+// Text chat for network games: Enter opens the prompt, Enter again sends, and messages show for a
+// few seconds in the corner. Everything shown goes through a replaceable filter, and /mute hides
+// what the others say. The network session carries the messages, see lobby.rs
+use bevy::prelude::*;
+
+use crate::menu::MenuFocus;
+use crate::widgets::{spawn_text_input, TextInput, TextSubmitted};
+
+const MESSAGE_SECONDS: f32 = 8.0;
+const MAX_MESSAGE_LEN: usize = 80;
+const MAX_SHOWN: usize = 6;
+const FILTERED_WORDS: [&str; 4] = ["damn", "crap", "hell", "idiot"];
+const MUTE_COMMAND: &str = "/mute";
+
+// typed here, for the network session to send on
+#[derive(Event)]
+pub struct ChatSent(pub String);
+
+// said by someone, seat 0 being the game itself
+#[derive(Event)]
+pub struct ChatReceived {
+    pub from: u8,
+    pub text: String,
+}
+
+// what every shown message goes through, replace it to change how words are filtered
+#[derive(Resource)]
+pub struct ChatFilter(pub Box<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Default for ChatFilter {
+    // listed words are starred out whatever their case
+    fn default() -> Self {
+        ChatFilter(Box::new(|text| {
+            text.split(' ')
+                .map(|word| {
+                    let bare: String = word
+                        .chars()
+                        .filter(|char| char.is_alphanumeric())
+                        .collect::<String>()
+                        .to_lowercase();
+                    if FILTERED_WORDS.contains(&bare.as_str()) {
+                        "*".repeat(word.chars().count())
+                    } else {
+                        word.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Chat {
+    pub open: bool,
+    pub muted: bool,
+    messages: Vec<(String, Timer)>,
+}
+
+#[derive(Component)]
+pub struct ChatPrompt;
+
+#[derive(Component)]
+pub struct ChatInput;
+
+#[derive(Component)]
+struct ChatLog;
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Chat>()
+            .init_resource::<ChatFilter>()
+            .add_event::<ChatSent>()
+            .add_event::<ChatReceived>()
+            .add_systems(Startup, setup_chat)
+            .add_systems(
+                Update,
+                (open_chat, send_chat, receive_chat, show_chat).chain(),
+            );
+    }
+}
+
+// for gameplay input that shouldn't fire while a message is being typed
+pub fn chat_closed(chat: Option<Res<Chat>>) -> bool {
+    chat.is_none_or(|chat| !chat.open)
+}
+
+fn setup_chat(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(48.0),
+                left: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((TextBundle::default(), ChatLog));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(4.0)),
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    ChatPrompt,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Say:",
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::GOLD,
+                            ..default()
+                        },
+                    ));
+                    spawn_text_input(parent, MAX_MESSAGE_LEN, ChatInput);
+                });
+        });
+}
+
+// Enter opens the prompt unless a menu or another text field has it, screens with a menu open it
+// through a button instead
+pub fn open_chat(
+    keyboard_input: Res<Input<KeyCode>>,
+    menu_focus: Res<MenuFocus>,
+    mut chat: ResMut<Chat>,
+    text_input_query: Query<&InheritedVisibility, (With<TextInput>, Without<ChatInput>)>,
+    mut prompt_query: Query<&mut Visibility, With<ChatPrompt>>,
+    mut input_query: Query<&mut TextInput, With<ChatInput>>,
+) {
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    if !chat.open
+        && !typing
+        && menu_focus.0.is_none()
+        && keyboard_input.just_pressed(KeyCode::Return)
+    {
+        chat.open = true;
+    }
+    if !chat.is_changed() {
+        return;
+    }
+    let shown = prompt_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    if chat.open && !shown {
+        for mut input in &mut input_query {
+            input.value.clear();
+        }
+    }
+    for mut visibility in &mut prompt_query {
+        *visibility = if chat.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn send_chat(
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    mut chat_sent_event: EventWriter<ChatSent>,
+    mut chat_received_event: EventWriter<ChatReceived>,
+    mut chat: ResMut<Chat>,
+    input_query: Query<(), With<ChatInput>>,
+) {
+    let Some(text) = text_submitted_event
+        .read()
+        .find(|event| input_query.contains(event.input))
+        .map(|event| event.value.trim().to_string())
+    else {
+        return;
+    };
+    chat.open = false;
+    if text == MUTE_COMMAND {
+        chat.muted = !chat.muted;
+        info!(muted = chat.muted, "Chat muted");
+        let notice = if chat.muted {
+            "Chat muted, /mute again to hear the others"
+        } else {
+            "Chat unmuted"
+        };
+        chat_received_event.send(ChatReceived {
+            from: 0,
+            text: notice.to_string(),
+        });
+    } else if !text.is_empty() {
+        chat_sent_event.send(ChatSent(text));
+    }
+}
+
+// anything said, by us or the others, is shown until it times out
+fn receive_chat(
+    time: Res<Time<Real>>,
+    mut chat_received_event: EventReader<ChatReceived>,
+    mut chat: ResMut<Chat>,
+    filter: Res<ChatFilter>,
+) {
+    for event in chat_received_event.read() {
+        let line = match event.from {
+            0 => event.text.clone(),
+            from => format!("P{from}: {}", (filter.0)(&event.text)),
+        };
+        chat.messages
+            .push((line, Timer::from_seconds(MESSAGE_SECONDS, TimerMode::Once)));
+    }
+    if chat.messages.is_empty() {
+        return;
+    }
+    for (_, timer) in &mut chat.messages {
+        timer.tick(time.delta());
+    }
+    chat.messages.retain(|(_, timer)| !timer.finished());
+    let excess = chat.messages.len().saturating_sub(MAX_SHOWN);
+    chat.messages.drain(..excess);
+}
+
+fn show_chat(chat: Res<Chat>, mut log_query: Query<&mut Text, With<ChatLog>>) {
+    if !chat.is_changed() {
+        return;
+    }
+    for mut text in &mut log_query {
+        text.sections = chat
+            .messages
+            .iter()
+            .map(|(line, timer)| {
+                // fading out over the last second
+                let alpha = timer.remaining_secs().min(1.0);
+                TextSection::new(
+                    format!("{line}\n"),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE.with_a(alpha),
+                        ..default()
+                    },
+                )
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,381 @@
+// Cheats
+// The tilde key opens a console for cheats while playing, pausing the game until it closes:
+// spawn_apple [n] drops the next n apples just ahead of player one, grow [n] adds segments,
+// speed <n>x runs the game n times as fast and noclip lets the snake through walls and bodies,
+// held only at the edge of the board. Each command goes out as a CheatEntered event for the game's
+// own systems to act on. Typing the Konami code during play earns the rainbow skin. Achievements
+// stop counting once a cheat is used, and a ranked run has no cheats at all
+use std::collections::VecDeque;
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::achievements::{Achievement, AchievementEarned, Achievements};
+use crate::menu::MenuFocus;
+use crate::playfield::PlayfieldMask;
+use crate::widgets::{spawn_text_input, TextInput, TextSubmitted};
+use crate::{Apple, PendingGrowth, Player, SnakeBody, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const MAX_COMMAND_LEN: usize = 40;
+const MAX_SPEED: f64 = 8.0;
+const APPLE_DISTANCE: i32 = 2; // cells ahead of the head
+const KONAMI_CODE: [KeyCode; 10] = [
+    KeyCode::Up,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::B,
+    KeyCode::A,
+];
+const HELP: &str = "spawn_apple [n], grow [n], speed <n>x, noclip";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CheatCommand {
+    SpawnApple(u32),
+    Grow(u32),
+    Speed(f64),
+    Noclip,
+}
+
+impl CheatCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("{name} takes one argument at most"));
+        }
+        let count = |argument: Option<&str>| match argument {
+            None => Ok(1),
+            Some(count) => count
+                .parse::<u32>()
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| format!("{name} expects a count, got {count}")),
+        };
+        match name {
+            "spawn_apple" => count(argument).map(CheatCommand::SpawnApple),
+            "grow" => count(argument).map(CheatCommand::Grow),
+            "speed" => argument
+                .map(|factor| factor.trim_end_matches('x'))
+                .and_then(|factor| factor.parse::<f64>().ok())
+                .filter(|factor| *factor > 0.0 && *factor <= MAX_SPEED)
+                .map(CheatCommand::Speed)
+                .ok_or_else(|| format!("speed expects a factor up to {MAX_SPEED}x, like 2x")),
+            "noclip" if argument.is_none() => Ok(CheatCommand::Noclip),
+            "noclip" => Err("noclip takes no argument".to_string()),
+            other => Err(format!("Unknown cheat {other:?}, try {HELP}")),
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct CheatEntered(pub CheatCommand);
+
+#[derive(Resource)]
+pub struct Cheats {
+    pub used: bool,
+    pub speed: f64, // multiplies the tick rate
+    apples_ahead: u32,
+}
+
+impl Default for Cheats {
+    fn default() -> Self {
+        Cheats {
+            used: false,
+            speed: 1.0,
+            apples_ahead: 0,
+        }
+    }
+}
+
+// on player one's head while noclip is on
+#[derive(Component)]
+pub struct Noclip;
+
+#[derive(Resource, Default)]
+pub struct CheatConsole {
+    pub open: bool,
+    paused_game: bool,
+}
+
+#[derive(Component)]
+struct ConsolePrompt;
+
+#[derive(Component)]
+struct ConsoleInput;
+
+#[derive(Component)]
+struct ConsoleReply;
+
+pub struct CheatsPlugin;
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Cheats>()
+            .init_resource::<CheatConsole>()
+            .add_event::<CheatEntered>()
+            .add_systems(Startup, setup_console)
+            .add_systems(
+                Update,
+                (
+                    (toggle_console, submit_cheat, apply_cheats).chain(),
+                    enter_konami_code,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                place_apples_ahead
+                    .in_set(SnakeSystemSet::Spawning)
+                    .after(crate::spawn_apple),
+            );
+    }
+}
+
+// for the achievements, which a cheat run doesn't earn
+pub fn no_cheats_used(cheats: Option<Res<Cheats>>) -> bool {
+    cheats.is_none_or(|cheats| !cheats.used)
+}
+
+fn setup_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(12.0),
+                    left: Val::Px(12.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+            ConsolePrompt,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        ">",
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::LIME_GREEN,
+                            ..default()
+                        },
+                    ));
+                    spawn_text_input(parent, MAX_COMMAND_LEN, ConsoleInput);
+                });
+            parent.spawn((
+                TextBundle::from_section(
+                    HELP,
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+                ConsoleReply,
+            ));
+        });
+}
+
+// tilde opens it unless a menu or another text field has the keys, tilde or Escape closes it
+#[allow(clippy::too_many_arguments)]
+fn toggle_console(
+    keyboard_input: Res<Input<KeyCode>>,
+    menu_focus: Res<MenuFocus>,
+    mut console: ResMut<CheatConsole>,
+    mut time: ResMut<Time<Virtual>>,
+    text_input_query: Query<&InheritedVisibility, (With<TextInput>, Without<ConsoleInput>)>,
+    mut prompt_query: Query<&mut Visibility, With<ConsolePrompt>>,
+    mut input_query: Query<&mut TextInput, With<ConsoleInput>>,
+) {
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    let open = if console.open {
+        !keyboard_input.any_just_pressed([KeyCode::Grave, KeyCode::Escape])
+    } else {
+        !typing && menu_focus.0.is_none() && keyboard_input.just_pressed(KeyCode::Grave)
+    };
+    if open == console.open {
+        return;
+    }
+    console.open = open;
+    info!(open, "Cheat console toggled");
+    // leave time alone if something else paused it
+    if open {
+        for mut input in &mut input_query {
+            input.value.clear();
+        }
+        if !time.is_paused() {
+            time.pause();
+            console.paused_game = true;
+        }
+    } else if std::mem::take(&mut console.paused_game) {
+        time.unpause();
+    }
+    for mut visibility in &mut prompt_query {
+        *visibility = if open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// the console stays open for the next command, the reply line says how the last one went
+fn submit_cheat(
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    mut cheat_entered_event: EventWriter<CheatEntered>,
+    mut input_query: Query<(Entity, &mut TextInput), With<ConsoleInput>>,
+    mut reply_query: Query<&mut Text, With<ConsoleReply>>,
+) {
+    let Ok((entity, mut input)) = input_query.get_single_mut() else {
+        return;
+    };
+    let Some(line) = text_submitted_event
+        .read()
+        .find(|event| event.input == entity)
+        .map(|event| event.value.trim().to_string())
+    else {
+        return;
+    };
+    input.value.clear();
+    let (reply, color) = match CheatCommand::parse(&line) {
+        Ok(command) => {
+            info!(?command, "Cheat entered");
+            cheat_entered_event.send(CheatEntered(command));
+            (line, Color::WHITE)
+        }
+        Err(error) => (error, Color::ORANGE_RED),
+    };
+    for mut text in &mut reply_query {
+        text.sections[0].value = reply.clone();
+        text.sections[0].style.color = color;
+    }
+}
+
+fn apply_cheats(
+    mut commands: Commands,
+    mut cheat_entered_event: EventReader<CheatEntered>,
+    mut cheats: ResMut<Cheats>,
+    mut pending_growth: ResMut<PendingGrowth>,
+    snake_head_query: Query<(Entity, &Player, Has<Noclip>), With<SnakeHead>>,
+    apple_query: Query<Entity, With<Apple>>,
+) {
+    for event in cheat_entered_event.read() {
+        cheats.used = true;
+        match event.0 {
+            // the apple on the board makes way, spawn_apple puts down the next one
+            CheatCommand::SpawnApple(count) => {
+                for entity in &apple_query {
+                    commands.entity(entity).despawn();
+                }
+                cheats.apples_ahead = count;
+            }
+            CheatCommand::Grow(segments) => {
+                *pending_growth.0.entry(Player::ONE).or_default() += segments;
+            }
+            CheatCommand::Speed(factor) => cheats.speed = factor,
+            CheatCommand::Noclip => {
+                for (entity, _, noclip) in snake_head_query
+                    .iter()
+                    .filter(|(_, player, _)| **player == Player::ONE)
+                {
+                    if noclip {
+                        commands.entity(entity).remove::<Noclip>();
+                    } else {
+                        commands.entity(entity).insert(Noclip);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// an apple that doesn't fit there stays where it spawned, but still counts
+fn place_apples_ahead(
+    mut cheats: ResMut<Cheats>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    mut apple_query: Query<(&mut Apple, &mut Transform), Added<Apple>>,
+) {
+    if cheats.apples_ahead == 0 {
+        return;
+    }
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let mut ahead = snake_head.position;
+    for _ in 0..APPLE_DISTANCE {
+        ahead = snake_head.direction.step(ahead);
+    }
+    for (mut apple, mut transform) in &mut apple_query {
+        cheats.apples_ahead = cheats.apples_ahead.saturating_sub(1);
+        let Some(&origin) = apple.footprint.first() else {
+            continue;
+        };
+        let footprint: Vec<(i32, i32)> = apple
+            .footprint
+            .iter()
+            .map(|cell| (cell.0 - origin.0 + ahead.0, cell.1 - origin.1 + ahead.1))
+            .collect();
+        let fits = footprint.iter().all(|cell| {
+            !playfield_mask.is_wall(*cell)
+                && !snake_body_query
+                    .iter()
+                    .any(|snake_body| snake_body.position == *cell)
+        });
+        if !fits {
+            continue;
+        }
+        transform.translation.x += (ahead.0 - origin.0) as f32 * PIXEL_UNIT_SIZE;
+        transform.translation.y += (ahead.1 - origin.1) as f32 * PIXEL_UNIT_SIZE;
+        apple.footprint = footprint;
+    }
+}
+
+// the last ten keys pressed, compared to the code after each one
+fn enter_konami_code(
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    mut achievements: ResMut<Achievements>,
+    mut achievement_earned_event: EventWriter<AchievementEarned>,
+    mut pressed: Local<VecDeque<KeyCode>>,
+) {
+    for key_code in keyboard_input_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .filter_map(|event| event.key_code)
+    {
+        if pressed.len() == KONAMI_CODE.len() {
+            pressed.pop_front();
+        }
+        pressed.push_back(key_code);
+        if pressed.iter().eq(KONAMI_CODE.iter()) {
+            info!("Konami code entered");
+            if achievements.earn(Achievement::Konami, &mut achievement_earned_event) {
+                achievements.save();
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Game configuration
+// Tuning values read from assets/game.config.ron, reapplied whenever the file changes
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use serde::Deserialize;
+
+use crate::args::Args;
+use crate::ron_asset::RonAssetLoader;
+use crate::spawn_balance::SpawnBalance;
+use crate::tick_pipeline::TickPipeline;
+use crate::SpeedCurve;
+
+#[derive(Asset, TypePath, Deserialize)]
+struct GameConfig {
+    speed_curve: SpeedCurve,
+    #[serde(default)]
+    window: WindowConfig,
+    #[serde(default)]
+    spawn_balance: SpawnBalance,
+    #[serde(default)]
+    tick_pipeline: TickPipeline,
+}
+
+// primary window metadata, applied by the window config plugin
+#[derive(Resource, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub title: String,
+    pub icon: String, // asset path
+    pub min_width: f32,
+    pub min_height: f32,
+    pub allow_maximize: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: "Snake".to_string(),
+            icon: "icon.png".to_string(),
+            min_width: 480.0,
+            min_height: 480.0,
+            allow_maximize: false,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct GameConfigHandle(Handle<GameConfig>);
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<GameConfig>()
+            .init_resource::<WindowConfig>()
+            .init_resource::<SpawnBalance>()
+            .register_asset_loader(RonAssetLoader::<GameConfig>::new(&["config.ron"]))
+            .add_systems(Startup, load_config)
+            .add_systems(Update, apply_config);
+    }
+}
+
+fn load_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameConfigHandle(asset_server.load("game.config.ron")));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_config(
+    args: Res<Args>,
+    mut config_events: EventReader<AssetEvent<GameConfig>>,
+    config_handle: Res<GameConfigHandle>,
+    configs: Res<Assets<GameConfig>>,
+    mut speed_curve: ResMut<SpeedCurve>,
+    mut window_config: ResMut<WindowConfig>,
+    mut spawn_balance: ResMut<SpawnBalance>,
+    mut tick_pipeline: ResMut<TickPipeline>,
+) {
+    for event in config_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != config_handle.0.id() {
+            continue;
+        }
+        if let Some(config) = configs.get(*id) {
+            *speed_curve = config.speed_curve.clone();
+            // balanced spawns and fixed tick rates aren't in verify_replay, ranked runs stay uniform
+            if !args.ranked() {
+                *spawn_balance = config.spawn_balance;
+            }
+            *tick_pipeline = TickPipeline {
+                ticks_per_second: config
+                    .tick_pipeline
+                    .ticks_per_second
+                    .filter(|_| !args.ranked()),
+                forced_ticks_per_second: tick_pipeline.forced_ticks_per_second,
+                ..config.tick_pipeline.clone()
+            };
+            // the window is only touched when its section actually changed
+            if *window_config != config.window {
+                *window_config = config.window.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Endless corridor
+// Runner variant (--corridor): the board keeps scrolling right, new columns come in with random
+// wall segments that get more common the further it goes, and whatever falls off the left edge
+// is gone, the snake included
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::items::PowerUp;
+use crate::obstacles::stays_connected;
+use crate::occupancy::Occupancy;
+use crate::playfield::PlayfieldMask;
+use crate::seed::RunRng;
+use crate::{move_snake, Apple, Background, Border, SnakeSystemSet, PLAYFIELD};
+
+const SCROLL_TICKS: u32 = 5; // ticks per column
+const OBSTACLE_CHANCE: f64 = 0.2; // per new column at the start
+const OBSTACLE_CHANCE_PER_COLUMN: f64 = 0.002;
+const MAX_OBSTACLE_CHANCE: f64 = 0.7;
+const MIN_OBSTACLE_LENGTH: i32 = 2;
+const MAX_OBSTACLE_LENGTH: i32 = 8;
+
+pub struct CorridorPlugin;
+
+impl Plugin for CorridorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            scroll_corridor
+                .in_set(SnakeSystemSet::Movement)
+                .before(move_snake),
+        )
+        .add_systems(Update, move_board);
+    }
+}
+
+fn scroll_corridor(
+    mut commands: Commands,
+    mut ticks: Local<u32>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut run_rng: ResMut<RunRng>,
+    occupancy: Res<Occupancy>,
+    apple_query: Query<(Entity, &Apple)>,
+    power_up_query: Query<(Entity, &PowerUp)>,
+) {
+    *ticks += 1;
+    if !ticks.is_multiple_of(SCROLL_TICKS) {
+        return;
+    }
+    playfield_mask.scroll_right();
+
+    let rng = &mut run_rng.0;
+    let column = playfield_mask.scroll() + PLAYFIELD.0 / 2;
+    let chance = (OBSTACLE_CHANCE + playfield_mask.scroll() as f64 * OBSTACLE_CHANCE_PER_COLUMN)
+        .min(MAX_OBSTACLE_CHANCE);
+    if rng.gen_bool(chance) {
+        let length = rng.gen_range(MIN_OBSTACLE_LENGTH..=MAX_OBSTACLE_LENGTH);
+        let bottom = rng.gen_range(-PLAYFIELD.1 / 2..=PLAYFIELD.1 / 2 - length + 1);
+        // never on top of a snake that got to the right edge first
+        let segment: Vec<(i32, i32)> = (bottom..bottom + length)
+            .map(|y| (column, y))
+            .filter(|position| occupancy.get(*position).is_none())
+            .collect();
+        // nor cutting off part of the board
+        if stays_connected(&playfield_mask, |position| segment.contains(&position)) {
+            for position in segment {
+                playfield_mask.add_wall(position);
+            }
+        }
+    }
+
+    // a new apple spawns once the old one is out of reach
+    for (entity, apple) in &apple_query {
+        if apple
+            .footprint
+            .iter()
+            .any(|position| playfield_mask.is_wall(*position))
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (entity, power_up) in &power_up_query {
+        if playfield_mask.is_wall(power_up.position) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn move_board(
+    playfield_mask: Res<PlayfieldMask>,
+    mut board_query: Query<&mut Transform, Or<(With<Background>, With<Border>)>>,
+) {
+    if !playfield_mask.is_changed() {
+        return;
+    }
+    for mut transform in &mut board_query {
+        transform.translation.x = playfield_mask.world_offset().x;
+    }
+}
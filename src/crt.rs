@@ -0,0 +1,178 @@
+// CRT filter
+// With --crt (or the CRT filter setting) the main camera draws into an image the size of the
+// window instead, and a camera of this module's own shows that image through a fullscreen material
+// with scanlines, a slight curvature and chromatic aberration. The HUD is drawn by that second
+// camera on top, so it stays sharp and clickable
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{AsBindGroup, Extent3d, ShaderRef};
+use bevy::render::view::RenderLayers;
+use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle};
+use bevy::window::{PrimaryWindow, WindowRef, WindowResized};
+
+use crate::board_texture::render_target_image;
+use crate::camera::MainCamera;
+use crate::graphics::GraphicsSettings;
+
+const CRT_LAYER: u8 = 3; // the fullscreen quad stays out of the main camera
+const CURVATURE: f32 = 0.04;
+const ABERRATION: f32 = 0.006; // of the screen width at the edges
+const SCANLINE_STRENGTH: f32 = 0.25;
+
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+struct CrtMaterial {
+    #[uniform(0)]
+    resolution: Vec2,
+    #[uniform(0)]
+    curvature: f32,
+    #[uniform(0)]
+    aberration: f32,
+    #[uniform(0)]
+    scanline_strength: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    screen: Handle<Image>,
+}
+
+impl Material2d for CrtMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/crt.wgsl".into()
+    }
+}
+
+// what the main camera draws into while the filter is on
+#[derive(Resource)]
+struct CrtScreen {
+    image: Handle<Image>,
+    material: Handle<CrtMaterial>,
+}
+
+#[derive(Component)]
+struct CrtCamera;
+
+#[derive(Component)]
+struct CrtQuad;
+
+pub struct CrtPlugin;
+
+impl Plugin for CrtPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<CrtMaterial>::default())
+            .add_systems(Startup, setup_crt)
+            .add_systems(Update, (apply_crt_setting, fit_crt_screen));
+    }
+}
+
+fn setup_crt(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut crt_materials: ResMut<Assets<CrtMaterial>>,
+) {
+    // sized to the window once it reports its size
+    let image = images.add(render_target_image(UVec2::ONE));
+    let material = crt_materials.add(CrtMaterial {
+        resolution: Vec2::ONE,
+        curvature: CURVATURE,
+        aberration: ABERRATION,
+        scanline_strength: SCANLINE_STRENGTH,
+        screen: image.clone(),
+    });
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                // after the main camera has filled the image
+                order: 1,
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(CRT_LAYER),
+        CrtCamera,
+    ));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Quad::new(Vec2::ONE).into()).into(),
+            material: material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        RenderLayers::layer(CRT_LAYER),
+        CrtQuad,
+    ));
+    commands.insert_resource(CrtScreen { image, material });
+}
+
+// switches the main camera between the window and the image, the HUD going with the window
+#[allow(clippy::type_complexity)]
+fn apply_crt_setting(
+    mut commands: Commands,
+    graphics_settings: Res<GraphicsSettings>,
+    crt_screen: Res<CrtScreen>,
+    mut main_camera_query: Query<(Entity, &mut Camera), (With<MainCamera>, Without<CrtCamera>)>,
+    mut crt_camera_query: Query<&mut Camera, With<CrtCamera>>,
+    mut quad_query: Query<&mut Visibility, With<CrtQuad>>,
+    mut applied: Local<Option<bool>>,
+) {
+    let crt = graphics_settings.crt;
+    let Ok((main_camera, mut camera)) = main_camera_query.get_single_mut() else {
+        return;
+    };
+    if *applied == Some(crt) {
+        return;
+    }
+    *applied = Some(crt);
+    camera.target = if crt {
+        RenderTarget::Image(crt_screen.image.clone())
+    } else {
+        RenderTarget::Window(WindowRef::Primary)
+    };
+    commands
+        .entity(main_camera)
+        .insert(UiCameraConfig { show_ui: !crt });
+    for mut crt_camera in &mut crt_camera_query {
+        crt_camera.is_active = crt;
+    }
+    for mut visibility in &mut quad_query {
+        *visibility = if crt {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// the image matches the window pixel for pixel, the quad covers it
+fn fit_crt_screen(
+    mut window_resized_event: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    crt_screen: Res<CrtScreen>,
+    mut images: ResMut<Assets<Image>>,
+    mut crt_materials: ResMut<Assets<CrtMaterial>>,
+    mut quad_query: Query<&mut Transform, With<CrtQuad>>,
+    mut fitted: Local<bool>,
+) {
+    let resized = window_resized_event.read().count() > 0;
+    if *fitted && !resized {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    *fitted = true;
+    let pixels = UVec2::new(window.physical_width(), window.physical_height()).max(UVec2::ONE);
+    if let Some(image) = images.get_mut(&crt_screen.image) {
+        image.resize(Extent3d {
+            width: pixels.x,
+            height: pixels.y,
+            depth_or_array_layers: 1,
+        });
+    }
+    if let Some(material) = crt_materials.get_mut(&crt_screen.material) {
+        material.resolution = pixels.as_vec2();
+    }
+    for mut transform in &mut quad_query {
+        transform.scale = Vec3::new(window.width(), window.height(), 1.0);
+    }
+}
@@ -0,0 +1,124 @@
+// Day and night
+// With --day-night the board slowly darkens into night and brightens back over a run, moving the
+// theme's background and the border with it. The snakes and apples are lifted away from the
+// background whenever it gets too close to them, so they stay easy to make out at any hour
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::theme::{apply_theme, reload_theme, Theme};
+use crate::unlocks::apply_cosmetics;
+use crate::Border;
+
+const DAY_SECONDS: f32 = 240.0; // from noon round to noon again
+const UPDATE_SECONDS: f32 = 0.5; // every recolor goes over all the sprites
+const NIGHT_BRIGHTNESS: f32 = 0.3;
+const NIGHT_TINT: Color = Color::rgb(0.15, 0.2, 0.45);
+const NIGHT_TINT_AMOUNT: f32 = 0.35;
+const MIN_CONTRAST: f32 = 3.0; // luminance ratio against the background
+const BORDER_DAY: Color = Color::BLACK;
+const BORDER_NIGHT: Color = Color::rgb(0.55, 0.6, 0.75);
+
+#[derive(Resource, Default)]
+struct DayNight {
+    elapsed: f32,
+    since_update: f32,
+    base: Option<Theme>,    // as loaded or equipped, before the time of day
+    written: Option<Theme>, // the last colors set here, anything else is a new base
+}
+
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DayNight>().add_systems(
+            Update,
+            pass_time
+                .after(reload_theme)
+                .after(apply_cosmetics)
+                .before(apply_theme),
+        );
+    }
+}
+
+// 1 at noon, 0 at midnight
+fn daylight(elapsed: f32) -> f32 {
+    0.5 + 0.5 * (elapsed / DAY_SECONDS * TAU).cos()
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let [r0, g0, b0, a] = from.as_rgba_f32();
+    let [r1, g1, b1, _] = to.as_rgba_f32();
+    Color::rgba(
+        r0 + (r1 - r0) * amount,
+        g0 + (g1 - g0) * amount,
+        b0 + (b1 - b0) * amount,
+        a,
+    )
+}
+
+fn luminance(color: Color) -> f32 {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn contrast(a: Color, b: Color) -> f32 {
+    let (lighter, darker) = if luminance(a) > luminance(b) {
+        (luminance(a), luminance(b))
+    } else {
+        (luminance(b), luminance(a))
+    };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+// toward white on a dark background and black on a light one, only as far as needed
+fn readable(color: Color, background: Color) -> Color {
+    let away = if luminance(background) < 0.2 {
+        Color::WHITE
+    } else {
+        Color::BLACK
+    };
+    (0..=10)
+        .map(|step| mix(color, away, step as f32 / 10.0))
+        .find(|candidate| contrast(*candidate, background) >= MIN_CONTRAST)
+        .unwrap_or(away)
+}
+
+fn pass_time(
+    time: Res<Time>,
+    mut day_night: ResMut<DayNight>,
+    mut theme: ResMut<Theme>,
+    mut border_query: Query<&mut Sprite, With<Border>>,
+) {
+    day_night.elapsed += time.delta_seconds();
+    day_night.since_update += time.delta_seconds();
+    let rebased = day_night.written.as_ref() != Some(&*theme);
+    if rebased {
+        day_night.base = Some(theme.clone());
+    } else if day_night.since_update < UPDATE_SECONDS {
+        return;
+    }
+    day_night.since_update = 0.0;
+    let Some(base) = day_night.base.clone() else {
+        return;
+    };
+
+    let darkness = 1.0 - daylight(day_night.elapsed);
+    let dimmed = mix(
+        base.background,
+        Color::BLACK,
+        darkness * (1.0 - NIGHT_BRIGHTNESS),
+    );
+    let background = mix(dimmed, NIGHT_TINT, darkness * NIGHT_TINT_AMOUNT);
+    let cycled = Theme {
+        snake_head: readable(base.snake_head, background),
+        snake_body: readable(base.snake_body, background),
+        apple: readable(base.apple, background),
+        background,
+    };
+    *theme = cycled.clone();
+    day_night.written = Some(cycled);
+    for mut sprite in &mut border_query {
+        sprite.color = mix(BORDER_DAY, BORDER_NIGHT, darkness);
+    }
+}
@@ -0,0 +1,169 @@
+// Debug UI
+// Egui windows for settings, mode select and live tuning, toggled with F1
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crate::ai::AiSkill;
+use crate::graphics::{DisplaySettings, GraphicsQuality, GraphicsSettings};
+use crate::input::{DiagonalPolicy, InputSettings};
+use crate::music::MusicVolume;
+use crate::theme::Theme;
+use crate::{MovementSettings, SpeedCurve};
+
+#[derive(Resource, Default)]
+struct DebugUiVisible(bool);
+
+pub struct DebugUiPlugin;
+
+impl Plugin for DebugUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(EguiPlugin)
+            .init_resource::<DebugUiVisible>()
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_ui,
+                    (
+                        settings_window,
+                        mode_select_window,
+                        tuning_window,
+                        versus_window.run_if(resource_exists::<AiSkill>()),
+                    )
+                        .run_if(|visible: Res<DebugUiVisible>| visible.0),
+                ),
+            );
+    }
+}
+
+fn toggle_debug_ui(keyboard_input: Res<Input<KeyCode>>, mut visible: ResMut<DebugUiVisible>) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        visible.0 = !visible.0;
+    }
+}
+
+fn settings_window(
+    mut contexts: EguiContexts,
+    mut graphics_settings: ResMut<GraphicsSettings>,
+    mut display_settings: ResMut<DisplaySettings>,
+    mut input_settings: ResMut<InputSettings>,
+    mut music_volume: ResMut<MusicVolume>,
+) {
+    egui::Window::new("Settings").show(contexts.ctx_mut(), |ui| {
+        let mut quality = graphics_settings.quality;
+        ui.horizontal(|ui| {
+            ui.label("Graphics");
+            ui.radio_value(&mut quality, GraphicsQuality::Low, "Low");
+            ui.radio_value(&mut quality, GraphicsQuality::High, "High");
+            ui.radio_value(&mut quality, GraphicsQuality::Fancy, "Fancy");
+        });
+        let mut low_power = graphics_settings.low_power;
+        ui.checkbox(&mut low_power, "Low power (30 fps, no ambient effects)");
+        let mut crt = graphics_settings.crt;
+        ui.checkbox(&mut crt, "CRT filter");
+        // only write on change, effects are toggled through change detection
+        if quality != graphics_settings.quality
+            || low_power != graphics_settings.low_power
+            || crt != graphics_settings.crt
+        {
+            graphics_settings.quality = quality;
+            graphics_settings.low_power = low_power;
+            graphics_settings.crt = crt;
+        }
+
+        // edited on a copy, every write saves the settings file
+        let mut display = display_settings.clone();
+        ui.checkbox(&mut display.vsync, "Vsync");
+        ui.checkbox(&mut display.borderless, "Borderless fullscreen");
+        let mut capped = display.frame_cap.is_some();
+        let mut frame_cap = display.frame_cap.unwrap_or(60.0);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut capped, "Frame cap");
+            ui.add_enabled(
+                capped,
+                egui::Slider::new(&mut frame_cap, 30.0..=360.0).suffix(" fps"),
+            );
+        });
+        display.frame_cap = capped.then_some(frame_cap);
+        if display != *display_settings {
+            *display_settings = display;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Same-frame turns");
+            let policy = &mut input_settings.diagonal_policy;
+            ui.radio_value(policy, DiagonalPolicy::LatestPress, "Latest wins");
+            ui.radio_value(policy, DiagonalPolicy::EarliestPress, "Earliest wins");
+        });
+        ui.add(egui::Slider::new(&mut music_volume.0, 0.0..=1.0).text("Music volume"));
+    });
+}
+
+fn versus_window(mut contexts: EguiContexts, mut skill: ResMut<AiSkill>) {
+    egui::Window::new("CPU opponent").show(contexts.ctx_mut(), |ui| {
+        ui.add(egui::Slider::new(&mut skill.reaction_ticks, 0..=5).text("Reaction delay (ticks)"));
+        ui.add(egui::Slider::new(&mut skill.lookahead, 1..=128).text("Lookahead (cells)"));
+        ui.add(egui::Slider::new(&mut skill.mistake_chance, 0.0..=0.5).text("Mistake chance"));
+    });
+}
+
+fn mode_select_window(mut contexts: EguiContexts, mut movement_settings: ResMut<MovementSettings>) {
+    egui::Window::new("Mode select").show(contexts.ctx_mut(), |ui| {
+        for (name, mode) in [
+            ("Classic", MovementSettings::classic()),
+            ("Ice rink", MovementSettings::ice_rink()),
+        ] {
+            if ui
+                .selectable_label(*movement_settings == mode, name)
+                .clicked()
+            {
+                *movement_settings = mode;
+            }
+        }
+    });
+}
+
+fn color_picker(ui: &mut egui::Ui, label: &str, color: &mut Color) -> bool {
+    let mut rgba = color.as_rgba_f32();
+    let changed = ui
+        .horizontal(|ui| {
+            let changed = ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed();
+            ui.label(label);
+            changed
+        })
+        .inner;
+    if changed {
+        *color = Color::rgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+    changed
+}
+
+fn tuning_window(
+    mut contexts: EguiContexts,
+    mut speed_curve: ResMut<SpeedCurve>,
+    mut theme: ResMut<Theme>,
+) {
+    egui::Window::new("Tuning").show(contexts.ctx_mut(), |ui| {
+        ui.heading("Speed curve");
+        ui.add(
+            egui::Slider::new(&mut speed_curve.base_tickrate, 0.02..=0.3).text("Base tickrate (s)"),
+        );
+        ui.add(
+            egui::Slider::new(&mut speed_curve.speedup_per_segment, 0.0..=0.005)
+                .text("Speedup per segment (s)"),
+        );
+        let base_tickrate = speed_curve.base_tickrate;
+        ui.add(
+            egui::Slider::new(&mut speed_curve.min_tickrate, 0.02..=base_tickrate)
+                .text("Min tickrate (s)"),
+        );
+
+        ui.heading("Colors");
+        let mut edited = theme.clone();
+        let mut changed = color_picker(ui, "Snake head", &mut edited.snake_head);
+        changed |= color_picker(ui, "Snake body", &mut edited.snake_body);
+        changed |= color_picker(ui, "Apple", &mut edited.apple);
+        changed |= color_picker(ui, "Background", &mut edited.background);
+        if changed {
+            *theme = edited;
+        }
+    });
+}
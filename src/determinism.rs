@@ -0,0 +1,260 @@
+// Determinism check
+// --check-determinism [boards] [--check-ticks <ticks>] [--seed <n>] plays each board twice side by
+// side from the same seed and the same bot inputs, once with the body kept as a path and once
+// with a cell per segment as the game keeps its entities, and stops at the first tick the two
+// boards differ. Every tick the bitboard occupancy grid is also checked cell by cell against a
+// plain map of the same occupants. Anything that drifts here would break replays and netplay
+use std::collections::VecDeque;
+
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::ai::{AiSkill, BotView, Greedy, Strategy};
+use crate::args::Args;
+use crate::game_state::render_ascii;
+use crate::occupancy::{Occupancy, Occupant};
+use crate::pathfinding::is_blocked;
+use crate::replay::ReplaySim;
+use crate::seed::RunSeed;
+use crate::PLAYFIELD;
+
+const DEFAULT_BOARDS: u64 = 16;
+const DEFAULT_TICKS: u32 = 5_000;
+const RNG_SALT: u64 = 0x6465_7465; // the bot's own choices, apart from the apples
+
+pub fn check_from_args(args: &Args) -> Option<Result<String, String>> {
+    let boards = match args.values("--check-determinism")?.first() {
+        Some(count) => match count.parse::<u64>() {
+            Ok(boards) => boards,
+            Err(_) => {
+                return Some(Err(
+                    "--check-determinism expects a number of boards".to_string()
+                ))
+            }
+        },
+        None => DEFAULT_BOARDS,
+    };
+    let max_ticks = match args.values("--check-ticks") {
+        Some(values) => match values.first().map(|ticks| ticks.parse()) {
+            Some(Ok(ticks)) => ticks,
+            _ => return Some(Err("--check-ticks expects a number of ticks".to_string())),
+        },
+        None => DEFAULT_TICKS,
+    };
+    let first_seed = RunSeed::from_args(args).0;
+    let mut ticks = 0;
+    for board in 0..boards {
+        match check_board(first_seed.wrapping_add(board), max_ticks) {
+            Ok(board_ticks) => ticks += board_ticks as u64,
+            Err(error) => return Some(Err(error)),
+        }
+    }
+    Some(Ok(format!(
+        "{boards} boards from seed {first_seed}, {ticks} ticks: the boards and grids agreed on every tick"
+    )))
+}
+
+// ticks played, or what differed first
+fn check_board(seed: u64, max_ticks: u32) -> Result<u32, String> {
+    let mut path_sim = ReplaySim::new(seed, Vec::new()).with_tick_limit(max_ticks);
+    let mut cell_sim = ReplaySim::<VecDeque<(i32, i32)>>::new_with_body_cells(seed, Vec::new())
+        .with_tick_limit(max_ticks);
+    let mut bot = Greedy::default();
+    let mut rng = StdRng::seed_from_u64(seed ^ RNG_SALT);
+    loop {
+        let (path_state, cell_state) = (path_sim.state(), cell_sim.state());
+        if path_state != cell_state {
+            return Err(format!(
+                "seed {seed}, tick {}: the boards differ\npath body:\n{}\ncell per segment:\n{}",
+                path_sim.tick,
+                render_ascii(&path_state),
+                render_ascii(&cell_state),
+            ));
+        }
+
+        let occupancy = Occupancy::from_board(&path_sim.playfield_mask, path_sim.occupants());
+        check_grid(&path_sim, &occupancy)
+            .map_err(|error| format!("seed {seed}, tick {}: {error}", path_sim.tick))?;
+
+        let apple = path_sim.apple();
+        let view = BotView {
+            occupancy: &occupancy,
+            snake_head: path_sim.head(),
+            apple: apple.as_ref(),
+            skill: &AiSkill::NORMAL,
+        };
+        let direction = bot.choose_direction(&view, &mut rng);
+        path_sim.steer(direction);
+        cell_sim.steer(direction);
+        let (path_running, cell_running) = (path_sim.step(), cell_sim.step());
+        if path_running != cell_running {
+            return Err(format!(
+                "seed {seed}, tick {}: only one board ended",
+                path_sim.tick
+            ));
+        }
+        if !path_running {
+            return Ok(path_sim.tick);
+        }
+    }
+}
+
+// the bitboards against a map filled as the grid used to be, over the board and a ring past it
+fn check_grid(sim: &ReplaySim, occupancy: &Occupancy) -> Result<(), String> {
+    let mut cells: HashMap<(i32, i32), Occupant> = sim
+        .playfield_mask
+        .walls()
+        .map(|position| (position, Occupant::Wall))
+        .collect();
+    cells.extend(sim.occupants());
+    let (reach_x, reach_y) = (PLAYFIELD.0 / 2 + 2, PLAYFIELD.1 / 2 + 2);
+    let scroll = sim.playfield_mask.scroll();
+    for x in scroll - reach_x..=scroll + reach_x {
+        for y in -reach_y..=reach_y {
+            let position = (x, y);
+            let expected = cells.get(&position).copied();
+            if occupancy.get(position) != expected {
+                return Err(format!(
+                    "the grid has {:?} at {position:?}, the map {expected:?}",
+                    occupancy.get(position)
+                ));
+            }
+            let blocked = occupancy.is_out_of_bounds(position)
+                || matches!(
+                    expected,
+                    Some(Occupant::SnakeHead | Occupant::SnakeBody | Occupant::Wall)
+                );
+            if is_blocked(occupancy, position) != blocked {
+                return Err(format!(
+                    "the grid and the map disagree on {position:?} blocking"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::items::ItemTable;
+    use crate::seed::RunRng;
+    use crate::spawn_balance::SpawnBalance;
+    use crate::stone::StonePlugin;
+    use crate::theme::Theme;
+    use crate::{
+        errors, shield, Apple, AppleEaten, MovementSettings, SnakeBody, SnakeDied, SnakeGamePlugin,
+        SnakeHead,
+    };
+
+    const TICKS: u32 = 400;
+
+    #[test]
+    fn boards_agree() {
+        for seed in 0..8 {
+            check_board(seed, TICKS).unwrap();
+        }
+    }
+
+    // the game's own tick systems, run a tick at a time without a window or a clock
+    fn ranked_game(seed: u64) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((SnakeGamePlugin::new(), StonePlugin))
+            .init_resource::<Theme>()
+            .init_resource::<ItemTable>()
+            .init_resource::<SpawnBalance>()
+            .insert_resource(MovementSettings::classic())
+            .insert_resource(RunRng(StdRng::seed_from_u64(seed)))
+            .add_event::<shield::ShieldBroken>()
+            .add_event::<errors::ErrorReported>();
+        app.world.run_schedule(Startup);
+        app
+    }
+
+    fn game_snake(world: &mut World) -> Vec<(i32, i32)> {
+        let snake_head = world.query::<&SnakeHead>().single(world);
+        std::iter::once(snake_head.position)
+            .chain(
+                snake_head
+                    .segments
+                    .iter()
+                    .map(|segment| world.get::<SnakeBody>(*segment).unwrap().position),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn game_matches_replay_sim() {
+        for seed in 0..4 {
+            let mut app = ranked_game(seed);
+            let mut sim = ReplaySim::new(seed, Vec::new()).with_tick_limit(TICKS);
+            let mut bot = Greedy::default();
+            let mut rng = StdRng::seed_from_u64(seed ^ RNG_SALT);
+            loop {
+                let occupancy = Occupancy::from_board(&sim.playfield_mask, sim.occupants());
+                let apple = sim.apple();
+                let view = BotView {
+                    occupancy: &occupancy,
+                    snake_head: sim.head(),
+                    apple: apple.as_ref(),
+                    skill: &AiSkill::HARD,
+                };
+                let direction = bot.choose_direction(&view, &mut rng);
+                sim.steer(direction);
+                let mut snake_head_query = app.world.query::<&mut SnakeHead>();
+                snake_head_query
+                    .single_mut(&mut app.world)
+                    .potential_direction = direction;
+
+                let running = sim.step();
+                app.world.run_schedule(FixedUpdate);
+                let tick = sim.tick;
+                assert_eq!(
+                    game_snake(&mut app.world),
+                    sim.snake().collect::<Vec<_>>(),
+                    "seed {seed}, tick {tick}: the snakes differ"
+                );
+                let apples: Vec<Vec<(i32, i32)>> = app
+                    .world
+                    .query::<&Apple>()
+                    .iter(&app.world)
+                    .map(|apple| apple.footprint.clone())
+                    .collect();
+                let sim_apples: Vec<Vec<(i32, i32)>> = sim
+                    .apple
+                    .iter()
+                    .map(|(footprint, _)| footprint.clone())
+                    .collect();
+                assert_eq!(
+                    apples, sim_apples,
+                    "seed {seed}, tick {tick}: the apples differ"
+                );
+                let died = !app.world.resource::<Events<SnakeDied>>().is_empty();
+                if !running {
+                    assert_eq!(
+                        died,
+                        sim.tick < TICKS,
+                        "seed {seed}: the runs ended differently"
+                    );
+                    break;
+                }
+                assert!(
+                    !died,
+                    "seed {seed}, tick {tick}: only the game's snake died"
+                );
+            }
+            // events are never cleared without the frame loop, so every one is still here
+            let points: u32 = app
+                .world
+                .resource::<Events<AppleEaten>>()
+                .iter_current_update_events()
+                .map(|event| event.points)
+                .sum();
+            assert_eq!(points, sim.score, "seed {seed}: the scores differ");
+        }
+    }
+}
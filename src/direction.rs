@@ -0,0 +1,88 @@
+// Direction
+// The four directions a snake can face, with the helpers movement, replays and config need
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Error)]
+#[error("unknown direction {0:?}, expected up, down, left or right")]
+pub struct ParseDirectionError(String);
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    // a quarter turn counter-clockwise, to the snake's left
+    pub fn left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    // one cell in this direction, y pointing up
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    pub fn step(self, position: (i32, i32)) -> (i32, i32) {
+        let delta = self.delta();
+        (position.0 + delta.0, position.1 + delta.1)
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        };
+        f.write_str(name)
+    }
+}
+
+impl TryFrom<&str> for Direction {
+    type Error = ParseDirectionError;
+
+    // case-insensitive, also accepts the first letter
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "up" | "u" => Ok(Direction::Up),
+            "down" | "d" => Ok(Direction::Down),
+            "left" | "l" => Ok(Direction::Left),
+            "right" | "r" => Ok(Direction::Right),
+            _ => Err(ParseDirectionError(value.to_string())),
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// Hazard director
+// Endless mode (--endless): pressure builds with time, length and score, and the director
+// schedules hazards from it: rocks blocking a cell for a while, rotten apples that cost length and
+// points, and speed surges. When each one starts, how often it comes and how strong it is follow
+// the curves in assets/director.ron
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use serde::Deserialize;
+
+use crate::event_log::EventLogged;
+use crate::playfield::PlayfieldMask;
+use crate::ron_asset::RonAssetLoader;
+use crate::score::Score;
+use crate::seed::RunRng;
+use crate::{
+    get_valid_apple_spawn, shrink_snake, Apple, Player, SnakeBody, SnakeHead, SnakeSystemSet,
+    PIXEL_UNIT_SIZE,
+};
+
+const ROCK_COLOR: Color = Color::rgb(0.35, 0.3, 0.3);
+const ROTTEN_COLOR: Color = Color::rgb(0.4, 0.45, 0.1);
+const ROTTEN_SHRINK: usize = 2; // segments lost per rotten apple
+const ROTTEN_PENALTY: u32 = 2;
+
+// a value growing or easing with pressure, never past its limit
+#[derive(Clone, Deserialize)]
+pub struct Curve {
+    pub base: f32,
+    pub per_pressure: f32,
+    pub limit: f32,
+}
+
+impl Curve {
+    fn at(&self, pressure: f32) -> f32 {
+        let value = self.base + self.per_pressure * pressure;
+        if self.per_pressure < 0.0 {
+            value.max(self.limit)
+        } else {
+            value.min(self.limit)
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HazardSchedule {
+    pub from_pressure: f32,
+    pub interval_seconds: Curve,
+    pub amount: Curve, // rocks and rotten apples per wave, the tick interval factor for surges
+    pub lifetime_seconds: f32,
+}
+
+#[derive(Resource, Asset, TypePath, Clone, Deserialize)]
+pub struct DirectorTable {
+    pub pressure_per_second: f32,
+    pub pressure_per_segment: f32,
+    pub pressure_per_point: f32,
+    pub rocks: HazardSchedule,
+    pub rotten_apples: HazardSchedule,
+    pub speed_surges: HazardSchedule,
+}
+
+// used until assets/director.ron has loaded
+impl Default for DirectorTable {
+    fn default() -> Self {
+        DirectorTable {
+            pressure_per_second: 0.1,
+            pressure_per_segment: 0.5,
+            pressure_per_point: 0.2,
+            rocks: HazardSchedule {
+                from_pressure: 5.0,
+                interval_seconds: Curve {
+                    base: 15.0,
+                    per_pressure: -0.2,
+                    limit: 4.0,
+                },
+                amount: Curve {
+                    base: 1.0,
+                    per_pressure: 0.05,
+                    limit: 5.0,
+                },
+                lifetime_seconds: 12.0,
+            },
+            rotten_apples: HazardSchedule {
+                from_pressure: 10.0,
+                interval_seconds: Curve {
+                    base: 20.0,
+                    per_pressure: -0.2,
+                    limit: 6.0,
+                },
+                amount: Curve {
+                    base: 1.0,
+                    per_pressure: 0.02,
+                    limit: 3.0,
+                },
+                lifetime_seconds: 10.0,
+            },
+            speed_surges: HazardSchedule {
+                from_pressure: 15.0,
+                interval_seconds: Curve {
+                    base: 30.0,
+                    per_pressure: -0.2,
+                    limit: 12.0,
+                },
+                amount: Curve {
+                    base: 0.8,
+                    per_pressure: -0.005,
+                    limit: 0.5,
+                },
+                lifetime_seconds: 4.0,
+            },
+        }
+    }
+}
+
+#[derive(Resource)]
+struct DirectorTableHandle(Handle<DirectorTable>);
+
+// seconds until each hazard's next wave, counted once its pressure is reached
+#[derive(Resource, Default)]
+struct Director {
+    elapsed: f32,
+    next_rocks: Option<f32>,
+    next_rotten_apples: Option<f32>,
+    next_speed_surge: Option<f32>,
+    surge_left: f32,
+}
+
+// factor on the tick interval, below 1 while a surge lasts
+#[derive(Resource)]
+pub struct SpeedSurge(pub f64);
+
+#[derive(Clone, Copy, PartialEq)]
+enum HazardKind {
+    Rock, // a wall while it lasts
+    RottenApple,
+}
+
+#[derive(Component)]
+struct Hazard {
+    kind: HazardKind,
+    position: (i32, i32),
+    seconds_left: f32,
+}
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DirectorTable>()
+            .init_asset::<DirectorTable>()
+            .register_asset_loader(RonAssetLoader::<DirectorTable>::new(&["director.ron"]))
+            .init_resource::<Director>()
+            .insert_resource(SpeedSurge(1.0))
+            .add_systems(Startup, load_director_table)
+            .add_systems(
+                FixedUpdate,
+                (
+                    eat_rotten_apples.in_set(SnakeSystemSet::Collision),
+                    (expire_hazards, schedule_hazards)
+                        .chain()
+                        .in_set(SnakeSystemSet::Spawning),
+                ),
+            )
+            .add_systems(Update, reload_director_table);
+    }
+}
+
+fn load_director_table(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DirectorTableHandle(asset_server.load("director.ron")));
+}
+
+fn reload_director_table(
+    mut director_table_events: EventReader<AssetEvent<DirectorTable>>,
+    director_table_handle: Res<DirectorTableHandle>,
+    director_tables: Res<Assets<DirectorTable>>,
+    mut director_table: ResMut<DirectorTable>,
+) {
+    for event in director_table_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != director_table_handle.0.id() {
+            continue;
+        }
+        if let Some(loaded_director_table) = director_tables.get(*id) {
+            *director_table = loaded_director_table.clone();
+        }
+    }
+}
+
+// counts a hazard's timer down, Some with the wave size when it is due
+fn due(
+    next: &mut Option<f32>,
+    schedule: &HazardSchedule,
+    pressure: f32,
+    delta_seconds: f32,
+) -> Option<f32> {
+    if pressure < schedule.from_pressure {
+        return None;
+    }
+    let seconds_left = next.get_or_insert_with(|| schedule.interval_seconds.at(pressure));
+    *seconds_left -= delta_seconds;
+    if *seconds_left > 0.0 {
+        return None;
+    }
+    *next = Some(schedule.interval_seconds.at(pressure));
+    Some(schedule.amount.at(pressure))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn schedule_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    director_table: Res<DirectorTable>,
+    score: Res<Score>,
+    mut director: ResMut<Director>,
+    mut speed_surge: ResMut<SpeedSurge>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut run_rng: ResMut<RunRng>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    hazard_query: Query<&Hazard>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    let delta_seconds = time.delta_seconds();
+    director.elapsed += delta_seconds;
+    let length = snake_head_query
+        .iter()
+        .map(|snake_head| snake_head.segments.len())
+        .max()
+        .unwrap_or(0);
+    let pressure = director.elapsed * director_table.pressure_per_second
+        + length as f32 * director_table.pressure_per_segment
+        + score.0 as f32 * director_table.pressure_per_point;
+    let director = &mut *director;
+
+    // cells taken by anything a hazard shouldn't land on
+    let mut used_positions: Vec<(i32, i32)> = snake_body_query
+        .iter()
+        .map(|snake_body| snake_body.position)
+        .collect();
+    for apple in &apple_query {
+        used_positions.extend_from_slice(&apple.footprint);
+    }
+    used_positions.extend(hazard_query.iter().map(|hazard| hazard.position));
+    // none once the board is full
+    let mut free_cell = |used_positions: &mut Vec<(i32, i32)>, playfield_mask: &PlayfieldMask| {
+        let position = get_valid_apple_spawn(
+            &mut run_rng.0,
+            playfield_mask,
+            used_positions.clone(),
+            &snake_head_query,
+            1,
+            None,
+        )?[0];
+        used_positions.push(position);
+        Some(position)
+    };
+
+    if let Some(amount) = due(
+        &mut director.next_rocks,
+        &director_table.rocks,
+        pressure,
+        delta_seconds,
+    ) {
+        let rocks = amount.round() as usize;
+        for _ in 0..rocks {
+            let Some(position) = free_cell(&mut used_positions, &playfield_mask) else {
+                break;
+            };
+            playfield_mask.add_wall(position);
+            commands.spawn((
+                hazard_sprite(position, ROCK_COLOR, 1.0),
+                Hazard {
+                    kind: HazardKind::Rock,
+                    position,
+                    seconds_left: director_table.rocks.lifetime_seconds,
+                },
+            ));
+        }
+        info!(rocks, pressure, "Rocks fell");
+        event_logged_event.send(EventLogged(format!("{rocks} rocks fell")));
+    }
+    if let Some(amount) = due(
+        &mut director.next_rotten_apples,
+        &director_table.rotten_apples,
+        pressure,
+        delta_seconds,
+    ) {
+        let apples = amount.round() as usize;
+        for _ in 0..apples {
+            let Some(position) = free_cell(&mut used_positions, &playfield_mask) else {
+                break;
+            };
+            commands.spawn((
+                hazard_sprite(position, ROTTEN_COLOR, 0.8),
+                Hazard {
+                    kind: HazardKind::RottenApple,
+                    position,
+                    seconds_left: director_table.rotten_apples.lifetime_seconds,
+                },
+            ));
+        }
+        info!(apples, pressure, "Rotten apples spawned");
+        event_logged_event.send(EventLogged("Rotten apples appeared".to_string()));
+    }
+    if let Some(factor) = due(
+        &mut director.next_speed_surge,
+        &director_table.speed_surges,
+        pressure,
+        delta_seconds,
+    ) {
+        speed_surge.0 = factor as f64;
+        director.surge_left = director_table.speed_surges.lifetime_seconds;
+        info!(factor, pressure, "Speed surge");
+        event_logged_event.send(EventLogged("Speed surge!".to_string()));
+    }
+}
+
+fn hazard_sprite(position: (i32, i32), color: Color, scale: f32) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(PIXEL_UNIT_SIZE * scale)),
+            ..default()
+        },
+        transform: Transform::from_translation(Vec3::new(
+            position.0 as f32 * PIXEL_UNIT_SIZE,
+            position.1 as f32 * PIXEL_UNIT_SIZE,
+            0.0,
+        )),
+        ..default()
+    }
+}
+
+fn expire_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut director: ResMut<Director>,
+    mut speed_surge: ResMut<SpeedSurge>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut hazard_query: Query<(Entity, &mut Hazard)>,
+) {
+    let delta_seconds = time.delta_seconds();
+    for (entity, mut hazard) in &mut hazard_query {
+        hazard.seconds_left -= delta_seconds;
+        if hazard.seconds_left > 0.0 {
+            continue;
+        }
+        if hazard.kind == HazardKind::Rock {
+            playfield_mask.remove_wall(hazard.position);
+        }
+        commands.entity(entity).despawn();
+    }
+    if director.surge_left > 0.0 {
+        director.surge_left -= delta_seconds;
+        if director.surge_left <= 0.0 {
+            speed_surge.0 = 1.0;
+        }
+    }
+}
+
+// anything passing over one takes the hit, player one's points included
+fn eat_rotten_apples(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    hazard_query: Query<(Entity, &Hazard)>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    for (entity, hazard) in &hazard_query {
+        if hazard.kind != HazardKind::RottenApple {
+            continue;
+        }
+        let Some((player, mut snake_head)) = snake_head_query
+            .iter_mut()
+            .find(|(_, snake_head)| snake_head.path.contains(&hazard.position))
+        else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        for _ in 0..ROTTEN_SHRINK {
+            shrink_snake(&mut commands, &mut snake_head, &snake_body_query);
+        }
+        if *player == Player::ONE {
+            score.0 = score.0.saturating_sub(ROTTEN_PENALTY);
+            event_logged_event.send(EventLogged(format!("Rotten apple: -{ROTTEN_PENALTY}")));
+        }
+    }
+}
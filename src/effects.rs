@@ -0,0 +1,166 @@
+// Ambient effects
+// Drifting particles, a background pulse on combos and a vignette that tightens with speed
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::graphics::{effects_enabled, GraphicsSettings};
+use crate::score::Combo;
+use crate::theme::Theme;
+use crate::{speed_factor, Background, MovementSettings, PIXEL_UNIT_SIZE, PLAYFIELD};
+
+const PARTICLE_COUNT: usize = 40;
+const PARTICLE_SPEED: f32 = 12.0;
+const PULSE_DECAY: f32 = 2.5; // pulse strength lost per second
+const PULSE_PER_COMBO: f32 = 0.04;
+const MAX_PULSE: f32 = 0.25;
+const VIGNETTE_RINGS: usize = 6;
+const VIGNETTE_BASE_WIDTH: f32 = 24.0; // outermost ring width at classic speed
+const VIGNETTE_MAX_WIDTH: f32 = 96.0;
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+}
+
+#[derive(Component)]
+struct VignetteRing(usize);
+
+#[derive(Resource, Default)]
+struct BackgroundPulse {
+    strength: f32,
+    last_combo: u32,
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundPulse>()
+            .add_systems(Startup, (spawn_particles, spawn_vignette))
+            .add_systems(
+                Update,
+                (
+                    show_effects.run_if(resource_changed::<GraphicsSettings>()),
+                    pulse_background,
+                    (drift_particles, tighten_vignette).run_if(effects_enabled),
+                ),
+            );
+    }
+}
+
+fn spawn_particles(mut commands: Commands) {
+    let mut rng = rand::thread_rng();
+    let half_extents = playfield_half_extents();
+    for _ in 0..PARTICLE_COUNT {
+        let size = rng.gen_range(2.0..5.0);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::WHITE.with_a(rng.gen_range(0.05..0.2)),
+                    custom_size: Some(Vec2::new(size, size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    rng.gen_range(-half_extents.x..half_extents.x),
+                    rng.gen_range(-half_extents.y..half_extents.y),
+                    -0.08,
+                )),
+                ..default()
+            },
+            Particle {
+                velocity: Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+                    * PARTICLE_SPEED
+                    * rng.gen_range(0.5..1.5),
+            },
+        ));
+    }
+}
+
+fn spawn_vignette(mut commands: Commands) {
+    for ring in 0..VIGNETTE_RINGS {
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                border_color: Color::BLACK.with_a(0.1).into(),
+                z_index: ZIndex::Global(-1),
+                ..default()
+            },
+            VignetteRing(ring),
+        ));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn show_effects(
+    graphics_settings: Res<GraphicsSettings>,
+    mut effect_query: Query<&mut Visibility, Or<(With<Particle>, With<VignetteRing>)>>,
+) {
+    let visibility = if effects_enabled(graphics_settings) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut effect_visibility in &mut effect_query {
+        *effect_visibility = visibility;
+    }
+}
+
+fn playfield_half_extents() -> Vec2 {
+    Vec2::new(
+        PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE / 2.0,
+        PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE / 2.0,
+    )
+}
+
+fn drift_particles(time: Res<Time>, mut particle_query: Query<(&Particle, &mut Transform)>) {
+    let half_extents = playfield_half_extents();
+    for (particle, mut transform) in &mut particle_query {
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        // wrap around the board edges
+        if transform.translation.x.abs() > half_extents.x {
+            transform.translation.x = -transform.translation.x.signum() * half_extents.x;
+        }
+        if transform.translation.y.abs() > half_extents.y {
+            transform.translation.y = -transform.translation.y.signum() * half_extents.y;
+        }
+    }
+}
+
+fn pulse_background(
+    time: Res<Time>,
+    graphics_settings: Res<GraphicsSettings>,
+    combo: Res<Combo>,
+    theme: Res<Theme>,
+    mut pulse: ResMut<BackgroundPulse>,
+    mut background_query: Query<&mut Sprite, With<Background>>,
+) {
+    if effects_enabled(graphics_settings) && combo.count > pulse.last_combo && combo.count > 1 {
+        pulse.strength = (combo.count as f32 * PULSE_PER_COMBO).min(MAX_PULSE);
+    }
+    pulse.last_combo = combo.count;
+    pulse.strength =
+        (pulse.strength - PULSE_DECAY * time.delta_seconds() * pulse.strength).max(0.0);
+    let strength = pulse.strength;
+    for mut sprite in &mut background_query {
+        let [r, g, b, a] = theme.background.as_rgba_f32();
+        sprite.color = Color::rgba(r + strength, g + strength, b + strength, a);
+    }
+}
+
+fn tighten_vignette(
+    movement_settings: Res<MovementSettings>,
+    fixed_time: Res<Time<Fixed>>,
+    mut ring_query: Query<(&VignetteRing, &mut Style)>,
+) {
+    let width = (VIGNETTE_BASE_WIDTH * speed_factor(&movement_settings, &fixed_time))
+        .min(VIGNETTE_MAX_WIDTH);
+    for (ring, mut style) in &mut ring_query {
+        let ring_width = width * (ring.0 + 1) as f32 / VIGNETTE_RINGS as f32;
+        style.border = UiRect::all(Val::Px(ring_width));
+    }
+}
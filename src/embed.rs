@@ -0,0 +1,126 @@
+// Embedding
+// A host app can give the board a rect in its own world instead of the window it would otherwise
+// fill: everything the game draws in the world hangs off one root entity moved and scaled into
+// that rect, and the host's camera looks at it rather than one of ours. The HUD stays an overlay
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::camera::board_size;
+
+// where the board goes in the host's world
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct BoardPlacement {
+    pub center: Vec2,
+    pub scale: f32,
+}
+
+impl BoardPlacement {
+    // as large as fits in the rect without stretching, centered in it
+    pub fn fit(rect: Rect) -> Self {
+        let scale = (rect.size() / board_size()).min_element();
+        BoardPlacement {
+            center: rect.center(),
+            scale,
+        }
+    }
+}
+
+#[derive(Component)]
+struct BoardRoot;
+
+pub struct EmbedPlugin {
+    pub placement: BoardPlacement,
+}
+
+impl Plugin for EmbedPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.placement)
+            .add_systems(Startup, setup_board_root)
+            .add_systems(
+                PostUpdate,
+                (
+                    place_board_root,
+                    (adopt_board_entities, forget_despawned_children),
+                    // adopted this frame, drawn in place this frame
+                    apply_deferred,
+                )
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn setup_board_root(mut commands: Commands) {
+    commands.spawn((SpatialBundle::default(), BoardRoot));
+}
+
+// the host may move the board around by changing the placement
+fn place_board_root(
+    placement: Res<BoardPlacement>,
+    mut root_query: Query<&mut Transform, With<BoardRoot>>,
+) {
+    if !placement.is_changed() {
+        return;
+    }
+    for mut transform in &mut root_query {
+        *transform = Transform::from_translation(placement.center.extend(0.0))
+            .with_scale(Vec3::splat(placement.scale));
+    }
+}
+
+// game systems keep spawning and moving things in board space, their transforms just become
+// relative to the root
+#[allow(clippy::type_complexity)]
+fn adopt_board_entities(
+    mut commands: Commands,
+    root_query: Query<Entity, With<BoardRoot>>,
+    entity_query: Query<
+        Entity,
+        (
+            Added<GlobalTransform>,
+            Without<Parent>,
+            Without<Node>,
+            Without<Camera>,
+            Without<BoardRoot>,
+        ),
+    >,
+) {
+    let Ok(root) = root_query.get_single() else {
+        return;
+    };
+    for entity in &entity_query {
+        commands.entity(root).add_child(entity);
+    }
+}
+
+// a plain despawn leaves the entity in the root's children, with the game spawning and despawning
+// segments and apples all run long that list would only grow
+fn forget_despawned_children(
+    mut commands: Commands,
+    mut removed_parents: RemovedComponents<Parent>,
+    root_query: Query<Entity, With<BoardRoot>>,
+) {
+    if removed_parents.read().count() == 0 {
+        return;
+    }
+    for root in &root_query {
+        commands.add(PruneChildren(root));
+    }
+}
+
+struct PruneChildren(Entity);
+
+impl Command for PruneChildren {
+    fn apply(self, world: &mut World) {
+        let Some(children) = world.entity_mut(self.0).take::<Children>() else {
+            return;
+        };
+        let alive: Vec<Entity> = children
+            .iter()
+            .copied()
+            .filter(|child| world.get_entity(*child).is_some())
+            .collect();
+        world.entity_mut(self.0).push_children(&alive);
+    }
+}
@@ -0,0 +1,57 @@
+// Errors
+// Recoverable problems reported by systems and logged in one place, instead of panicking mid-frame
+use bevy::ecs::query::QuerySingleError;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum GameError {
+    #[error("{system}: expected a single {entity}, found several")]
+    MultipleEntities {
+        system: &'static str,
+        entity: &'static str,
+    },
+}
+
+#[derive(Event)]
+pub struct ErrorReported(pub GameError);
+
+pub struct ErrorsPlugin;
+
+impl Plugin for ErrorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ErrorReported>()
+            .add_systems(Last, log_errors);
+    }
+}
+
+// a missing entity is normal (before the first spawn, during respawns) and yields None
+// quietly, duplicates are a bug and get reported
+pub fn single_or_report<T>(
+    result: Result<T, QuerySingleError>,
+    system: &'static str,
+    entity: &'static str,
+    error_events: &mut EventWriter<ErrorReported>,
+) -> Option<T> {
+    match result {
+        Ok(item) => Some(item),
+        Err(QuerySingleError::NoEntities(_)) => None,
+        Err(QuerySingleError::MultipleEntities(_)) => {
+            error_events.send(ErrorReported(GameError::MultipleEntities {
+                system,
+                entity,
+            }));
+            None
+        }
+    }
+}
+
+// each distinct error is logged once, a broken system would otherwise report every frame
+fn log_errors(mut error_events: EventReader<ErrorReported>, mut logged: Local<HashSet<GameError>>) {
+    for ErrorReported(error) in error_events.read() {
+        if logged.insert(error.clone()) {
+            error!("{error}");
+        }
+    }
+}
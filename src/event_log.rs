@@ -0,0 +1,252 @@
+// Event log
+// A collapsible panel under the score listing what just happened ("Big apple spawned", "Shield
+// used", "Combo x4!"), each line fading out after a few seconds. Clicking its header or pressing E
+// folds it away. Modes send EventLogged for their own lines, the default font is ASCII only
+use bevy::prelude::*;
+
+use crate::achievements::AchievementEarned;
+use crate::items::{ItemEffect, ItemTable, PowerUpCollected};
+use crate::menu::menu_closed;
+use crate::score::Combo;
+use crate::shield::ShieldBroken;
+use crate::{Apple, Player, SnakeDied};
+
+const ENTRY_SECONDS: f32 = 6.0;
+const MAX_ENTRIES: usize = 8;
+const MIN_LOGGED_COMBO: u32 = 3;
+const HEADER_COLOR: Color = Color::GOLD;
+
+// a line for the log
+#[derive(Event)]
+pub struct EventLogged(pub String);
+
+#[derive(Resource)]
+struct EventLog {
+    collapsed: bool,
+    entries: Vec<(String, Timer)>,
+}
+
+#[derive(Component)]
+struct EventLogHeader;
+
+#[derive(Component)]
+struct EventLogEntries;
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EventLog {
+            collapsed: false,
+            entries: Vec::new(),
+        })
+        .add_event::<EventLogged>()
+        .add_systems(Startup, setup_event_log)
+        .add_systems(
+            Update,
+            (
+                (
+                    log_apples,
+                    log_items,
+                    log_combos,
+                    log_achievements,
+                    log_deaths,
+                ),
+                toggle_event_log.run_if(menu_closed),
+                collect_entries,
+                show_event_log,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn setup_event_log(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(48.0),
+                left: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: HEADER_COLOR,
+                        ..default()
+                    },
+                ),
+                Interaction::None,
+                EventLogHeader,
+            ));
+            parent.spawn((TextBundle::default(), EventLogEntries));
+        });
+}
+
+// rarer apples than the common ones are worth a line
+fn log_apples(
+    apple_query: Query<&Name, Added<Apple>>,
+    item_table: Res<ItemTable>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    let most_common = item_table
+        .apples
+        .iter()
+        .map(|definition| definition.spawn_weight)
+        .max()
+        .unwrap_or(0);
+    for name in &apple_query {
+        let rare = item_table.apples.iter().any(|definition| {
+            definition.name == name.as_str() && definition.spawn_weight < most_common
+        });
+        if rare {
+            event_logged_event.send(EventLogged(format!("{name} spawned")));
+        }
+    }
+}
+
+fn log_items(
+    mut power_up_collected_event: EventReader<PowerUpCollected>,
+    mut shield_broken_event: EventReader<ShieldBroken>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    for event in power_up_collected_event.read() {
+        for effect in &event.effects {
+            let name = match effect {
+                ItemEffect::Shield => "Shield",
+                ItemEffect::Magnet => "Magnet",
+                ItemEffect::Stone => "Stone",
+            };
+            event_logged_event.send(EventLogged(format!("{name} picked up")));
+        }
+    }
+    for _ in shield_broken_event.read() {
+        event_logged_event.send(EventLogged("Shield used".to_string()));
+    }
+}
+
+// once per new count, from the third apple in a row on
+fn log_combos(
+    combo: Res<Combo>,
+    mut logged: Local<u32>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    if combo.count >= MIN_LOGGED_COMBO && combo.count > *logged {
+        event_logged_event.send(EventLogged(format!("Combo x{}!", combo.count)));
+    }
+    *logged = combo.count;
+}
+
+fn log_achievements(
+    mut achievement_earned_event: EventReader<AchievementEarned>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    for event in achievement_earned_event.read() {
+        event_logged_event.send(EventLogged(format!("Achievement: {}", event.0.name())));
+    }
+}
+
+fn log_deaths(
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    for event in snake_died_event.read() {
+        let who = if event.player == Player::ONE {
+            "You".to_string()
+        } else {
+            format!("P{}", event.player.0)
+        };
+        event_logged_event.send(EventLogged(format!("{who} died ({:?})", event.cause)));
+    }
+}
+
+fn toggle_event_log(
+    keyboard_input: Res<Input<KeyCode>>,
+    header_query: Query<&Interaction, (Changed<Interaction>, With<EventLogHeader>)>,
+    mut event_log: ResMut<EventLog>,
+) {
+    let clicked = header_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if clicked || keyboard_input.just_pressed(KeyCode::E) {
+        event_log.collapsed = !event_log.collapsed;
+    }
+}
+
+// entries keep timing out while the panel is collapsed
+fn collect_entries(
+    time: Res<Time<Real>>,
+    mut event_logged_event: EventReader<EventLogged>,
+    mut event_log: ResMut<EventLog>,
+) {
+    for event in event_logged_event.read() {
+        event_log.entries.push((
+            event.0.clone(),
+            Timer::from_seconds(ENTRY_SECONDS, TimerMode::Once),
+        ));
+    }
+    if event_log.entries.is_empty() {
+        return;
+    }
+    for (_, timer) in &mut event_log.entries {
+        timer.tick(time.delta());
+    }
+    event_log.entries.retain(|(_, timer)| !timer.finished());
+    let excess = event_log.entries.len().saturating_sub(MAX_ENTRIES);
+    event_log.entries.drain(..excess);
+}
+
+#[allow(clippy::type_complexity)]
+fn show_event_log(
+    event_log: Res<EventLog>,
+    mut header_query: Query<&mut Text, With<EventLogHeader>>,
+    mut entries_query: Query<
+        (&mut Text, &mut Visibility),
+        (With<EventLogEntries>, Without<EventLogHeader>),
+    >,
+) {
+    if !event_log.is_changed() {
+        return;
+    }
+    for mut text in &mut header_query {
+        let count = event_log.entries.len();
+        text.sections[0].value = match (event_log.collapsed, count) {
+            (true, 0) => "+ Events (E)".to_string(),
+            (true, count) => format!("+ Events ({count})"),
+            (false, _) => "- Events".to_string(),
+        };
+    }
+    for (mut text, mut visibility) in &mut entries_query {
+        *visibility = if event_log.collapsed {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        text.sections = event_log
+            .entries
+            .iter()
+            .rev()
+            .map(|(line, timer)| {
+                // fading out over the last two seconds, newest on top
+                let alpha = (timer.remaining_secs() / 2.0).min(1.0);
+                TextSection::new(
+                    format!("{line}\n"),
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE.with_a(alpha),
+                        ..default()
+                    },
+                )
+            })
+            .collect();
+    }
+}
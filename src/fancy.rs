@@ -0,0 +1,200 @@
+// Fancy graphics
+// With --fancy-graphics (or Fancy in the settings) the snakes get custom shaders drawn over their
+// sprites: slowly shimmering scales on the body and glowing eyes on the head that follow its
+// heading. The overlays only shade, the sprite underneath still carries the theme's colors and
+// any tint, so everything else keeps working on the sprites alone
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::transform::TransformSystem;
+
+use crate::graphics::{GraphicsQuality, GraphicsSettings};
+use crate::{SnakeBody, SnakeHead, PIXEL_UNIT_SIZE};
+
+const OVERLAY_DEPTH: f32 = 0.05; // above the sprite it shades
+const SCALE_SHADE: Color = Color::rgba(0.0, 0.0, 0.0, 0.35);
+const SCALES_PER_CELL: f32 = 3.0;
+const SHIMMER_SPEED: f32 = 1.5;
+const EYE_GLOW: Color = Color::rgb(1.0, 0.95, 0.4);
+const EYE_PULSE_SPEED: f32 = 3.0;
+
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+struct ScalesMaterial {
+    #[uniform(0)]
+    shade: Color,
+    #[uniform(0)]
+    cell_size: f32,
+    #[uniform(0)]
+    scales_per_cell: f32,
+    #[uniform(0)]
+    shimmer_speed: f32,
+}
+
+impl Material2d for ScalesMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/snake_scales.wgsl".into()
+    }
+}
+
+// one per head, for its own heading
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+struct HeadMaterial {
+    #[uniform(0)]
+    glow: Color,
+    #[uniform(0)]
+    direction: Vec2,
+    #[uniform(0)]
+    pulse_speed: f32,
+}
+
+impl Material2d for HeadMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/snake_head.wgsl".into()
+    }
+}
+
+// the cell quad and the scales every segment shares
+#[derive(Resource)]
+struct FancyAssets {
+    quad: Mesh2dHandle,
+    scales: Handle<ScalesMaterial>,
+}
+
+// on the overlay, for the segment or head it follows; segments are despawned without their
+// children, so overlays are not children and go away on their own
+#[derive(Component)]
+struct Overlay(Entity);
+
+#[derive(Component)]
+struct HeadOverlay(Handle<HeadMaterial>);
+
+// has its overlay already
+#[derive(Component)]
+struct Dressed;
+
+pub struct FancyGraphicsPlugin;
+
+impl Plugin for FancyGraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            Material2dPlugin::<ScalesMaterial>::default(),
+            Material2dPlugin::<HeadMaterial>::default(),
+        ))
+        .add_systems(Startup, setup_fancy_assets)
+        .add_systems(Update, (dress_segments, dress_heads).run_if(fancy_graphics))
+        .add_systems(
+            PostUpdate,
+            (follow_owners, turn_eyes).before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+fn fancy_graphics(graphics_settings: Res<GraphicsSettings>) -> bool {
+    graphics_settings.quality == GraphicsQuality::Fancy && !graphics_settings.low_power
+}
+
+fn setup_fancy_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut scales_materials: ResMut<Assets<ScalesMaterial>>,
+) {
+    commands.insert_resource(FancyAssets {
+        quad: meshes
+            .add(shape::Quad::new(Vec2::splat(PIXEL_UNIT_SIZE)).into())
+            .into(),
+        scales: scales_materials.add(ScalesMaterial {
+            shade: SCALE_SHADE,
+            cell_size: PIXEL_UNIT_SIZE,
+            scales_per_cell: SCALES_PER_CELL,
+            shimmer_speed: SHIMMER_SPEED,
+        }),
+    });
+}
+
+fn dress_segments(
+    mut commands: Commands,
+    fancy_assets: Res<FancyAssets>,
+    segment_query: Query<Entity, (With<SnakeBody>, Without<Dressed>)>,
+) {
+    for segment in &segment_query {
+        commands.entity(segment).insert(Dressed);
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: fancy_assets.quad.clone(),
+                material: fancy_assets.scales.clone(),
+                ..default()
+            },
+            Overlay(segment),
+        ));
+    }
+}
+
+fn dress_heads(
+    mut commands: Commands,
+    fancy_assets: Res<FancyAssets>,
+    mut head_materials: ResMut<Assets<HeadMaterial>>,
+    head_query: Query<Entity, (With<SnakeHead>, Without<Dressed>)>,
+) {
+    for head in &head_query {
+        let material = head_materials.add(HeadMaterial {
+            glow: EYE_GLOW,
+            direction: Vec2::X,
+            pulse_speed: EYE_PULSE_SPEED,
+        });
+        commands.entity(head).insert(Dressed);
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: fancy_assets.quad.clone(),
+                material: material.clone(),
+                ..default()
+            },
+            Overlay(head),
+            HeadOverlay(material),
+        ));
+    }
+}
+
+// overlays sit on their owner, hide with it and with the setting, and go when it does
+#[allow(clippy::type_complexity)]
+fn follow_owners(
+    mut commands: Commands,
+    graphics_settings: Res<GraphicsSettings>,
+    owner_query: Query<(&Transform, &Visibility), (With<Dressed>, Without<Overlay>)>,
+    mut overlay_query: Query<(Entity, &Overlay, &mut Transform, &mut Visibility)>,
+) {
+    let shown = fancy_graphics(graphics_settings);
+    for (entity, overlay, mut transform, mut visibility) in &mut overlay_query {
+        let Ok((owner_transform, owner_visibility)) = owner_query.get(overlay.0) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        *transform = *owner_transform;
+        transform.translation.z += OVERLAY_DEPTH;
+        *visibility = if shown {
+            *owner_visibility
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn turn_eyes(
+    head_query: Query<&SnakeHead, Changed<SnakeHead>>,
+    overlay_query: Query<(&Overlay, &HeadOverlay)>,
+    mut head_materials: ResMut<Assets<HeadMaterial>>,
+) {
+    for (overlay, head_overlay) in &overlay_query {
+        let Ok(snake_head) = head_query.get(overlay.0) else {
+            continue;
+        };
+        let (x, y) = snake_head.direction.delta();
+        let direction = Vec2::new(x as f32, y as f32);
+        // a write re-uploads the material, most ticks the heading stays the same
+        let turned = head_materials
+            .get(&head_overlay.0)
+            .is_some_and(|material| material.direction != direction);
+        if let Some(material) = head_materials.get_mut(&head_overlay.0).filter(|_| turned) {
+            material.direction = direction;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// Frame budget
+// Watches how long frames take and, when they stay over budget for a few seconds (an integrated
+// GPU on a huge board, say), turns the graphics down a step: the CRT filter first, then the snake
+// shaders, then the ambient particles and effects. Each step is noted in the event log. It never
+// turns them back up, and changing the graphics settings by hand, or --no-auto-quality, leaves
+// them alone for good
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::event_log::EventLogged;
+use crate::graphics::{frame_cap, DisplaySettings, GraphicsQuality, GraphicsSettings};
+
+const FRAME_BUDGET_SECONDS: f32 = 1.0 / 50.0; // or a little over the frame cap's frame time
+const OVER_BUDGET_SECONDS: f32 = 3.0; // of slow frames before a step down
+const SETTLE_SECONDS: f32 = 5.0; // at startup and after a step, while pipelines get compiled
+const SMOOTHING: f32 = 0.1; // weight of the newest frame in the average
+const HITCH_SECONDS: f32 = 0.25; // longer frames are a stall or a dragged window, not load
+
+#[derive(Resource)]
+struct FrameBudget {
+    average: f32,
+    over_budget: f32,
+    settle: f32,
+    written: Option<(GraphicsQuality, bool)>, // what was last set here
+    overridden: bool,
+}
+
+pub struct FrameBudgetPlugin;
+
+impl Plugin for FrameBudgetPlugin {
+    fn build(&self, app: &mut App) {
+        if Args::of(app).has("--no-auto-quality") {
+            return;
+        }
+        app.insert_resource(FrameBudget {
+            average: 0.0,
+            over_budget: 0.0,
+            settle: SETTLE_SECONDS,
+            written: None,
+            overridden: false,
+        })
+        .add_systems(Last, scale_quality);
+    }
+}
+
+// one step down, None when there is nothing left to turn off
+fn lowered(graphics_settings: &GraphicsSettings) -> Option<(GraphicsQuality, bool, &'static str)> {
+    match (graphics_settings.crt, graphics_settings.quality) {
+        (true, quality) => Some((quality, false, "CRT filter")),
+        (false, GraphicsQuality::Fancy) => Some((GraphicsQuality::High, false, "snake shaders")),
+        (false, GraphicsQuality::High) => Some((GraphicsQuality::Low, false, "ambient effects")),
+        (false, GraphicsQuality::Low) => None,
+    }
+}
+
+fn scale_quality(
+    time: Res<Time<Real>>,
+    mut frame_budget: ResMut<FrameBudget>,
+    display_settings: Res<DisplaySettings>,
+    mut graphics_settings: ResMut<GraphicsSettings>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    let current = (graphics_settings.quality, graphics_settings.crt);
+    if frame_budget.overridden {
+        return;
+    }
+    if frame_budget
+        .written
+        .is_some_and(|written| written != current)
+    {
+        info!("Graphics changed by hand, automatic quality scaling is off");
+        frame_budget.overridden = true;
+        return;
+    }
+    frame_budget.written = Some(current);
+
+    let budget = frame_cap(&display_settings, &graphics_settings)
+        .map_or(FRAME_BUDGET_SECONDS, |frame_cap| {
+            FRAME_BUDGET_SECONDS.max(1.1 / frame_cap.max(1.0))
+        });
+    let delta = time.delta_seconds();
+    if frame_budget.settle > 0.0 {
+        frame_budget.settle -= delta;
+        frame_budget.average = budget;
+        return;
+    }
+    if delta > HITCH_SECONDS {
+        return;
+    }
+    frame_budget.average += (delta - frame_budget.average) * SMOOTHING;
+    if frame_budget.average <= budget {
+        frame_budget.over_budget = 0.0;
+        return;
+    }
+    frame_budget.over_budget += delta;
+    if frame_budget.over_budget < OVER_BUDGET_SECONDS {
+        return;
+    }
+    frame_budget.over_budget = 0.0;
+    let Some((quality, crt, turned_off)) = lowered(&graphics_settings) else {
+        return;
+    };
+    warn!(
+        average_ms = frame_budget.average * 1000.0,
+        "Frames over budget, turning off the {turned_off}"
+    );
+    event_logged_event.send(EventLogged(format!("Running slow: {turned_off} off")));
+    graphics_settings.quality = quality;
+    graphics_settings.crt = crt;
+    frame_budget.written = Some((quality, crt));
+    frame_budget.settle = SETTLE_SECONDS;
+}
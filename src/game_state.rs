@@ -0,0 +1,121 @@
+// Game state
+// A plain snapshot of the board, taken from the running game or from a headless replay, to write
+// out as JSON or draw as text with render_ascii for bug reports, logs and CI failures
+use bevy::utils::HashMap;
+use serde::Serialize;
+
+use crate::direction::Direction;
+
+#[derive(Serialize, PartialEq)]
+pub struct SnakeState {
+    pub player: u8,
+    pub direction: Direction,
+    pub potential_direction: Direction,
+    pub cells: Vec<(i32, i32)>, // head first
+    pub path: Vec<(i32, i32)>,  // entered during the last tick
+}
+
+#[derive(Serialize, PartialEq)]
+pub struct AppleState {
+    pub footprint: Vec<(i32, i32)>,
+    pub points: u32,
+    pub growth: u32,
+}
+
+#[derive(Serialize, PartialEq)]
+pub struct GameState {
+    pub seed: u64,
+    pub score: u32,
+    pub half_extents: (i32, i32),
+    pub scroll: i32,
+    pub wrap: bool,
+    pub walls: Vec<(i32, i32)>,
+    pub snakes: Vec<SnakeState>,
+    pub apples: Vec<AppleState>,
+    pub power_ups: Vec<(i32, i32)>,
+}
+
+// # walls, heads by player number, o bodies, A apples, + power-ups, X where parts overlap
+pub fn render_ascii(state: &GameState) -> String {
+    let mut cells: HashMap<(i32, i32), char> = HashMap::default();
+    let mut put = |position: (i32, i32), symbol: char| {
+        cells
+            .entry(position)
+            .and_modify(|taken| *taken = 'X')
+            .or_insert(symbol);
+    };
+    for position in &state.walls {
+        put(*position, '#');
+    }
+    for apple in &state.apples {
+        for position in &apple.footprint {
+            put(*position, 'A');
+        }
+    }
+    for position in &state.power_ups {
+        put(*position, '+');
+    }
+    for snake in &state.snakes {
+        let head = char::from_digit(snake.player as u32 % 10, 10).unwrap_or('H');
+        for (index, position) in snake.cells.iter().enumerate() {
+            put(*position, if index == 0 { head } else { 'o' });
+        }
+    }
+    // the border ring and anything out on it, like a head that just hit it
+    let (half_width, half_height) = state.half_extents;
+    let mut grid = String::new();
+    for y in (-half_height - 1..=half_height + 1).rev() {
+        for x in state.scroll - half_width - 1..=state.scroll + half_width + 1 {
+            let border = (x - state.scroll).abs() > half_width || y.abs() > half_height;
+            grid.push(match cells.get(&(x, y)) {
+                Some(symbol) => *symbol,
+                None if border => '#',
+                None => '.',
+            });
+        }
+        grid.push('\n');
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_board() {
+        let state = GameState {
+            seed: 0,
+            score: 0,
+            half_extents: (3, 2),
+            scroll: 0,
+            wrap: false,
+            walls: vec![(-3, 2), (0, 0)],
+            snakes: vec![SnakeState {
+                player: 1,
+                direction: Direction::Right,
+                potential_direction: Direction::Right,
+                cells: vec![(1, -1), (0, -1), (-1, -1), (-1, 0)],
+                path: vec![(1, -1)],
+            }],
+            apples: vec![AppleState {
+                footprint: vec![(2, 1), (0, 0)],
+                points: 1,
+                growth: 1,
+            }],
+            power_ups: vec![(4, 1)],
+        };
+        assert_eq!(
+            render_ascii(&state),
+            "\
+#########
+##......#
+#.....A.+
+#..oX...#
+#..oo1..#
+#.......#
+#########
+"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// Graphics settings
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::storage::{read_ron_save, write_ron_save};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsQuality {
+    Low,   // gameplay visuals only
+    High,  // with ambient effects
+    Fancy, // and shaders on the snakes
+}
+
+#[derive(Resource)]
+pub struct GraphicsSettings {
+    pub quality: GraphicsQuality,
+    pub low_power: bool, // caps the frame rate and skips ambient effects, game logic still ticks normally
+    pub crt: bool,       // the CRT filter over the picture
+}
+
+impl GraphicsSettings {
+    pub fn from_args(args: &Args) -> Self {
+        let quality = if args.has("--low-graphics") {
+            GraphicsQuality::Low
+        } else if args.has("--fancy-graphics") {
+            GraphicsQuality::Fancy
+        } else {
+            GraphicsQuality::High
+        };
+        let low_power = args.has("--low-power");
+        let crt = args.has("--crt");
+        GraphicsSettings {
+            quality,
+            low_power,
+            crt,
+        }
+    }
+}
+
+// run condition for purely cosmetic systems
+pub fn effects_enabled(graphics_settings: Res<GraphicsSettings>) -> bool {
+    graphics_settings.quality != GraphicsQuality::Low && !graphics_settings.low_power
+}
+
+const LOW_POWER_FRAME_CAP: f32 = 30.0;
+const DISPLAY_SETTINGS_FILE: &str = "display.ron";
+
+// window presentation options, saved to display.ron whenever they change
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub vsync: bool,
+    pub frame_cap: Option<f32>, // frames per second, None for uncapped
+    pub borderless: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            vsync: true,
+            frame_cap: None,
+            borderless: false,
+        }
+    }
+}
+
+impl DisplaySettings {
+    // a missing or unreadable file falls back to the defaults
+    pub fn load() -> Self {
+        read_ron_save(DISPLAY_SETTINGS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(error) = write_ron_save(DISPLAY_SETTINGS_FILE, self) {
+            warn!("Could not save display settings: {error}");
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.borderless {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_display_settings)
+            .add_systems(Last, limit_frame_rate);
+    }
+}
+
+fn apply_display_settings(
+    display_settings: Res<DisplaySettings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    // the window is created from the loaded settings, only later edits need applying
+    if !display_settings.is_changed() || display_settings.is_added() {
+        return;
+    }
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.present_mode = display_settings.present_mode();
+        window.mode = display_settings.window_mode();
+    }
+    display_settings.save();
+}
+
+// frames per second the game holds itself to, if any
+pub fn frame_cap(
+    display_settings: &DisplaySettings,
+    graphics_settings: &GraphicsSettings,
+) -> Option<f32> {
+    if graphics_settings.low_power {
+        Some(
+            display_settings
+                .frame_cap
+                .map_or(LOW_POWER_FRAME_CAP, |frame_cap| {
+                    frame_cap.min(LOW_POWER_FRAME_CAP)
+                }),
+        )
+    } else {
+        display_settings.frame_cap
+    }
+}
+
+// sleeps off whatever is left of the frame budget
+fn limit_frame_rate(
+    display_settings: Res<DisplaySettings>,
+    graphics_settings: Res<GraphicsSettings>,
+    mut frame_start: Local<Option<Instant>>,
+) {
+    let frame_cap = frame_cap(&display_settings, &graphics_settings);
+    if let (Some(frame_cap), Some(start)) = (frame_cap, *frame_start) {
+        let frame_time = Duration::from_secs_f32(1.0 / frame_cap.max(1.0));
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            std::thread::sleep(frame_time - elapsed);
+        }
+    }
+    *frame_start = Some(Instant::now());
+}
@@ -0,0 +1,28 @@
+// Gravity
+// --gravity drags the snake one extra cell down every few ticks unless it is heading up, turning
+// the board into something of a platformer. move_snake adds the pull once the snake has turned
+use bevy::prelude::*;
+
+use crate::SnakeSystemSet;
+
+const PULL_TICKS: u32 = 3;
+
+#[derive(Resource, Default)]
+pub struct Gravity {
+    ticks: u32,
+    pub pulling: bool, // on this tick
+}
+
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Gravity>()
+            .add_systems(FixedUpdate, tick_gravity.in_set(SnakeSystemSet::Input));
+    }
+}
+
+fn tick_gravity(mut gravity: ResMut<Gravity>) {
+    gravity.ticks += 1;
+    gravity.pulling = gravity.ticks.is_multiple_of(PULL_TICKS);
+}
@@ -0,0 +1,159 @@
+// Hamiltonian bot
+// Follows a fixed cycle through the board so it can never trap itself, taking shortcuts toward
+// the apple while the snake is short enough that skipping ahead can't reach its own tail
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+
+use crate::ai::{BotView, Greedy, Strategy};
+use crate::direction::Direction;
+use crate::playfield::PlayfieldMask;
+
+// past this share of the cycle the snake just follows it
+const SHORTCUT_MAX_FILL: f32 = 0.5;
+const GROWTH_BUFFER: usize = 4; // cells kept between the new head and the tail for pending growth
+
+#[derive(Clone)]
+pub struct Hamiltonian {
+    // position of each cell along the cycle
+    order: HashMap<(i32, i32), usize>,
+    cells: Vec<(i32, i32)>,
+    // for apples off the cycle
+    fallback: Greedy,
+}
+
+impl Hamiltonian {
+    // an odd by odd board has no cycle through every cell, so this one skips the bottom-right
+    // corner: the bottom row is the way back, columns are walked up and down in turn, and the
+    // last three columns finish with a two wide zigzag ending next to where the bottom row starts.
+    // None when a wall inside the board breaks it, as every shape but the rectangle does
+    pub fn new(playfield_mask: &PlayfieldMask) -> Option<Self> {
+        let (half_width, half_height) = playfield_mask.half_extents();
+        let (width, height) = (2 * half_width + 1, 2 * half_height + 1);
+        // too small to turn around in
+        if width < 3 || height < 3 {
+            return None;
+        }
+        let (min_x, min_y) = (playfield_mask.scroll() - half_width, -half_height);
+        let cell = |x: i32, y: i32| (min_x + x, min_y + y);
+        let mut cells = Vec::new();
+        for x in (0..width - 1).rev() {
+            cells.push(cell(x, 0));
+        }
+        for x in 0..width - 3 {
+            if x % 2 == 0 {
+                cells.extend((1..height).map(|y| cell(x, y)));
+            } else {
+                cells.extend((1..height).rev().map(|y| cell(x, y)));
+            }
+        }
+        cells.extend((1..height).map(|y| cell(width - 3, y)));
+        for y in (1..height).rev() {
+            if (height - 1 - y) % 2 == 0 {
+                cells.extend([cell(width - 2, y), cell(width - 1, y)]);
+            } else {
+                cells.extend([cell(width - 1, y), cell(width - 2, y)]);
+            }
+        }
+        if cells.iter().any(|cell| playfield_mask.is_wall(*cell)) {
+            return None;
+        }
+        let order = cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (*cell, index))
+            .collect();
+        Some(Hamiltonian {
+            order,
+            cells,
+            fallback: Greedy::default(),
+        })
+    }
+
+    // steps forward along the cycle from one cell to another
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + self.cells.len() - from) % self.cells.len()
+    }
+}
+
+impl Strategy for Hamiltonian {
+    fn choose_direction(&mut self, view: &BotView, rng: &mut StdRng) -> Direction {
+        let snake_head = view.snake_head;
+        let apple = view.apple.and_then(|apple| {
+            apple
+                .footprint
+                .iter()
+                .filter_map(|cell| self.order.get(cell).copied())
+                .min_by_key(|index| {
+                    self.order
+                        .get(&snake_head.position)
+                        .map_or(0, |head| self.distance(*head, *index))
+                })
+        });
+        let (Some(head), Some(apple)) = (self.order.get(&snake_head.position).copied(), apple)
+        else {
+            // the head or the apple is in the skipped corner
+            return self.fallback.choose_direction(view, rng);
+        };
+        let next_on_cycle = self.cells[(head + 1) % self.cells.len()];
+        if view.is_blocked(next_on_cycle) {
+            // another snake is in the way
+            return self.fallback.choose_direction(view, rng);
+        }
+        let mut target = next_on_cycle;
+
+        let length = snake_head.segments.len() + 1;
+        if (length as f32) < self.cells.len() as f32 * SHORTCUT_MAX_FILL {
+            let tail = self
+                .order
+                .get(&snake_head.tail_position)
+                .map_or(head, |tail| *tail);
+            // skipping ahead is safe as long as it stays short of the tail
+            let free_ahead = self.distance(head, tail).saturating_sub(GROWTH_BUFFER);
+            let to_apple = self.distance(head, apple);
+            let mut best = 1;
+            for direction in Direction::ALL {
+                let next = direction.step(snake_head.position);
+                if view.is_blocked(next) {
+                    continue;
+                }
+                let Some(index) = self.order.get(&next) else {
+                    continue;
+                };
+                let skip = self.distance(head, *index);
+                if skip > best && skip < free_ahead && skip <= to_apple {
+                    best = skip;
+                    target = next;
+                }
+            }
+        }
+        Direction::ALL
+            .into_iter()
+            .find(|direction| direction.step(snake_head.position) == target)
+            .unwrap_or(snake_head.direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playfield::PlayfieldShape;
+
+    #[test]
+    fn cycle_covers_the_board_but_one_corner() {
+        let playfield_mask = PlayfieldMask::new(PlayfieldShape::Rectangle).with_board(9, 7);
+        let cycle = Hamiltonian::new(&playfield_mask).unwrap();
+        assert_eq!(cycle.cells.len(), 9 * 7 - 1);
+        assert_eq!(cycle.order.len(), cycle.cells.len());
+        assert!(!cycle.order.contains_key(&(4, -3)));
+        for (index, cell) in cycle.cells.iter().enumerate() {
+            let next = cycle.cells[(index + 1) % cycle.cells.len()];
+            assert_eq!((cell.0 - next.0).abs() + (cell.1 - next.1).abs(), 1);
+        }
+    }
+
+    #[test]
+    fn shaped_boards_have_no_cycle() {
+        let playfield_mask = PlayfieldMask::new(PlayfieldShape::Cross);
+        assert!(Hamiltonian::new(&playfield_mask).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+// Heatmap
+// Cell visits and deaths accumulated across sessions in heatmap.ron, shown as a color-graded grid after game over
+use std::collections::HashMap;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::playfield::PlayfieldMask;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::{GameOver, SnakeDied, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const HEATMAP_FILE: &str = "heatmap.ron";
+const COLD_COLOR: Color = Color::rgba(0.1, 0.2, 1.0, 0.15);
+const HOT_COLOR: Color = Color::rgba(1.0, 0.2, 0.1, 0.85);
+const DEATH_COLOR: Color = Color::WHITE;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HeatmapData {
+    runs: u32,
+    visits: HashMap<(i32, i32), u32>,
+    deaths: HashMap<(i32, i32), u32>,
+}
+
+#[derive(Resource)]
+struct Heatmap {
+    data: HeatmapData,
+    run_recorded: bool,
+}
+
+#[derive(Component)]
+struct HeatmapCell;
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Heatmap {
+            data: read_ron_save(HEATMAP_FILE).unwrap_or_default(),
+            run_recorded: false,
+        })
+        .add_systems(FixedUpdate, record_visits.after(SnakeSystemSet::Movement))
+        .add_systems(Update, (record_deaths.before(show_heatmap), show_heatmap))
+        .add_systems(Last, save_heatmap);
+    }
+}
+
+fn record_visits(mut heatmap: ResMut<Heatmap>, snake_head_query: Query<&SnakeHead>) {
+    for snake_head in &snake_head_query {
+        for position in &snake_head.path {
+            *heatmap.data.visits.entry(*position).or_default() += 1;
+        }
+    }
+}
+
+fn record_deaths(
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut heatmap: ResMut<Heatmap>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<&SnakeHead>,
+) {
+    // several collisions can report the same death in one frame, and a dead snake keeps
+    // reporting it while the heatmap is up
+    if snake_died_event.read().count() == 0 || heatmap.run_recorded {
+        return;
+    }
+    for snake_head in &snake_head_query {
+        let position = last_on_board(&playfield_mask, snake_head.position);
+        *heatmap.data.deaths.entry(position).or_default() += 1;
+    }
+}
+
+// a head that ran off the edge is counted at the edge cell it left
+fn last_on_board(playfield_mask: &PlayfieldMask, (x, y): (i32, i32)) -> (i32, i32) {
+    let (half_width, half_height) = playfield_mask.half_extents();
+    let scroll = playfield_mask.scroll();
+    (
+        x.clamp(scroll - half_width, scroll + half_width),
+        y.clamp(-half_height, half_height),
+    )
+}
+
+fn show_heatmap(
+    mut commands: Commands,
+    mut game_over_event: EventReader<GameOver>,
+    mut heatmap: ResMut<Heatmap>,
+    mut time: ResMut<Time<Virtual>>,
+    playfield_mask: Res<PlayfieldMask>,
+) {
+    if game_over_event.read().count() == 0 || heatmap.run_recorded {
+        return;
+    }
+    time.pause();
+    heatmap.data.runs += 1;
+    heatmap.run_recorded = true;
+
+    let max_visits = heatmap.data.visits.values().copied().max().unwrap_or(1) as f32;
+    let max_deaths = heatmap.data.deaths.values().copied().max().unwrap_or(1) as f32;
+    // over this run's board, as far as it has scrolled and closed in
+    let (half_width, half_height) = playfield_mask.half_extents();
+    let scroll = playfield_mask.scroll();
+    for x in scroll - half_width..=scroll + half_width {
+        for y in -half_height..=half_height {
+            let translation =
+                Vec3::new(x as f32 * PIXEL_UNIT_SIZE, y as f32 * PIXEL_UNIT_SIZE, 0.5);
+            if let Some(visits) = heatmap.data.visits.get(&(x, y)) {
+                let heat = (*visits as f32 / max_visits).sqrt();
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: lerp_color(COLD_COLOR, HOT_COLOR, heat),
+                            custom_size: Some(Vec2::splat(PIXEL_UNIT_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(translation),
+                        ..default()
+                    },
+                    HeatmapCell,
+                ));
+            }
+            // deaths as a marker that grows with how often they happened here
+            if let Some(deaths) = heatmap.data.deaths.get(&(x, y)) {
+                let size = PIXEL_UNIT_SIZE * (0.3 + 0.5 * *deaths as f32 / max_deaths);
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: DEATH_COLOR,
+                            custom_size: Some(Vec2::splat(size)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(translation + Vec3::Z * 0.01),
+                        ..default()
+                    },
+                    HeatmapCell,
+                ));
+            }
+        }
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            format!("Heatmap of {} runs", heatmap.data.runs),
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(48.0),
+            justify_self: JustifySelf::Center,
+            ..default()
+        }),
+        HeatmapCell,
+    ));
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let (from, to) = (from.as_rgba_f32(), to.as_rgba_f32());
+    let channel = |index: usize| from[index] + (to[index] - from[index]) * t;
+    Color::rgba(channel(0), channel(1), channel(2), channel(3))
+}
+
+fn save_heatmap(mut app_exit_event: EventReader<AppExit>, mut heatmap: ResMut<Heatmap>) {
+    if app_exit_event.read().count() == 0 {
+        return;
+    }
+    // a run quit before game over still counts
+    if !heatmap.run_recorded {
+        heatmap.data.runs += 1;
+        heatmap.run_recorded = true;
+    }
+    if let Err(error) = write_ron_save(HEATMAP_FILE, &heatmap.data) {
+        warn!("Could not save heatmap: {error}");
+    }
+}
@@ -0,0 +1,723 @@
+// High score
+// Top ten tables across runs, one per rule set so wrap-mode scores don't mix with classic ones,
+// kept in the profile's highscore.ron and written out periodically, on exit and on panic. The game
+// over screen shows the run's table, asks for initials when the run placed, and F or the rules
+// dropdown switches to the other tables. Its Exit button ends the game
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::args::{Args, FlagKind};
+use crate::menu::{navigate_menu, spawn_button, MenuActivated};
+use crate::profile::Profile;
+use crate::score::Score;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::widgets::{spawn_text_input, TextInput, TextSubmitted};
+use crate::{GameOver, MovementSettings};
+
+pub const HIGH_SCORE_FILE: &str = "highscore.ron";
+const AUTOSAVE_SECONDS: f32 = 30.0;
+const TABLE_SIZE: usize = 10;
+const INITIALS_LEN: usize = 3;
+const UNNAMED_INITIALS: &str = "???"; // a run that placed but never got its initials
+const ROW_COLOR: Color = Color::WHITE;
+const RUN_ROW_COLOR: Color = Color::GOLD;
+const OPTION_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
+
+// latest table for the panic hook, which has no access to the world
+static PANIC_SNAPSHOT: Mutex<Option<(String, Leaderboards)>> = Mutex::new(None);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub initials: String,
+    pub score: u32,
+    pub mode: String,
+    pub date: String, // YYYY-MM-DD
+}
+
+// best first, at most TABLE_SIZE entries
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScoreTable {
+    // the table with the entry inserted in order, and where it landed if it made the cut
+    fn with_entry(&self, entry: &HighScoreEntry) -> (HighScoreTable, Option<usize>) {
+        let mut entries = self.entries.clone();
+        let index = entries
+            .iter()
+            .position(|ranked| entry.score > ranked.score)
+            .unwrap_or(entries.len());
+        if entry.score == 0 || index >= TABLE_SIZE {
+            return (HighScoreTable { entries }, None);
+        }
+        entries.insert(index, entry.clone());
+        entries.truncate(TABLE_SIZE);
+        (HighScoreTable { entries }, Some(index))
+    }
+}
+
+// the rules a run was played under, the flags changing them with their values in a fixed order
+#[derive(Clone)]
+pub struct Ruleset {
+    pub id: String, // a hash of the flags, so the same rules match however they were typed
+    pub label: String,
+}
+
+impl Ruleset {
+    pub fn from_args(args: &Args) -> Self {
+        let mut flags: Vec<String> = args
+            .flags(FlagKind::Rules)
+            .chain(args.flags(FlagKind::Difficulty))
+            .map(|flag| match args.value(flag) {
+                Some(value) => format!("{flag} {value}"),
+                None => flag.to_string(),
+            })
+            .collect();
+        flags.sort();
+        flags.dedup();
+        Ruleset::from_flags(&flags)
+    }
+
+    fn from_flags(flags: &[String]) -> Self {
+        let key = flags.join(" ");
+        // FNV-1a, stable across builds unlike the std hasher
+        let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        let label = if key.is_empty() {
+            "Classic".to_string()
+        } else {
+            key.replace("--", "")
+        };
+        Ruleset {
+            id: format!("{hash:016x}"),
+            label,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub label: String,
+    pub table: HighScoreTable,
+}
+
+// by ruleset id
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Leaderboards {
+    pub boards: BTreeMap<String, Leaderboard>,
+}
+
+impl Leaderboards {
+    fn load(file: &str) -> Self {
+        if let Ok(leaderboards) = read_ron_save(file) {
+            return leaderboards;
+        }
+        // files from before there was a table per rule set, and before that a single best score
+        let table = read_ron_save::<HighScoreTable>(file)
+            .or_else(|_| read_ron_save::<LegacyHighScore>(file).map(LegacyHighScore::into_table))
+            .unwrap_or_default();
+        let mut leaderboards = Leaderboards::default();
+        if !table.entries.is_empty() {
+            let classic = Ruleset::from_flags(&[]);
+            leaderboards.boards.insert(
+                classic.id,
+                Leaderboard {
+                    label: classic.label,
+                    table,
+                },
+            );
+        }
+        leaderboards
+    }
+
+    fn save(&self, file: &str) {
+        if let Err(error) = write_ron_save(file, self) {
+            warn!("Could not save high scores: {error}");
+        }
+    }
+
+    fn table(&self, id: &str) -> HighScoreTable {
+        self.boards
+            .get(id)
+            .map(|board| board.table.clone())
+            .unwrap_or_default()
+    }
+
+    fn set_table(&mut self, ruleset: &Ruleset, table: HighScoreTable) {
+        if table.entries.is_empty() {
+            return;
+        }
+        self.boards.insert(
+            ruleset.id.clone(),
+            Leaderboard {
+                label: ruleset.label.clone(),
+                table,
+            },
+        );
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyHighScore {
+    best: u32,
+    #[serde(default)]
+    name: String,
+}
+
+impl LegacyHighScore {
+    fn into_table(self) -> HighScoreTable {
+        let initials = if self.name.is_empty() {
+            UNNAMED_INITIALS.to_string()
+        } else {
+            self.name
+                .chars()
+                .take(INITIALS_LEN)
+                .collect::<String>()
+                .to_uppercase()
+        };
+        HighScoreTable::default()
+            .with_entry(&HighScoreEntry {
+                initials,
+                score: self.best,
+                mode: String::new(),
+                date: String::new(),
+            })
+            .0
+    }
+}
+
+#[derive(Resource)]
+pub struct HighScores {
+    file: Option<String>, // None until a profile is picked, nothing is saved before
+    leaderboards: Leaderboards,
+    ruleset: Ruleset,
+    run: HighScoreEntry,
+    committed: bool, // the run's entry is in its table with its initials
+    placed: Option<usize>,
+}
+
+impl HighScores {
+    // until initials are entered the run is saved as unnamed so quitting or crashing keeps it
+    fn leaderboards_to_save(&self) -> Leaderboards {
+        let mut leaderboards = self.leaderboards.clone();
+        if !self.committed {
+            let (table, _) = self.shown_table(&self.ruleset.id);
+            leaderboards.set_table(&self.ruleset, table);
+        }
+        leaderboards
+    }
+
+    // a table as the game over screen shows it, the run's own with the run in it
+    fn shown_table(&self, id: &str) -> (HighScoreTable, Option<usize>) {
+        let table = self.leaderboards.table(id);
+        if id != self.ruleset.id {
+            (table, None)
+        } else if self.committed {
+            (table, self.placed)
+        } else {
+            table.with_entry(&self.run)
+        }
+    }
+
+    fn label(&self, id: &str) -> String {
+        match self.leaderboards.boards.get(id) {
+            Some(board) => board.label.clone(),
+            None => self.ruleset.label.clone(),
+        }
+    }
+
+    // the run's rule set first, then the others in a fixed order
+    fn ids(&self) -> Vec<String> {
+        let mut ids = vec![self.ruleset.id.clone()];
+        ids.extend(
+            self.leaderboards
+                .boards
+                .keys()
+                .filter(|id| **id != self.ruleset.id)
+                .cloned(),
+        );
+        ids
+    }
+
+    pub fn save(&self) {
+        if let Some(file) = &self.file {
+            self.leaderboards_to_save().save(file);
+        }
+    }
+
+    fn snapshot(&self) {
+        if let (Some(file), Ok(mut snapshot)) = (&self.file, PANIC_SNAPSHOT.lock()) {
+            *snapshot = Some((file.clone(), self.leaderboards_to_save()));
+        }
+    }
+}
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let ruleset = Ruleset::from_args(&Args::of(app));
+        app.insert_resource(HighScores {
+            file: None,
+            leaderboards: Leaderboards::default(),
+            ruleset,
+            run: HighScoreEntry {
+                initials: UNNAMED_INITIALS.to_string(),
+                score: 0,
+                mode: String::new(),
+                date: today(),
+            },
+            committed: false,
+            placed: None,
+        })
+        .init_resource::<LeaderboardFilter>()
+        .insert_resource(AutosaveTimer(Timer::from_seconds(
+            AUTOSAVE_SECONDS,
+            TimerMode::Repeating,
+        )))
+        .add_systems(Startup, (install_panic_hook, setup_game_over_screen))
+        .add_systems(
+            Update,
+            (
+                load_high_scores.run_if(resource_added::<Profile>()),
+                track_run.run_if(resource_changed::<Score>()),
+                autosave,
+                show_game_over_screen,
+                submit_initials,
+                filter_leaderboards,
+                exit_game_over_screen.after(navigate_menu),
+            ),
+        )
+        .add_systems(Last, save_on_exit);
+    }
+}
+
+// real time, so a paused game still gets saved
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+#[derive(Component)]
+struct GameOverScreen;
+
+// the row with the initials field, only shown while entering them
+#[derive(Component)]
+struct InitialsPrompt;
+
+#[derive(Component)]
+struct InitialsInput;
+
+#[derive(Component)]
+struct TableText;
+
+// which table the game over screen shows, None for the run's own
+#[derive(Resource, Default)]
+struct LeaderboardFilter {
+    shown: Option<String>,
+    open: bool,
+}
+
+// the dropdown's current choice, a click opens it
+#[derive(Component)]
+struct FilterButton;
+
+#[derive(Component)]
+struct FilterOptions;
+
+#[derive(Component)]
+struct FilterOption(String);
+
+// hidden while the initials are being entered, Enter submits them first
+#[derive(Component)]
+struct ExitButton;
+
+fn today() -> String {
+    let (year, month, day) = civil_today();
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// days since the epoch to a civil date, after Howard Hinnant's date algorithms
+pub fn civil_today() -> (i64, u32, u32) {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // counted from March
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // try_lock, the panic may have happened while the snapshot was held
+        if let Ok(snapshot) = PANIC_SNAPSHOT.try_lock() {
+            if let Some((file, leaderboards)) = snapshot.as_ref() {
+                leaderboards.save(file);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+fn autosave(
+    time: Res<Time<Real>>,
+    mut autosave_timer: ResMut<AutosaveTimer>,
+    high_scores: Res<HighScores>,
+) {
+    if autosave_timer.0.tick(time.delta()).just_finished() {
+        high_scores.save();
+    }
+}
+
+fn track_run(
+    score: Res<Score>,
+    movement_settings: Res<MovementSettings>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if high_scores.committed {
+        return;
+    }
+    high_scores.run.score = score.0;
+    high_scores.run.mode = movement_settings.mode_name().to_string();
+    high_scores.snapshot();
+}
+
+fn load_high_scores(profile: Res<Profile>, mut high_scores: ResMut<HighScores>) {
+    let file = profile.file(HIGH_SCORE_FILE);
+    high_scores.leaderboards = Leaderboards::load(&file);
+    high_scores.file = Some(file);
+    high_scores.snapshot();
+}
+
+fn setup_game_over_screen(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 24.0,
+        color: ROW_COLOR,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(80.0),
+                    left: Val::Px(12.0),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            GameOverScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Game over - high scores",
+                TextStyle {
+                    font_size: 32.0,
+                    color: RUN_ROW_COLOR,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    InitialsPrompt,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "You placed! Initials:",
+                        text_style,
+                    ));
+                    spawn_text_input(parent, INITIALS_LEN, InitialsInput);
+                });
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: RUN_ROW_COLOR,
+                        ..default()
+                    },
+                ),
+                Interaction::None,
+                FilterButton,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::left(Val::Px(16.0)),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                FilterOptions,
+            ));
+            parent.spawn((TextBundle::default(), TableText));
+            spawn_button(parent, "Exit", ExitButton);
+        });
+}
+
+fn table_sections(table: &HighScoreTable, highlight: Option<usize>) -> Vec<TextSection> {
+    if table.entries.is_empty() {
+        return vec![TextSection::new(
+            "No scores yet",
+            TextStyle {
+                font_size: 24.0,
+                color: ROW_COLOR,
+                ..default()
+            },
+        )];
+    }
+    table
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            TextSection::new(
+                format!(
+                    "{:>2}. {:<3} {:>6}  {:<8} {}\n",
+                    index + 1,
+                    entry.initials,
+                    entry.score,
+                    entry.mode,
+                    entry.date
+                ),
+                TextStyle {
+                    font_size: 24.0,
+                    color: if highlight == Some(index) {
+                        RUN_ROW_COLOR
+                    } else {
+                        ROW_COLOR
+                    },
+                    ..default()
+                },
+            )
+        })
+        .collect()
+}
+
+#[allow(clippy::type_complexity)]
+fn show_game_over_screen(
+    mut game_over_event: EventReader<GameOver>,
+    high_scores: Res<HighScores>,
+    mut screen_query: Query<&mut Visibility, With<GameOverScreen>>,
+    mut visibility_query: Query<
+        (&mut Visibility, Has<InitialsPrompt>),
+        (
+            Or<(With<InitialsPrompt>, With<ExitButton>)>,
+            Without<GameOverScreen>,
+        ),
+    >,
+    mut filter: ResMut<LeaderboardFilter>,
+    mut time: ResMut<Time<Virtual>>,
+    mut shown: Local<bool>,
+) {
+    // a dead snake keeps reporting game over
+    if game_over_event.read().count() == 0 || *shown {
+        return;
+    }
+    *shown = true;
+    time.pause();
+
+    let (_, placed) = high_scores.shown_table(&high_scores.ruleset.id);
+    info!(placed = ?placed, ruleset = high_scores.ruleset.label, "Game over screen shown");
+    for mut visibility in &mut screen_query {
+        *visibility = Visibility::Inherited;
+    }
+    // initials first if the run made the table
+    for (mut visibility, is_prompt) in &mut visibility_query {
+        *visibility = if is_prompt == placed.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    filter.shown = None; // draws the run's table
+}
+
+#[allow(clippy::type_complexity)]
+fn submit_initials(
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    input_query: Query<(), With<InitialsInput>>,
+    mut visibility_query: Query<
+        (&mut Visibility, Has<InitialsPrompt>),
+        Or<(With<InitialsPrompt>, With<ExitButton>)>,
+    >,
+    mut filter: ResMut<LeaderboardFilter>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let Some(event) = text_submitted_event
+        .read()
+        .find(|event| input_query.contains(event.input))
+    else {
+        return;
+    };
+    let initials = event.value.trim().to_uppercase();
+    high_scores.run.initials = if initials.is_empty() {
+        UNNAMED_INITIALS.to_string()
+    } else {
+        initials
+    };
+    let (table, placed) = high_scores.shown_table(&high_scores.ruleset.id);
+    let ruleset = high_scores.ruleset.clone();
+    high_scores.leaderboards.set_table(&ruleset, table);
+    high_scores.placed = placed;
+    high_scores.committed = true;
+    high_scores.save();
+    info!(
+        initials = high_scores.run.initials,
+        "High score initials entered"
+    );
+
+    for (mut visibility, is_prompt) in &mut visibility_query {
+        *visibility = if is_prompt {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+    filter.shown = None;
+}
+
+// F steps through the tables, a click on the current one lists them all to pick from
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn filter_leaderboards(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    high_scores: Res<HighScores>,
+    mut filter: ResMut<LeaderboardFilter>,
+    screen_query: Query<&InheritedVisibility, With<GameOverScreen>>,
+    text_input_query: Query<&InheritedVisibility, With<TextInput>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<FilterButton>)>,
+    option_query: Query<(&Interaction, &FilterOption), Changed<Interaction>>,
+    mut options_query: Query<(Entity, &mut Visibility), With<FilterOptions>>,
+    mut text_query: ParamSet<(
+        Query<&mut Text, With<FilterButton>>,
+        Query<&mut Text, With<TableText>>,
+    )>,
+) {
+    if !screen_query.iter().any(|visibility| visibility.get()) {
+        return;
+    }
+    let ids = high_scores.ids();
+    let shown = filter
+        .shown
+        .clone()
+        .unwrap_or_else(|| high_scores.ruleset.id.clone());
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    if !typing && keyboard_input.just_pressed(KeyCode::F) {
+        let index = ids.iter().position(|id| *id == shown).unwrap_or(0);
+        filter.shown = Some(ids[(index + 1) % ids.len()].clone());
+        filter.open = false;
+    }
+    if button_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        filter.open = !filter.open;
+    }
+    if let Some((_, option)) = option_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+    {
+        filter.shown = Some(option.0.clone());
+        filter.open = false;
+    }
+    if !filter.is_changed() && !high_scores.is_changed() {
+        return;
+    }
+
+    let shown = filter
+        .shown
+        .clone()
+        .unwrap_or_else(|| high_scores.ruleset.id.clone());
+    for (options, mut visibility) in &mut options_query {
+        commands.entity(options).despawn_descendants();
+        *visibility = if filter.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if !filter.open {
+            continue;
+        }
+        commands.entity(options).with_children(|parent| {
+            for id in &ids {
+                parent.spawn((
+                    TextBundle::from_section(
+                        high_scores.label(id),
+                        TextStyle {
+                            font_size: 22.0,
+                            color: if *id == shown {
+                                RUN_ROW_COLOR
+                            } else {
+                                OPTION_COLOR
+                            },
+                            ..default()
+                        },
+                    ),
+                    Interaction::None,
+                    FilterOption(id.clone()),
+                ));
+            }
+        });
+    }
+    let arrow = if filter.open { "^" } else { "v" };
+    for mut text in &mut text_query.p0() {
+        text.sections[0].value = format!("Rules: {} {arrow}  (F)", high_scores.label(&shown));
+    }
+    let (table, placed) = high_scores.shown_table(&shown);
+    for mut text in &mut text_query.p1() {
+        text.sections = table_sections(&table, placed);
+    }
+}
+
+fn exit_game_over_screen(
+    mut menu_activated_event: EventReader<MenuActivated>,
+    button_query: Query<(), With<ExitButton>>,
+    mut app_exit_event: EventWriter<AppExit>,
+) {
+    if menu_activated_event
+        .read()
+        .any(|event| button_query.contains(event.0))
+    {
+        app_exit_event.send(AppExit);
+    }
+}
+
+fn save_on_exit(mut app_exit_event: EventReader<AppExit>, high_scores: Res<HighScores>) {
+    if app_exit_event.read().count() > 0 {
+        high_scores.save();
+    }
+}
@@ -0,0 +1,97 @@
+// Bot hints
+// With --hints [easy|normal|hard], the pause action (P or Start) pauses the game and ghost arrows
+// show the moves the bot would make next from player one's spot, to compare a plan against. Easy
+// hints look far ahead and show a long stretch, hard ones are the easy bot's short guesses
+use bevy::prelude::*;
+
+use crate::ai::{AiSkill, BotView, Greedy, Strategy};
+use crate::args::Args;
+use crate::input::{Action, ActionState};
+use crate::menu::menu_closed;
+use crate::occupancy::Occupancy;
+use crate::{Apple, Player, SnakeHead, PIXEL_UNIT_SIZE};
+
+const HINT_COLOR: Color = Color::rgba(0.6, 0.9, 1.0, 0.7);
+const ARROW_HEAD: f32 = 0.3; // cells
+
+#[derive(Resource)]
+struct Hints {
+    skill: AiSkill,
+    moves: usize,
+    bot: Greedy,
+}
+
+pub struct HintsPlugin;
+
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        let (skill, moves) = match Args::of(app).value("--hints") {
+            Some("easy") => (AiSkill::HARD, 8),
+            Some("hard") => (AiSkill::EASY, 2),
+            _ => (AiSkill::NORMAL, 4),
+        };
+        app.insert_resource(Hints {
+            skill,
+            moves,
+            bot: Greedy::default(),
+        })
+        .add_systems(Update, (toggle_pause.run_if(menu_closed), draw_hints));
+    }
+}
+
+// leaves time alone if something else paused it
+fn toggle_pause(
+    action_state: Res<ActionState>,
+    mut time: ResMut<Time<Virtual>>,
+    mut paused_for_hints: Local<bool>,
+) {
+    if !action_state.just_pressed(Action::Pause) {
+        return;
+    }
+    if std::mem::take(&mut *paused_for_hints) {
+        time.unpause();
+    } else if !time.is_paused() {
+        time.pause();
+        *paused_for_hints = true;
+    }
+    info!(paused = *paused_for_hints, "Paused for hints");
+}
+
+// gizmos only last a frame, so this redraws every paused frame, fainter further ahead
+fn draw_hints(
+    time: Res<Time<Virtual>>,
+    hints: Res<Hints>,
+    occupancy: Res<Occupancy>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    apple_query: Query<&Apple>,
+    mut gizmos: Gizmos,
+) {
+    if !time.is_paused() {
+        return;
+    }
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let view = BotView {
+        occupancy: &occupancy,
+        snake_head,
+        apple: apple_query.iter().next(),
+        skill: &hints.skill,
+    };
+    let path = hints.bot.planned_path(&view, hints.moves);
+    let cell =
+        |position: (i32, i32)| Vec2::new(position.0 as f32, position.1 as f32) * PIXEL_UNIT_SIZE;
+    let mut from = snake_head.position;
+    for (index, to) in path.into_iter().enumerate() {
+        let color = HINT_COLOR.with_a(HINT_COLOR.a() * (1.0 - index as f32 / hints.moves as f32));
+        let (start, tip) = (cell(from), cell(to));
+        let back = (tip - start).normalize_or_zero() * ARROW_HEAD * PIXEL_UNIT_SIZE;
+        gizmos.line_2d(start, tip, color);
+        gizmos.line_2d(tip, tip - back + back.perp() * 0.6, color);
+        gizmos.line_2d(tip, tip - back - back.perp() * 0.6, color);
+        from = to;
+    }
+}
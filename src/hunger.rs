@@ -0,0 +1,140 @@
+// Hunger
+// Optional hunger meter: it drains every tick and apples refill it, a starving snake shrinks
+use bevy::prelude::*;
+
+use crate::{
+    shrink_snake, AppleEaten, DeathCause, Player, SnakeBody, SnakeDied, SnakeHead, SnakeSystemSet,
+};
+
+const HUNGER_MAX: u32 = 150; // ticks until starving on an empty stomach
+const HUNGER_PER_POINT: u32 = 60;
+const STARVE_SHRINK_TICKS: u32 = 10;
+const BAR_WIDTH: f32 = 120.0;
+const BAR_COLOR: Color = Color::YELLOW_GREEN;
+const BAR_STARVING_COLOR: Color = Color::ORANGE_RED;
+
+#[derive(Resource)]
+struct Hunger {
+    value: u32,
+    starving_ticks: u32,
+}
+
+#[derive(Component)]
+struct HungerBar;
+
+pub struct HungerPlugin;
+
+impl Plugin for HungerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Hunger {
+            value: HUNGER_MAX,
+            starving_ticks: 0,
+        })
+        .add_systems(Startup, setup_hunger_bar)
+        .add_systems(FixedUpdate, drain_hunger.in_set(SnakeSystemSet::Collision))
+        .add_systems(Update, update_hunger_bar);
+    }
+}
+
+fn setup_hunger_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                right: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Hunger",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(10.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    border_color: Color::WHITE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_COLOR.into(),
+                            ..default()
+                        },
+                        HungerBar,
+                    ));
+                });
+        });
+}
+
+fn drain_hunger(
+    mut commands: Commands,
+    mut hunger: ResMut<Hunger>,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut snake_died_event: EventWriter<SnakeDied>,
+    new_snake_query: Query<(), Added<SnakeHead>>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+) {
+    // a respawned snake starts on a full stomach
+    if !new_snake_query.is_empty() {
+        hunger.value = HUNGER_MAX;
+        hunger.starving_ticks = 0;
+    }
+    for event in apple_eaten_event.read() {
+        hunger.value = (hunger.value + event.points * HUNGER_PER_POINT).min(HUNGER_MAX);
+        hunger.starving_ticks = 0;
+    }
+
+    if hunger.value > 0 {
+        hunger.value -= 1;
+        return;
+    }
+    hunger.starving_ticks += 1;
+    if !hunger.starving_ticks.is_multiple_of(STARVE_SHRINK_TICKS) {
+        return;
+    }
+    // the stomach is shared, every snake wastes away together
+    for (player, mut snake_head) in &mut snake_head_query {
+        if !shrink_snake(&mut commands, &mut snake_head, &snake_body_query) {
+            snake_died_event.send(SnakeDied {
+                player: *player,
+                cause: DeathCause::Starvation,
+            });
+        }
+    }
+}
+
+fn update_hunger_bar(
+    hunger: Res<Hunger>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<HungerBar>>,
+) {
+    for (mut style, mut background_color) in &mut bar_query {
+        style.width = Val::Percent(hunger.value as f32 / HUNGER_MAX as f32 * 100.0);
+        background_color.0 = if hunger.value == 0 {
+            BAR_STARVING_COLOR
+        } else {
+            BAR_COLOR
+        };
+    }
+}
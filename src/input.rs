@@ -0,0 +1,344 @@
+// Input
+// The action map, what each action is bound to on the keyboard, a gamepad and a touch screen,
+// saved per profile, and how presses landing in the same frame are resolved. ActionState holds
+// which actions are down this frame on any device, for everything but steering; steering goes
+// through the input sources, which keep the order keys went down in
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::touch::Touches;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::direction::Direction;
+
+pub const ACTION_MAP_FILE: &str = "actions.ron";
+pub const KEY_BINDINGS_FILE: &str = "keybinds.ron"; // from before the action map, carried over
+const STICK_THRESHOLD: f32 = 0.5;
+const SWIPE_PIXELS: f32 = 30.0; // shorter touches are taps
+
+// which turn wins when several direction keys are pressed in one frame, e.g. Up and Left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiagonalPolicy {
+    LatestPress,   // the last key to go down, by event order
+    EarliestPress, // the first key to go down, later ones are ignored
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Boost,
+    Ability,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Pause,
+        Action::Boost,
+        Action::Ability,
+    ];
+
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::Up => Some(Direction::Up),
+            Action::Down => Some(Direction::Down),
+            Action::Left => Some(Direction::Left),
+            Action::Right => Some(Direction::Right),
+            Action::Pause | Action::Boost | Action::Ability => None,
+        }
+    }
+}
+
+// the left stick pushed one way, a swipe across the screen or a tap on it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Motion {
+    LeftStick(Direction),
+    Swipe(Direction),
+    Tap,
+}
+
+// any of them triggers the action
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionBindings {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<GamepadButtonType>,
+    pub motions: Vec<Motion>,
+}
+
+// the first snake's controls, saved per profile
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    pub up: ActionBindings,
+    pub down: ActionBindings,
+    pub left: ActionBindings,
+    pub right: ActionBindings,
+    pub pause: ActionBindings,
+    pub boost: ActionBindings,
+    pub ability: ActionBindings,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let direction = |keys: [KeyCode; 3], button, direction| ActionBindings {
+            keys: keys.to_vec(),
+            buttons: vec![button],
+            motions: vec![Motion::LeftStick(direction), Motion::Swipe(direction)],
+        };
+        ActionMap {
+            up: direction(
+                [KeyCode::Up, KeyCode::W, KeyCode::I],
+                GamepadButtonType::DPadUp,
+                Direction::Up,
+            ),
+            down: direction(
+                [KeyCode::Down, KeyCode::S, KeyCode::K],
+                GamepadButtonType::DPadDown,
+                Direction::Down,
+            ),
+            left: direction(
+                [KeyCode::Left, KeyCode::A, KeyCode::J],
+                GamepadButtonType::DPadLeft,
+                Direction::Left,
+            ),
+            right: direction(
+                [KeyCode::Right, KeyCode::D, KeyCode::L],
+                GamepadButtonType::DPadRight,
+                Direction::Right,
+            ),
+            pause: ActionBindings {
+                keys: vec![KeyCode::P],
+                buttons: vec![GamepadButtonType::Start],
+                motions: Vec::new(),
+            },
+            boost: ActionBindings {
+                keys: vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+                buttons: vec![GamepadButtonType::RightTrigger2],
+                motions: Vec::new(),
+            },
+            ability: ActionBindings {
+                keys: vec![KeyCode::Space],
+                buttons: vec![GamepadButtonType::South],
+                motions: vec![Motion::Tap],
+            },
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn bindings(&self, action: Action) -> &ActionBindings {
+        match action {
+            Action::Up => &self.up,
+            Action::Down => &self.down,
+            Action::Left => &self.left,
+            Action::Right => &self.right,
+            Action::Pause => &self.pause,
+            Action::Boost => &self.boost,
+            Action::Ability => &self.ability,
+        }
+    }
+
+    // the first direction whose bindings match
+    fn direction(&self, matches: impl Fn(&ActionBindings) -> bool) -> Option<Direction> {
+        Action::ALL
+            .into_iter()
+            .filter(|action| matches(self.bindings(*action)))
+            .find_map(Action::direction)
+    }
+
+    pub fn key_direction(&self, key_code: KeyCode) -> Option<Direction> {
+        self.direction(|bindings| bindings.keys.contains(&key_code))
+    }
+
+    // a button going down, or else the stick held over
+    pub fn gamepad_direction(
+        &self,
+        gamepad: Gamepad,
+        gamepad_buttons: &Input<GamepadButton>,
+        gamepad_axes: &Axis<GamepadAxis>,
+    ) -> Option<Direction> {
+        let pressed = self.direction(|bindings| {
+            bindings.buttons.iter().any(|button_type| {
+                gamepad_buttons.just_pressed(GamepadButton::new(gamepad, *button_type))
+            })
+        });
+        pressed.or_else(|| {
+            let stick = Motion::LeftStick(stick_direction(gamepad, gamepad_axes)?);
+            self.direction(|bindings| bindings.motions.contains(&stick))
+        })
+    }
+
+    pub fn motion_direction(&self, motion: Motion) -> Option<Direction> {
+        self.direction(|bindings| bindings.motions.contains(&motion))
+    }
+
+    // keys saved before there was an action map, everything else as it comes by default
+    pub fn with_key_bindings(key_bindings: KeyBindings) -> Self {
+        let mut action_map = ActionMap::default();
+        action_map.up.keys = key_bindings.up;
+        action_map.down.keys = key_bindings.down;
+        action_map.left.keys = key_bindings.left;
+        action_map.right.keys = key_bindings.right;
+        action_map
+    }
+}
+
+// the keys-only bindings of keybinds.ron, read once to carry them over
+#[derive(Deserialize)]
+pub struct KeyBindings {
+    up: Vec<KeyCode>,
+    down: Vec<KeyCode>,
+    left: Vec<KeyCode>,
+    right: Vec<KeyCode>,
+}
+
+// the left stick's heading, when pushed far enough
+pub fn stick_direction(gamepad: Gamepad, gamepad_axes: &Axis<GamepadAxis>) -> Option<Direction> {
+    let axis = |axis_type| {
+        gamepad_axes
+            .get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    let (x, y) = (
+        axis(GamepadAxisType::LeftStickX),
+        axis(GamepadAxisType::LeftStickY),
+    );
+    if x.abs().max(y.abs()) < STICK_THRESHOLD {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x > 0.0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        })
+    } else {
+        Some(if y > 0.0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        })
+    }
+}
+
+// touches lifted this frame, in window coordinates with y pointing down
+pub fn touch_motions(touches: &Touches) -> impl Iterator<Item = Motion> + '_ {
+    touches.iter_just_released().map(|touch| {
+        let swipe = touch.position() - touch.start_position();
+        if swipe.length() < SWIPE_PIXELS {
+            Motion::Tap
+        } else if swipe.x.abs() > swipe.y.abs() {
+            Motion::Swipe(if swipe.x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Motion::Swipe(if swipe.y > 0.0 {
+                Direction::Down
+            } else {
+                Direction::Up
+            })
+        }
+    })
+}
+
+#[derive(Resource)]
+pub struct InputSettings {
+    pub diagonal_policy: DiagonalPolicy,
+    pub actions: ActionMap,
+}
+
+impl InputSettings {
+    // --diagonal-policy <latest|earliest>
+    pub fn from_args(args: &Args) -> Self {
+        let diagonal_policy = match args.value("--diagonal-policy") {
+            Some("earliest") => DiagonalPolicy::EarliestPress,
+            _ => DiagonalPolicy::LatestPress,
+        };
+        InputSettings {
+            diagonal_policy,
+            actions: ActionMap::default(),
+        }
+    }
+}
+
+// the direction pressed this frame under the given policy, events arrive in press order
+pub fn resolve_direction(
+    keyboard_input_events: &mut EventReader<KeyboardInput>,
+    policy: DiagonalPolicy,
+    actions: &ActionMap,
+) -> Option<Direction> {
+    let mut pressed = keyboard_input_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .filter_map(|event| {
+            event
+                .key_code
+                .and_then(|key_code| actions.key_direction(key_code))
+        });
+    match policy {
+        DiagonalPolicy::LatestPress => pressed.last(),
+        DiagonalPolicy::EarliestPress => pressed.next(),
+    }
+}
+
+// the actions held on any device, and those that went down this frame
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+pub fn update_action_state(
+    input_settings: Res<InputSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    touches: Res<Touches>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let motions: Vec<Motion> = gamepads
+        .iter()
+        .filter_map(|gamepad| stick_direction(gamepad, &gamepad_axes))
+        .map(Motion::LeftStick)
+        .chain(touch_motions(&touches))
+        .collect();
+    let pressed: HashSet<Action> = Action::ALL
+        .into_iter()
+        .filter(|action| {
+            let bindings = input_settings.actions.bindings(*action);
+            keyboard_input.any_pressed(bindings.keys.iter().copied())
+                || gamepads.iter().any(|gamepad| {
+                    bindings.buttons.iter().any(|button_type| {
+                        gamepad_buttons.pressed(GamepadButton::new(gamepad, *button_type))
+                    })
+                })
+                || motions
+                    .iter()
+                    .any(|motion| bindings.motions.contains(motion))
+        })
+        .collect();
+    action_state.just_pressed = pressed.difference(&action_state.pressed).copied().collect();
+    action_state.pressed = pressed;
+}
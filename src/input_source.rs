@@ -0,0 +1,289 @@
+// Input sources
+// Everything that steers a snake is an InputSource: the keyboard, a gamepad, touch swipes, a
+// recorded run, players over the network and the bots. Each is a resource read by a system of
+// its own, once a frame or once a tick, that writes direction intents into one queue; every tick
+// apply_intents then hands each player the last direction queued for them, just before the snakes
+// move. Another control scheme is another source, added with app.add_input_source
+use bevy::ecs::system::{StaticSystemParam, SystemParam, SystemParamItem};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::touch::Touches;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::battle::Battle;
+use crate::chat::Chat;
+use crate::cheats::CheatConsole;
+use crate::direction::Direction;
+use crate::input::{
+    resolve_direction, touch_motions, update_action_state, ActionState, InputSettings,
+};
+use crate::menu::MenuFocus;
+use crate::replay::{read_replay_file, ReplayInput};
+use crate::seed::RunSeed;
+use crate::{Player, SnakeHead, SnakeSystemSet};
+
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionIntent {
+    pub player: Player,
+    pub direction: Direction,
+}
+
+// filled by the sources, emptied every tick
+#[derive(Resource, Default)]
+pub struct DirectionIntents(Vec<DirectionIntent>);
+
+impl DirectionIntents {
+    pub fn push(&mut self, player: Player, direction: Direction) {
+        self.0.push(DirectionIntent { player, direction });
+    }
+}
+
+pub(crate) trait InputSource: Resource {
+    // whatever the source reads from the world
+    type Param: SystemParam + 'static;
+    // sources that follow the simulation rather than a device, the bots or the network, are read
+    // every tick instead of every frame
+    const PER_TICK: bool = false;
+
+    fn poll(
+        &mut self,
+        param: &mut SystemParamItem<'_, '_, Self::Param>,
+        intents: &mut DirectionIntents,
+    );
+}
+
+pub(crate) trait AddInputSource {
+    fn add_input_source<S: InputSource>(&mut self, source: S) -> &mut Self;
+}
+
+impl AddInputSource for App {
+    fn add_input_source<S: InputSource>(&mut self, source: S) -> &mut Self {
+        self.insert_resource(source);
+        let poll = poll_input_source::<S>.in_set(SnakeSystemSet::Input);
+        if S::PER_TICK {
+            self.add_systems(FixedUpdate, poll)
+        } else {
+            self.add_systems(Update, poll)
+        }
+    }
+}
+
+fn poll_input_source<S: InputSource>(
+    mut source: ResMut<S>,
+    mut param: StaticSystemParam<S::Param>,
+    mut intents: ResMut<DirectionIntents>,
+) {
+    source.poll(&mut param, &mut intents);
+}
+
+// player one's own controls, all of them at once, and the actions they are held down for
+pub struct InputSourcePlugin;
+
+impl Plugin for InputSourcePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DirectionIntents>()
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, update_action_state.after(InputSystem))
+            .add_input_source(KeyboardSource)
+            .add_input_source(GamepadSource)
+            .add_input_source(TouchSource)
+            .add_systems(
+                FixedUpdate,
+                apply_intents
+                    .after(SnakeSystemSet::Input)
+                    .before(SnakeSystemSet::Movement),
+            );
+        if let Some(replay_source) = ReplaySource::from_args(&Args::of(app)) {
+            app.add_input_source(replay_source);
+        }
+    }
+}
+
+fn apply_intents(
+    mut intents: ResMut<DirectionIntents>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+) {
+    for intent in intents.0.drain(..) {
+        for (_, mut snake_head) in snake_head_query
+            .iter_mut()
+            .filter(|(player, _)| **player == intent.player)
+        {
+            snake_head.potential_direction = intent.direction;
+        }
+    }
+}
+
+// player one steers unless a menu, the chat or the cheat console has the keys, a battle seats its
+// own players
+#[derive(SystemParam)]
+pub struct PlayerOneControls<'w> {
+    menu_focus: Res<'w, MenuFocus>,
+    chat: Option<Res<'w, Chat>>,
+    console: Option<Res<'w, CheatConsole>>,
+    battle: Option<Res<'w, Battle>>,
+}
+
+impl PlayerOneControls<'_> {
+    fn steering(&self) -> bool {
+        self.menu_focus.0.is_none()
+            && self.chat.as_ref().is_none_or(|chat| !chat.open)
+            && self.console.as_ref().is_none_or(|console| !console.open)
+            && self.battle.is_none()
+    }
+}
+
+#[derive(Resource)]
+pub struct KeyboardSource;
+
+impl InputSource for KeyboardSource {
+    type Param = (
+        EventReader<'static, 'static, KeyboardInput>,
+        Res<'static, InputSettings>,
+        PlayerOneControls<'static>,
+    );
+
+    fn poll(
+        &mut self,
+        (keyboard_input_events, input_settings, controls): &mut SystemParamItem<
+            '_,
+            '_,
+            Self::Param,
+        >,
+        intents: &mut DirectionIntents,
+    ) {
+        if !controls.steering() {
+            return;
+        }
+        if let Some(direction) = resolve_direction(
+            keyboard_input_events,
+            input_settings.diagonal_policy,
+            &input_settings.actions,
+        ) {
+            intents.push(Player::ONE, direction);
+        }
+    }
+}
+
+// the first pad connected
+#[derive(Resource)]
+pub struct GamepadSource;
+
+impl InputSource for GamepadSource {
+    type Param = (
+        Res<'static, Gamepads>,
+        Res<'static, Input<GamepadButton>>,
+        Res<'static, Axis<GamepadAxis>>,
+        Res<'static, InputSettings>,
+        PlayerOneControls<'static>,
+    );
+
+    fn poll(
+        &mut self,
+        (gamepads, gamepad_buttons, gamepad_axes, input_settings, controls): &mut SystemParamItem<
+            '_,
+            '_,
+            Self::Param,
+        >,
+        intents: &mut DirectionIntents,
+    ) {
+        if !controls.steering() {
+            return;
+        }
+        let direction = gamepads.iter().next().and_then(|gamepad| {
+            input_settings
+                .actions
+                .gamepad_direction(gamepad, gamepad_buttons, gamepad_axes)
+        });
+        if let Some(direction) = direction {
+            intents.push(Player::ONE, direction);
+        }
+    }
+}
+
+// a swipe across the screen, taps are left to the ability
+#[derive(Resource)]
+pub struct TouchSource;
+
+impl InputSource for TouchSource {
+    type Param = (
+        Res<'static, Touches>,
+        Res<'static, InputSettings>,
+        PlayerOneControls<'static>,
+    );
+
+    fn poll(
+        &mut self,
+        (touches, input_settings, controls): &mut SystemParamItem<'_, '_, Self::Param>,
+        intents: &mut DirectionIntents,
+    ) {
+        if !controls.steering() {
+            return;
+        }
+        for direction in touch_motions(touches)
+            .filter_map(|motion| input_settings.actions.motion_direction(motion))
+        {
+            intents.push(Player::ONE, direction);
+        }
+    }
+}
+
+// --play-inputs <replay> steers player one as the recorded run did, on the same ticks; the run
+// needs that replay's --seed to go the same way
+#[derive(Resource)]
+pub struct ReplaySource {
+    seed: u64,
+    inputs: Vec<ReplayInput>,
+    next_input: usize,
+    tick: u32,
+}
+
+impl ReplaySource {
+    fn from_args(args: &Args) -> Option<Self> {
+        let Some(path) = args.values("--play-inputs")?.first().copied() else {
+            args.problem("--play-inputs expects a replay file".to_string());
+            return None;
+        };
+        match read_replay_file(path) {
+            Ok(replay) => Some(ReplaySource {
+                seed: replay.seed,
+                inputs: replay.inputs,
+                next_input: 0,
+                tick: 0,
+            }),
+            Err(error) => {
+                args.problem(error);
+                None
+            }
+        }
+    }
+}
+
+impl InputSource for ReplaySource {
+    type Param = Res<'static, RunSeed>;
+    const PER_TICK: bool = true;
+
+    fn poll(
+        &mut self,
+        seed: &mut SystemParamItem<'_, '_, Self::Param>,
+        intents: &mut DirectionIntents,
+    ) {
+        if self.tick == 0 && seed.0 != self.seed {
+            warn!(
+                run_seed = seed.0,
+                replay_seed = self.seed,
+                "Playing inputs recorded on another seed, the run will go its own way"
+            );
+        }
+        // counted as the recorder counts them
+        self.tick += 1;
+        while let Some(input) = self
+            .inputs
+            .get(self.next_input)
+            .filter(|input| input.tick <= self.tick)
+        {
+            intents.push(Player::ONE, input.direction);
+            self.next_input += 1;
+        }
+    }
+}
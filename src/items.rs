@@ -0,0 +1,388 @@
+// Items
+// Apple, power-up and obstacle definitions read from assets/items.ron, plus power-up spawning
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::args::Args;
+use crate::playfield::PlayfieldMask;
+use crate::ron_asset::RonAssetLoader;
+use crate::seed::RunRng;
+use crate::tween::Tween;
+use crate::{
+    get_valid_apple_spawn, Apple, Player, SnakeBody, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE,
+};
+
+const POWER_UP_SPAWN_SECONDS: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ItemEffect {
+    Shield,
+    Magnet, // pulls the apple toward the snake for a few seconds
+    Stone,  // the cell it was eaten on becomes a wall once the snakes are off it
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AppleDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<Color>, // the theme's apple color when unset
+    #[serde(default = "default_size")]
+    pub size: i32, // covers a size x size block
+    pub points: u32,
+    pub growth: u32,
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+    pub spawn_weight: u32,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct PowerUpDefinition {
+    pub name: String,
+    pub color: Color,
+    pub effects: Vec<ItemEffect>,
+    pub lifetime_seconds: f32,
+    pub spawn_weight: u32,
+}
+
+// a wall shape --obstacles scatters, its cells given from the one it is placed at
+#[derive(Clone, Deserialize)]
+pub struct ObstacleDefinition {
+    pub name: String,
+    pub cells: Vec<(i32, i32)>,
+    pub spawn_weight: u32,
+}
+
+fn default_size() -> i32 {
+    1
+}
+
+impl AppleDefinition {
+    // at least a cell, and no wider than the board it spawns on
+    pub fn fitting_size(size: i32, playfield_mask: &PlayfieldMask) -> i32 {
+        let (half_width, half_height) = playfield_mask.half_extents();
+        size.clamp(1, (2 * half_width + 1).min(2 * half_height + 1))
+    }
+
+    // fitted whenever an apple is rolled, the board may have changed since the table loaded
+    pub fn size_on(&self, playfield_mask: &PlayfieldMask) -> i32 {
+        AppleDefinition::fitting_size(self.size, playfield_mask)
+    }
+}
+
+#[derive(Resource, Asset, TypePath, Clone, Deserialize)]
+pub struct ItemTable {
+    pub apples: Vec<AppleDefinition>,
+    pub power_ups: Vec<PowerUpDefinition>,
+    #[serde(default = "default_obstacles")]
+    pub obstacles: Vec<ObstacleDefinition>,
+}
+
+// posts and straight walls up to four cells long, across and upright
+fn default_obstacles() -> Vec<ObstacleDefinition> {
+    let wall = |name: &str, cells: Vec<(i32, i32)>, spawn_weight| ObstacleDefinition {
+        name: name.to_string(),
+        cells,
+        spawn_weight,
+    };
+    vec![
+        wall("Post", vec![(0, 0)], 2),
+        wall("Short wall", vec![(0, 0), (1, 0)], 1),
+        wall("Short upright wall", vec![(0, 0), (0, 1)], 1),
+        wall("Wall", vec![(0, 0), (1, 0), (2, 0)], 1),
+        wall("Upright wall", vec![(0, 0), (0, 1), (0, 2)], 1),
+        wall("Long wall", vec![(0, 0), (1, 0), (2, 0), (3, 0)], 1),
+        wall("Long upright wall", vec![(0, 0), (0, 1), (0, 2), (0, 3)], 1),
+    ]
+}
+
+// used until assets/items.ron has loaded
+impl Default for ItemTable {
+    fn default() -> Self {
+        ItemTable {
+            apples: vec![
+                AppleDefinition {
+                    name: "Apple".to_string(),
+                    color: None,
+                    size: 1,
+                    points: 1,
+                    growth: 1,
+                    effects: Vec::new(),
+                    spawn_weight: 9,
+                },
+                AppleDefinition {
+                    name: "Big apple".to_string(),
+                    color: None,
+                    size: 2,
+                    points: 4,
+                    growth: 4,
+                    effects: Vec::new(),
+                    spawn_weight: 1,
+                },
+                AppleDefinition {
+                    name: "Stone fruit".to_string(),
+                    color: Some(Color::GRAY),
+                    size: 1,
+                    points: 2,
+                    growth: 1,
+                    effects: vec![ItemEffect::Stone],
+                    spawn_weight: 2,
+                },
+            ],
+            power_ups: vec![
+                PowerUpDefinition {
+                    name: "Shield".to_string(),
+                    color: Color::CYAN,
+                    effects: vec![ItemEffect::Shield],
+                    lifetime_seconds: 10.0,
+                    spawn_weight: 1,
+                },
+                PowerUpDefinition {
+                    name: "Magnet".to_string(),
+                    color: Color::FUCHSIA,
+                    effects: vec![ItemEffect::Magnet],
+                    lifetime_seconds: 10.0,
+                    spawn_weight: 1,
+                },
+            ],
+            obstacles: default_obstacles(),
+        }
+    }
+}
+
+// picks a definition with probability proportional to its spawn weight
+pub fn pick_weighted<'a, T>(
+    rng: &mut impl Rng,
+    definitions: &'a [T],
+    weight: impl Fn(&T) -> u32,
+) -> Option<&'a T> {
+    let total: u32 = definitions.iter().map(&weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = rng.gen_range(0..total);
+    definitions.iter().find(|definition| {
+        let weight = weight(definition);
+        if roll < weight {
+            return true;
+        }
+        roll -= weight;
+        false
+    })
+}
+
+#[derive(Component)]
+pub struct PowerUp {
+    pub position: (i32, i32),
+    effects: Vec<ItemEffect>,
+    lifetime: Timer,
+}
+
+#[derive(Event)]
+pub struct PowerUpCollected {
+    pub player: Player,
+    pub effects: Vec<ItemEffect>,
+}
+
+#[derive(Resource)]
+pub struct ItemTableHandle(Handle<ItemTable>);
+
+impl ItemTableHandle {
+    // items.ron is still on its way, a failed load leaves the built-in table in place for good
+    pub fn is_loading(&self, asset_server: &AssetServer) -> bool {
+        matches!(
+            asset_server.load_state(self.0.id()),
+            LoadState::NotLoaded | LoadState::Loading
+        )
+    }
+}
+
+#[derive(Resource)]
+struct PowerUpSpawnTimer(Timer);
+
+pub struct ItemsPlugin;
+
+impl Plugin for ItemsPlugin {
+    // a ranked run keeps to the built-in table without power-ups, as verify_replay plays it
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemTable>()
+            .init_asset::<ItemTable>()
+            .register_asset_loader(RonAssetLoader::<ItemTable>::new(&["items.ron"]))
+            .add_event::<PowerUpCollected>()
+            .insert_resource(PowerUpSpawnTimer(Timer::from_seconds(
+                POWER_UP_SPAWN_SECONDS,
+                TimerMode::Repeating,
+            )))
+            .add_systems(
+                FixedUpdate,
+                collect_power_ups.in_set(SnakeSystemSet::Collision),
+            )
+            .add_systems(Update, expire_power_ups.in_set(SnakeSystemSet::Spawning));
+        if Args::of(app).ranked() {
+            return;
+        }
+        app.add_systems(Startup, load_item_table).add_systems(
+            Update,
+            (
+                reload_item_table,
+                spawn_power_up.in_set(SnakeSystemSet::Spawning),
+            ),
+        );
+    }
+}
+
+fn load_item_table(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ItemTableHandle(asset_server.load("items.ron")));
+}
+
+pub fn reload_item_table(
+    mut item_table_events: EventReader<AssetEvent<ItemTable>>,
+    item_table_handle: Res<ItemTableHandle>,
+    item_tables: Res<Assets<ItemTable>>,
+    mut item_table: ResMut<ItemTable>,
+) {
+    for event in item_table_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != item_table_handle.0.id() {
+            continue;
+        }
+        if let Some(loaded_item_table) = item_tables.get(*id) {
+            *item_table = loaded_item_table.clone();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_power_up(
+    mut commands: Commands,
+    time: Res<Time>,
+    item_table: Res<ItemTable>,
+    mut spawn_timer: ResMut<PowerUpSpawnTimer>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    power_up_query: Query<(), With<PowerUp>>,
+    mut run_rng: ResMut<RunRng>,
+    playfield_mask: Res<PlayfieldMask>,
+) {
+    if !spawn_timer.0.tick(time.delta()).just_finished() || !power_up_query.is_empty() {
+        return;
+    }
+    let Some(definition) = pick_weighted(&mut run_rng.0, &item_table.power_ups, |definition| {
+        definition.spawn_weight
+    }) else {
+        return;
+    };
+
+    let mut used_positions: Vec<(i32, i32)> = snake_body_query
+        .iter()
+        .map(|snake_body| snake_body.position)
+        .collect();
+    for apple in &apple_query {
+        used_positions.extend_from_slice(&apple.footprint);
+    }
+    let Some(footprint) = get_valid_apple_spawn(
+        &mut run_rng.0,
+        &playfield_mask,
+        used_positions,
+        &snake_head_query,
+        1,
+        None,
+    ) else {
+        return;
+    };
+    let position = footprint[0];
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: definition.color,
+                custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE * 0.7, PIXEL_UNIT_SIZE * 0.7)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(
+                position.0 as f32 * PIXEL_UNIT_SIZE,
+                position.1 as f32 * PIXEL_UNIT_SIZE,
+                0.0,
+            ))
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ..default()
+        },
+        Name::new(definition.name.clone()),
+        PowerUp {
+            position,
+            effects: definition.effects.clone(),
+            lifetime: Timer::from_seconds(definition.lifetime_seconds, TimerMode::Once),
+        },
+        Tween::pop_in(),
+    ));
+}
+
+fn expire_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut power_up_query: Query<(Entity, &mut PowerUp, &Sprite)>,
+) {
+    for (entity, mut power_up, sprite) in &mut power_up_query {
+        if power_up.lifetime.tick(time.delta()).finished() {
+            commands
+                .entity(entity)
+                .remove::<PowerUp>()
+                .insert(Tween::fade_out(sprite.color));
+        }
+    }
+}
+
+pub fn collect_power_ups(
+    mut commands: Commands,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    power_up_query: Query<(Entity, &PowerUp)>,
+    mut power_up_collected_event: EventWriter<PowerUpCollected>,
+) {
+    for (entity, power_up) in &power_up_query {
+        // first snake over it takes it
+        if let Some((player, _)) = snake_head_query
+            .iter()
+            .find(|(_, snake_head)| snake_head.path.contains(&power_up.position))
+        {
+            commands.entity(entity).despawn();
+            power_up_collected_event.send(PowerUpCollected {
+                player: *player,
+                effects: power_up.effects.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playfield::PlayfieldShape;
+
+    #[test]
+    fn shipped_table_matches_the_built_in_one() {
+        let shipped: ItemTable = ron::from_str(include_str!("../assets/items.ron")).unwrap();
+        let built_in = ItemTable::default();
+        let names = |table: &ItemTable| -> Vec<String> {
+            table
+                .apples
+                .iter()
+                .map(|apple| apple.name.clone())
+                .chain(table.obstacles.iter().map(|obstacle| obstacle.name.clone()))
+                .collect()
+        };
+        assert_eq!(names(&shipped), names(&built_in));
+    }
+
+    #[test]
+    fn apple_sizes_fit_the_board() {
+        let playfield_mask = PlayfieldMask::new(PlayfieldShape::Rectangle).with_board(7, 5);
+        assert_eq!(AppleDefinition::fitting_size(0, &playfield_mask), 1);
+        assert_eq!(AppleDefinition::fitting_size(2, &playfield_mask), 2);
+        assert_eq!(AppleDefinition::fitting_size(9, &playfield_mask), 5);
+    }
+}
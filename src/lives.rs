@@ -0,0 +1,230 @@
+// Lives
+// Optional lives: dying respawns a short snake on its starting row until none are left. A row
+// something is in the way on gives way to the nearest clear one
+use bevy::prelude::*;
+
+use crate::bones::Bone;
+use crate::playfield::PlayfieldMask;
+use crate::snake_layout::SnakeLayout;
+use crate::theme::Theme;
+use crate::tween::Tween;
+use crate::{
+    game_over, spawn_snake_from_layout, Apple, PendingGrowth, Player, SnakeBody, SnakeDied,
+    SnakeHead,
+};
+
+const START_LIVES: u32 = 3;
+const RESPAWN_LENGTH: i32 = 3;
+const INVULNERABILITY_SECONDS: f32 = 2.0;
+const BLINK_RATE: f32 = 12.0;
+
+#[derive(Resource)]
+pub struct Lives(pub u32);
+
+// while present on the head, collisions are ignored and walls hold the snake in place
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+
+#[derive(Component)]
+struct LivesText;
+
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Lives(START_LIVES))
+            .add_systems(Startup, setup_lives_text)
+            .add_systems(
+                Update,
+                (
+                    lose_life.before(game_over),
+                    blink_invulnerable,
+                    update_lives_text,
+                ),
+            );
+    }
+}
+
+fn setup_lives_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        LivesText,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lose_life(
+    mut commands: Commands,
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut lives: ResMut<Lives>,
+    mut pending_growth: ResMut<PendingGrowth>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<(Entity, &Apple, &Sprite)>,
+    bone_query: Query<(Entity, &Bone, &Sprite)>,
+    sprite_query: Query<(&Sprite, &Transform)>,
+    theme: Res<Theme>,
+    playfield_mask: Res<PlayfieldMask>,
+) {
+    // several collisions can report the same death in one frame, and a remote snake that dies is
+    // out of the game rather than respawned
+    let mut died: Vec<Player> = snake_died_event
+        .read()
+        .map(|event| event.player)
+        .filter(|player| !player.is_remote())
+        .collect();
+    died.sort_by_key(|player| player.0);
+    died.dedup();
+    if died.is_empty() {
+        return;
+    }
+    // the lives are shared, a frame with deaths costs one
+    lives.0 = lives.0.saturating_sub(1);
+    info!(lives_left = lives.0, "Life lost");
+    if lives.0 == 0 {
+        return;
+    }
+
+    // where the snakes still alive are after this frame's ticks, the occupancy only catches up later
+    let mut occupied = Vec::new();
+    for (entity, player, snake_head) in &snake_head_query {
+        if !died.contains(player) {
+            occupied.push(snake_head.position);
+            occupied.extend(
+                snake_head
+                    .segments
+                    .iter()
+                    .filter_map(|segment| snake_body_query.get(*segment).ok())
+                    .map(|snake_body| snake_body.position),
+            );
+            continue;
+        }
+        for segment in snake_head.segments.iter().chain([&entity]) {
+            // a copy of the old body stays behind and fades away
+            if let Ok((sprite, transform)) = sprite_query.get(*segment) {
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: sprite.clone(),
+                        transform: *transform,
+                        ..default()
+                    },
+                    Tween::fade_out(sprite.color),
+                ));
+            }
+            commands.entity(*segment).despawn();
+        }
+    }
+    let mut respawned: Vec<(i32, i32)> = Vec::new();
+    let mut stranded = false;
+    for player in died {
+        pending_growth.0.remove(&player);
+
+        let blocked = |position: (i32, i32)| {
+            playfield_mask.is_wall(position)
+                || occupied.contains(&position)
+                || respawned.contains(&position)
+        };
+        let Some(layout) = respawn_layout(player, &playfield_mask, blocked) else {
+            // walled in everywhere, there is nowhere to come back
+            warn!(
+                player = player.0,
+                "No room to respawn, that was the last life"
+            );
+            stranded = true;
+            continue;
+        };
+        respawned.extend_from_slice(layout.cells());
+
+        // food the new snake would spawn on top of goes
+        for (entity, apple, sprite) in &apple_query {
+            if apple
+                .footprint
+                .iter()
+                .any(|position| layout.cells().contains(position))
+            {
+                commands
+                    .entity(entity)
+                    .remove::<Apple>()
+                    .insert(Tween::fade_out(sprite.color));
+            }
+        }
+        for (entity, bone, sprite) in &bone_query {
+            if layout.cells().contains(&bone.position) {
+                commands
+                    .entity(entity)
+                    .remove::<Bone>()
+                    .insert(Tween::fade_out(sprite.color));
+            }
+        }
+
+        let snake_head = spawn_snake_from_layout(&mut commands, &theme, player, &layout);
+        commands
+            .entity(snake_head)
+            .insert(Invulnerable(Timer::from_seconds(
+                INVULNERABILITY_SECONDS,
+                TimerMode::Once,
+            )));
+    }
+    // the others still come back first, game_over ends the run right after
+    if stranded {
+        lives.0 = 0;
+    }
+}
+
+// on the player's own row if it is clear, otherwise the nearest clear row going outwards from it
+fn respawn_layout(
+    player: Player,
+    playfield_mask: &PlayfieldMask,
+    blocked: impl Fn((i32, i32)) -> bool,
+) -> Option<SnakeLayout> {
+    let row = player.spawn_row();
+    let half_height = playfield_mask.half_extents().1;
+    (0..=2 * half_height)
+        .flat_map(|distance| [row - distance, row + distance])
+        .filter(|y| y.abs() <= half_height)
+        .map(|y| SnakeLayout::in_row(y, RESPAWN_LENGTH, playfield_mask.start_column()))
+        .find(|layout| !layout.cells().iter().any(|cell| blocked(*cell)))
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut snake_head_query: Query<(Entity, &SnakeHead, &mut Invulnerable, &mut Sprite)>,
+    mut snake_body_query: Query<&mut Sprite, (With<SnakeBody>, Without<SnakeHead>)>,
+) {
+    for (entity, snake_head, mut invulnerable, mut sprite) in &mut snake_head_query {
+        let alpha = if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            1.0
+        } else if (invulnerable.0.elapsed_secs() * BLINK_RATE).sin() > 0.0 {
+            1.0
+        } else {
+            0.3
+        };
+        sprite.color.set_a(alpha);
+        for segment in &snake_head.segments {
+            if let Ok(mut sprite) = snake_body_query.get_mut(*segment) {
+                sprite.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+fn update_lives_text(lives: Res<Lives>, mut text_query: Query<&mut Text, With<LivesText>>) {
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = format!("Lives: {}", lives.0);
+    }
+}
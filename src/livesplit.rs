@@ -0,0 +1,135 @@
+// LiveSplit
+// With the livesplit feature, --livesplit [address] drives a LiveSplit timer through its server
+// component (LiveSplit Server, listening on port 16834 by default) from the run's splits: the run
+// starting resets the timer and starts it, every split sets the game time and splits, and game
+// over sets the final game time and pauses, leaving the attempt up until the next run resets it.
+// Game time is the run's own clock, so the timer skips pauses the way the splits do. A timer that
+// isn't there or goes away is tried again at the next milestone, the game carries on regardless
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::splits::{RunMilestone, SplitsPlugin};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:16834";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200); // a local server answers at once
+
+#[derive(Resource)]
+struct LiveSplitServer {
+    address: SocketAddr,
+    stream: Option<TcpStream>,
+    warned: bool, // once per outage
+}
+
+impl LiveSplitServer {
+    // one command per line, as the server reads them
+    fn send(&mut self, commands: &[String]) {
+        if self.stream.is_none() {
+            match TcpStream::connect_timeout(&self.address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    info!(address = %self.address, "Connected to LiveSplit");
+                    let _ = stream.set_nodelay(true);
+                    self.stream = Some(stream);
+                    self.warned = false;
+                }
+                Err(error) => {
+                    if !std::mem::replace(&mut self.warned, true) {
+                        warn!(address = %self.address, "Could not reach LiveSplit: {error}");
+                    }
+                    return;
+                }
+            }
+        }
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        let lines: String = commands
+            .iter()
+            .map(|command| format!("{command}\r\n"))
+            .collect();
+        if let Err(error) = stream.write_all(lines.as_bytes()) {
+            warn!("Lost LiveSplit: {error}");
+            self.stream = None;
+        }
+    }
+}
+
+pub struct LiveSplitPlugin;
+
+impl Plugin for LiveSplitPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(address) = address_from_args(&Args::of(app)) else {
+            return;
+        };
+        if !app.is_plugin_added::<SplitsPlugin>() {
+            app.add_plugins(SplitsPlugin);
+        }
+        app.insert_resource(LiveSplitServer {
+            address,
+            stream: None,
+            warned: false,
+        })
+        .add_systems(Update, follow_run);
+    }
+}
+
+// --livesplit [ip:port]
+fn address_from_args(args: &Args) -> Option<SocketAddr> {
+    let address = args
+        .values("--livesplit")?
+        .first()
+        .copied()
+        .unwrap_or(DEFAULT_ADDRESS);
+    match address.parse() {
+        Ok(address) => Some(address),
+        Err(_) => {
+            args.problem(format!(
+                "--livesplit expects an address like {DEFAULT_ADDRESS}, not {address:?}"
+            ));
+            None
+        }
+    }
+}
+
+// h:mm:ss.ss, which the server reads times as
+fn game_time(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        hundredths / 360_000,
+        hundredths / 6000 % 60,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+fn follow_run(
+    mut run_milestone_event: EventReader<RunMilestone>,
+    mut server: ResMut<LiveSplitServer>,
+) {
+    for milestone in run_milestone_event.read() {
+        let commands = match milestone {
+            RunMilestone::Started => vec![
+                "reset".to_string(),
+                "initgametime".to_string(),
+                "starttimer".to_string(),
+            ],
+            RunMilestone::Split { seconds, .. } => {
+                vec![
+                    format!("setgametime {}", game_time(*seconds)),
+                    "split".to_string(),
+                ]
+            }
+            RunMilestone::Ended { seconds } => {
+                vec![
+                    format!("setgametime {}", game_time(*seconds)),
+                    "pause".to_string(),
+                ]
+            }
+        };
+        server.send(&commands);
+    }
+}
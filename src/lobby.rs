@@ -0,0 +1,688 @@
+// Lobby
+// Network game setup (--lobby): host or join by address or room code, pick a skin, toggle ready
+// and, as the host, the mutators. Once everyone is ready the host sends its run seed and every
+// player counts down together into the same board. Only the setup and the chat are shared so far,
+// each player still plays their own snake on it
+use std::io;
+use std::net::{SocketAddr, SocketAddrV4, TcpListener};
+
+use bevy::prelude::*;
+use bevy::tasks::Task;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{open_chat, Chat, ChatPlugin, ChatReceived, ChatSent};
+use crate::menu::{navigate_menu, spawn_button, MenuActivated};
+use crate::net::{self, Connection};
+use crate::seed::{RunRng, RunSeed};
+use crate::theme::Theme;
+use crate::widgets::{spawn_text_input, TextSubmitted};
+use crate::{Apple, MovementSettings, SpeedCurve};
+
+const COUNTDOWN_SECONDS: f32 = 3.0;
+const MIN_PLAYERS: usize = 2;
+const ADDRESS_LEN: usize = 21; // 255.255.255.255:65535
+const SPEED_UP_PER_SEGMENT: f64 = 0.001;
+// name, head, body
+const SKINS: [(&str, Color, Color); 4] = [
+    ("Classic", Color::GREEN, Color::WHITE),
+    ("Ember", Color::ORANGE_RED, Color::GOLD),
+    ("Frost", Color::CYAN, Color::ALICE_BLUE),
+    ("Violet", Color::PURPLE, Color::PINK),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Mutator {
+    IceRink,
+    SpeedUp,
+}
+
+impl Mutator {
+    const ALL: [Mutator; 2] = [Mutator::IceRink, Mutator::SpeedUp];
+
+    fn name(self) -> &'static str {
+        match self {
+            Mutator::IceRink => "Ice rink",
+            Mutator::SpeedUp => "Speed up",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct LobbyPlayer {
+    seat: u8,
+    skin: usize,
+    ready: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+enum LobbyMessage {
+    // client to host
+    Join {
+        skin: usize,
+    },
+    Ready(bool),
+    Skin(usize),
+    // host to client
+    Welcome {
+        seat: u8,
+    },
+    Roster {
+        players: Vec<LobbyPlayer>,
+        mutators: Vec<Mutator>,
+    },
+    Start {
+        seed: u64,
+        mutators: Vec<Mutator>,
+    },
+    // either way, the host passes it on to everyone
+    Chat {
+        from: u8,
+        text: String,
+    },
+}
+
+enum Transport {
+    None,
+    Host {
+        listener: TcpListener,
+        clients: Vec<(u8, Connection)>, // by seat
+    },
+    Client(Connection),
+}
+
+enum SessionState {
+    Choosing, // hosting or joining
+    Joining(SocketAddr),
+    Lobby,
+    Countdown(Timer),
+    Playing,
+}
+
+#[derive(Resource)]
+struct Session {
+    state: SessionState,
+    transport: Transport,
+    connecting: Option<Task<io::Result<Connection>>>,
+    players: Vec<LobbyPlayer>,
+    seat: u8, // our own
+    skin: usize,
+    mutators: Vec<Mutator>,
+    seed: u64,
+    status: String,
+}
+
+impl Session {
+    fn me(&mut self) -> Option<&mut LobbyPlayer> {
+        let seat = self.seat;
+        self.players.iter_mut().find(|player| player.seat == seat)
+    }
+
+    fn leave(&mut self, status: String) {
+        info!(status, "Left the lobby");
+        self.state = SessionState::Choosing;
+        self.transport = Transport::None;
+        self.connecting = None;
+        self.players.clear();
+        self.status = status;
+    }
+
+    // the host tells everyone whenever anything changes
+    fn broadcast(&mut self, message: &LobbyMessage) {
+        let Transport::Host { clients, .. } = &mut self.transport else {
+            return;
+        };
+        clients.retain_mut(|(seat, connection)| match connection.send(message) {
+            Ok(()) => true,
+            Err(error) => {
+                info!(seat = *seat, "Player dropped: {error}");
+                false
+            }
+        });
+        let connected: Vec<u8> = clients.iter().map(|(seat, _)| *seat).collect();
+        let host = self.seat;
+        self.players
+            .retain(|player| player.seat == host || connected.contains(&player.seat));
+    }
+
+    fn roster(&self) -> LobbyMessage {
+        LobbyMessage::Roster {
+            players: self.players.clone(),
+            mutators: self.mutators.clone(),
+        }
+    }
+
+    fn start_countdown(&mut self) {
+        info!(seed = self.seed, mutators = ?self.mutators, "Countdown started");
+        self.state =
+            SessionState::Countdown(Timer::from_seconds(COUNTDOWN_SECONDS, TimerMode::Once));
+    }
+}
+
+#[derive(Component)]
+struct LobbyScreen;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum LobbyButton {
+    Host,
+    Join,
+    Ready,
+    Skin,
+    Mutator(Mutator),
+    Chat,
+    Leave,
+}
+
+#[derive(Component)]
+struct AddressPrompt;
+
+#[derive(Component)]
+struct AddressInput;
+
+#[derive(Component)]
+struct LobbyText;
+
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ChatPlugin)
+            .add_systems(Startup, setup_lobby)
+            .add_systems(
+                Update,
+                (
+                    press_lobby_buttons.after(navigate_menu).before(open_chat),
+                    join_lobby,
+                    finish_joining,
+                    run_host,
+                    run_client,
+                    relay_chat,
+                    count_down,
+                    update_lobby_screen,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_lobby(mut commands: Commands, seed: Res<RunSeed>, mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+    commands.insert_resource(Session {
+        state: SessionState::Choosing,
+        transport: Transport::None,
+        connecting: None,
+        players: Vec::new(),
+        seat: 1,
+        skin: 0,
+        mutators: Vec::new(),
+        seed: seed.0,
+        status: "Host a game or join one".to_string(),
+    });
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LobbyScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Lobby",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                LobbyText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Room code or address:",
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+                    spawn_text_input(parent, ADDRESS_LEN, AddressInput);
+                })
+                .insert(AddressPrompt);
+            for (label, button) in [
+                ("Host", LobbyButton::Host),
+                ("Join", LobbyButton::Join),
+                ("Ready", LobbyButton::Ready),
+                ("Skin", LobbyButton::Skin),
+            ] {
+                spawn_button(parent, label, button);
+            }
+            for mutator in Mutator::ALL {
+                spawn_button(parent, mutator.name(), LobbyButton::Mutator(mutator));
+            }
+            spawn_button(parent, "Chat", LobbyButton::Chat);
+            spawn_button(parent, "Leave", LobbyButton::Leave);
+        });
+}
+
+fn press_lobby_buttons(
+    mut menu_activated_event: EventReader<MenuActivated>,
+    mut session: ResMut<Session>,
+    mut chat: ResMut<Chat>,
+    button_query: Query<&LobbyButton>,
+    mut address_query: Query<&mut Visibility, With<AddressPrompt>>,
+) {
+    let Some(button) = menu_activated_event
+        .read()
+        .find_map(|event| button_query.get(event.0).ok().copied())
+    else {
+        return;
+    };
+    let session = &mut *session;
+    match (button, &session.state) {
+        (LobbyButton::Host, SessionState::Choosing) => {
+            let listener = match TcpListener::bind(("0.0.0.0", net::DEFAULT_PORT))
+                .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            {
+                Ok(listener) => listener,
+                Err(error) => {
+                    session.status = format!("Could not host: {error}");
+                    return;
+                }
+            };
+            let code = net::room_code(SocketAddrV4::new(net::local_address(), net::DEFAULT_PORT));
+            info!(code, "Hosting a lobby");
+            session.transport = Transport::Host {
+                listener,
+                clients: Vec::new(),
+            };
+            session.seat = 1;
+            session.players = vec![LobbyPlayer {
+                seat: 1,
+                skin: session.skin,
+                ready: false,
+            }];
+            session.state = SessionState::Lobby;
+            session.status = format!("Room code {code}");
+        }
+        (LobbyButton::Join, SessionState::Choosing) => {
+            for mut visibility in &mut address_query {
+                *visibility = Visibility::Inherited;
+            }
+        }
+        (LobbyButton::Ready, SessionState::Lobby) => {
+            let Some(me) = session.me() else {
+                return;
+            };
+            me.ready = !me.ready;
+            let ready = me.ready;
+            match &mut session.transport {
+                Transport::Client(connection) => {
+                    if let Err(error) = connection.send(&LobbyMessage::Ready(ready)) {
+                        session.leave(format!("Lost the host: {error}"));
+                    }
+                }
+                _ => {
+                    let roster = session.roster();
+                    session.broadcast(&roster);
+                }
+            }
+        }
+        (LobbyButton::Skin, SessionState::Choosing | SessionState::Lobby) => {
+            session.skin = (session.skin + 1) % SKINS.len();
+            let skin = session.skin;
+            if let Some(me) = session.me() {
+                me.skin = skin;
+            }
+            match &mut session.transport {
+                Transport::Client(connection) => {
+                    if let Err(error) = connection.send(&LobbyMessage::Skin(skin)) {
+                        session.leave(format!("Lost the host: {error}"));
+                    }
+                }
+                Transport::Host { .. } => {
+                    let roster = session.roster();
+                    session.broadcast(&roster);
+                }
+                Transport::None => {}
+            }
+        }
+        // only the host picks the rules
+        (LobbyButton::Mutator(mutator), SessionState::Lobby) => {
+            if !matches!(session.transport, Transport::Host { .. }) {
+                return;
+            }
+            if let Some(index) = session.mutators.iter().position(|m| *m == mutator) {
+                session.mutators.remove(index);
+            } else {
+                session.mutators.push(mutator);
+            }
+            let roster = session.roster();
+            session.broadcast(&roster);
+        }
+        // Enter on the button while typing sends instead
+        (LobbyButton::Chat, _) => chat.open = true,
+        (LobbyButton::Leave, SessionState::Lobby) => {
+            session.leave("Host a game or join one".to_string());
+        }
+        _ => {}
+    }
+}
+
+fn join_lobby(
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    mut session: ResMut<Session>,
+    input_query: Query<(), With<AddressInput>>,
+) {
+    let Some(text) = text_submitted_event
+        .read()
+        .find(|event| input_query.contains(event.input))
+        .map(|event| event.value.clone())
+    else {
+        return;
+    };
+    if !matches!(session.state, SessionState::Choosing) {
+        return;
+    }
+    let Some(address) = net::parse_address(&text) else {
+        session.status = format!("{text:?} is not a room code or address");
+        return;
+    };
+    session.connecting = Some(Connection::connect(address));
+    session.state = SessionState::Joining(address);
+    session.status = format!("Joining {address}");
+}
+
+fn finish_joining(mut session: ResMut<Session>) {
+    let SessionState::Joining(address) = session.state else {
+        return;
+    };
+    let Some(connected) = net::finished(&mut session.connecting) else {
+        return;
+    };
+    let skin = session.skin;
+    let joined = connected.and_then(|mut connection| {
+        connection.send(&LobbyMessage::Join { skin })?;
+        Ok(connection)
+    });
+    match joined {
+        Ok(connection) => {
+            info!(%address, "Joined a lobby");
+            session.transport = Transport::Client(connection);
+            session.state = SessionState::Lobby;
+            session.status = format!("Joined {address}, waiting for the host");
+        }
+        Err(error) => session.leave(format!("Could not join {address}: {error}")),
+    }
+}
+
+// the connections stay open once the game is on, for the chat
+fn run_host(
+    mut session: ResMut<Session>,
+    chat: Res<Chat>,
+    mut chat_received_event: EventWriter<ChatReceived>,
+) {
+    let session = &mut *session;
+    let lobby_open = matches!(session.state, SessionState::Lobby);
+    let Transport::Host { listener, clients } = &mut session.transport else {
+        return;
+    };
+    let mut changed = false;
+    while let Some((stream, address)) = lobby_open.then(|| listener.accept().ok()).flatten() {
+        let seat = (1..=u8::MAX)
+            .find(|seat| !session.players.iter().any(|player| player.seat == *seat))
+            .unwrap_or(u8::MAX);
+        let Ok(mut connection) = Connection::new(stream) else {
+            continue;
+        };
+        if connection.send(&LobbyMessage::Welcome { seat }).is_err() {
+            continue;
+        }
+        info!(%address, seat, "Player joined");
+        clients.push((seat, connection));
+        session.players.push(LobbyPlayer {
+            seat,
+            skin: 0,
+            ready: false,
+        });
+        changed = true;
+    }
+    let players = &mut session.players;
+    let mut said = Vec::new();
+    clients.retain_mut(|(seat, connection)| {
+        let messages = match connection.receive() {
+            Ok(messages) => messages,
+            Err(error) => {
+                info!(seat = *seat, "Player left: {error}");
+                players.retain(|player| player.seat != *seat);
+                changed = true;
+                return false;
+            }
+        };
+        let Some(player) = players.iter_mut().find(|player| player.seat == *seat) else {
+            return true;
+        };
+        for message in messages {
+            match message {
+                LobbyMessage::Join { skin } | LobbyMessage::Skin(skin) => player.skin = skin,
+                LobbyMessage::Ready(ready) => player.ready = ready,
+                LobbyMessage::Chat { text, .. } => {
+                    said.push((*seat, text));
+                    continue;
+                }
+                _ => continue,
+            }
+            changed = true;
+        }
+        true
+    });
+    if changed {
+        let roster = session.roster();
+        session.broadcast(&roster);
+    }
+    for (from, text) in said {
+        session.broadcast(&LobbyMessage::Chat {
+            from,
+            text: text.clone(),
+        });
+        if !chat.muted {
+            chat_received_event.send(ChatReceived { from, text });
+        }
+    }
+
+    if lobby_open
+        && session.players.len() >= MIN_PLAYERS
+        && session.players.iter().all(|player| player.ready)
+    {
+        let start = LobbyMessage::Start {
+            seed: session.seed,
+            mutators: session.mutators.clone(),
+        };
+        session.broadcast(&start);
+        session.start_countdown();
+    }
+}
+
+fn run_client(
+    mut session: ResMut<Session>,
+    chat: Res<Chat>,
+    mut chat_received_event: EventWriter<ChatReceived>,
+) {
+    let Transport::Client(connection) = &mut session.transport else {
+        return;
+    };
+    let messages = match connection.receive() {
+        Ok(messages) => messages,
+        Err(error) => {
+            session.leave(format!("Lost the host: {error}"));
+            return;
+        }
+    };
+    for message in messages {
+        match message {
+            LobbyMessage::Welcome { seat } => {
+                session.seat = seat;
+                session.status = format!("Joined as player {seat}");
+            }
+            LobbyMessage::Roster { players, mutators } => {
+                session.players = players;
+                session.mutators = mutators;
+            }
+            LobbyMessage::Start { seed, mutators } => {
+                session.seed = seed;
+                session.mutators = mutators;
+                session.start_countdown();
+            }
+            // our own messages come back too
+            LobbyMessage::Chat { from, text } if !chat.muted || from == session.seat => {
+                chat_received_event.send(ChatReceived { from, text });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn relay_chat(
+    mut chat_sent_event: EventReader<ChatSent>,
+    mut chat_received_event: EventWriter<ChatReceived>,
+    mut session: ResMut<Session>,
+) {
+    for ChatSent(text) in chat_sent_event.read() {
+        let message = LobbyMessage::Chat {
+            from: session.seat,
+            text: text.clone(),
+        };
+        match &mut session.transport {
+            Transport::Host { .. } => {
+                session.broadcast(&message);
+                chat_received_event.send(ChatReceived {
+                    from: session.seat,
+                    text: text.clone(),
+                });
+            }
+            Transport::Client(connection) => {
+                if let Err(error) = connection.send(&message) {
+                    warn!("Could not send a chat message: {error}");
+                }
+            }
+            Transport::None => chat_received_event.send(ChatReceived {
+                from: 0,
+                text: "Not connected, host or join first".to_string(),
+            }),
+        }
+    }
+}
+
+// in real time, the game is still paused
+#[allow(clippy::too_many_arguments)]
+fn count_down(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut session: ResMut<Session>,
+    mut theme: ResMut<Theme>,
+    mut movement_settings: ResMut<MovementSettings>,
+    mut speed_curve: ResMut<SpeedCurve>,
+    screen_query: Query<Entity, With<LobbyScreen>>,
+    apple_query: Query<Entity, With<Apple>>,
+) {
+    let SessionState::Countdown(timer) = &mut session.state else {
+        return;
+    };
+    if !timer.tick(real_time.delta()).finished() {
+        return;
+    }
+    session.state = SessionState::Playing;
+    // the host's seed, so everyone gets the same apples from here on
+    commands.insert_resource(RunSeed(session.seed));
+    commands.insert_resource(RunRng(StdRng::seed_from_u64(session.seed)));
+    for entity in &apple_query {
+        commands.entity(entity).despawn();
+    }
+    for mutator in &session.mutators {
+        match mutator {
+            Mutator::IceRink => *movement_settings = MovementSettings::ice_rink(),
+            Mutator::SpeedUp => speed_curve.speedup_per_segment = SPEED_UP_PER_SEGMENT,
+        }
+    }
+    let (_, head, body) = SKINS[session.skin];
+    theme.snake_head = head;
+    theme.snake_body = body;
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    time.unpause();
+    info!(seed = session.seed, "Network game started");
+}
+
+fn update_lobby_screen(session: Res<Session>, mut text_query: Query<&mut Text, With<LobbyText>>) {
+    if !session.is_changed() {
+        return;
+    }
+    let mut lines = vec![session.status.clone()];
+    lines.push(format!("Your skin: {}", SKINS[session.skin].0));
+    for player in &session.players {
+        lines.push(format!(
+            "Player {}{} - {} - {}",
+            player.seat,
+            if player.seat == session.seat {
+                " (you)"
+            } else {
+                ""
+            },
+            SKINS.get(player.skin).map_or("?", |skin| skin.0),
+            if player.ready { "ready" } else { "not ready" }
+        ));
+    }
+    if !session.players.is_empty() {
+        let mutators: Vec<&str> = session
+            .mutators
+            .iter()
+            .map(|mutator| mutator.name())
+            .collect();
+        lines.push(format!(
+            "Mutators: {}",
+            if mutators.is_empty() {
+                "none".to_string()
+            } else {
+                mutators.join(", ")
+            }
+        ));
+    }
+    if let SessionState::Countdown(timer) = &session.state {
+        lines.push(format!("Starting in {}", timer.remaining_secs().ceil()));
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,47 @@
+// Logging
+// Log level from the command line and info events for the moments worth finding in a bug report
+use std::str::FromStr;
+
+use bevy::log::{Level, LogPlugin};
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::AppleEaten;
+
+const DEFAULT_LEVEL: Level = Level::INFO;
+
+// --log-level <error|warn|info|debug|trace>
+pub fn log_plugin_from_args(args: &Args) -> LogPlugin {
+    let level = match args.values("--log-level") {
+        Some(values) => match values.first().map(|level| Level::from_str(level)) {
+            Some(Ok(level)) => level,
+            _ => {
+                args.problem(
+                    "--log-level expects one of error, warn, info, debug, trace".to_string(),
+                );
+                DEFAULT_LEVEL
+            }
+        },
+        None => DEFAULT_LEVEL,
+    };
+    LogPlugin { level, ..default() }
+}
+
+pub struct LoggingPlugin;
+
+impl Plugin for LoggingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, log_apples_eaten);
+    }
+}
+
+fn log_apples_eaten(mut apple_eaten_event: EventReader<AppleEaten>) {
+    for event in apple_eaten_event.read() {
+        info!(
+            position = ?event.position,
+            points = event.points,
+            growth = event.growth,
+            "Apple eaten"
+        );
+    }
+}
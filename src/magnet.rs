@@ -0,0 +1,122 @@
+// Magnet
+// Item effect that pulls the apple one cell toward the snake head every tick for a few seconds
+use bevy::prelude::*;
+
+use crate::items::{collect_power_ups, ItemEffect, PowerUpCollected};
+use crate::occupancy::{Occupancy, Occupant};
+use crate::{Apple, AppleEaten, Player, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const MAGNET_SECONDS: f32 = 5.0;
+
+// present while a magnet is active
+#[derive(Resource)]
+pub struct MagnetEffect {
+    player: Player,
+    remaining: Timer,
+}
+
+pub struct MagnetPlugin;
+
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                grant_magnet.after(collect_power_ups),
+                pull_apples.run_if(resource_exists::<MagnetEffect>()),
+            )
+                .chain()
+                .in_set(SnakeSystemSet::Collision),
+        );
+    }
+}
+
+fn grant_magnet(
+    mut commands: Commands,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut power_up_collected_event: EventReader<PowerUpCollected>,
+) {
+    let granted = apple_eaten_event
+        .read()
+        .map(|event| (event.player, &event.effects))
+        .chain(
+            power_up_collected_event
+                .read()
+                .map(|event| (event.player, &event.effects)),
+        )
+        .filter(|(_, effects)| effects.contains(&ItemEffect::Magnet))
+        .map(|(player, _)| player)
+        .last();
+    // picking up another magnet restarts the timer
+    if let Some(player) = granted {
+        commands.insert_resource(MagnetEffect {
+            player,
+            remaining: Timer::from_seconds(MAGNET_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pull_apples(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut magnet: ResMut<MagnetEffect>,
+    occupancy: Res<Occupancy>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    mut apple_query: Query<(&mut Apple, &mut Transform), Without<SnakeHead>>,
+) {
+    if magnet.remaining.tick(time.delta()).finished() {
+        commands.remove_resource::<MagnetEffect>();
+        return;
+    }
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == magnet.player)
+    else {
+        return;
+    };
+
+    for (mut apple, mut transform) in &mut apple_query {
+        let Some(&(x, y)) = apple.footprint.first() else {
+            continue;
+        };
+        let offset = (snake_head.position.0 - x, snake_head.position.1 - y);
+        // close the larger gap first, sidestep along the other axis when blocked
+        let mut steps = [(offset.0.signum(), 0), (0, offset.1.signum())];
+        if offset.1.abs() > offset.0.abs() {
+            steps.swap(0, 1);
+        }
+        let Some(footprint) = steps
+            .iter()
+            .filter(|step| **step != (0, 0))
+            .map(|step| {
+                apple
+                    .footprint
+                    .iter()
+                    .map(|cell| (cell.0 + step.0, cell.1 + step.1))
+                    .collect::<Vec<_>>()
+            })
+            // onto free cells or its own, heads block too so the snake makes the last step itself
+            .find(|footprint| {
+                footprint.iter().all(|cell| {
+                    !occupancy.is_off_board(*cell)
+                        && match occupancy.get(*cell) {
+                            None => true,
+                            Some(Occupant::Apple) => apple.footprint.contains(cell),
+                            Some(_) => false,
+                        }
+                })
+            })
+        else {
+            continue;
+        };
+        // sprites sit on the center of their footprint
+        let count = footprint.len() as f32;
+        let center = footprint.iter().fold(Vec2::ZERO, |sum, cell| {
+            sum + Vec2::new(cell.0 as f32, cell.1 as f32)
+        }) / count;
+        transform.translation.x = center.x * PIXEL_UNIT_SIZE;
+        transform.translation.y = center.y * PIXEL_UNIT_SIZE;
+        apple.footprint = footprint;
+    }
+}
@@ -1,26 +1,192 @@
 // Snake
 // Simple game of snake in Rust using Bevy
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use direction::Direction;
+
+mod ability;
+mod achievements;
+mod ai;
+mod apple_prediction;
+mod args;
+mod assist;
+mod battle;
+mod bitboard;
+mod board_dump;
+mod board_texture;
+mod body_path;
+mod bones;
+mod camera;
+mod chat;
+mod cheats;
+mod config;
+mod corridor;
+mod crt;
+mod day_night;
+mod determinism;
+mod direction;
+mod director;
+mod effects;
+mod embed;
+mod errors;
+mod event_log;
+mod fancy;
+mod frame_budget;
+mod game_state;
+mod graphics;
+mod gravity;
+mod hamiltonian;
+mod heatmap;
+mod high_score;
+mod hints;
+mod hunger;
+mod input;
+mod input_source;
+mod items;
+mod lives;
+mod lobby;
+mod logging;
+mod magnet;
+mod menu;
+mod minimap;
+mod molt;
+mod music;
+mod net;
+mod objectives;
+mod obstacles;
+mod occupancy;
+mod occupancy_bench;
+mod pathfinding;
+mod playfield;
+mod prediction;
+mod profile;
+mod quit;
+mod replay;
+mod replay_format;
+mod replay_render;
+mod replay_viewer;
+mod ron_asset;
+mod route;
+mod scenario;
+mod score;
+mod scoring;
+mod seasonal;
+mod seed;
+mod shield;
+mod snake_layout;
+mod spawn_balance;
+mod spectate;
+mod splits;
+mod stats_window;
+mod stone;
+mod storage;
+mod telemetry;
+mod theme;
+mod tick_pipeline;
+mod training;
+mod tutorial;
+mod tween;
+mod unlocks;
+mod widgets;
+mod window_config;
+mod wrap;
+mod zen;
+mod zones;
+
+#[cfg(feature = "egui")]
+mod debug_ui;
+#[cfg(feature = "livesplit")]
+mod livesplit;
+#[cfg(feature = "scripting")]
+mod scripting;
 
 const PIXEL_UNIT_SIZE: f32 = 24.0;
 const TICKRATE: f64 = 0.08;
 const PLAYFIELD: (i32, i32) = (33, 33); // must be odd as snake starts in the middle
-const PLAYFIELD_MAX_INDEX: u32 = (PLAYFIELD.0 * PLAYFIELD.1) as u32;
+const MIN_BOARD: i32 = 5; // cells across and down, room for a snake to turn around
+const START_LENGTH: i32 = 2; // head included
+const SPAWN_PROTECTION_CELLS: i32 = 2; // ahead of each head, kept free of new items
+const REGION_ATTEMPTS: u32 = 32; // tries within a preferred spawn region before using the whole board
+const SPAWN_ATTEMPTS: u32 = 1024; // random tries before going through every free spot
+
+// which snake an entity belongs to, on the head and on every body segment
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Player(u8);
+
+impl Player {
+    const ONE: Player = Player(1); // the keyboard player
+
+    // snakes start on separate rows, the first one through the center
+    fn spawn_row(self) -> i32 {
+        let index = self.0.saturating_sub(1) as i32;
+        let offset = (index + 1) / 2 * 4;
+        if index % 2 == 1 {
+            offset
+        } else {
+            -offset
+        }
+    }
+
+    // past every local seat, a snake steered over the network
+    fn is_remote(self) -> bool {
+        self.0 >= spectate::FIRST_REMOTE_PLAYER
+    }
+}
+
+// where the core logic runs, in this order both every frame and every tick, for plugins and mods
+// to slot their systems in relative to it
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SnakeSystemSet {
+    Input,     // steering from keys, bots and remote players
+    Movement,  // heads step, bodies follow and grow
+    Collision, // what the move ran into: walls, bodies, items, apples to score
+    Spawning,  // apples, stones, power-ups and hazards for the next tick
+    Rendering, // HUD and sprites catching up with the board
+}
+
+impl SnakeSystemSet {
+    const ORDER: (Self, Self, Self, Self, Self) = (
+        SnakeSystemSet::Input,
+        SnakeSystemSet::Movement,
+        SnakeSystemSet::Collision,
+        SnakeSystemSet::Spawning,
+        SnakeSystemSet::Rendering,
+    );
+}
 
 #[derive(Component)]
 struct SnakeHead {
     direction: Direction,
     potential_direction: Direction,
+    // directions waiting out the input latency, oldest first
+    delayed_directions: VecDeque<Direction>,
     position: (i32, i32),
+    // cells the head entered during the last tick
+    path: Vec<(i32, i32)>,
+    // extra cells to dash through on the next tick
+    pending_dash: u32,
+    // body segments from the neck to the tail
+    segments: Vec<Entity>,
+    // cell the tail last left, where the next segment grows
+    tail_position: (i32, i32),
 }
 
 impl SnakeHead {
-    fn new() -> Self {
+    fn new(position: (i32, i32), segments: Vec<Entity>, tail_position: (i32, i32)) -> Self {
         SnakeHead {
             direction: Direction::Right,
             potential_direction: Direction::Right,
-            position: (0, 0),
+            delayed_directions: VecDeque::new(),
+            position,
+            path: Vec::new(),
+            pending_dash: 0,
+            segments,
+            tail_position,
         }
     }
 }
@@ -30,293 +196,1203 @@ struct SnakeBody {
     position: (i32, i32),
 }
 
-#[derive(Resource)]
-struct LastPosition {
-    value: (i32, i32),
+#[derive(Component)]
+struct Background;
+
+#[derive(Component)]
+struct Border;
+
+// segments still owed to each snake, grown one per tick
+#[derive(Resource, Default)]
+struct PendingGrowth(HashMap<Player, u32>);
+
+#[derive(Resource, PartialEq)]
+struct MovementSettings {
+    step_size: u32,       // cells moved per tick
+    input_latency: usize, // ticks before a direction change takes effect
+}
+
+impl MovementSettings {
+    fn classic() -> Self {
+        MovementSettings {
+            step_size: 1,
+            input_latency: 0,
+        }
+    }
+
+    // easter egg: the snake slides on ice and reacts a tick late
+    fn ice_rink() -> Self {
+        MovementSettings {
+            step_size: 2,
+            input_latency: 1,
+        }
+    }
+
+    fn mode_name(&self) -> &'static str {
+        if *self == MovementSettings::ice_rink() {
+            "Ice rink"
+        } else if *self == MovementSettings::classic() {
+            "Classic"
+        } else {
+            "Custom"
+        }
+    }
+}
+
+// tick length as a function of snake length
+#[derive(Resource, Clone, Deserialize)]
+struct SpeedCurve {
+    base_tickrate: f64,
+    speedup_per_segment: f64, // seconds shaved off the tick per body segment
+    min_tickrate: f64,
+}
+
+impl SpeedCurve {
+    fn tickrate(&self, body_length: usize) -> f64 {
+        (self.base_tickrate - self.speedup_per_segment * body_length as f64).max(self.min_tickrate)
+    }
+}
+
+// snake speed in cells per second relative to the classic pace
+fn speed_factor(movement_settings: &MovementSettings, fixed_time: &Time<Fixed>) -> f32 {
+    let cells_per_second = movement_settings.step_size as f64 / fixed_time.timestep().as_secs_f64();
+    (cells_per_second * TICKRATE) as f32
 }
 
 #[derive(Component)]
 struct Apple {
+    footprint: Vec<(i32, i32)>,
+    color: Option<Color>, // the theme's apple color when unset
+    growth: u32,
+    points: u32,
+    effects: Vec<items::ItemEffect>,
+}
+
+#[derive(Event)]
+struct AppleEaten {
+    player: Player,
+    growth: u32,
+    points: u32,
+    effects: Vec<items::ItemEffect>,
     position: (i32, i32),
 }
 
 #[derive(Event)]
-struct AppleEaten;
+struct SnakeDied {
+    player: Player,
+    cause: DeathCause,
+}
+
+// the last life is gone
+#[derive(Event)]
+struct GameOver;
 
-#[derive(PartialEq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+enum DeathCause {
+    Wall,
+    SelfCollision,
+    OtherSnake,
+    Starvation,
 }
 
+// the board, the snakes and the rules they move by, with everything else in main layered on top.
+// Configured by its builder alone, the command line only reaches it through main:
+// SnakeGamePlugin::new().board(33, 33).tickrate(0.08).wrap(true).players(2)
+pub struct SnakeGamePlugin {
+    board: (i32, i32),
+    playfield: playfield::PlayfieldShape,
+    tickrate: f64,
+    wrap: bool,
+    players: u8,
+    placement: Option<Rect>,
+    texture: Option<UVec2>,
+}
+
+impl SnakeGamePlugin {
+    pub fn new() -> Self {
+        SnakeGamePlugin {
+            board: PLAYFIELD,
+            playfield: playfield::PlayfieldShape::Rectangle,
+            tickrate: TICKRATE,
+            wrap: false,
+            players: 1,
+            placement: None,
+            texture: None,
+        }
+    }
+
+    // cells across and down, odd and from MIN_BOARD to PLAYFIELD, other sizes are fitted with a
+    // warning
+    pub fn board(mut self, width: i32, height: i32) -> Self {
+        self.board = (width, height);
+        self
+    }
+
+    pub fn playfield(mut self, shape: playfield::PlayfieldShape) -> Self {
+        self.playfield = shape;
+        self
+    }
+
+    // seconds per tick before the snake speeds up
+    pub fn tickrate(mut self, tickrate: f64) -> Self {
+        self.tickrate = tickrate;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    // snakes at the start, player one on the keyboard and the others left to bots or remote seats.
+    // Only as many as have their starting rows on the board, with a warning for the rest
+    pub fn players(mut self, players: u8) -> Self {
+        self.players = players.max(1);
+        self
+    }
+
+    // fits the board into this rect of the host's world, drawn by the host's camera
+    pub fn placement(mut self, rect: Rect) -> Self {
+        self.placement = Some(rect);
+        self
+    }
+
+    // also draws the board into an image of this many pixels, see BoardTexture
+    pub fn render_to_texture(mut self, width: u32, height: u32) -> Self {
+        self.texture = Some(UVec2::new(width, height));
+        self
+    }
+}
+
+impl Default for SnakeGamePlugin {
+    fn default() -> Self {
+        SnakeGamePlugin::new()
+    }
+}
+
+impl Plugin for SnakeGamePlugin {
+    fn build(&self, app: &mut App) {
+        let board = fit_board(self.board);
+        if board != self.board {
+            warn!(
+                asked = ?self.board,
+                used = ?board,
+                "Boards are odd and {MIN_BOARD} to {} cells a side",
+                PLAYFIELD.0.min(PLAYFIELD.1)
+            );
+        }
+        let mut playfield_mask =
+            playfield::PlayfieldMask::new(self.playfield).with_board(board.0, board.1);
+        playfield_mask.wrap = self.wrap;
+        // the spawn rows go outwards from the middle, the first one off the board ends them
+        let fitting_players = (1..=self.players)
+            .take_while(|player| {
+                snake_layout::SnakeLayout::starting(
+                    Player(*player),
+                    START_LENGTH,
+                    playfield_mask.start_column(),
+                )
+                .check_fits(&playfield_mask)
+                .is_ok()
+            })
+            .count() as u8;
+        let players = fitting_players.max(1);
+        if players != self.players {
+            warn!(
+                asked = self.players,
+                used = players,
+                "Not every player has a starting row on this board"
+            );
+        }
+        app.insert_resource(playfield_mask)
+            .init_resource::<PendingGrowth>()
+            .init_resource::<spawn_balance::RecentSpawns>()
+            .insert_resource(SpeedCurve {
+                base_tickrate: self.tickrate,
+                speedup_per_segment: 0.0,
+                min_tickrate: self.tickrate / 2.0,
+            })
+            .add_systems(Startup, (setup_ui, setup_snake))
+            .add_systems(
+                Update,
+                (
+                    apply_speed_curve.in_set(SnakeSystemSet::Movement),
+                    // a battle runs its own rounds instead
+                    game_over
+                        .in_set(SnakeSystemSet::Collision)
+                        .run_if(not(resource_exists::<battle::Battle>())),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (move_snake, grow_snake_body.after(move_snake))
+                        .in_set(SnakeSystemSet::Movement),
+                    // every tick, several can pass in one frame
+                    (border_collision, snake_body_collision).in_set(SnakeSystemSet::Collision),
+                    // on the tick, so a replay draws the same apples
+                    spawn_apple
+                        .in_set(SnakeSystemSet::Spawning)
+                        .after(stone::place_stones),
+                ),
+            )
+            .configure_sets(Update, SnakeSystemSet::ORDER.chain())
+            .configure_sets(FixedUpdate, SnakeSystemSet::ORDER.chain())
+            .add_event::<AppleEaten>()
+            .add_event::<SnakeDied>()
+            .add_event::<GameOver>()
+            .insert_resource(Time::<Fixed>::from_seconds(self.tickrate))
+            .insert_resource(StartingPlayers(players));
+        app.add_plugins((
+            tick_pipeline::TickPipelinePlugin,
+            input_source::InputSourcePlugin,
+        ));
+        if self.wrap {
+            app.add_plugins(wrap::WrapPlugin);
+        }
+        if let Some(rect) = self.placement {
+            app.add_plugins(embed::EmbedPlugin {
+                placement: embed::BoardPlacement::fit(rect),
+            });
+        }
+        if let Some(resolution) = self.texture {
+            app.add_plugins(board_texture::BoardTexturePlugin { resolution });
+        }
+    }
+}
+
+// the nearest odd size within MIN_BOARD and PLAYFIELD on each side
+fn fit_board((width, height): (i32, i32)) -> (i32, i32) {
+    let fit = |size: i32, most: i32| (size.clamp(MIN_BOARD, most) - 1) / 2 * 2 + 1;
+    (fit(width, PLAYFIELD.0), fit(height, PLAYFIELD.1))
+}
+
+// a mode that runs without the game, None when its flag wasn't given
+type Tool = fn(&args::Args) -> Option<Result<String, String>>;
+
+// how many snakes setup_snake spawns
+#[derive(Resource)]
+struct StartingPlayers(u8);
+
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .insert_resource(LastPosition { value: (0, 0) })
-        .add_systems(Startup, (setup_ui, setup_snake))
-        .add_systems(
-            Update,
-            (
-                spawn_apple,
-                player_input,
-                border_collision,
-                snake_body_collision.after(move_snake_head),
-            ),
-        )
-        .add_systems(
-            FixedUpdate,
-            (
-                move_snake_body,
-                move_snake_head.after(move_snake_body),
-                grow_snake_body.after(move_snake_head),
-            ),
+    let args = args::Args::from_env();
+
+    // checking a submitted run needs no window, and neither do bot training, the determinism check
+    // and the occupancy benchmark
+    let tools: [Tool; 4] = [
+        replay::verify_from_args,
+        training::train_from_args,
+        determinism::check_from_args,
+        occupancy_bench::bench_from_args,
+    ];
+    for tool in tools {
+        if let Some(result) = tool(&args) {
+            finish_tool(&args, result.map(|report| println!("{report}")));
+        }
+    }
+
+    // and rendering to frames, which can be going to stdout
+    if let Some(result) = replay_render::render_from_args(&args) {
+        finish_tool(&args, result.map(|summary| eprintln!("{summary}")));
+    }
+
+    // a replay plays back without a game behind it
+    if let Some(replay) = replay_viewer::replay_from_args(&args) {
+        let replay = replay.unwrap_or_else(|error| finish_tool(&args, Err(error)));
+        App::new()
+            .insert_resource(args.clone())
+            .add_plugins((
+                DefaultPlugins.set(logging::log_plugin_from_args(&args)),
+                args::ArgsPlugin,
+                replay_viewer::ReplayViewerPlugin { replay },
+            ))
+            .run();
+        return;
+    }
+
+    // a spectator only draws what the host streams to it
+    if let Some(host) = spectate::host_from_args(&args) {
+        App::new()
+            .insert_resource(args.clone())
+            .add_plugins((
+                DefaultPlugins.set(logging::log_plugin_from_args(&args)),
+                args::ArgsPlugin,
+                spectate::SpectatorPlugin { host },
+            ))
+            .run();
+        return;
+    }
+
+    let movement_settings = if args.has("--ice-rink") {
+        MovementSettings::ice_rink()
+    } else {
+        MovementSettings::classic()
+    };
+
+    let display_settings = graphics::DisplaySettings::load();
+
+    let mut app = App::new();
+    app.insert_resource(args.clone())
+        .add_plugins(
+            DefaultPlugins
+                .set(logging::log_plugin_from_args(&args))
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        present_mode: display_settings.present_mode(),
+                        mode: display_settings.window_mode(),
+                        ..default()
+                    }),
+                    // the optional stats window should not keep the game running
+                    exit_condition: bevy::window::ExitCondition::OnPrimaryClosed,
+                    // closing goes through the quit confirmation
+                    close_when_requested: false,
+                }),
         )
-        .add_event::<AppleEaten>()
-        .insert_resource(Time::<Fixed>::from_seconds(TICKRATE))
-        .run();
-}
-
-fn setup_ui(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
-    commands.spawn(NodeBundle {
-        style: Style {
-            border: UiRect::all(Val::Px(1.0)),
-            width: Val::Px(PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE),
-            height: Val::Px(PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE),
-            align_self: AlignSelf::Center,
-            justify_self: JustifySelf::Center,
-            ..default()
-        },
-        border_color: Color::BLACK.into(),
-        ..default()
-    });
-    commands.spawn((SpriteBundle {
-        sprite: Sprite {
-            color: Color::GRAY.into(),
-            custom_size: Some(Vec2::new(
-                PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE,
-                PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE,
-            )),
-            ..default()
-        },
-        transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.1)),
-        ..default()
-    },));
+        .add_plugins(args::ArgsPlugin)
+        .add_plugins((
+            score::ScorePlugin,
+            zones::ScoreZonePlugin,
+            shield::ShieldPlugin,
+            magnet::MagnetPlugin,
+            ability::AbilityPlugin,
+            effects::EffectsPlugin,
+            music::MusicPlugin,
+            seasonal::SeasonalPlugin,
+            theme::ThemePlugin,
+            config::ConfigPlugin,
+            items::ItemsPlugin,
+            occupancy::OccupancyPlugin,
+            tutorial::TutorialPlugin,
+            playfield::PlayfieldPlugin,
+            stone::StonePlugin,
+        ))
+        // run records and diagnostics
+        .add_plugins((
+            high_score::HighScorePlugin,
+            profile::ProfilePlugin,
+            achievements::AchievementsPlugin,
+            unlocks::UnlocksPlugin,
+            seed::SeedPlugin,
+            replay::ReplayPlugin,
+            route::RoutePlugin,
+            errors::ErrorsPlugin,
+            logging::LoggingPlugin,
+            board_dump::BoardDumpPlugin,
+        ))
+        // window and presentation
+        .add_plugins((
+            camera::CameraPlugin,
+            minimap::MinimapPlugin,
+            graphics::DisplayPlugin,
+            tween::TweenPlugin,
+            window_config::WindowConfigPlugin,
+            menu::MenuPlugin,
+            widgets::WidgetsPlugin,
+            quit::QuitPlugin,
+            event_log::EventLogPlugin,
+            fancy::FancyGraphicsPlugin,
+            crt::CrtPlugin,
+            frame_budget::FrameBudgetPlugin,
+        ))
+        .insert_resource(graphics::GraphicsSettings::from_args(&args))
+        .insert_resource(display_settings)
+        .insert_resource(input::InputSettings::from_args(&args))
+        .insert_resource(movement_settings);
+    let mut snake_game = SnakeGamePlugin::new()
+        .playfield(playfield::PlayfieldShape::from_args(&args))
+        .wrap(args.has("--wrap"));
+    let board_window = board_texture::resolution_from_args(&args);
+    if let Some(resolution) = board_window {
+        snake_game = snake_game.render_to_texture(resolution.x, resolution.y);
+    }
+    app.add_plugins(snake_game);
+    if board_window.is_some() {
+        app.add_plugins(board_texture::BoardWindowPlugin);
+    }
+    if args.has("--lives") {
+        app.add_plugins(lives::LivesPlugin);
+    }
+    if args.has("--hunger") {
+        app.add_plugins(hunger::HungerPlugin);
+    }
+    if args.has("--telemetry") {
+        app.add_plugins((telemetry::TelemetryPlugin, heatmap::HeatmapPlugin));
+    }
+    if args.has("--splits") {
+        app.add_plugins(splits::SplitsPlugin);
+    }
+    #[cfg(feature = "livesplit")]
+    if args.has("--livesplit") {
+        app.add_plugins(livesplit::LiveSplitPlugin);
+    }
+    if args.has("--battle") {
+        app.add_plugins((battle::BattlePlugin, ai::AiPlugin));
+    } else if args.has("--versus-cpu") || args.has("--demo") {
+        app.add_plugins((ai::AiPlugin, bones::BonesPlugin));
+    }
+    if args.has("--obstacles") {
+        app.add_plugins(obstacles::ObstaclesPlugin);
+    }
+    if args.has("--corridor") {
+        app.add_plugins(corridor::CorridorPlugin);
+    }
+    if args.has("--molt") {
+        app.add_plugins(molt::MoltPlugin);
+    }
+    if args.has("--gravity") {
+        app.add_plugins(gravity::GravityPlugin);
+    }
+    if args.has("--zen") {
+        app.add_plugins(zen::ZenPlugin);
+    }
+    if args.has("--scenarios") {
+        app.add_plugins(scenario::ScenarioPlugin);
+    }
+    if args.has("--assist") {
+        app.add_plugins(assist::AssistPlugin);
+    }
+    if args.has("--hints") {
+        app.add_plugins(hints::HintsPlugin);
+    }
+    if args.has("--predict-apples") {
+        app.add_plugins(apple_prediction::ApplePredictionPlugin);
+    }
+    if args.has("--objectives") {
+        app.add_plugins(objectives::ObjectivesPlugin);
+    }
+    if args.has("--endless") {
+        app.add_plugins(director::DirectorPlugin);
+    }
+    if args.has("--day-night") {
+        app.add_plugins(day_night::DayNightPlugin);
+    }
+    if args.has("--lobby") {
+        app.add_plugins(lobby::LobbyPlugin);
+    }
+    if args.has("--spectator-port") {
+        app.add_plugins(spectate::SpectateHostPlugin);
+    }
+    if args.has("--stats-window") {
+        app.add_plugins(stats_window::StatsWindowPlugin);
+    }
+    #[cfg(feature = "egui")]
+    app.add_plugins(debug_ui::DebugUiPlugin);
+    if !args.ranked() {
+        app.add_plugins(cheats::CheatsPlugin);
+    }
+    // mods can change anything, a ranked run plays without them
+    #[cfg(feature = "scripting")]
+    if !args.ranked() {
+        app.add_plugins(scripting::ScriptingPlugin);
+    }
+    app.run();
 }
 
-fn setup_snake(mut commands: Commands, mut last_position: ResMut<LastPosition>) {
+// the end of a mode that runs without the game, with no log to note its problems in
+fn finish_tool(args: &args::Args, result: Result<(), String>) -> ! {
+    for problem in args.take_problems() {
+        eprintln!("{problem}");
+    }
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+    std::process::exit(0);
+}
+
+fn setup_ui(
+    mut commands: Commands,
+    theme: Res<theme::Theme>,
+    playfield_mask: Res<playfield::PlayfieldMask>,
+    placement: Option<Res<embed::BoardPlacement>>,
+) {
+    // an embedded board is drawn by the host's camera
+    if placement.is_none() {
+        commands.spawn((Camera2dBundle::default(), camera::MainCamera));
+    }
+    // border, drawn in the world so it stays put when the camera follows the snake
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
-                color: Color::GREEN.into(),
-                custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                color: Color::BLACK,
+                custom_size: Some(playfield_mask.size() + 2.0),
                 ..default()
             },
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.2)),
             ..default()
         },
-        SnakeHead::new(),
+        Border,
     ));
-
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
-                color: Color::WHITE.into(),
-                custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                color: theme.background,
+                custom_size: Some(playfield_mask.size()),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(-PIXEL_UNIT_SIZE, 0.0, 100.0)),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, -0.1)),
             ..default()
         },
-        SnakeBody { position: (-1, 0) },
+        Background,
     ));
+}
 
-    last_position.value = (-2, 0);
+fn setup_snake(
+    mut commands: Commands,
+    theme: Res<theme::Theme>,
+    playfield_mask: Res<playfield::PlayfieldMask>,
+    starting_players: Res<StartingPlayers>,
+) {
+    for player in 1..=starting_players.0 {
+        spawn_snake(
+            &mut commands,
+            &theme,
+            &playfield_mask,
+            Player(player),
+            START_LENGTH,
+        );
+    }
 }
 
-fn get_valid_apple_spawn(used_positions: Vec<(i32, i32)>) -> (i32, i32) {
-    let mut valid_spawn = rand::thread_rng().gen_range(0..PLAYFIELD_MAX_INDEX) as i32;
-    while used_positions.contains(&(
-        valid_spawn % PLAYFIELD.0 - PLAYFIELD.0 / 2,
-        valid_spawn / PLAYFIELD.1 - PLAYFIELD.1 / 2,
-    )) {
-        valid_spawn = rand::thread_rng().gen_range(0..PLAYFIELD_MAX_INDEX) as i32;
+// spawns a snake facing right with its head in the playfield's start column on its row, returning
+// the head entity
+fn spawn_snake(
+    commands: &mut Commands,
+    theme: &theme::Theme,
+    playfield_mask: &playfield::PlayfieldMask,
+    player: Player,
+    length: i32,
+) -> Entity {
+    let layout = snake_layout::SnakeLayout::starting(player, length, playfield_mask.start_column());
+    spawn_snake_from_layout(commands, theme, player, &layout)
+}
+
+// facing away from the neck, returning the head entity
+fn spawn_snake_from_layout(
+    commands: &mut Commands,
+    theme: &theme::Theme,
+    player: Player,
+    layout: &snake_layout::SnakeLayout,
+) -> Entity {
+    let cells = layout.cells();
+    let segments = cells[1..]
+        .iter()
+        .map(|position| {
+            commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: theme.snake_body,
+                            custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            position.0 as f32 * PIXEL_UNIT_SIZE,
+                            position.1 as f32 * PIXEL_UNIT_SIZE,
+                            0.0,
+                        )),
+                        ..default()
+                    },
+                    SnakeBody {
+                        position: *position,
+                    },
+                    player,
+                ))
+                .id()
+        })
+        .collect();
+
+    let head = cells[0];
+    let direction = cells
+        .get(1)
+        .and_then(|neck| {
+            Direction::ALL
+                .into_iter()
+                .find(|direction| direction.step(*neck) == head)
+        })
+        .unwrap_or(Direction::Right);
+    // carries on past the tail the way the body runs
+    let tail = cells[cells.len() - 1];
+    let before_tail = cells
+        .len()
+        .checked_sub(2)
+        .map_or(direction.step(tail), |index| cells[index]);
+    let tail_position = (2 * tail.0 - before_tail.0, 2 * tail.1 - before_tail.1);
+
+    let mut snake_head = SnakeHead::new(head, segments, tail_position);
+    snake_head.direction = direction;
+    snake_head.potential_direction = direction;
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.snake_head,
+                    custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    head.0 as f32 * PIXEL_UNIT_SIZE,
+                    head.1 as f32 * PIXEL_UNIT_SIZE,
+                    0.0,
+                )),
+                ..default()
+            },
+            snake_head,
+            player,
+        ))
+        .id()
+}
+
+// picks a free size x size footprint inside the playfield, also keeping clear of the heads and the
+// cells just ahead of them so nothing lands in a snake's mouth
+// with a region, origins there are tried first before falling back to the whole board, and on a
+// board with rooms the first tries also stay out of the rooms the snakes are in
+// None when nothing that size fits anywhere
+fn get_valid_apple_spawn<'a>(
+    rng: &mut impl Rng,
+    playfield_mask: &playfield::PlayfieldMask,
+    mut used_positions: Vec<(i32, i32)>,
+    snake_heads: impl IntoIterator<Item = &'a SnakeHead>,
+    size: i32,
+    region: Option<spawn_balance::Quadrant>,
+) -> Option<Vec<(i32, i32)>> {
+    let mut occupied_rooms = Vec::new();
+    for snake_head in snake_heads {
+        occupied_rooms.extend(playfield_mask.room(snake_head.position));
+        let mut position = snake_head.position;
+        used_positions.push(position);
+        for _ in 0..SPAWN_PROTECTION_CELLS {
+            position = snake_head.direction.step(position);
+            used_positions.push(position);
+        }
+    }
+    let (half_width, half_height) = playfield_mask.half_extents();
+    let size = size.max(1);
+    let footprint_at = |origin: (i32, i32)| -> Vec<(i32, i32)> {
+        (0..size)
+            .flat_map(|dx| (0..size).map(move |dy| (origin.0 + dx, origin.1 + dy)))
+            .collect()
+    };
+    let is_free = |footprint: &[(i32, i32)]| {
+        !footprint
+            .iter()
+            .any(|position| playfield_mask.is_wall(*position) || used_positions.contains(position))
+    };
+    let mut region_attempts = if region.is_some() { REGION_ATTEMPTS } else { 0 };
+    let mut room_attempts = REGION_ATTEMPTS;
+    for _ in 0..SPAWN_ATTEMPTS {
+        let (x_range, y_range) = match region {
+            Some(quadrant) if region_attempts > 0 => {
+                region_attempts -= 1;
+                quadrant.origin_ranges(size, (half_width, half_height))
+            }
+            _ => (
+                -half_width..=half_width - size + 1,
+                -half_height..=half_height - size + 1,
+            ),
+        };
+        if x_range.is_empty() || y_range.is_empty() {
+            continue;
+        }
+        let origin = (
+            rng.gen_range(x_range) + playfield_mask.scroll(),
+            rng.gen_range(y_range),
+        );
+        let footprint = footprint_at(origin);
+        if !is_free(&footprint) {
+            continue;
+        }
+        if room_attempts > 0 {
+            room_attempts -= 1;
+            let in_occupied_room = footprint.iter().any(|position| {
+                playfield_mask
+                    .room(*position)
+                    .is_some_and(|room| occupied_rooms.contains(&room))
+            });
+            if in_occupied_room {
+                continue;
+            }
+        }
+        return Some(footprint);
+    }
+    // a board this crowded has few enough free spots left to list them all
+    let mut free: Vec<Vec<(i32, i32)>> = (-half_width..=half_width - size + 1)
+        .flat_map(|x| {
+            (-half_height..=half_height - size + 1).map(move |y| (x + playfield_mask.scroll(), y))
+        })
+        .map(footprint_at)
+        .filter(|footprint| is_free(footprint))
+        .collect();
+    if free.is_empty() {
+        return None;
     }
+    let index = rng.gen_range(0..free.len());
+    Some(free.swap_remove(index))
+}
+
+// the next apple's kind and footprint, on the board as it is now; apple_prediction draws the same
+// from a copy of the generator
+#[allow(clippy::too_many_arguments)]
+fn roll_apple<'a, 'b>(
+    rng: &mut impl Rng,
+    item_table: &'a items::ItemTable,
+    spawn_balance: spawn_balance::SpawnBalance,
+    recent_spawns: &spawn_balance::RecentSpawns,
+    playfield_mask: &playfield::PlayfieldMask,
+    used_positions: Vec<(i32, i32)>,
+    snake_heads: impl IntoIterator<Item = &'b SnakeHead>,
+) -> Option<(&'a items::AppleDefinition, Vec<(i32, i32)>)> {
+    let definition = items::pick_weighted(rng, &item_table.apples, |definition| {
+        definition.spawn_weight
+    })?;
+    let region = match spawn_balance {
+        spawn_balance::SpawnBalance::Uniform => None,
+        spawn_balance::SpawnBalance::Balanced => recent_spawns.pick_quadrant(rng),
+    };
+    let footprint = get_valid_apple_spawn(
+        rng,
+        playfield_mask,
+        used_positions,
+        snake_heads,
+        definition.size_on(playfield_mask),
+        region,
+    )?;
+    Some((definition, footprint))
+}
 
-    (
-        (valid_spawn % PLAYFIELD.0 - PLAYFIELD.0 / 2) as i32,
-        (valid_spawn / PLAYFIELD.1 - PLAYFIELD.1 / 2) as i32,
-    )
+// where snakes' bodies and power-ups keep new apples away from
+fn apple_blocked_positions(
+    snake_body_query: &Query<&SnakeBody>,
+    power_up_query: &Query<&items::PowerUp>,
+) -> Vec<(i32, i32)> {
+    snake_body_query
+        .iter()
+        .map(|snake_body| snake_body.position)
+        .chain(power_up_query.iter().map(|power_up| power_up.position))
+        .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_apple(
     mut commands: Commands,
     snake_head_query: Query<&SnakeHead>,
     snake_body_query: Query<&SnakeBody>,
     apple_query: Query<&Apple>,
+    power_up_query: Query<&items::PowerUp>,
+    item_table: Res<items::ItemTable>,
+    theme: Res<theme::Theme>,
+    spawn_balance: Res<spawn_balance::SpawnBalance>,
+    mut recent_spawns: ResMut<spawn_balance::RecentSpawns>,
+    mut run_rng: ResMut<seed::RunRng>,
+    playfield_mask: Res<playfield::PlayfieldMask>,
 ) {
     if !apple_query.is_empty() {
         return;
     }
-
-    let mut snake_positions = Vec::new();
-    let snake_head = snake_head_query.single();
-    for snake_body in &snake_body_query {
-        snake_positions.push(snake_body.position);
-    }
-    snake_positions.push(snake_head.position);
-    let valid_spawn = get_valid_apple_spawn(snake_positions);
+    let Some((definition, footprint)) = roll_apple(
+        &mut run_rng.0,
+        &item_table,
+        *spawn_balance,
+        &recent_spawns,
+        &playfield_mask,
+        apple_blocked_positions(&snake_body_query, &power_up_query),
+        &snake_head_query,
+    ) else {
+        return;
+    };
+    let size = definition.size_on(&playfield_mask);
+    recent_spawns.record(footprint[0]);
+    // the sprite is centered on the footprint, which starts at its bottom-left cell
+    let center_offset = (size - 1) as f32 / 2.0;
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
-                color: Color::RED.into(),
-                custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                color: definition.color.unwrap_or(theme.apple),
+                custom_size: Some(Vec2::new(
+                    size as f32 * PIXEL_UNIT_SIZE,
+                    size as f32 * PIXEL_UNIT_SIZE,
+                )),
                 ..default()
             },
             transform: Transform::from_translation(Vec3::new(
-                valid_spawn.0 as f32 * PIXEL_UNIT_SIZE,
-                valid_spawn.1 as f32 * PIXEL_UNIT_SIZE,
+                (footprint[0].0 as f32 + center_offset) * PIXEL_UNIT_SIZE,
+                (footprint[0].1 as f32 + center_offset) * PIXEL_UNIT_SIZE,
                 0.0,
             )),
             ..default()
         },
+        Name::new(definition.name.clone()),
         Apple {
-            position: valid_spawn,
+            footprint,
+            color: definition.color,
+            growth: definition.growth,
+            points: definition.points,
+            effects: definition.effects.clone(),
         },
+        tween::Tween::pop_in(),
     ));
 }
 
-fn move_snake_head(
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn move_snake(
     mut commands: Commands,
-    mut snake_head_query: Query<(&mut SnakeHead, &mut Transform)>,
-    apple_query: Query<(Entity, &mut Apple)>,
+    movement_settings: Res<MovementSettings>,
+    playfield_mask: Res<playfield::PlayfieldMask>,
+    mut snake_head_query: Query<(
+        &Player,
+        &mut SnakeHead,
+        &mut Transform,
+        Has<lives::Invulnerable>,
+        Has<shield::Shield>,
+        Has<cheats::Noclip>,
+    )>,
+    mut snake_body_query: Query<(Entity, &mut SnakeBody, &mut Transform), Without<SnakeHead>>,
+    apple_query: Query<(Entity, &Apple)>,
     mut apple_eaten_event: EventWriter<AppleEaten>,
+    mut shield_broken_event: EventWriter<shield::ShieldBroken>,
+    mut error_events: EventWriter<errors::ErrorReported>,
+    teams: Option<Res<battle::Teams>>,
+    gravity: Option<Res<gravity::Gravity>>,
 ) {
-    let (mut snake_head, mut transform) = snake_head_query.single_mut();
-    match snake_head.potential_direction {
-        Direction::Up => {
-            if snake_head.direction != Direction::Down {
-                snake_head.direction = Direction::Up;
+    let _span = debug_span!("move_snake").entered();
+    // snakes keep moving while no apple is on the board
+    let mut apple = errors::single_or_report(
+        apple_query.get_single(),
+        "move_snake",
+        "apple",
+        &mut error_events,
+    );
+
+    // where every snake was at the start of the tick, teammates bump into each other instead
+    let team_cells: Vec<(Player, (i32, i32))> = match &teams {
+        Some(_) => snake_head_query
+            .iter()
+            .flat_map(|(player, snake_head, ..)| {
+                std::iter::once(snake_head.position)
+                    .chain(snake_head.segments.iter().filter_map(|segment| {
+                        snake_body_query
+                            .get(*segment)
+                            .ok()
+                            .map(|(_, snake_body, _)| snake_body.position)
+                    }))
+                    .map(|position| (*player, position))
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    for (player, mut snake_head, mut transform, invulnerable, shielded, noclip) in
+        &mut snake_head_query
+    {
+        // noclip goes through bodies as invulnerability does, and through walls up to the edge
+        let invulnerable = invulnerable || noclip;
+        let is_wall = |position| {
+            if noclip {
+                playfield_mask.is_off_board(position)
+            } else {
+                playfield_mask.is_wall(position)
             }
-        }
-        Direction::Down => {
-            if snake_head.direction != Direction::Up {
-                snake_head.direction = Direction::Down;
+        };
+        let potential_direction = snake_head.potential_direction;
+        snake_head.delayed_directions.push_back(potential_direction);
+        while snake_head.delayed_directions.len() > movement_settings.input_latency {
+            let Some(next_direction) = snake_head.delayed_directions.pop_front() else {
+                break;
+            };
+            // no reversing into the neck
+            if next_direction != snake_head.direction.opposite()
+                && next_direction != snake_head.direction
+            {
+                debug!(player = player.0, direction = %next_direction, "Snake turned");
+                snake_head.direction = next_direction;
             }
         }
-        Direction::Left => {
-            if snake_head.direction != Direction::Right {
-                snake_head.direction = Direction::Left;
+
+        snake_head.path.clear();
+        let dash_cells = std::mem::take(&mut snake_head.pending_dash);
+        let moving_cells = dash_cells + movement_settings.step_size;
+        // gravity adds a cell down after the snake's own, unless it is climbing
+        let pulled = gravity.as_ref().is_some_and(|gravity| gravity.pulling)
+            && snake_head.direction != Direction::Up;
+        for step in 0..moving_cells + pulled as u32 {
+            // dashing passes over bodies but is cut short by walls
+            let dashing = step < dash_cells;
+            let step_direction = if step < moving_cells {
+                snake_head.direction
+            } else {
+                Direction::Down
+            };
+            let next_position = playfield_mask.wrapped(step_direction.step(snake_head.position));
+            // a dash ends at a wall and drops its cells left, the tick's own step can still hit it
+            if dashing && is_wall(next_position) {
+                continue;
+            }
+            // an invulnerable snake waits at the wall instead of dying
+            if invulnerable && is_wall(next_position) {
+                break;
+            }
+            // a shield absorbs the hit and the snake stops for this tick
+            if shielded && !invulnerable && !dashing {
+                // the own tail moves out of the way, so only the rest of the bodies block
+                let tail = snake_head.segments.last().copied();
+                let blocked = snake_body_query.iter().any(|(entity, snake_body, _)| {
+                    Some(entity) != tail && snake_body.position == next_position
+                });
+                if is_wall(next_position) || blocked {
+                    shield_broken_event.send(shield::ShieldBroken {
+                        player: *player,
+                        position: snake_head.position,
+                    });
+                    break;
+                }
+            }
+            // and a teammate in the way halts the snake for this tick
+            if !dashing
+                && teams.as_ref().is_some_and(|teams| {
+                    team_cells.iter().any(|(owner, position)| {
+                        *position == next_position && teams.allies(*owner, *player)
+                    })
+                })
+            {
+                break;
+            }
+            let mut prev_position = std::mem::replace(&mut snake_head.position, next_position);
+            snake_head.path.push(next_position);
+            for segment in &snake_head.segments {
+                if let Ok((_, mut snake_body, _)) = snake_body_query.get_mut(*segment) {
+                    std::mem::swap(&mut snake_body.position, &mut prev_position);
+                }
+            }
+            snake_head.tail_position = prev_position;
+
+            if let Some((apple_entity, eaten_apple)) =
+                apple.filter(|(_, apple)| apple.footprint.contains(&snake_head.position))
+            {
+                commands.entity(apple_entity).despawn();
+                apple_eaten_event.send(AppleEaten {
+                    player: *player,
+                    growth: eaten_apple.growth,
+                    points: eaten_apple.points,
+                    effects: eaten_apple.effects.clone(),
+                    position: snake_head.position,
+                });
+                apple = None;
+            }
+
+            // stop sliding on a fatal cell so the collision systems catch it
+            if !invulnerable
+                && !dashing
+                && (is_wall(snake_head.position)
+                    || snake_body_query
+                        .iter()
+                        .any(|(_, snake_body, _)| snake_body.position == snake_head.position))
+            {
+                break;
             }
         }
-        Direction::Right => {
-            if snake_head.direction != Direction::Left {
-                snake_head.direction = Direction::Right;
+
+        transform.translation.x = snake_head.position.0 as f32 * PIXEL_UNIT_SIZE;
+        transform.translation.y = snake_head.position.1 as f32 * PIXEL_UNIT_SIZE;
+        for segment in &snake_head.segments {
+            if let Ok((_, snake_body, mut transform)) = snake_body_query.get_mut(*segment) {
+                transform.translation = Vec3::new(
+                    snake_body.position.0 as f32 * PIXEL_UNIT_SIZE,
+                    snake_body.position.1 as f32 * PIXEL_UNIT_SIZE,
+                    0.0,
+                );
             }
         }
     }
+}
 
-    match snake_head.direction {
-        Direction::Up => transform.translation.y += PIXEL_UNIT_SIZE,
-        Direction::Down => transform.translation.y -= PIXEL_UNIT_SIZE,
-        Direction::Left => transform.translation.x -= PIXEL_UNIT_SIZE,
-        Direction::Right => transform.translation.x += PIXEL_UNIT_SIZE,
+fn grow_snake_body(
+    mut commands: Commands,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut pending_growth: ResMut<PendingGrowth>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+    theme: Res<theme::Theme>,
+) {
+    let _span = debug_span!("grow_snake_body").entered();
+    for event in apple_eaten_event.read() {
+        *pending_growth.0.entry(event.player).or_default() += event.growth;
     }
-    snake_head.position = (
-        (transform.translation.x / PIXEL_UNIT_SIZE) as i32,
-        (transform.translation.y / PIXEL_UNIT_SIZE) as i32,
-    );
-
-    let (apple_entity, apple) = apple_query.single();
-    if snake_head.position == apple.position {
-        commands.entity(apple_entity).despawn();
-        apple_eaten_event.send(AppleEaten);
+    for (player, mut snake_head) in &mut snake_head_query {
+        let Some(pending) = pending_growth.0.get_mut(player) else {
+            continue;
+        };
+        if *pending == 0 {
+            continue;
+        }
+        *pending -= 1;
+        let position = snake_head.tail_position;
+        debug!(player = player.0, remaining = *pending, "Growing snake");
+        let segment = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: theme.snake_body,
+                        custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        position.0 as f32 * PIXEL_UNIT_SIZE,
+                        position.1 as f32 * PIXEL_UNIT_SIZE,
+                        0.0,
+                    )),
+                    ..default()
+                },
+                SnakeBody { position },
+                *player,
+            ))
+            .id();
+        snake_head.segments.push(segment);
     }
 }
 
-fn move_snake_body(
-    mut snake_head_query: Query<&mut SnakeHead>,
-    mut snake_body_query: Query<(&mut SnakeBody, &mut Transform)>,
-    mut last_position: ResMut<LastPosition>,
+// the longest snake sets the pace for everyone, boosting speeds it up
+fn apply_speed_curve(
+    speed_curve: Res<SpeedCurve>,
+    stamina: Res<ability::Stamina>,
+    speed_surge: Option<Res<director::SpeedSurge>>,
+    cheats: Option<Res<cheats::Cheats>>,
+    tick_pipeline: Res<tick_pipeline::TickPipeline>,
+    snake_head_query: Query<&SnakeHead>,
+    mut fixed_time: ResMut<Time<Fixed>>,
 ) {
-    let snake_head = snake_head_query.single_mut();
-    let mut prev_position = snake_head.position;
-    for (mut snake_body, mut transform) in &mut snake_body_query {
-        let temp = snake_body.position;
-        snake_body.position = prev_position;
-        prev_position = temp;
-        transform.translation = Vec3::new(
-            snake_body.position.0 as f32 * PIXEL_UNIT_SIZE,
-            snake_body.position.1 as f32 * PIXEL_UNIT_SIZE,
-            0.0,
-        );
+    let body_length = snake_head_query
+        .iter()
+        .map(|snake_head| snake_head.segments.len())
+        .max()
+        .unwrap_or(0);
+    let mut tickrate = tick_pipeline
+        .tickrate()
+        .unwrap_or_else(|| speed_curve.tickrate(body_length));
+    if stamina.boosting {
+        tickrate *= ability::BOOST_TICKRATE_FACTOR;
+    }
+    if let Some(speed_surge) = speed_surge {
+        tickrate *= speed_surge.0;
+    }
+    if let Some(cheats) = cheats {
+        tickrate /= cheats.speed;
+    }
+    if (fixed_time.timestep().as_secs_f64() - tickrate).abs() > f64::EPSILON {
+        fixed_time.set_timestep_seconds(tickrate);
     }
-    last_position.value = prev_position;
 }
 
-fn grow_snake_body(
-    mut commands: Commands,
-    mut apple_eaten_event: EventReader<AppleEaten>,
-    last_position: Res<LastPosition>,
+// removes the tail segment, returning false if there was none left to remove
+fn shrink_snake(
+    commands: &mut Commands,
+    snake_head: &mut SnakeHead,
+    snake_body_query: &Query<&SnakeBody>,
+) -> bool {
+    let Some(tail_entity) = snake_head.segments.pop() else {
+        return false;
+    };
+    if let Ok(tail) = snake_body_query.get(tail_entity) {
+        snake_head.tail_position = tail.position;
+    }
+    commands.entity(tail_entity).despawn();
+    true
+}
+
+// heads the collisions can kill, invulnerable and noclip ones pass through
+type Vulnerable = (Without<lives::Invulnerable>, Without<cheats::Noclip>);
+
+fn border_collision(
+    playfield_mask: Res<playfield::PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead), Vulnerable>,
+    mut snake_died_event: EventWriter<SnakeDied>,
 ) {
-    if apple_eaten_event.is_empty() {
-        return;
+    for (player, snake_head) in &snake_head_query {
+        if playfield_mask.is_wall(snake_head.position) {
+            snake_died_event.send(SnakeDied {
+                player: *player,
+                cause: DeathCause::Wall,
+            });
+        }
     }
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::WHITE.into(),
-                custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
-                ..default()
-            },
-            transform: Transform::from_translation(Vec3::new(
-                last_position.value.0 as f32 * PIXEL_UNIT_SIZE,
-                last_position.value.1 as f32 * PIXEL_UNIT_SIZE,
-                0.0,
-            )),
-            ..default()
-        },
-        SnakeBody {
-            position: last_position.value,
-        },
-    ));
-    apple_eaten_event.clear();
 }
 
-fn border_collision(mut snake_head_query: Query<&mut SnakeHead>) {
-    let snake_head = snake_head_query.single_mut();
-    if snake_head.position.0.abs() > PLAYFIELD.0 / 2
-        || snake_head.position.1.abs() > PLAYFIELD.1 / 2
-    {
-        println!("Game Over!");
-        std::process::exit(0);
+fn snake_body_collision(
+    snake_head_query: Query<(Entity, &Player, &SnakeHead), Vulnerable>,
+    all_snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+    snake_body_query: Query<(&Player, &SnakeBody)>,
+    mut snake_died_event: EventWriter<SnakeDied>,
+    teams: Option<Res<battle::Teams>>,
+) {
+    let allies = |a: Player, b: Player| teams.as_ref().is_some_and(|teams| teams.allies(a, b));
+    for (entity, player, snake_head) in &snake_head_query {
+        // teammates only ever bump
+        let hit = snake_body_query.iter().find(|(owner, snake_body)| {
+            snake_head.position == snake_body.position && !allies(**owner, *player)
+        });
+        // running head first into another snake counts as hitting it
+        let head_on = all_snake_head_query
+            .iter()
+            .any(|(other, other_player, other_head)| {
+                other != entity
+                    && other_head.position == snake_head.position
+                    && !allies(*other_player, *player)
+            });
+        let cause = match hit {
+            Some((owner, _)) if owner == player => DeathCause::SelfCollision,
+            Some(_) => DeathCause::OtherSnake,
+            None if head_on => DeathCause::OtherSnake,
+            None => continue,
+        };
+        snake_died_event.send(SnakeDied {
+            player: *player,
+            cause,
+        });
     }
 }
 
-fn snake_body_collision(snake_head_query: Query<&SnakeHead>, snake_body_query: Query<&SnakeBody>) {
-    let snake_head = snake_head_query.single();
-    for snake_body in &snake_body_query {
-        if snake_head.position == snake_body.position {
-            println!("Game Over!");
-            std::process::exit(0);
-        }
+fn game_over(
+    mut snake_died_event: EventReader<SnakeDied>,
+    lives: Option<Res<lives::Lives>>,
+    score: Res<score::Score>,
+    mut game_over_event: EventWriter<GameOver>,
+) {
+    let mut died = snake_died_event.read().map(|event| event.player);
+    // with lives enabled, deaths are handled by respawning until none are left
+    match lives {
+        Some(lives) if lives.0 > 0 || died.next().is_none() => return,
+        // without them a rival dying only leaves its bones behind
+        None if !died.any(|player| player == Player::ONE) => return,
+        _ => {}
     }
+    info!(score = score.0, "Game over");
+    // the game over screen exits once the player is done with it
+    game_over_event.send(GameOver);
 }
 
-fn player_input(keyboard_input: Res<Input<KeyCode>>, mut snake_head_query: Query<&mut SnakeHead>) {
-    if let Ok(mut snake_head) = snake_head_query.get_single_mut() {
-        if keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::W, KeyCode::I]) {
-            snake_head.potential_direction = Direction::Up;
-        }
-        if keyboard_input.any_just_pressed([KeyCode::Down, KeyCode::S, KeyCode::K]) {
-            snake_head.potential_direction = Direction::Down;
-        }
-        if keyboard_input.any_just_pressed([KeyCode::Left, KeyCode::A, KeyCode::J]) {
-            snake_head.potential_direction = Direction::Left;
-        }
-        if keyboard_input.any_just_pressed([KeyCode::Right, KeyCode::D, KeyCode::L]) {
-            snake_head.potential_direction = Direction::Right;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embeds_from_the_builder() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(
+            SnakeGamePlugin::new()
+                .board(15, 11)
+                .playfield(playfield::PlayfieldShape::Cross)
+                .tickrate(0.1)
+                .wrap(true)
+                .players(2),
+        );
+        let playfield_mask = app.world.resource::<playfield::PlayfieldMask>();
+        assert_eq!(playfield_mask.half_extents(), (7, 5));
+        assert_eq!(playfield_mask.shape, playfield::PlayfieldShape::Cross);
+        assert!(playfield_mask.wrap);
+        assert_eq!(app.world.resource::<StartingPlayers>().0, 2);
+        assert_eq!(app.world.resource::<SpeedCurve>().base_tickrate, 0.1);
+        assert_eq!(
+            app.world.resource::<Time<Fixed>>().timestep().as_secs_f64(),
+            0.1
+        );
     }
 }
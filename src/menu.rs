@@ -0,0 +1,143 @@
+// Menu
+// Focusable buttons shared by every screen: arrow keys, the d-pad or hovering move the focus,
+// Enter, A or a click activates
+use bevy::prelude::*;
+
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const FOCUS_COLOR: Color = Color::GOLD;
+
+#[derive(Component)]
+pub struct MenuButton;
+
+// the focused button among the visible ones, there is only ever one screen open at a time
+#[derive(Resource, Default)]
+pub struct MenuFocus(pub Option<Entity>);
+
+#[derive(Event)]
+pub struct MenuActivated(pub Entity);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .add_event::<MenuActivated>()
+            .add_systems(Update, (navigate_menu, show_focus).chain());
+    }
+}
+
+// for gameplay input that shouldn't fire while a screen is open
+pub fn menu_closed(focus: Res<MenuFocus>) -> bool {
+    focus.0.is_none()
+}
+
+pub fn spawn_button(parent: &mut ChildBuilder, label: &str, marker: impl Bundle) -> Entity {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                border_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            MenuButton,
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn navigate_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    button_query: Query<(Entity, &GlobalTransform, &InheritedVisibility), With<MenuButton>>,
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<MenuButton>)>,
+    mut focus: ResMut<MenuFocus>,
+    mut menu_activated_event: EventWriter<MenuActivated>,
+) {
+    // reading order, so rows and columns both work
+    let mut buttons: Vec<(Entity, Vec3)> = button_query
+        .iter()
+        .filter(|(_, _, visibility)| visibility.get())
+        .map(|(entity, transform, _)| (entity, transform.translation()))
+        .collect();
+    buttons.sort_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)));
+    if buttons.is_empty() {
+        focus.0 = None;
+        return;
+    }
+
+    let mut index = focus
+        .0
+        .and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused))
+        .unwrap_or(0);
+    let pressed = |keys: [KeyCode; 2], button_type| {
+        keyboard_input.any_just_pressed(keys)
+            || gamepad_just_pressed(&gamepads, &gamepad_buttons, button_type)
+    };
+    if pressed([KeyCode::Up, KeyCode::Left], GamepadButtonType::DPadUp) {
+        index = index.checked_sub(1).unwrap_or(buttons.len() - 1);
+    }
+    if pressed([KeyCode::Down, KeyCode::Right], GamepadButtonType::DPadDown) {
+        index = (index + 1) % buttons.len();
+    }
+    let mut activated = pressed([KeyCode::Return, KeyCode::Space], GamepadButtonType::South);
+    // the mouse only counts when it moves onto or clicks a button, so it doesn't fight the keys
+    for (entity, interaction) in &interaction_query {
+        let Some(position) = buttons.iter().position(|(button, _)| *button == entity) else {
+            continue;
+        };
+        match interaction {
+            Interaction::Hovered => index = position,
+            Interaction::Pressed => {
+                index = position;
+                activated = true;
+            }
+            Interaction::None => {}
+        }
+    }
+
+    let focused = buttons[index].0;
+    focus.0 = Some(focused);
+    if activated {
+        menu_activated_event.send(MenuActivated(focused));
+    }
+}
+
+fn show_focus(
+    focus: Res<MenuFocus>,
+    mut button_query: Query<(Entity, &mut BorderColor), With<MenuButton>>,
+) {
+    for (entity, mut border_color) in &mut button_query {
+        border_color.0 = if focus.0 == Some(entity) {
+            FOCUS_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+    }
+}
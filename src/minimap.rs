@@ -0,0 +1,103 @@
+// Minimap
+// A cell-per-pixel view of the occupancy grid, shown when the board does not fit in the window
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::board_exceeds_window;
+use crate::occupancy::{update_occupancy, Occupancy, Occupant};
+use crate::PLAYFIELD;
+
+const MINIMAP_SCALE: f32 = 4.0; // screen pixels per cell
+const EMPTY_COLOR: [u8; 4] = [0, 0, 0, 160];
+const SNAKE_HEAD_COLOR: [u8; 4] = [0, 255, 0, 255];
+const SNAKE_BODY_COLOR: [u8; 4] = [255, 255, 255, 255];
+const APPLE_COLOR: [u8; 4] = [255, 0, 0, 255];
+const POWER_UP_COLOR: [u8; 4] = [0, 255, 255, 255];
+const WALL_COLOR: [u8; 4] = [80, 80, 80, 255];
+
+#[derive(Component)]
+struct Minimap(Handle<Image>);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_minimap)
+            .add_systems(PostUpdate, update_minimap.after(update_occupancy));
+    }
+}
+
+fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: PLAYFIELD.0 as u32,
+            height: PLAYFIELD.1 as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &EMPTY_COLOR,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+    commands.spawn((
+        ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(44.0),
+                right: Val::Px(12.0),
+                width: Val::Px(PLAYFIELD.0 as f32 * MINIMAP_SCALE),
+                height: Val::Px(PLAYFIELD.1 as f32 * MINIMAP_SCALE),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            image: handle.clone().into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Minimap(handle),
+    ));
+}
+
+fn update_minimap(
+    occupancy: Res<Occupancy>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut minimap_query: Query<(&Minimap, &mut Visibility)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (Ok(window), Ok((minimap, mut visibility))) =
+        (window_query.get_single(), minimap_query.get_single_mut())
+    else {
+        return;
+    };
+    if !board_exceeds_window(window) {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let Some(image) = images.get_mut(&minimap.0) else {
+        return;
+    };
+    for row in 0..PLAYFIELD.1 {
+        for column in 0..PLAYFIELD.0 {
+            // image rows run top to bottom, board rows bottom to top
+            let position = (
+                column - PLAYFIELD.0 / 2 + occupancy.scroll(),
+                PLAYFIELD.1 / 2 - row,
+            );
+            let color = match occupancy.get(position) {
+                None => EMPTY_COLOR,
+                Some(Occupant::SnakeHead) => SNAKE_HEAD_COLOR,
+                Some(Occupant::SnakeBody) => SNAKE_BODY_COLOR,
+                Some(Occupant::Apple) => APPLE_COLOR,
+                Some(Occupant::PowerUp) => POWER_UP_COLOR,
+                Some(Occupant::Wall) => WALL_COLOR,
+            };
+            let index = ((row * PLAYFIELD.0 + column) * 4) as usize;
+            image.data[index..index + 4].copy_from_slice(&color);
+        }
+    }
+}
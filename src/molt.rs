@@ -0,0 +1,105 @@
+// Molting
+// Optional length cap (--molt [length], 24 by default): a snake reaching it sheds the back half of
+// its body, which stays behind as husk tiles that block like walls for a while and then crumble
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::event_log::EventLogged;
+use crate::playfield::PlayfieldMask;
+use crate::{shrink_snake, Player, SnakeBody, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const DEFAULT_MOLT_LENGTH: usize = 24; // head included
+const MIN_MOLT_LENGTH: usize = 4;
+const HUSK_TICKS: u32 = 20;
+const HUSK_COLOR: Color = Color::rgb(0.55, 0.45, 0.3);
+
+#[derive(Resource)]
+struct MoltLength(usize);
+
+#[derive(Component)]
+struct Husk {
+    position: (i32, i32),
+    ticks_left: u32,
+}
+
+pub struct MoltPlugin;
+
+impl Plugin for MoltPlugin {
+    fn build(&self, app: &mut App) {
+        let length = Args::of(app)
+            .value("--molt")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MOLT_LENGTH)
+            .max(MIN_MOLT_LENGTH);
+        app.insert_resource(MoltLength(length)).add_systems(
+            FixedUpdate,
+            (decay_husks, molt).chain().in_set(SnakeSystemSet::Spawning),
+        );
+    }
+}
+
+fn molt(
+    mut commands: Commands,
+    molt_length: Res<MoltLength>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut snake_head_query: Query<(&Player, &mut SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    for (player, mut snake_head) in &mut snake_head_query {
+        let length = snake_head.segments.len() + 1;
+        if length < molt_length.0 {
+            continue;
+        }
+        info!(player = player.0, length, "Snake molted");
+        event_logged_event.send(EventLogged(format!("Molted at length {length}")));
+        for _ in 0..length / 2 {
+            let Some(position) = snake_head
+                .segments
+                .last()
+                .and_then(|segment| snake_body_query.get(*segment).ok())
+                .map(|snake_body| snake_body.position)
+            else {
+                break;
+            };
+            shrink_snake(&mut commands, &mut snake_head, &snake_body_query);
+            playfield_mask.add_wall(position);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: HUSK_COLOR,
+                        custom_size: Some(Vec2::splat(PIXEL_UNIT_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        position.0 as f32 * PIXEL_UNIT_SIZE,
+                        position.1 as f32 * PIXEL_UNIT_SIZE,
+                        -0.04, // over the wall tile drawn for it
+                    )),
+                    ..default()
+                },
+                Husk {
+                    position,
+                    ticks_left: HUSK_TICKS,
+                },
+            ));
+        }
+    }
+}
+
+// husks fade as they crumble
+fn decay_husks(
+    mut commands: Commands,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut husk_query: Query<(Entity, &mut Husk, &mut Sprite)>,
+) {
+    for (entity, mut husk, mut sprite) in &mut husk_query {
+        husk.ticks_left -= 1;
+        if husk.ticks_left == 0 {
+            playfield_mask.remove_wall(husk.position);
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite.color = HUSK_COLOR.with_a(husk.ticks_left as f32 / HUSK_TICKS as f32);
+    }
+}
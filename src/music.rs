@@ -0,0 +1,269 @@
+// Music
+// Procedurally synthesized soundtrack whose stems fade in as the snake grows,
+// playing faster and higher as it speeds up
+use bevy::audio::{AddAudioSource, Source, Volume};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::Duration;
+use serde::Deserialize;
+
+use crate::seasonal::SeasonalEvent;
+use crate::{speed_factor, MovementSettings, SnakeBody, START_LENGTH};
+
+const SAMPLE_RATE: u32 = 44_100;
+const STEP_SECONDS: f32 = 0.125;
+const FADE_PER_SECOND: f32 = 0.2;
+const LENGTH_FOR_FULL_INTENSITY: f32 = 30.0;
+const TEMPO_PER_SPEED: f32 = 0.15; // playback speed gained per unit of speed factor
+const MAX_TEMPO: f32 = 1.5;
+
+const E2: f32 = 82.41;
+const F2: f32 = 87.31;
+const G2: f32 = 98.0;
+const A2: f32 = 110.0;
+const C3: f32 = 130.81;
+const F4: f32 = 349.23;
+const G4: f32 = 392.0;
+const A4: f32 = 440.0;
+const B4: f32 = 493.88;
+const C5: f32 = 523.25;
+const D5: f32 = 587.33;
+const DS5: f32 = 622.25;
+const E5: f32 = 659.25;
+const F5: f32 = 698.46;
+const G5: f32 = 783.99;
+const A5: f32 = 880.0;
+const HIT: f32 = 1.0; // any non-zero pitch triggers a noise hit
+
+#[derive(Clone, Copy)]
+enum Waveform {
+    Triangle,
+    Square,
+    Noise,
+}
+
+// which set of stems plays, seasonal events pick their own
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum Soundtrack {
+    #[default]
+    Default,
+    Spooky,
+    Festive,
+}
+
+// a looping pattern of notes, one per step, 0.0 being a rest
+#[derive(Asset, TypePath)]
+struct Stem {
+    waveform: Waveform,
+    notes: Vec<f32>,
+    decay: f32, // how fast each note fades
+    gain: f32,
+}
+
+struct StemDecoder {
+    waveform: Waveform,
+    notes: Vec<f32>,
+    decay: f32,
+    gain: f32,
+    samples_per_step: u64,
+    sample: u64,
+    phase: f32,
+    noise_state: u32,
+}
+
+impl Iterator for StemDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let step = (self.sample / self.samples_per_step) as usize % self.notes.len();
+        let seconds_into_step = (self.sample % self.samples_per_step) as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+
+        let frequency = self.notes[step];
+        if frequency == 0.0 {
+            return Some(0.0);
+        }
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32) % 1.0;
+        let wave = match self.waveform {
+            Waveform::Triangle => 4.0 * (self.phase - 0.5).abs() - 1.0,
+            Waveform::Square => {
+                if self.phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Noise => {
+                // xorshift
+                self.noise_state ^= self.noise_state << 13;
+                self.noise_state ^= self.noise_state >> 17;
+                self.noise_state ^= self.noise_state << 5;
+                self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+            }
+        };
+        Some(wave * (-seconds_into_step * self.decay).exp() * self.gain)
+    }
+}
+
+impl Source for StemDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for Stem {
+    type Decoder = StemDecoder;
+
+    type DecoderItem = <StemDecoder as Iterator>::Item;
+
+    fn decoder(&self) -> Self::Decoder {
+        StemDecoder {
+            waveform: self.waveform,
+            notes: self.notes.clone(),
+            decay: self.decay,
+            gain: self.gain,
+            samples_per_step: (STEP_SECONDS * SAMPLE_RATE as f32) as u64,
+            sample: 0,
+            phase: 0.0,
+            noise_state: 0x9e37_79b9,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MusicVolume(pub f32);
+
+impl Default for MusicVolume {
+    fn default() -> Self {
+        MusicVolume(0.25)
+    }
+}
+
+// a stem that plays once the music intensity reaches its threshold
+#[derive(Component)]
+struct MusicLayer {
+    threshold: f32,
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Stem>()
+            .init_resource::<MusicVolume>()
+            .add_systems(Startup, start_music)
+            .add_systems(Update, update_music_intensity);
+    }
+}
+
+fn start_music(
+    mut commands: Commands,
+    mut stems: ResMut<Assets<Stem>>,
+    event: Option<Res<SeasonalEvent>>,
+) {
+    let soundtrack = event.map_or(Soundtrack::Default, |event| event.0.soundtrack);
+    let (bass, lead) = match soundtrack {
+        Soundtrack::Default => (
+            [
+                A2, 0.0, 0.0, 0.0, A2, 0.0, 0.0, 0.0, F2, 0.0, 0.0, 0.0, G2, 0.0, G2, 0.0,
+            ],
+            [
+                A4, C5, E5, A5, A4, C5, E5, A5, F4, A4, C5, F5, G4, B4, D5, G5,
+            ],
+        ),
+        Soundtrack::Spooky => (
+            [
+                A2, 0.0, 0.0, A2, 0.0, 0.0, A2, 0.0, F2, 0.0, 0.0, F2, E2, 0.0, E2, 0.0,
+            ],
+            [
+                A4, 0.0, E5, 0.0, F5, E5, 0.0, 0.0, A4, 0.0, E5, 0.0, DS5, D5, 0.0, 0.0,
+            ],
+        ),
+        Soundtrack::Festive => (
+            [
+                C3, 0.0, G2, 0.0, C3, 0.0, G2, 0.0, F2, 0.0, C3, 0.0, G2, 0.0, G2, 0.0,
+            ],
+            [
+                E5, E5, E5, 0.0, E5, E5, E5, 0.0, E5, G5, C5, D5, E5, 0.0, 0.0, 0.0,
+            ],
+        ),
+    };
+    let layers = [
+        (
+            0.0,
+            Stem {
+                waveform: Waveform::Triangle,
+                notes: bass.to_vec(),
+                decay: 3.0,
+                gain: 0.6,
+            },
+        ),
+        (
+            0.3,
+            Stem {
+                waveform: Waveform::Noise,
+                notes: vec![
+                    HIT, 0.0, HIT, 0.0, HIT, 0.0, HIT, HIT, HIT, 0.0, HIT, 0.0, HIT, 0.0, HIT, HIT,
+                ],
+                decay: 30.0,
+                gain: 0.25,
+            },
+        ),
+        (
+            0.7,
+            Stem {
+                waveform: Waveform::Square,
+                notes: lead.to_vec(),
+                decay: 8.0,
+                gain: 0.15,
+            },
+        ),
+    ];
+    for (threshold, stem) in layers {
+        commands.spawn((
+            AudioSourceBundle {
+                source: stems.add(stem),
+                settings: PlaybackSettings::ONCE.with_volume(Volume::new_relative(0.0)),
+            },
+            MusicLayer { threshold },
+        ));
+    }
+}
+
+fn update_music_intensity(
+    time: Res<Time>,
+    movement_settings: Res<MovementSettings>,
+    fixed_time: Res<Time<Fixed>>,
+    music_volume: Res<MusicVolume>,
+    snake_body_query: Query<(), With<SnakeBody>>,
+    layer_query: Query<(&MusicLayer, &AudioSink)>,
+) {
+    let speed_factor = speed_factor(&movement_settings, &fixed_time);
+    let length = snake_body_query.iter().count() as f32 + 1.0;
+    let intensity = (length - START_LENGTH as f32) / LENGTH_FOR_FULL_INTENSITY + speed_factor - 1.0;
+    let tempo = (1.0 + (speed_factor - 1.0) * TEMPO_PER_SPEED).clamp(1.0, MAX_TEMPO);
+
+    let max_fade = FADE_PER_SECOND * time.delta_seconds();
+    for (layer, sink) in &layer_query {
+        let target = if intensity >= layer.threshold {
+            music_volume.0
+        } else {
+            0.0
+        };
+        let volume = sink.volume();
+        sink.set_volume(volume + (target - volume).clamp(-max_fade, max_fade));
+        sink.set_speed(tempo);
+    }
+}
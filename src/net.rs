@@ -0,0 +1,160 @@
+// Network transport
+// Messages between game instances as JSON lines over TCP, read and written without blocking so a
+// frame never waits on the network. What the socket doesn't take at once is queued and written on
+// later calls, whole lines only ever reach the other side in order. A peer that lets either side's
+// queue grow past its cap is dropped
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub const DEFAULT_PORT: u16 = 7879;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const ROOM_CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ"; // no I, L, O or U to misread
+const MAX_QUEUED_BYTES: usize = 4 * 1024 * 1024; // a few seconds of snapshots either way
+
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>, // a partial line left over from the last read
+    outgoing: Vec<u8>, // lines the socket hasn't taken yet
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+        })
+    }
+
+    // off the frame, a host that doesn't answer would hold it for the whole timeout
+    pub fn connect(address: impl ToSocketAddrs + Send + 'static) -> Task<io::Result<Self>> {
+        AsyncComputeTaskPool::get().spawn(async move {
+            let address = address
+                .to_socket_addrs()?
+                .next()
+                .ok_or(ErrorKind::NotFound)?;
+            Connection::new(TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?)
+        })
+    }
+
+    // queued behind anything not written yet, an error once the other side stopped reading
+    pub fn send(&mut self, message: &impl Serialize) -> io::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        self.outgoing.extend_from_slice(&line);
+        if self.outgoing.len() > MAX_QUEUED_BYTES {
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "peer is not keeping up",
+            ));
+        }
+        self.flush()
+    }
+
+    // writes as much of the queue as the socket takes now, the rest waits for the next call
+    pub fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    // every whole message that arrived since the last call, an error once the other side is gone
+    // or sent something that isn't one
+    pub fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Vec<T>> {
+        // called every frame, so the queue drains even when nothing new is sent
+        self.flush()?;
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::ConnectionAborted.into()),
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+            if self.incoming.len() > MAX_QUEUED_BYTES {
+                return Err(io::Error::new(ErrorKind::InvalidData, "peer sent too much"));
+            }
+        }
+        let Some(end) = self.incoming.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let lines: Vec<u8> = self.incoming.drain(..=end).collect();
+        lines
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).map_err(io::Error::from))
+            .collect()
+    }
+}
+
+// what a task came to once it's done, taking it
+pub fn finished<T>(task: &mut Option<Task<T>>) -> Option<T> {
+    if !task.as_ref()?.is_finished() {
+        return None;
+    }
+    task.take().map(block_on)
+}
+
+// the address other machines on the network reach this one at, no packet is actually sent
+pub fn local_address() -> Ipv4Addr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 168, 0, 1), 9))?;
+            socket.local_addr()
+        })
+        .ok()
+        .and_then(|address| match address.ip() {
+            std::net::IpAddr::V4(ip) => Some(ip),
+            std::net::IpAddr::V6(_) => None,
+        })
+        .unwrap_or(Ipv4Addr::LOCALHOST)
+}
+
+// an IPv4 address and port spelled out in ten characters that are easy to read out loud
+pub fn room_code(address: SocketAddrV4) -> String {
+    let mut value = (u64::from(u32::from(*address.ip())) << 16) | u64::from(address.port());
+    let mut code = vec![0; 10];
+    for char in code.iter_mut().rev() {
+        *char = ROOM_CODE_ALPHABET[(value % 32) as usize];
+        value /= 32;
+    }
+    String::from_utf8(code).unwrap_or_default()
+}
+
+// a room code or host:port, a bare host gets the default port
+pub fn parse_address(text: &str) -> Option<SocketAddr> {
+    let text = text.trim();
+    if let Ok(address) = text.parse() {
+        return Some(address);
+    }
+    if let Ok(ip) = text.parse::<Ipv4Addr>() {
+        return Some(SocketAddrV4::new(ip, DEFAULT_PORT).into());
+    }
+    if text.len() != 10 {
+        return None;
+    }
+    let mut value = 0u64;
+    for char in text.to_ascii_uppercase().bytes() {
+        let digit = ROOM_CODE_ALPHABET.iter().position(|c| *c == char)?;
+        value = value * 32 + digit as u64;
+    }
+    let ip = Ipv4Addr::from((value >> 16) as u32);
+    Some(SocketAddrV4::new(ip, (value & 0xffff) as u16).into())
+}
@@ -0,0 +1,209 @@
+// Objectives
+// With --objectives a mini-challenge is shown in the HUD, one at a time in rotation: each has a
+// time limit and a reward, bonus points or a shield. Failing one just moves on to the next
+use bevy::prelude::*;
+
+use crate::direction::Direction;
+use crate::event_log::EventLogged;
+use crate::score::Score;
+use crate::shield::Shield;
+use crate::{AppleEaten, Player, SnakeHead, SnakeSystemSet};
+
+const OBJECTIVES: [Objective; 4] = [
+    Objective {
+        goal: Goal::ApplesWithoutLeftTurn(3),
+        seconds: 45.0,
+        reward: Reward::Points(10),
+    },
+    Objective {
+        goal: Goal::ReachLength(30),
+        seconds: 60.0,
+        reward: Reward::Shield,
+    },
+    Objective {
+        goal: Goal::Apples(5),
+        seconds: 30.0,
+        reward: Reward::Points(15),
+    },
+    Objective {
+        goal: Goal::ApplesWithoutLeftTurn(5),
+        seconds: 60.0,
+        reward: Reward::Shield,
+    },
+];
+
+#[derive(Clone, Copy)]
+enum Goal {
+    ApplesWithoutLeftTurn(u32), // a left turn starts the count over
+    ReachLength(usize),         // head included
+    Apples(u32),
+}
+
+#[derive(Clone, Copy)]
+enum Reward {
+    Points(u32),
+    Shield,
+}
+
+struct Objective {
+    goal: Goal,
+    seconds: f32,
+    reward: Reward,
+}
+
+impl Objective {
+    fn describe(&self) -> String {
+        let goal = match self.goal {
+            Goal::ApplesWithoutLeftTurn(apples) => {
+                format!("Eat {apples} apples without turning left")
+            }
+            Goal::ReachLength(length) => format!("Reach length {length}"),
+            Goal::Apples(apples) => format!("Eat {apples} apples"),
+        };
+        let reward = match self.reward {
+            Reward::Points(points) => format!("+{points}"),
+            Reward::Shield => "shield".to_string(),
+        };
+        format!("{goal} ({reward})")
+    }
+}
+
+// player one's progress on the current objective
+#[derive(Resource, Default)]
+struct Objectives {
+    index: usize,
+    progress: u32,
+    elapsed: f32,
+    heading: Option<Direction>,
+}
+
+impl Objectives {
+    fn current(&self) -> &'static Objective {
+        &OBJECTIVES[self.index]
+    }
+
+    fn advance(&mut self) {
+        self.index = (self.index + 1) % OBJECTIVES.len();
+        self.progress = 0;
+        self.elapsed = 0.0;
+    }
+}
+
+#[derive(Component)]
+struct ObjectiveText;
+
+pub struct ObjectivesPlugin;
+
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Objectives>()
+            .add_systems(Startup, setup_objective_text)
+            .add_systems(
+                FixedUpdate,
+                track_objective.in_set(SnakeSystemSet::Collision),
+            )
+            .add_systems(Update, update_objective_text);
+    }
+}
+
+fn setup_objective_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        ObjectiveText,
+    ));
+}
+
+fn track_objective(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut objectives: ResMut<Objectives>,
+    mut score: ResMut<Score>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+    mut event_logged_event: EventWriter<EventLogged>,
+) {
+    let Some((head, _, snake_head)) = snake_head_query
+        .iter()
+        .find(|(_, player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let apples = apple_eaten_event
+        .read()
+        .filter(|event| event.player == Player::ONE)
+        .count() as u32;
+    let turned_left = objectives
+        .heading
+        .is_some_and(|heading| heading.left() == snake_head.direction);
+    objectives.heading = Some(snake_head.direction);
+    objectives.elapsed += time.delta_seconds();
+
+    let objective = objectives.current();
+    let done = match objective.goal {
+        Goal::ApplesWithoutLeftTurn(goal) => {
+            if turned_left {
+                objectives.progress = 0;
+            }
+            objectives.progress += apples;
+            objectives.progress >= goal
+        }
+        Goal::ReachLength(goal) => {
+            let length = snake_head.segments.len() + 1;
+            objectives.progress = length as u32;
+            length >= goal
+        }
+        Goal::Apples(goal) => {
+            objectives.progress += apples;
+            objectives.progress >= goal
+        }
+    };
+    if done {
+        match objective.reward {
+            Reward::Points(points) => score.0 += points,
+            Reward::Shield => {
+                commands.entity(head).insert(Shield);
+            }
+        }
+        info!(objective = objective.describe(), "Objective complete");
+        event_logged_event.send(EventLogged(format!(
+            "Objective complete: {}",
+            objective.describe()
+        )));
+        objectives.advance();
+    } else if objectives.elapsed >= objective.seconds {
+        event_logged_event.send(EventLogged("Objective missed".to_string()));
+        objectives.advance();
+    }
+}
+
+fn update_objective_text(
+    objectives: Res<Objectives>,
+    mut text_query: Query<&mut Text, With<ObjectiveText>>,
+) {
+    let objective = objectives.current();
+    let goal = match objective.goal {
+        Goal::ApplesWithoutLeftTurn(apples) | Goal::Apples(apples) => apples,
+        Goal::ReachLength(length) => length as u32,
+    };
+    let seconds_left = (objective.seconds - objectives.elapsed).max(0.0).ceil();
+    for mut text in &mut text_query {
+        text.sections[0].value = format!(
+            "{}  {}/{goal}  {seconds_left}s",
+            objective.describe(),
+            objectives.progress
+        );
+    }
+}
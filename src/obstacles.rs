@@ -0,0 +1,195 @@
+// Obstacles
+// Procedural wall layouts (--obstacles <density>): the wall shapes of the item table are scattered
+// from the run seed, and each one is only kept if every free cell can still reach every other. They
+// go up once items.ron has loaded, clear of whatever is on the board by then, and are scattered
+// again whenever it changes
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use rand::Rng;
+
+use crate::args::Args;
+use crate::items::{
+    pick_weighted, reload_item_table, ItemTable, ItemTableHandle, ObstacleDefinition,
+};
+use crate::pathfinding::flood_fill_with;
+use crate::playfield::PlayfieldMask;
+use crate::seed::RunRng;
+use crate::{Apple, Player, SnakeBody, SnakeHead, PLAYFIELD};
+
+const DEFAULT_DENSITY: f32 = 0.1; // share of the open cells turned into walls
+const MAX_DENSITY: f32 = 0.4;
+const ATTEMPTS_PER_CELL: usize = 8;
+const START_CLEARANCE: i32 = 6; // cells kept free on either side of each snake's start
+const STARTING_PLAYERS: u8 = 4;
+
+#[derive(Resource)]
+struct ObstacleDensity(f32);
+
+// the walls placed from the current table, taken down again when it changes
+#[derive(Resource, Default)]
+struct ObstacleWalls(Vec<(i32, i32)>);
+
+pub struct ObstaclesPlugin;
+
+impl Plugin for ObstaclesPlugin {
+    // --obstacles <density>, a share of the board between 0 and 0.4
+    fn build(&self, app: &mut App) {
+        let args = Args::of(app);
+        let density = match args.value("--obstacles") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                args.problem(format!(
+                    "Invalid obstacle density {value:?}, using {DEFAULT_DENSITY}"
+                ));
+                DEFAULT_DENSITY
+            }),
+            None => DEFAULT_DENSITY,
+        };
+        app.insert_resource(ObstacleDensity(density))
+            .init_resource::<ObstacleWalls>()
+            .add_systems(Update, place_obstacles.after(reload_item_table));
+    }
+}
+
+// a ranked run has no items.ron to wait for, nor does a broken one
+#[allow(clippy::too_many_arguments)]
+fn place_obstacles(
+    asset_server: Res<AssetServer>,
+    item_table_handle: Option<Res<ItemTableHandle>>,
+    item_table: Res<ItemTable>,
+    density: Res<ObstacleDensity>,
+    mut obstacle_walls: ResMut<ObstacleWalls>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut run_rng: ResMut<RunRng>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    mut placed: Local<bool>,
+) {
+    let loading = item_table_handle.is_some_and(|handle| handle.is_loading(&asset_server));
+    let reloaded = item_table.is_changed() && !item_table.is_added();
+    if loading || (*placed && !reloaded) {
+        return;
+    }
+    *placed = true;
+    for position in obstacle_walls.0.drain(..) {
+        playfield_mask.remove_wall(position);
+    }
+
+    let column = playfield_mask.start_column();
+    let mut keep_clear: Vec<(i32, i32)> = (1..=STARTING_PLAYERS)
+        .map(|player| Player(player).spawn_row())
+        .flat_map(|row| {
+            (column - START_CLEARANCE..=column + START_CLEARANCE).map(move |x| (x, row))
+        })
+        .collect();
+    keep_clear.extend(
+        snake_head_query
+            .iter()
+            .map(|snake_head| snake_head.position),
+    );
+    keep_clear.extend(
+        snake_body_query
+            .iter()
+            .map(|snake_body| snake_body.position),
+    );
+    for apple in &apple_query {
+        keep_clear.extend_from_slice(&apple.footprint);
+    }
+    let obstacles = generate(
+        &mut run_rng.0,
+        &playfield_mask,
+        &item_table.obstacles,
+        density.0,
+        &keep_clear,
+    );
+    info!(
+        count = obstacles.len(),
+        density = density.0,
+        "Obstacles placed"
+    );
+    for position in &obstacles {
+        playfield_mask.add_wall(*position);
+    }
+    obstacle_walls.0 = obstacles;
+}
+
+fn open_cells(playfield_mask: &PlayfieldMask) -> Vec<(i32, i32)> {
+    let scroll = playfield_mask.scroll();
+    (-PLAYFIELD.0 / 2..=PLAYFIELD.0 / 2)
+        .flat_map(|x| (-PLAYFIELD.1 / 2..=PLAYFIELD.1 / 2).map(move |y| (x + scroll, y)))
+        .filter(|position| !playfield_mask.is_wall(*position))
+        .collect()
+}
+
+// whether the open cells of the board, minus the extra walls, still form one area
+pub fn stays_connected(
+    playfield_mask: &PlayfieldMask,
+    extra_walls: impl Fn((i32, i32)) -> bool,
+) -> bool {
+    let free: Vec<(i32, i32)> = open_cells(playfield_mask)
+        .into_iter()
+        .filter(|position| !extra_walls(*position))
+        .collect();
+    let Some(start) = free.first() else {
+        return true;
+    };
+    let reached = flood_fill_with(
+        *start,
+        free.len(),
+        |position| playfield_mask.wrapped(position),
+        |position| playfield_mask.is_wall(position) || extra_walls(position),
+    );
+    reached.len() == free.len()
+}
+
+// wall cells covering roughly the given share of the open board, sorted so a seed always gives
+// the same layout
+pub fn generate(
+    rng: &mut impl Rng,
+    playfield_mask: &PlayfieldMask,
+    shapes: &[ObstacleDefinition],
+    density: f32,
+    keep_clear: &[(i32, i32)],
+) -> Vec<(i32, i32)> {
+    let open = open_cells(playfield_mask);
+    if open.is_empty() {
+        return Vec::new();
+    }
+    let target = (open.len() as f32 * density.clamp(0.0, MAX_DENSITY)) as usize;
+    let mut obstacles = HashSet::new();
+    for _ in 0..target * ATTEMPTS_PER_CELL {
+        if obstacles.len() >= target {
+            break;
+        }
+        let origin = open[rng.gen_range(0..open.len())];
+        let Some(shape) = pick_weighted(rng, shapes, |shape| shape.spawn_weight) else {
+            break;
+        };
+        // the whole shape or nothing, a wall crossing another is left out
+        let segment: Vec<(i32, i32)> = shape
+            .cells
+            .iter()
+            .map(|cell| (origin.0 + cell.0, origin.1 + cell.1))
+            .collect();
+        if segment.is_empty()
+            || segment.iter().any(|position| {
+                playfield_mask.is_wall(*position)
+                    || obstacles.contains(position)
+                    || keep_clear.contains(position)
+            })
+        {
+            continue;
+        }
+        obstacles.extend(segment.iter().copied());
+        if !stays_connected(playfield_mask, |position| obstacles.contains(&position)) {
+            for position in &segment {
+                obstacles.remove(position);
+            }
+            continue;
+        }
+        debug!(shape = shape.name, ?origin, "Obstacle kept");
+    }
+    let mut obstacles: Vec<(i32, i32)> = obstacles.into_iter().collect();
+    obstacles.sort();
+    obstacles
+}
@@ -0,0 +1,176 @@
+// Occupancy grid
+// What occupies each cell of the board, rebuilt every frame. Walls and snakes, which cover most of
+// a crowded board, are kept in bitboards over the playfield and its border; apples, power ups and
+// anything off that rectangle go in a map
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::bitboard::Bitboard;
+use crate::items::PowerUp;
+use crate::playfield::{grid_distance, wrap_around, PlayfieldMask};
+use crate::{Apple, SnakeBody, SnakeHead, PLAYFIELD};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Occupant {
+    SnakeHead,
+    SnakeBody,
+    Apple,
+    PowerUp,
+    Wall, // outside the playfield shape
+}
+
+#[derive(Resource, Default)]
+pub struct Occupancy {
+    walls: Bitboard,
+    snake_bodies: Bitboard,
+    snake_heads: Bitboard,
+    blocked: Bitboard, // walls and snakes together, for the searches
+    cells: HashMap<(i32, i32), Occupant>,
+    scroll: i32,              // of the playfield, moving its bounds
+    half_extents: (i32, i32), // of the open board, smaller ones and zen's closed rings included
+    wrap: bool,
+}
+
+impl Occupancy {
+    pub fn get(&self, position: (i32, i32)) -> Option<Occupant> {
+        // later kinds cover earlier ones, as they were inserted over them into the map
+        if self.snake_heads.contains(position) {
+            Some(Occupant::SnakeHead)
+        } else if self.snake_bodies.contains(position) {
+            Some(Occupant::SnakeBody)
+        } else if let Some(occupant) = self.cells.get(&position) {
+            Some(*occupant)
+        } else {
+            self.walls.contains(position).then_some(Occupant::Wall)
+        }
+    }
+
+    // a wall or a snake, without going through the map on the playfield
+    pub fn is_obstacle(&self, position: (i32, i32)) -> bool {
+        self.blocked.get(position).unwrap_or_else(|| {
+            matches!(
+                self.cells.get(&position),
+                Some(Occupant::SnakeHead | Occupant::SnakeBody | Occupant::Wall)
+            )
+        })
+    }
+
+    pub fn scroll(&self) -> i32 {
+        self.scroll
+    }
+
+    // for a board outside the world, a headless one say
+    pub fn from_board(
+        playfield_mask: &PlayfieldMask,
+        occupants: impl IntoIterator<Item = ((i32, i32), Occupant)>,
+    ) -> Self {
+        let mut occupancy = Occupancy::default();
+        occupancy.reset(playfield_mask);
+        for (position, occupant) in occupants {
+            occupancy.insert(position, occupant);
+        }
+        occupancy.finish();
+        occupancy
+    }
+
+    // nothing is on a wrapping board, a step off it comes back in on the far side
+    pub fn is_out_of_bounds(&self, position: (i32, i32)) -> bool {
+        !self.wrap && self.is_off_board(position)
+    }
+
+    // past the edge either way, for what can't cross it like a pulled apple
+    pub fn is_off_board(&self, position: (i32, i32)) -> bool {
+        (position.0 - self.scroll).abs() > self.half_extents.0
+            || position.1.abs() > self.half_extents.1
+    }
+
+    // as PlayfieldMask::wrapped, for the searches
+    pub fn wrapped(&self, position: (i32, i32)) -> (i32, i32) {
+        if !self.wrap {
+            return position;
+        }
+        wrap_around(position, self.scroll, self.half_extents)
+    }
+
+    // as PlayfieldMask::distance
+    pub fn distance(&self, a: (i32, i32), b: (i32, i32)) -> u32 {
+        grid_distance(a, b, self.wrap.then_some(self.half_extents))
+    }
+
+    // emptied down to the playfield's walls, the bitboards moved along with its scroll
+    fn reset(&mut self, playfield_mask: &PlayfieldMask) {
+        self.scroll = playfield_mask.scroll();
+        self.half_extents = playfield_mask.half_extents();
+        self.wrap = playfield_mask.wrap;
+        let origin = (self.scroll - PLAYFIELD.0 / 2 - 1, -PLAYFIELD.1 / 2 - 1);
+        let (width, height) = (PLAYFIELD.0 + 2, PLAYFIELD.1 + 2);
+        for bitboard in [
+            &mut self.walls,
+            &mut self.snake_bodies,
+            &mut self.snake_heads,
+            &mut self.blocked,
+        ] {
+            bitboard.reset(origin, width, height);
+        }
+        self.cells.clear();
+        for position in playfield_mask.walls() {
+            self.insert(position, Occupant::Wall);
+        }
+    }
+
+    fn insert(&mut self, position: (i32, i32), occupant: Occupant) {
+        let bitboard = match occupant {
+            Occupant::SnakeHead => &mut self.snake_heads,
+            Occupant::SnakeBody => &mut self.snake_bodies,
+            Occupant::Wall => &mut self.walls,
+            Occupant::Apple | Occupant::PowerUp => {
+                self.cells.insert(position, occupant);
+                return;
+            }
+        };
+        if !bitboard.insert(position) {
+            self.cells.insert(position, occupant);
+        }
+    }
+
+    fn finish(&mut self) {
+        for bitboard in [&self.walls, &self.snake_bodies, &self.snake_heads] {
+            self.blocked.union_with(bitboard);
+        }
+    }
+}
+
+pub struct OccupancyPlugin;
+
+impl Plugin for OccupancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Occupancy>()
+            .add_systems(PostUpdate, update_occupancy);
+    }
+}
+
+pub fn update_occupancy(
+    mut occupancy: ResMut<Occupancy>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    power_up_query: Query<&PowerUp>,
+) {
+    occupancy.reset(&playfield_mask);
+    for apple in &apple_query {
+        for position in &apple.footprint {
+            occupancy.insert(*position, Occupant::Apple);
+        }
+    }
+    for power_up in &power_up_query {
+        occupancy.insert(power_up.position, Occupant::PowerUp);
+    }
+    for snake_body in &snake_body_query {
+        occupancy.insert(snake_body.position, Occupant::SnakeBody);
+    }
+    for snake_head in &snake_head_query {
+        occupancy.insert(snake_head.position, Occupant::SnakeHead);
+    }
+    occupancy.finish();
+}
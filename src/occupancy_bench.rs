@@ -0,0 +1,184 @@
+// Occupancy benchmark
+// --bench-occupancy [size...] times what the occupancy grid does every tick, on square boards of
+// the given sizes (the real one and two giant ones when none are given), once with the bitboards
+// it keeps now and once with the hash sets it used to: rebuilding from the walls and a snake
+// filling half the board, checking every cell for an obstacle as a flood fill would, and whether
+// the snake lies on a wall anywhere
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::utils::HashSet;
+
+use crate::args::Args;
+use crate::bitboard::Bitboard;
+use crate::PLAYFIELD;
+
+const DEFAULT_SIZES: [i32; 3] = [PLAYFIELD.0, 129, 513];
+const CELLS_PER_SIZE: usize = 20_000_000; // looked up per size, rounds shrink as boards grow
+const STONE_SPACING: i32 = 7; // a stone every so many cells inside the border
+
+type Cells = Vec<(i32, i32)>;
+
+// a board of each kind, both filled from the same cells
+trait BenchGrid {
+    fn rebuild(&mut self, walls: &[(i32, i32)], body: &[(i32, i32)]);
+    fn is_blocked(&self, position: (i32, i32)) -> bool;
+    fn body_on_wall(&self) -> bool;
+}
+
+struct HashSetGrid {
+    walls: HashSet<(i32, i32)>,
+    body: HashSet<(i32, i32)>,
+}
+
+impl BenchGrid for HashSetGrid {
+    fn rebuild(&mut self, walls: &[(i32, i32)], body: &[(i32, i32)]) {
+        self.walls.clear();
+        self.body.clear();
+        self.walls.extend(walls);
+        self.body.extend(body);
+    }
+
+    fn is_blocked(&self, position: (i32, i32)) -> bool {
+        self.walls.contains(&position) || self.body.contains(&position)
+    }
+
+    fn body_on_wall(&self) -> bool {
+        self.body
+            .iter()
+            .any(|position| self.walls.contains(position))
+    }
+}
+
+struct BitboardGrid {
+    walls: Bitboard,
+    body: Bitboard,
+    blocked: Bitboard,
+}
+
+impl BenchGrid for BitboardGrid {
+    fn rebuild(&mut self, walls: &[(i32, i32)], body: &[(i32, i32)]) {
+        self.walls.clear();
+        self.body.clear();
+        self.blocked.clear();
+        for position in walls {
+            self.walls.insert(*position);
+        }
+        for position in body {
+            self.body.insert(*position);
+        }
+        self.blocked.union_with(&self.walls);
+        self.blocked.union_with(&self.body);
+    }
+
+    fn is_blocked(&self, position: (i32, i32)) -> bool {
+        self.blocked.contains(position)
+    }
+
+    fn body_on_wall(&self) -> bool {
+        self.walls.intersects(&self.body)
+    }
+}
+
+pub fn bench_from_args(args: &Args) -> Option<Result<String, String>> {
+    let sizes: Result<Vec<i32>, _> = args
+        .values("--bench-occupancy")?
+        .into_iter()
+        .map(|size| size.parse::<i32>())
+        .collect();
+    let sizes = match sizes {
+        Ok(sizes) if sizes.iter().all(|size| *size >= 3) => sizes,
+        _ => {
+            return Some(Err(
+                "--bench-occupancy expects board sizes of 3 or more".to_string()
+            ))
+        }
+    };
+    let sizes = if sizes.is_empty() {
+        DEFAULT_SIZES.to_vec()
+    } else {
+        sizes
+    };
+    sizes
+        .into_iter()
+        .map(bench_size)
+        .collect::<Result<Vec<_>, _>>()
+        .map(|lines| lines.join("\n"))
+        .into()
+}
+
+// the border and scattered stones, and a snake winding up the rows from the bottom
+fn board_cells(size: i32) -> (Cells, Cells) {
+    let half = size / 2;
+    let cells = || (-half..=half).flat_map(move |y| (-half..=half).map(move |x| (x, y)));
+    let walls = cells()
+        .filter(|(x, y)| {
+            x.abs() == half
+                || y.abs() == half
+                || (x.rem_euclid(STONE_SPACING) == 0 && y.rem_euclid(STONE_SPACING) == 0)
+        })
+        .collect();
+    let row = |y: i32| {
+        let columns = -half + 1..half;
+        let row: Vec<(i32, i32)> = columns.map(|x| (x, y)).collect();
+        if (y + half) % 2 == 0 {
+            row.into_iter().rev().collect()
+        } else {
+            row
+        }
+    };
+    let inside = ((size - 2) * (size - 2)) as usize;
+    let body = (-half + 1..half).flat_map(row).take(inside / 2).collect();
+    (walls, body)
+}
+
+fn time_grid(
+    grid: &mut impl BenchGrid,
+    size: i32,
+    rounds: usize,
+    walls: &[(i32, i32)],
+    body: &[(i32, i32)],
+) -> (Duration, usize) {
+    let half = size / 2;
+    let started = Instant::now();
+    let mut blocked = 0;
+    for _ in 0..rounds {
+        grid.rebuild(black_box(walls), black_box(body));
+        for y in -half..=half {
+            for x in -half..=half {
+                blocked += grid.is_blocked(black_box((x, y))) as usize;
+            }
+        }
+        blocked += grid.body_on_wall() as usize;
+    }
+    (started.elapsed() / rounds as u32, blocked)
+}
+
+fn bench_size(size: i32) -> Result<String, String> {
+    let (walls, body) = board_cells(size);
+    let rounds = (CELLS_PER_SIZE / (size * size) as usize).max(1);
+    let origin = (-(size / 2), -(size / 2));
+    let mut hash_set_grid = HashSetGrid {
+        walls: HashSet::default(),
+        body: HashSet::default(),
+    };
+    let mut bitboard_grid = BitboardGrid {
+        walls: Bitboard::new(origin, size, size),
+        body: Bitboard::new(origin, size, size),
+        blocked: Bitboard::new(origin, size, size),
+    };
+    let (hash_set, hash_set_blocked) = time_grid(&mut hash_set_grid, size, rounds, &walls, &body);
+    let (bitboard, bitboard_blocked) = time_grid(&mut bitboard_grid, size, rounds, &walls, &body);
+    if hash_set_blocked != bitboard_blocked {
+        return Err(format!(
+            "{size}x{size}: the grids disagree, {hash_set_blocked} blocked against {bitboard_blocked}"
+        ));
+    }
+    Ok(format!(
+        "{size}x{size}, {} snake cells: hash sets {:.1}µs a tick, bitboards {:.1}µs a tick ({:.1}x)",
+        body.len(),
+        hash_set.as_secs_f64() * 1e6,
+        bitboard.as_secs_f64() * 1e6,
+        hash_set.as_secs_f64() / bitboard.as_secs_f64().max(f64::EPSILON),
+    ))
+}
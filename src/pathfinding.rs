@@ -0,0 +1,116 @@
+// Pathfinding
+// Searches over the occupancy grid for bots and assists: A* paths, flood fills and reachable area,
+// walls and snakes block while apples and power ups are free to cross, and the edges of a wrapping
+// board lead around to the far side
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use bevy::utils::{HashMap, HashSet};
+
+use crate::direction::Direction;
+use crate::occupancy::Occupancy;
+
+pub fn is_blocked(occupancy: &Occupancy, position: (i32, i32)) -> bool {
+    occupancy.is_out_of_bounds(position) || occupancy.is_obstacle(position)
+}
+
+// free cells connected to the start, the start included, stopping once the limit is reached
+pub fn flood_fill(occupancy: &Occupancy, start: (i32, i32), limit: usize) -> HashSet<(i32, i32)> {
+    flood_fill_with(
+        start,
+        limit,
+        |position| occupancy.wrapped(position),
+        |position| is_blocked(occupancy, position),
+    )
+}
+
+// the same over any grid, for boards that are still being laid out
+pub fn flood_fill_with(
+    start: (i32, i32),
+    limit: usize,
+    wrapped: impl Fn((i32, i32)) -> (i32, i32),
+    is_blocked: impl Fn((i32, i32)) -> bool,
+) -> HashSet<(i32, i32)> {
+    let mut visited = HashSet::from([start]);
+    let mut frontier = VecDeque::from([start]);
+    while let Some(position) = frontier.pop_front() {
+        for direction in Direction::ALL {
+            if visited.len() >= limit {
+                return visited;
+            }
+            let next = wrapped(direction.step(position));
+            if !is_blocked(next) && visited.insert(next) {
+                frontier.push_back(next);
+            }
+        }
+    }
+    visited
+}
+
+pub fn reachable_area(occupancy: &Occupancy, start: (i32, i32), limit: usize) -> usize {
+    flood_fill(occupancy, start, limit).len()
+}
+
+// shortest path to the nearest goal, without the start and ending on the goal
+pub fn find_path(
+    occupancy: &Occupancy,
+    start: (i32, i32),
+    goals: &[(i32, i32)],
+) -> Option<Vec<(i32, i32)>> {
+    let heuristic = |position: (i32, i32)| {
+        goals
+            .iter()
+            .map(|goal| occupancy.distance(position, *goal))
+            .min()
+            .unwrap_or(0)
+    };
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::default();
+    let mut cost = HashMap::from([(start, 0)]);
+    let mut open = BinaryHeap::from([Reverse((heuristic(start), start))]);
+    while let Some(Reverse((_, position))) = open.pop() {
+        if goals.contains(&position) {
+            let mut path = vec![position];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(*previous);
+            }
+            path.pop();
+            path.reverse();
+            return Some(path);
+        }
+        let next_cost = cost[&position] + 1;
+        for direction in Direction::ALL {
+            let next = occupancy.wrapped(direction.step(position));
+            if is_blocked(occupancy, next) || cost.get(&next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            cost.insert(next, next_cost);
+            came_from.insert(next, position);
+            open.push(Reverse((next_cost + heuristic(next), next)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playfield::{PlayfieldMask, PlayfieldShape};
+
+    #[test]
+    fn wraps_around_the_edge() {
+        let mut playfield_mask = PlayfieldMask::new(PlayfieldShape::Rectangle).with_board(9, 7);
+        let path = find_path(
+            &Occupancy::from_board(&playfield_mask, []),
+            (-4, 0),
+            &[(4, 0)],
+        );
+        assert_eq!(path.map(|path| path.len()), Some(8));
+        playfield_mask.wrap = true;
+        let occupancy = Occupancy::from_board(&playfield_mask, []);
+        assert_eq!(
+            find_path(&occupancy, (-4, 0), &[(4, 0)]),
+            Some(vec![(4, 0)])
+        );
+        assert_eq!(flood_fill(&occupancy, (0, 0), usize::MAX).len(), 9 * 7);
+    }
+}
@@ -0,0 +1,268 @@
+// Playfield shapes
+// Boards other than the full rectangle (--playfield circle|cross|donut|rooms): cells outside the
+// shape are walls, drawn as tiles and treated like the border by collisions, spawns and bots.
+// With --wrap the border is no wall, a snake leaving one edge comes back in on the other
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::args::Args;
+use crate::{PIXEL_UNIT_SIZE, PLAYFIELD};
+
+const WALL_COLOR: Color = Color::BLACK;
+const CROSS_ARM_HALF_WIDTH: i32 = 5;
+const DONUT_HOLE_RADIUS: f32 = 6.5;
+const DONUT_START_COLUMN: i32 = 11; // in the ring, right of the hole
+
+// three by three rooms, the inner walls run along these columns and rows
+const ROOM_WALL: i32 = 6;
+const DOOR_CENTERS: [i32; 3] = [-11, 0, 11]; // one door in the middle of each wall between rooms
+const DOOR_HALF_WIDTH: i32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayfieldShape {
+    Rectangle,
+    Circle,
+    Cross,
+    Donut,
+    Rooms,
+}
+
+impl PlayfieldShape {
+    // --playfield <rectangle|circle|cross|donut>
+    pub fn from_args(args: &Args) -> Self {
+        match args.value("--playfield") {
+            Some("circle") => PlayfieldShape::Circle,
+            Some("cross") => PlayfieldShape::Cross,
+            Some("donut") => PlayfieldShape::Donut,
+            Some("rooms") => PlayfieldShape::Rooms,
+            Some("rectangle") | None => PlayfieldShape::Rectangle,
+            Some(other) => {
+                args.problem(format!("Unknown playfield {other:?}, using the rectangle"));
+                PlayfieldShape::Rectangle
+            }
+        }
+    }
+
+    fn is_open(self, (x, y): (i32, i32)) -> bool {
+        let radius = (PLAYFIELD.0.min(PLAYFIELD.1) / 2) as f32 + 0.5;
+        let distance_squared = (x * x + y * y) as f32;
+        match self {
+            PlayfieldShape::Rectangle => true,
+            PlayfieldShape::Circle => distance_squared <= radius * radius,
+            PlayfieldShape::Cross => {
+                x.abs() <= CROSS_ARM_HALF_WIDTH || y.abs() <= CROSS_ARM_HALF_WIDTH
+            }
+            PlayfieldShape::Donut => {
+                distance_squared <= radius * radius
+                    && distance_squared > DONUT_HOLE_RADIUS * DONUT_HOLE_RADIUS
+            }
+            PlayfieldShape::Rooms => {
+                let in_door = |c: i32| {
+                    DOOR_CENTERS
+                        .iter()
+                        .any(|center| (c - center).abs() <= DOOR_HALF_WIDTH)
+                };
+                // walls between rooms are open only at their doors
+                (x.abs() != ROOM_WALL || in_door(y)) && (y.abs() != ROOM_WALL || in_door(x))
+            }
+        }
+    }
+}
+
+// the cells of the board rectangle that are walls for this run
+#[derive(Resource)]
+pub struct PlayfieldMask {
+    pub shape: PlayfieldShape,
+    walls: HashSet<(i32, i32)>,
+    // columns the board has moved right over the world, only the endless corridor scrolls
+    scroll: i32,
+    // rings around the edge that are closed off, only zen mode opens them up as it goes
+    inset: i32,
+    // cells across and down, the world is laid out for PLAYFIELD so never more than that
+    board: (i32, i32),
+    pub wrap: bool,
+}
+
+#[derive(Component)]
+struct WallTile;
+
+impl PlayfieldMask {
+    pub fn new(shape: PlayfieldShape) -> Self {
+        let walls = (-PLAYFIELD.0 / 2..=PLAYFIELD.0 / 2)
+            .flat_map(|x| (-PLAYFIELD.1 / 2..=PLAYFIELD.1 / 2).map(move |y| (x, y)))
+            .filter(|cell| !shape.is_open(*cell))
+            .collect();
+        PlayfieldMask {
+            shape,
+            walls,
+            scroll: 0,
+            inset: 0,
+            board: PLAYFIELD,
+            wrap: false,
+        }
+    }
+
+    // a smaller board around the same center, odd sizes so it still has a middle cell
+    pub fn with_board(mut self, width: i32, height: i32) -> Self {
+        let odd = |size: i32, most: i32| (size.clamp(1, most) - 1) / 2 * 2 + 1;
+        self.board = (odd(width, PLAYFIELD.0), odd(height, PLAYFIELD.1));
+        let (half_width, half_height) = (self.board.0 / 2, self.board.1 / 2);
+        self.walls
+            .retain(|wall| wall.0.abs() <= half_width && wall.1.abs() <= half_height);
+        self
+    }
+
+    pub fn is_wall(&self, position: (i32, i32)) -> bool {
+        self.is_off_board(position) || self.walls.contains(&position)
+    }
+
+    // past the edge, leaving the walls inside out of it
+    pub fn is_off_board(&self, position: (i32, i32)) -> bool {
+        let (half_width, half_height) = self.half_extents();
+        (position.0 - self.scroll).abs() > half_width || position.1.abs() > half_height
+    }
+
+    // where a step off the board lands, back in on the far side when wrapping
+    pub fn wrapped(&self, position: (i32, i32)) -> (i32, i32) {
+        if !self.wrap {
+            return position;
+        }
+        wrap_around(position, self.scroll, self.half_extents())
+    }
+
+    // cells apart along the grid, the short way around when wrapping
+    pub fn distance(&self, a: (i32, i32), b: (i32, i32)) -> u32 {
+        grid_distance(a, b, self.wrap.then(|| self.half_extents()))
+    }
+
+    // how far the open board reaches from its center on either side
+    pub fn half_extents(&self) -> (i32, i32) {
+        (self.board.0 / 2 - self.inset, self.board.1 / 2 - self.inset)
+    }
+
+    // the open board in pixels
+    pub fn size(&self) -> Vec2 {
+        let (half_width, half_height) = self.half_extents();
+        Vec2::new(
+            (2 * half_width + 1) as f32 * PIXEL_UNIT_SIZE,
+            (2 * half_height + 1) as f32 * PIXEL_UNIT_SIZE,
+        )
+    }
+
+    pub fn inset(&self) -> i32 {
+        self.inset
+    }
+
+    // the center cell always stays open
+    pub fn set_inset(&mut self, inset: i32) {
+        self.inset = inset.clamp(0, self.board.0.min(self.board.1) / 2);
+    }
+
+    pub fn scroll(&self) -> i32 {
+        self.scroll
+    }
+
+    // where the board's center is in the world, cells themselves always sit at their grid position
+    pub fn world_offset(&self) -> Vec2 {
+        Vec2::new(self.scroll as f32 * PIXEL_UNIT_SIZE, 0.0)
+    }
+
+    // walls left behind by the board are dropped
+    pub fn scroll_right(&mut self) {
+        self.scroll += 1;
+        let left_edge = self.scroll - self.board.0 / 2;
+        self.walls.retain(|wall| wall.0 >= left_edge);
+    }
+
+    pub fn add_wall(&mut self, position: (i32, i32)) {
+        self.walls.insert(position);
+    }
+
+    pub fn remove_wall(&mut self, position: (i32, i32)) {
+        self.walls.remove(&position);
+    }
+
+    // column and row of the room a cell is in, None on boards without rooms
+    pub fn room(&self, (x, y): (i32, i32)) -> Option<(i32, i32)> {
+        let index = |c: i32| (c > -ROOM_WALL) as i32 + (c > ROOM_WALL) as i32;
+        (self.shape == PlayfieldShape::Rooms).then(|| (index(x), index(y)))
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.walls.iter().copied()
+    }
+
+    // column the snakes start in, every shape has room for them on their rows there
+    pub fn start_column(&self) -> i32 {
+        match self.shape {
+            PlayfieldShape::Donut => DONUT_START_COLUMN,
+            _ => 0,
+        }
+    }
+}
+
+// a position off a board of these extents brought back in on the far side, for anything keeping
+// the board's bounds without the mask
+pub fn wrap_around(
+    (x, y): (i32, i32),
+    scroll: i32,
+    (half_width, half_height): (i32, i32),
+) -> (i32, i32) {
+    (
+        (x - scroll + half_width).rem_euclid(2 * half_width + 1) - half_width + scroll,
+        (y + half_height).rem_euclid(2 * half_height + 1) - half_height,
+    )
+}
+
+// around the edges of a board of the given extents when it wraps
+pub fn grid_distance(a: (i32, i32), b: (i32, i32), wrapping: Option<(i32, i32)>) -> u32 {
+    let apart = |a: i32, b: i32, half: Option<i32>| {
+        let straight = a.abs_diff(b);
+        half.map_or(straight, |half| {
+            let across = (2 * half + 1) as u32;
+            (straight % across).min(across - straight % across)
+        })
+    };
+    apart(a.0, b.0, wrapping.map(|(half_width, _)| half_width))
+        + apart(a.1, b.1, wrapping.map(|(_, half_height)| half_height))
+}
+
+pub struct PlayfieldPlugin;
+
+impl Plugin for PlayfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_walls);
+    }
+}
+
+// rebuilt whenever the walls change, which is only ever at startup unless the board scrolls
+fn draw_walls(
+    mut commands: Commands,
+    playfield_mask: Res<PlayfieldMask>,
+    wall_tile_query: Query<Entity, With<WallTile>>,
+) {
+    if !playfield_mask.is_changed() {
+        return;
+    }
+    for entity in &wall_tile_query {
+        commands.entity(entity).despawn();
+    }
+    for (x, y) in playfield_mask.walls() {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE, PIXEL_UNIT_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    x as f32 * PIXEL_UNIT_SIZE,
+                    y as f32 * PIXEL_UNIT_SIZE,
+                    -0.05,
+                )),
+                ..default()
+            },
+            WallTile,
+        ));
+    }
+}
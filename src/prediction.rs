@@ -0,0 +1,98 @@
+// Prediction
+// A remote player's own snake is moved locally the moment they steer instead of a round trip
+// later: every authoritative snapshot resets it to where the host says it is, then the inputs the
+// host hasn't applied yet are replayed on top for the ticks since
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::direction::Direction;
+use crate::playfield::wrap_around;
+use crate::spectate::SnakeState;
+
+#[derive(Resource, Default)]
+pub struct Prediction {
+    next_sequence: u64,
+    // sent but not yet acknowledged by the host, oldest first
+    pending: VecDeque<(u64, Direction)>,
+    authoritative: Option<SnakeState>,
+    wrapping: Option<(i32, (i32, i32))>, // scroll and half extents of a host board that wraps
+    ticks_since_snapshot: u32,
+    predicted: Vec<(i32, i32)>, // head first
+    pub mispredictions: u32,
+}
+
+impl Prediction {
+    // numbers a new input to send, it applies locally right away
+    pub fn push_input(&mut self, direction: Direction) -> u64 {
+        self.next_sequence += 1;
+        self.pending.push_back((self.next_sequence, direction));
+        self.replay();
+        self.next_sequence
+    }
+
+    // a fresh snapshot of our snake, with the last of our inputs the host had applied by then
+    pub fn reconcile(
+        &mut self,
+        snake: SnakeState,
+        acknowledged: u64,
+        wrapping: Option<(i32, (i32, i32))>,
+    ) {
+        self.pending
+            .retain(|(sequence, _)| *sequence > acknowledged);
+        if self.predicted != snake.cells {
+            self.mispredictions += 1;
+            debug!(mispredictions = self.mispredictions, "Prediction corrected");
+        }
+        self.authoritative = Some(snake);
+        self.wrapping = wrapping;
+        self.ticks_since_snapshot = 0;
+        self.replay();
+    }
+
+    // a local tick at the host's rate, until the next snapshot catches up with it
+    pub fn tick(&mut self) {
+        if self.authoritative.is_some() {
+            self.ticks_since_snapshot += 1;
+            self.replay();
+        }
+    }
+
+    pub fn forget(&mut self) {
+        if self.authoritative.take().is_some() {
+            self.predicted.clear();
+        }
+    }
+
+    pub fn cells(&self) -> &[(i32, i32)] {
+        &self.predicted
+    }
+
+    // the host applies at most one new input per tick, like a player steering
+    fn replay(&mut self) {
+        let Some(snake) = &self.authoritative else {
+            return;
+        };
+        let mut cells = snake.cells.clone();
+        let mut direction = snake.direction;
+        let mut inputs = self.pending.iter().map(|(_, direction)| *direction);
+        for _ in 0..self.ticks_since_snapshot {
+            if let Some(input) = inputs.next() {
+                if input != direction.opposite() {
+                    direction = input;
+                }
+            }
+            let Some(head) = cells.first().copied() else {
+                break;
+            };
+            // growth isn't predicted, the next snapshot brings it in
+            let head = direction.step(head);
+            let head = self.wrapping.map_or(head, |(scroll, half_extents)| {
+                wrap_around(head, scroll, half_extents)
+            });
+            cells.insert(0, head);
+            cells.pop();
+        }
+        self.predicted = cells;
+    }
+}
@@ -0,0 +1,345 @@
+// Profiles
+// Everyone sharing the machine gets their own profile, picked or created on a screen at startup
+// or named with --profile <name>. High scores, key bindings, achievements and unlocks are saved
+// per profile under profiles/<id>/, the list itself in profiles.ron
+use std::io::ErrorKind;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::high_score::HIGH_SCORE_FILE;
+use crate::input::{ActionMap, InputSettings, KeyBindings, ACTION_MAP_FILE, KEY_BINDINGS_FILE};
+use crate::menu::{spawn_button, MenuActivated};
+use crate::ron_asset::RonAssetError;
+use crate::storage::{read_ron_save, write_ron_save, StoragePaths};
+use crate::widgets::{spawn_text_input, TextSubmitted};
+
+const PROFILES_FILE: &str = "profiles.ron";
+const MAX_PROFILES: usize = 8;
+const NAME_LEN: usize = 12;
+const DEFAULT_NAME: &str = "Player 1";
+// saves from before there were profiles, the first profile created takes them over
+const SHARED_FILES: [&str; 2] = [HIGH_SCORE_FILE, KEY_BINDINGS_FILE];
+
+// the one playing, inserted once picked
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: u32,
+    pub name: String,
+}
+
+impl Profile {
+    // where one of this profile's saves goes, for the storage functions
+    pub fn file(&self, name: &str) -> String {
+        format!("profiles/{}/{name}", self.id)
+    }
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+struct ProfileList {
+    profiles: Vec<Profile>,
+    last: Option<u32>, // picked the last time, for starts that skip the screen
+}
+
+impl ProfileList {
+    fn save(&self) {
+        if let Err(error) = write_ron_save(PROFILES_FILE, self) {
+            warn!("Could not save profiles: {error}");
+        }
+    }
+
+    fn create(&mut self, name: &str) -> Profile {
+        let profile = Profile {
+            id: self
+                .profiles
+                .iter()
+                .map(|profile| profile.id)
+                .max()
+                .unwrap_or(0)
+                + 1,
+            name: name.to_string(),
+        };
+        if self.profiles.is_empty() {
+            for file in SHARED_FILES {
+                match StoragePaths::get().rename(file, &profile.file(file)) {
+                    Ok(()) => info!("Moved {file} into profile {name}"),
+                    Err(error) if error.kind() == ErrorKind::NotFound => {}
+                    Err(error) => warn!("Could not move {file} into profile {name}: {error}"),
+                }
+            }
+        }
+        info!(id = profile.id, name, "Profile created");
+        self.profiles.push(profile.clone());
+        profile
+    }
+
+    // names match whatever their case
+    fn named(&mut self, name: &str) -> Profile {
+        match self
+            .profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+        {
+            Some(profile) => profile.clone(),
+            None => self.create(name),
+        }
+    }
+
+    fn pick(&mut self, profile: &Profile) {
+        self.last = Some(profile.id);
+        self.save();
+    }
+}
+
+#[derive(Component)]
+struct ProfileScreen;
+
+#[derive(Component, Clone, Copy)]
+enum ProfileButton {
+    Pick(u32),
+    New,
+}
+
+#[derive(Component)]
+struct NamePrompt;
+
+#[derive(Component)]
+struct NameInput;
+
+#[derive(Component)]
+struct ProfileStatus;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    // the modes that open on a screen of their own go straight to the last profile
+    fn build(&self, app: &mut App) {
+        let mut list: ProfileList = read_ron_save(PROFILES_FILE).unwrap_or_default();
+        let args = Args::of(app);
+        let named = args.value("--profile");
+        let other_start = ["--demo", "--scenarios", "--lobby", "--battle"]
+            .iter()
+            .any(|flag| args.has(flag));
+        let profile = match named {
+            Some(name) => Some(list.named(name)),
+            None if other_start => Some(
+                list.profiles
+                    .iter()
+                    .find(|profile| Some(profile.id) == list.last)
+                    .cloned()
+                    .unwrap_or_else(|| list.named(DEFAULT_NAME)),
+            ),
+            None => None,
+        };
+        if let Some(profile) = profile {
+            list.pick(&profile);
+            app.insert_resource(profile);
+        }
+        app.insert_resource(list)
+            .add_systems(
+                Startup,
+                setup_profile_screen.run_if(not(resource_exists::<Profile>())),
+            )
+            .add_systems(
+                Update,
+                (
+                    (press_profile_buttons, create_profile)
+                        .run_if(not(resource_exists::<Profile>())),
+                    load_action_map.run_if(resource_added::<Profile>()),
+                ),
+            );
+    }
+}
+
+fn setup_profile_screen(
+    mut commands: Commands,
+    list: Res<ProfileList>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.pause();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(15),
+                ..default()
+            },
+            ProfileScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Who's playing?",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::GOLD,
+                        ..default()
+                    },
+                ),
+                ProfileStatus,
+            ));
+            for profile in &list.profiles {
+                spawn_button(parent, &profile.name, ProfileButton::Pick(profile.id));
+            }
+            if list.profiles.len() < MAX_PROFILES {
+                spawn_button(parent, "New profile", ProfileButton::New);
+            }
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        // straight away when there is nobody to pick
+                        visibility: if list.profiles.is_empty() {
+                            Visibility::Inherited
+                        } else {
+                            Visibility::Hidden
+                        },
+                        ..default()
+                    },
+                    NamePrompt,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Name:",
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+                    spawn_text_input(parent, NAME_LEN, NameInput);
+                });
+        });
+}
+
+fn press_profile_buttons(
+    mut commands: Commands,
+    mut menu_activated_event: EventReader<MenuActivated>,
+    mut list: ResMut<ProfileList>,
+    mut time: ResMut<Time<Virtual>>,
+    button_query: Query<&ProfileButton>,
+    screen_query: Query<Entity, With<ProfileScreen>>,
+    mut prompt_query: Query<&mut Visibility, With<NamePrompt>>,
+) {
+    let Some(button) = menu_activated_event
+        .read()
+        .find_map(|event| button_query.get(event.0).ok().copied())
+    else {
+        return;
+    };
+    match button {
+        ProfileButton::Pick(id) => {
+            let Some(profile) = list
+                .profiles
+                .iter()
+                .find(|profile| profile.id == id)
+                .cloned()
+            else {
+                return;
+            };
+            list.pick(&profile);
+            start_as(&mut commands, profile, &mut time, &screen_query);
+        }
+        ProfileButton::New => {
+            for mut visibility in &mut prompt_query {
+                *visibility = Visibility::Inherited;
+            }
+        }
+    }
+}
+
+fn create_profile(
+    mut commands: Commands,
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    mut list: ResMut<ProfileList>,
+    mut time: ResMut<Time<Virtual>>,
+    input_query: Query<(), With<NameInput>>,
+    screen_query: Query<Entity, With<ProfileScreen>>,
+    mut status_query: Query<&mut Text, With<ProfileStatus>>,
+) {
+    let Some(name) = text_submitted_event
+        .read()
+        .find(|event| input_query.contains(event.input))
+        .map(|event| event.value.trim().to_string())
+    else {
+        return;
+    };
+    let taken = list
+        .profiles
+        .iter()
+        .any(|profile| profile.name.eq_ignore_ascii_case(&name));
+    let status = if name.is_empty() {
+        "Type a name first"
+    } else if taken {
+        "That name is taken"
+    } else if list.profiles.len() >= MAX_PROFILES {
+        "No room for another profile"
+    } else {
+        let profile = list.create(&name);
+        list.pick(&profile);
+        start_as(&mut commands, profile, &mut time, &screen_query);
+        return;
+    };
+    for mut text in &mut status_query {
+        text.sections[0].value = status.to_string();
+    }
+}
+
+fn start_as(
+    commands: &mut Commands,
+    profile: Profile,
+    time: &mut Time<Virtual>,
+    screen_query: &Query<Entity, With<ProfileScreen>>,
+) {
+    info!(id = profile.id, name = profile.name, "Playing as profile");
+    commands.insert_resource(profile);
+    for entity in screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    time.unpause();
+}
+
+// written out the first time, from the key bindings saved before if there are any, so there is a
+// file to edit
+fn load_action_map(profile: Res<Profile>, mut input_settings: ResMut<InputSettings>) {
+    let file = profile.file(ACTION_MAP_FILE);
+    input_settings.actions = match read_ron_save(&file) {
+        Ok(actions) => actions,
+        Err(RonAssetError::Io(error)) if error.kind() == ErrorKind::NotFound => {
+            let actions = match read_ron_save::<KeyBindings>(&profile.file(KEY_BINDINGS_FILE)) {
+                Ok(key_bindings) => ActionMap::with_key_bindings(key_bindings),
+                Err(_) => ActionMap::default(),
+            };
+            if let Err(error) = write_ron_save(&file, &actions) {
+                warn!("Could not save the action map: {error}");
+            }
+            actions
+        }
+        Err(error) => {
+            warn!("Could not read the action map, using the defaults: {error}");
+            ActionMap::default()
+        }
+    };
+}
@@ -0,0 +1,88 @@
+// Quit confirmation
+// Closing the window or pressing Escape asks before ending the run
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowCloseRequested};
+
+use crate::widgets::{answer_dialogs, spawn_confirm_dialog, DialogAnswered};
+
+#[derive(Component)]
+struct QuitDialog;
+
+pub struct QuitPlugin;
+
+impl Plugin for QuitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_quit_dialog)
+            .add_systems(Update, handle_quit_dialog.after(answer_dialogs));
+    }
+}
+
+fn setup_quit_dialog(mut commands: Commands) {
+    spawn_confirm_dialog(
+        &mut commands,
+        "Quit? Your run will be lost, the high score is saved.",
+        ["[Y] Quit", "[N] Keep playing"],
+        QuitDialog,
+    );
+}
+
+// windows are not closed automatically, see WindowPlugin in main
+#[allow(clippy::too_many_arguments)]
+fn handle_quit_dialog(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut close_requested_event: EventReader<WindowCloseRequested>,
+    mut dialog_answered_event: EventReader<DialogAnswered>,
+    primary_window_query: Query<(), With<PrimaryWindow>>,
+    mut dialog_query: Query<(Entity, &mut Visibility), With<QuitDialog>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut app_exit_event: EventWriter<AppExit>,
+    mut paused_by_dialog: Local<bool>,
+) {
+    let Ok((dialog, mut visibility)) = dialog_query.get_single_mut() else {
+        return;
+    };
+    let mut open = *visibility != Visibility::Hidden;
+    if open {
+        let answer = dialog_answered_event
+            .read()
+            .find(|event| event.dialog == dialog)
+            .map(|event| event.accepted);
+        match answer {
+            Some(true) => {
+                info!("Quit confirmed");
+                app_exit_event.send(AppExit);
+            }
+            Some(false) => open = false,
+            None => {}
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        open = true;
+    }
+    for event in close_requested_event.read() {
+        if primary_window_query.contains(event.window) {
+            open = true;
+        } else {
+            // secondary windows like the stats window just close
+            commands.entity(event.window).despawn_recursive();
+        }
+    }
+
+    if open != (*visibility != Visibility::Hidden) {
+        info!(open, "Quit dialog toggled");
+    }
+    // leave time alone if something else, like the post-game heatmap, paused it
+    if open {
+        *visibility = Visibility::Inherited;
+        if !time.is_paused() {
+            time.pause();
+            *paused_by_dialog = true;
+        }
+    } else {
+        *visibility = Visibility::Hidden;
+        if std::mem::take(&mut *paused_by_dialog) {
+            time.unpause();
+        }
+    }
+}
@@ -0,0 +1,481 @@
+// Replays
+// Ranked runs (--ranked) play the classic rules with nothing left to timing: no power-ups, score
+// zones or dash, the built-in item table and apples spawned on the tick. Their steering is
+// recorded tick by tick so a leaderboard can replay the run from its seed with verify_replay and
+// reject a score the inputs couldn't have made. Finished runs are saved under replays/ and queued
+// in submissions.jsonl for whatever uploads them
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::body_path::{BodyCells, BodyPath};
+use crate::direction::Direction;
+use crate::game_state::{render_ascii, AppleState, GameState, SnakeState};
+use crate::items::{pick_weighted, AppleDefinition, ItemEffect, ItemTable};
+use crate::occupancy::Occupant;
+use crate::playfield::{PlayfieldMask, PlayfieldShape};
+use crate::profile::Profile;
+use crate::replay_format::{Replay, ReplayMode};
+use crate::score::Score;
+use crate::seed::RunSeed;
+use crate::snake_layout::SnakeLayout;
+use crate::storage::{decode_base64, encode_base64, StoragePaths};
+use crate::{
+    get_valid_apple_spawn, move_snake, Apple, GameOver, Player, SnakeHead, SnakeSystemSet,
+    START_LENGTH,
+};
+
+const SUBMISSIONS_FILE: &str = "submissions.jsonl";
+// the direction steered toward from this tick on, counted from 1
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u32,
+    pub direction: Direction,
+}
+
+// what a leaderboard gets, it replays the inputs to check the score
+#[derive(Serialize, Deserialize)]
+pub struct ScoreSubmission {
+    pub player: String,
+    pub score: u32,
+    pub replay: String, // the replay file in base64
+}
+
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    tick: u32,
+    inputs: Vec<ReplayInput>,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if !Args::of(app).ranked() {
+            return;
+        }
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(
+                FixedUpdate,
+                record_inputs
+                    .in_set(SnakeSystemSet::Movement)
+                    .before(move_snake),
+            )
+            .add_systems(Update, submit_score);
+    }
+}
+
+// what move_snake is about to steer by this tick
+fn record_inputs(
+    mut recorder: ResMut<ReplayRecorder>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+) {
+    recorder.tick += 1;
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let direction = snake_head.potential_direction;
+    let last = recorder
+        .inputs
+        .last()
+        .map_or(Direction::Right, |input| input.direction);
+    if direction != last {
+        let tick = recorder.tick;
+        recorder.inputs.push(ReplayInput { tick, direction });
+    }
+}
+
+fn submit_score(
+    mut game_over_event: EventReader<GameOver>,
+    recorder: Res<ReplayRecorder>,
+    score: Res<Score>,
+    seed: Res<RunSeed>,
+    profile: Option<Res<Profile>>,
+    mut submitted: Local<bool>,
+) {
+    if game_over_event.read().count() == 0 || std::mem::replace(&mut *submitted, true) {
+        return;
+    }
+    // the same check a leaderboard makes, a mismatch here is a bug in the game
+    let verified = verify_replay(seed.0, &recorder.inputs);
+    if verified.0 != score.0 {
+        warn!(
+            score = score.0,
+            verified = verified.0,
+            "The replay doesn't reproduce the score"
+        );
+    }
+    let replay = Replay {
+        seed: seed.0,
+        mode: ReplayMode::Classic,
+        mutators: Vec::new(),
+        inputs: recorder.inputs.clone(),
+    };
+    let replay = match replay.to_bytes() {
+        Ok(replay) => replay,
+        Err(error) => {
+            warn!("Could not save the replay: {error}");
+            return;
+        }
+    };
+    let file = format!("replays/{}.replay", seed.0);
+    if let Err(error) = StoragePaths::get().write_bytes(&file, &replay) {
+        warn!("Could not save the replay: {error}");
+    }
+    let submission = ScoreSubmission {
+        player: profile.map_or_else(String::new, |profile| profile.name.clone()),
+        score: score.0,
+        replay: encode_base64(&replay),
+    };
+    let result = serde_json::to_string(&submission)
+        .map_err(|error| error.to_string())
+        .and_then(|line| {
+            StoragePaths::get()
+                .append_line(SUBMISSIONS_FILE, &line)
+                .map_err(|error| error.to_string())
+        });
+    match result {
+        Ok(()) => info!(
+            score = score.0,
+            bytes = replay.len(),
+            "Ranked run queued for submission"
+        ),
+        Err(error) => warn!("Could not queue the ranked run: {error}"),
+    }
+}
+
+// --verify-replay <file> plays back a replay file, printing the score it makes, or every run in
+// a submissions file, printing whether each claimed score holds up
+pub fn verify_from_args(args: &Args) -> Option<Result<String, String>> {
+    let Some(path) = args.values("--verify-replay")?.first().copied() else {
+        return Some(Err("--verify-replay expects a file".to_string()));
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => return Some(Err(format!("Could not read {path}: {error}"))),
+    };
+    if Replay::from_bytes(&bytes).is_ok() {
+        return Some(
+            read_replay_file(path)
+                .map(|replay| verify_replay(replay.seed, &replay.inputs).0.to_string()),
+        );
+    }
+    let Ok(text) = String::from_utf8(bytes) else {
+        return Some(Err(format!("{path} is neither a replay nor submissions")));
+    };
+    let mut report = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let verdict = serde_json::from_str::<ScoreSubmission>(line)
+            .map_err(|error| error.to_string())
+            .and_then(|submission| {
+                let replay = decode_base64(&submission.replay)
+                    .ok_or_else(|| "the replay is not base64".to_string())
+                    .and_then(|bytes| {
+                        Replay::from_bytes(&bytes).map_err(|error| error.to_string())
+                    })?;
+                let verified = verify_ranked(&replay)?;
+                if verified.0 == submission.score {
+                    return Ok(format!(
+                        "{} claimed {}, replays to {}: accepted",
+                        submission.player, submission.score, verified.0
+                    ));
+                }
+                // where the replayed run ended, to see why it fell short
+                let mut sim = ReplaySim::new(replay.seed, replay.inputs);
+                while sim.step() {}
+                Ok(format!(
+                    "{} claimed {}, replays to {}: rejected, ended on tick {}\n{}",
+                    submission.player,
+                    submission.score,
+                    verified.0,
+                    sim.tick,
+                    render_ascii(&sim.state())
+                ))
+            });
+        report.push(match verdict {
+            Ok(verdict) => verdict,
+            Err(error) => format!("line {}: {error}", line_number + 1),
+        });
+    }
+    Some(Ok(report.join("\n")))
+}
+
+// a replay of a ranked run, the only rules that can be played back
+pub fn read_replay_file(path: &str) -> Result<Replay, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("Could not read {path}: {error}"))?;
+    let replay = Replay::from_bytes(&bytes).map_err(|error| format!("{path}: {error}"))?;
+    verify_ranked(&replay).map_err(|error| format!("{path}: {error}"))?;
+    Ok(replay)
+}
+
+fn verify_ranked(replay: &Replay) -> Result<Score, String> {
+    if replay.mode != ReplayMode::Classic || !replay.mutators.is_empty() {
+        return Err("not a ranked run".to_string());
+    }
+    Ok(verify_replay(replay.seed, &replay.inputs))
+}
+
+// plays a ranked run from its seed and inputs without the game, until the snake dies
+pub fn verify_replay(seed: u64, inputs: &[ReplayInput]) -> Score {
+    let mut sim = ReplaySim::new(seed, inputs.to_vec());
+    while sim.step() {}
+    Score(sim.score)
+}
+
+// a ranked run played a tick at a time, as move_snake, grow_snake_body, place_stones and
+// spawn_apple would, with the body kept as a path unless told otherwise
+pub struct ReplaySim<B = BodyPath> {
+    seed: u64,
+    rng: StdRng,
+    pub playfield_mask: PlayfieldMask,
+    item_table: ItemTable,
+    head: SnakeHead,
+    body: B,
+    pub apple: Option<(Vec<(i32, i32)>, AppleDefinition)>, // footprint, kind
+    pending_stones: Vec<(i32, i32)>,
+    pending_growth: u32,
+    pub score: u32,
+    inputs: Vec<ReplayInput>,
+    next_input: usize,
+    pub tick: u32,
+    max_ticks: u32,
+    dead: bool,
+}
+
+impl ReplaySim {
+    pub fn new(seed: u64, inputs: Vec<ReplayInput>) -> Self {
+        ReplaySim::new_with_body_cells(seed, inputs)
+    }
+}
+
+impl<B: BodyCells> ReplaySim<B> {
+    pub fn new_with_body_cells(seed: u64, inputs: Vec<ReplayInput>) -> Self {
+        let playfield_mask = PlayfieldMask::new(PlayfieldShape::Rectangle);
+        let layout =
+            SnakeLayout::starting(Player::ONE, START_LENGTH, playfield_mask.start_column());
+        // past the last input the snake goes straight, into a wall sooner or later
+        let last_tick = inputs.last().map_or(0, |input| input.tick);
+        ReplaySim {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            playfield_mask,
+            item_table: ItemTable::default(),
+            head: SnakeHead::new(layout.cells()[0], Vec::new(), layout.cells()[0]),
+            body: B::from_cells(layout.cells()[1..].iter().copied()),
+            apple: None,
+            pending_stones: Vec::new(),
+            pending_growth: 0,
+            score: 0,
+            inputs,
+            next_input: 0,
+            tick: 0,
+            max_ticks: last_tick + crate::PLAYFIELD.0.max(crate::PLAYFIELD.1) as u32 + 1,
+            dead: false,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.dead || self.tick >= self.max_ticks
+    }
+
+    // for a board steered live instead of from recorded inputs, which would stop it soon after
+    pub fn with_tick_limit(mut self, max_ticks: u32) -> Self {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    // turns on the next step, as a key press would
+    pub fn steer(&mut self, direction: Direction) {
+        self.head.potential_direction = direction;
+    }
+
+    pub fn head(&self) -> &SnakeHead {
+        &self.head
+    }
+
+    pub fn apple(&self) -> Option<Apple> {
+        self.apple.as_ref().map(|(footprint, definition)| Apple {
+            footprint: footprint.clone(),
+            color: definition.color,
+            growth: definition.growth,
+            points: definition.points,
+            effects: definition.effects.clone(),
+        })
+    }
+
+    // head first
+    pub fn snake(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        std::iter::once(self.head.position).chain(self.body.iter())
+    }
+
+    // for the occupancy grid, laid over the walls in this order
+    pub fn occupants(&self) -> impl Iterator<Item = ((i32, i32), Occupant)> + '_ {
+        let apple = self
+            .apple
+            .iter()
+            .flat_map(|(footprint, _)| footprint.iter());
+        apple
+            .map(|position| (*position, Occupant::Apple))
+            .chain(
+                self.body
+                    .iter()
+                    .map(|position| (position, Occupant::SnakeBody)),
+            )
+            .chain(std::iter::once((self.head.position, Occupant::SnakeHead)))
+    }
+
+    // head included
+    pub fn length(&self) -> usize {
+        1 + self.body.len()
+    }
+
+    pub fn state(&self) -> GameState {
+        let mut walls: Vec<(i32, i32)> = self.playfield_mask.walls().collect();
+        walls.sort();
+        GameState {
+            seed: self.seed,
+            score: self.score,
+            half_extents: self.playfield_mask.half_extents(),
+            scroll: self.playfield_mask.scroll(),
+            wrap: self.playfield_mask.wrap,
+            walls,
+            snakes: vec![SnakeState {
+                player: Player::ONE.0,
+                direction: self.head.direction,
+                potential_direction: self.head.potential_direction,
+                cells: self.snake().collect(),
+                path: vec![self.head.position],
+            }],
+            apples: self
+                .apple
+                .iter()
+                .map(|(footprint, definition)| AppleState {
+                    footprint: footprint.clone(),
+                    points: definition.points,
+                    growth: definition.growth,
+                })
+                .collect(),
+            power_ups: Vec::new(),
+        }
+    }
+
+    // plays the next tick, false once the run is over
+    pub fn step(&mut self) -> bool {
+        if self.finished() {
+            return false;
+        }
+        self.tick += 1;
+        // an eaten apple's despawn only applies at the end of the tick, so spawn_apple waits a tick
+        let had_apple = self.apple.is_some();
+        let head = &mut self.head;
+        while let Some(input) = self
+            .inputs
+            .get(self.next_input)
+            .filter(|input| input.tick <= self.tick)
+        {
+            head.potential_direction = input.direction;
+            self.next_input += 1;
+        }
+        if head.potential_direction != head.direction.opposite() {
+            head.direction = head.potential_direction;
+        }
+        let next_position = head.direction.step(head.position);
+        let previous = std::mem::replace(&mut head.position, next_position);
+        self.body.push_front(previous);
+        head.tail_position = self.body.pop_back().unwrap_or(previous);
+
+        if let Some((_, definition)) = self
+            .apple
+            .take_if(|(footprint, _)| footprint.contains(&head.position))
+        {
+            self.score += definition.points;
+            self.pending_growth += definition.growth;
+            if definition.effects.contains(&ItemEffect::Stone) {
+                self.pending_stones.push(head.position);
+            }
+        }
+        if self.pending_growth > 0 {
+            self.pending_growth -= 1;
+            self.body.push_back(head.tail_position);
+        }
+        // as stone.rs places them, once the snake is off the cell
+        let body = &self.body;
+        let playfield_mask = &mut self.playfield_mask;
+        self.pending_stones.retain(|position| {
+            let occupied = *position == head.position || body.contains(*position);
+            if !occupied {
+                playfield_mask.add_wall(*position);
+            }
+            occupied
+        });
+        if !had_apple {
+            if let Some(definition) =
+                pick_weighted(&mut self.rng, &self.item_table.apples, |definition| {
+                    definition.spawn_weight
+                })
+            {
+                let footprint = get_valid_apple_spawn(
+                    &mut self.rng,
+                    &self.playfield_mask,
+                    self.body.iter().collect(),
+                    [&self.head],
+                    definition.size_on(&self.playfield_mask),
+                    None,
+                );
+                self.apple = footprint.map(|footprint| (footprint, definition.clone()));
+            }
+        }
+
+        self.dead = self.playfield_mask.is_wall(self.head.position)
+            || self.body.contains(self.head.position);
+        !self.finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(tick: u32, direction: Direction) -> ReplayInput {
+        ReplayInput { tick, direction }
+    }
+
+    // steered after the apples on seed 7, then straight on into the wall
+    fn chase() -> Vec<ReplayInput> {
+        vec![
+            input(2, Direction::Down),
+            input(16, Direction::Left),
+            input(23, Direction::Up),
+            input(41, Direction::Right),
+            input(52, Direction::Up),
+        ]
+    }
+
+    #[test]
+    fn straight_into_the_wall_scores_nothing() {
+        let mut sim = ReplaySim::new(0, Vec::new());
+        while sim.step() {}
+        assert!(sim.playfield_mask.is_wall(sim.head().position));
+        assert_eq!(verify_replay(0, &[]).0, 0);
+    }
+
+    #[test]
+    fn replays_a_pinned_score() {
+        assert_eq!(verify_replay(7, &chase()).0, 2);
+    }
+
+    #[test]
+    fn tampered_inputs_score_differently() {
+        let mut inputs = chase();
+        inputs[1].direction = Direction::Right;
+        assert_ne!(verify_replay(7, &inputs).0, 2);
+        let mut inputs = chase();
+        inputs[0].tick = 3;
+        assert_ne!(verify_replay(7, &inputs).0, 2);
+    }
+}
@@ -0,0 +1,231 @@
+// Replay files
+// A recorded run as a few bytes: the "SNKR" magic, a format version, the seed, the movement mode,
+// the mutators it was played with and the inputs as LEB128 tick deltas. Readers take every
+// version written so far, a later version only appends fields, so the rules a run was played
+// under stay readable however the game changes
+use thiserror::Error;
+
+use crate::direction::Direction;
+use crate::replay::ReplayInput;
+
+const MAGIC: &[u8; 4] = b"SNKR";
+pub const REPLAY_VERSION: u8 = 1;
+
+// the movement rules, numbered as stored
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayMode {
+    Classic = 0,
+    IceRink = 1,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: ReplayMode,
+    pub mutators: Vec<String>, // rule flags on for the run, e.g. "--hunger"
+    pub inputs: Vec<ReplayInput>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayFormatError {
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error("replay version {0} is newer than this game")]
+    NewerVersion(u8),
+    #[error("the replay ends early")]
+    Truncated,
+    #[error("unknown movement mode {0}")]
+    UnknownMode(u8),
+    #[error("the replay is damaged")]
+    Corrupt,
+    #[error("input at tick {0} comes before the one at tick {1}")]
+    OutOfOrder(u32, u32),
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayFormatError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(REPLAY_VERSION);
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.push(self.mode as u8);
+        write_varint(&mut bytes, self.mutators.len() as u64);
+        for mutator in &self.mutators {
+            write_varint(&mut bytes, mutator.len() as u64);
+            bytes.extend(mutator.as_bytes());
+        }
+        write_varint(&mut bytes, self.inputs.len() as u64);
+        let mut last_tick = 0;
+        for input in &self.inputs {
+            let direction = Direction::ALL
+                .iter()
+                .position(|direction| *direction == input.direction)
+                .unwrap_or(0) as u64;
+            let delta = input
+                .tick
+                .checked_sub(last_tick)
+                .ok_or(ReplayFormatError::OutOfOrder(input.tick, last_tick))?;
+            write_varint(&mut bytes, (u64::from(delta) << 2) | direction);
+            last_tick = input.tick;
+        }
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayFormatError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayFormatError::NotAReplay);
+        }
+        let version = reader.byte()?;
+        if version > REPLAY_VERSION {
+            return Err(ReplayFormatError::NewerVersion(version));
+        }
+        // version 1 fields, in every version
+        let seed = u64::from_le_bytes(
+            reader
+                .take(8)?
+                .try_into()
+                .map_err(|_| ReplayFormatError::Truncated)?,
+        );
+        let mode = match reader.byte()? {
+            0 => ReplayMode::Classic,
+            1 => ReplayMode::IceRink,
+            mode => return Err(ReplayFormatError::UnknownMode(mode)),
+        };
+        let mutator_count = reader.varint()?;
+        let mut mutators = Vec::new();
+        for _ in 0..mutator_count {
+            let len = reader.varint()? as usize;
+            let mutator =
+                std::str::from_utf8(reader.take(len)?).map_err(|_| ReplayFormatError::Corrupt)?;
+            mutators.push(mutator.to_string());
+        }
+        let input_count = reader.varint()?;
+        let mut inputs = Vec::new();
+        let mut tick = 0u32;
+        for _ in 0..input_count {
+            let value = reader.varint()?;
+            tick = u32::try_from(value >> 2)
+                .ok()
+                .and_then(|delta| tick.checked_add(delta))
+                .ok_or(ReplayFormatError::Corrupt)?;
+            inputs.push(ReplayInput {
+                tick,
+                direction: Direction::ALL[(value & 3) as usize],
+            });
+        }
+        Ok(Replay {
+            seed,
+            mode,
+            mutators,
+            inputs,
+        })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReplayFormatError> {
+        if self.bytes.len() < len {
+            return Err(ReplayFormatError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, ReplayFormatError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, ReplayFormatError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayFormatError::Corrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(tick: u32, direction: Direction) -> ReplayInput {
+        ReplayInput { tick, direction }
+    }
+
+    #[test]
+    fn round_trips() {
+        let replay = Replay {
+            seed: 0xdead_beef_1234,
+            mode: ReplayMode::IceRink,
+            mutators: vec!["--hunger".to_string(), "--ice-rink".to_string()],
+            inputs: vec![
+                input(0, Direction::Up),
+                input(3, Direction::Left),
+                input(3, Direction::Down),
+                input(40_000, Direction::Right),
+            ],
+        };
+        let bytes = replay.to_bytes().unwrap();
+        assert_eq!(Replay::from_bytes(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn reads_version_1() {
+        // written by the first release of the format, kept byte for byte
+        let mut bytes = b"SNKR".to_vec();
+        bytes.push(1);
+        bytes.extend(42u64.to_le_bytes());
+        bytes.push(0);
+        bytes.extend([1, 8]);
+        bytes.extend(b"--hunger");
+        bytes.extend([2, 5 << 2 | 2, 0x80 | 0x03, 0x01]);
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            replay,
+            Replay {
+                seed: 42,
+                mode: ReplayMode::Classic,
+                mutators: vec!["--hunger".to_string()],
+                inputs: vec![
+                    input(5, Direction::ALL[2]),
+                    input(5 + 32, Direction::ALL[3])
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn refuses_inputs_out_of_order() {
+        let replay = Replay {
+            seed: 1,
+            mode: ReplayMode::Classic,
+            mutators: Vec::new(),
+            inputs: vec![input(9, Direction::Up), input(4, Direction::Down)],
+        };
+        assert!(matches!(
+            replay.to_bytes(),
+            Err(ReplayFormatError::OutOfOrder(4, 9))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+// Replay rendering
+// --render-replay <file> <output> draws a ranked replay without a window, one frame per tick: into
+// <output> as numbered PNGs, or to stdout as raw RGBA frames when <output> is "-", ready to pipe
+// into ffmpeg. --resolution <width>x<height> sets the frame size, the board is centered in it
+use std::io::Write;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::args::Args;
+use crate::replay::{read_replay_file, ReplaySim};
+use crate::ron_asset::read_ron_file;
+use crate::theme::{Theme, DEFAULT_THEME_PATH};
+use crate::{PLAYFIELD, TICKRATE};
+
+const DEFAULT_RESOLUTION: (u32, u32) = (1280, 720);
+const LETTERBOX_COLOR: Color = Color::BLACK;
+const WALL_COLOR: Color = Color::BLACK;
+
+// Some when asked for, with what to tell the user once it's done
+pub fn render_from_args(args: &Args) -> Option<Result<String, String>> {
+    let values = args.values("--render-replay")?;
+    let (Some(path), Some(output)) = (values.first(), values.get(1)) else {
+        return Some(Err(
+            "--render-replay expects a replay file and an output directory or -".to_string(),
+        ));
+    };
+    let resolution = match args.values("--resolution") {
+        Some(values) => match values.first().and_then(|value| parse_resolution(value)) {
+            Some(resolution) => resolution,
+            None => return Some(Err("--resolution expects <width>x<height>".to_string())),
+        },
+        None => DEFAULT_RESOLUTION,
+    };
+    Some(read_replay_file(path).and_then(|replay| {
+        let sim = ReplaySim::new(replay.seed, replay.inputs);
+        render(sim, output, resolution)
+    }))
+}
+
+fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    let resolution = (width.parse().ok()?, height.parse().ok()?);
+    (resolution.0 > 0 && resolution.1 > 0).then_some(resolution)
+}
+
+fn render(mut sim: ReplaySim, output: &str, resolution: (u32, u32)) -> Result<String, String> {
+    // the shipped theme, the game's built-in one when it can't be read
+    let theme: Theme = read_ron_file(format!("assets/{DEFAULT_THEME_PATH}")).unwrap_or_default();
+    let to_stdout = output == "-";
+    if !to_stdout {
+        std::fs::create_dir_all(output)
+            .map_err(|error| format!("Could not create {output}: {error}"))?;
+    }
+    let mut stdout = std::io::stdout().lock();
+    let mut frames = 0;
+    loop {
+        let pixels = draw_frame(&sim, &theme, resolution);
+        if to_stdout {
+            stdout
+                .write_all(&pixels)
+                .map_err(|error| format!("Could not write a frame: {error}"))?;
+        } else {
+            let path = Path::new(output).join(format!("frame{frames:06}.png"));
+            save_png(pixels, resolution, &path)?;
+        }
+        frames += 1;
+        if !sim.step() {
+            break;
+        }
+    }
+    let (width, height) = resolution;
+    let framerate = 1.0 / TICKRATE;
+    Ok(if to_stdout {
+        format!(
+            "Wrote {frames} frames, encode with: ffmpeg -f rawvideo -pix_fmt rgba -s {width}x{height} -framerate {framerate} -i - run.mp4"
+        )
+    } else {
+        format!(
+            "Wrote {frames} frames to {output}, encode with: ffmpeg -framerate {framerate} -i {output}/frame%06d.png run.mp4"
+        )
+    })
+}
+
+// RGBA rows from the top, cells as square blocks as large as the resolution allows
+fn draw_frame(sim: &ReplaySim, theme: &Theme, (width, height): (u32, u32)) -> Vec<u8> {
+    let mut pixels = LETTERBOX_COLOR
+        .as_rgba_u8()
+        .repeat((width * height) as usize);
+    let cell = (width / PLAYFIELD.0 as u32)
+        .min(height / PLAYFIELD.1 as u32)
+        .max(1);
+    let left = (width as i64 - (cell * PLAYFIELD.0 as u32) as i64) / 2;
+    let top = (height as i64 - (cell * PLAYFIELD.1 as u32) as i64) / 2;
+    let mut fill = |(x, y): (i32, i32), color: Color| {
+        // a head that died in the border isn't drawn over the letterbox
+        if sim.playfield_mask.is_off_board((x, y)) {
+            return;
+        }
+        let column = (x + PLAYFIELD.0 / 2) as i64;
+        let row = (PLAYFIELD.1 / 2 - y) as i64; // board y points up
+        let rgba = color.as_rgba_u8();
+        for pixel_y in top + row * cell as i64..top + (row + 1) * cell as i64 {
+            for pixel_x in left + column * cell as i64..left + (column + 1) * cell as i64 {
+                if (0..width as i64).contains(&pixel_x) && (0..height as i64).contains(&pixel_y) {
+                    let offset = (pixel_y as usize * width as usize + pixel_x as usize) * 4;
+                    pixels[offset..offset + 4].copy_from_slice(&rgba);
+                }
+            }
+        }
+    };
+    for x in -PLAYFIELD.0 / 2..=PLAYFIELD.0 / 2 {
+        for y in -PLAYFIELD.1 / 2..=PLAYFIELD.1 / 2 {
+            fill((x, y), theme.background);
+        }
+    }
+    for position in sim.playfield_mask.walls() {
+        fill(position, WALL_COLOR);
+    }
+    if let Some((footprint, definition)) = &sim.apple {
+        for position in footprint {
+            fill(*position, definition.color.unwrap_or(theme.apple));
+        }
+    }
+    for (index, position) in sim.snake().enumerate() {
+        let color = if index == 0 {
+            theme.snake_head
+        } else {
+            theme.snake_body
+        };
+        fill(position, color);
+    }
+    pixels
+}
+
+fn save_png(pixels: Vec<u8>, (width, height): (u32, u32), path: &Path) -> Result<(), String> {
+    let image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image
+        .try_into_dynamic()
+        .map_err(|error| error.to_string())?
+        .save(path)
+        .map_err(|error| format!("Could not save {}: {error}", path.display()))
+}
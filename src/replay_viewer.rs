@@ -0,0 +1,296 @@
+// Replay viewer
+// --replay <file> plays back a ranked run on its own, without a game. Space pauses, 1-4 pick 0.5x,
+// 1x, 2x or 4x speed, the arrow keys step a tick (ten with Shift), and clicking or dragging along
+// the timeline jumps to any tick. Going back plays the run again from its seed up to there
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::args::Args;
+use crate::replay::{read_replay_file, ReplaySim};
+use crate::replay_format::Replay;
+use crate::theme::Theme;
+use crate::{PIXEL_UNIT_SIZE, PLAYFIELD, TICKRATE};
+
+const SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+const SHIFT_STEP_TICKS: u32 = 10;
+const TIMELINE_WIDTH: f32 = 480.0;
+const TIMELINE_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const TIMELINE_FILL_COLOR: Color = Color::GOLD;
+
+// --replay <file>, an error when it can't be played
+pub fn replay_from_args(args: &Args) -> Option<Result<Replay, String>> {
+    Some(match args.values("--replay")?.first() {
+        Some(path) => read_replay_file(path),
+        None => Err("--replay expects a replay file".to_string()),
+    })
+}
+
+#[derive(Resource)]
+struct Playback {
+    replay: Replay,
+    sim: ReplaySim,
+    length: u32, // ticks until the snake dies
+    paused: bool,
+    speed: f32,
+    elapsed: f32, // toward the next tick, in seconds of playback
+}
+
+impl Playback {
+    // forward plays on, backward starts over from the seed
+    fn seek(&mut self, tick: u32) {
+        let tick = tick.min(self.length);
+        if tick < self.sim.tick {
+            self.sim = ReplaySim::new(self.replay.seed, self.replay.inputs.clone());
+        }
+        while self.sim.tick < tick && self.sim.step() {}
+        self.elapsed = 0.0;
+    }
+}
+
+#[derive(Component)]
+struct ReplayCell;
+
+#[derive(Component)]
+struct Timeline;
+
+#[derive(Component)]
+struct TimelineFill;
+
+#[derive(Component)]
+struct PlaybackText;
+
+pub struct ReplayViewerPlugin {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayViewerPlugin {
+    fn build(&self, app: &mut App) {
+        let mut sim = ReplaySim::new(self.replay.seed, self.replay.inputs.clone());
+        while sim.step() {}
+        info!(
+            seed = self.replay.seed,
+            ticks = sim.tick,
+            score = sim.score,
+            "Playing replay"
+        );
+        app.insert_resource(Playback {
+            sim: ReplaySim::new(self.replay.seed, self.replay.inputs.clone()),
+            replay: self.replay.clone(),
+            length: sim.tick,
+            paused: false,
+            speed: 1.0,
+            elapsed: 0.0,
+        })
+        .init_resource::<Theme>()
+        .add_systems(Startup, setup_viewer)
+        .add_systems(
+            Update,
+            (
+                control_playback,
+                scrub_timeline,
+                advance_playback,
+                (draw_board, update_timeline).run_if(resource_changed::<Playback>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn setup_viewer(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                PlaybackText,
+            ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(TIMELINE_WIDTH),
+                            height: Val::Px(14.0),
+                            ..default()
+                        },
+                        background_color: TIMELINE_COLOR.into(),
+                        ..default()
+                    },
+                    Interaction::None,
+                    RelativeCursorPosition::default(),
+                    Timeline,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: TIMELINE_FILL_COLOR.into(),
+                            ..default()
+                        },
+                        TimelineFill,
+                    ));
+                });
+        });
+}
+
+fn control_playback(keyboard_input: Res<Input<KeyCode>>, mut playback: ResMut<Playback>) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        // at the end, playing again starts over
+        if playback.paused && playback.sim.tick >= playback.length {
+            playback.seek(0);
+        }
+        playback.paused = !playback.paused;
+    }
+    let speed_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+    for (key, speed) in speed_keys.into_iter().zip(SPEEDS) {
+        if keyboard_input.just_pressed(key) {
+            playback.speed = speed;
+        }
+    }
+    let step = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        SHIFT_STEP_TICKS
+    } else {
+        1
+    };
+    let tick = playback.sim.tick;
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        playback.paused = true;
+        playback.seek(tick + step);
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        playback.paused = true;
+        playback.seek(tick.saturating_sub(step));
+    }
+}
+
+// held down, the timeline follows the cursor
+fn scrub_timeline(
+    mut playback: ResMut<Playback>,
+    timeline_query: Query<(&Interaction, &RelativeCursorPosition), With<Timeline>>,
+) {
+    for (interaction, cursor) in &timeline_query {
+        let (Interaction::Pressed, Some(position)) = (interaction, cursor.normalized) else {
+            continue;
+        };
+        let tick = (position.x.clamp(0.0, 1.0) * playback.length as f32).round() as u32;
+        if tick != playback.sim.tick {
+            playback.seek(tick);
+        }
+    }
+}
+
+fn advance_playback(time: Res<Time>, mut playback: ResMut<Playback>) {
+    if playback.paused {
+        return;
+    }
+    // only a new tick counts as a change, for the redraw
+    let elapsed = playback.elapsed + time.delta_seconds() * playback.speed;
+    let ticks = (elapsed / TICKRATE as f32) as u32;
+    playback.bypass_change_detection().elapsed = elapsed - ticks as f32 * TICKRATE as f32;
+    for _ in 0..ticks {
+        if !playback.sim.step() {
+            playback.paused = true;
+            break;
+        }
+    }
+}
+
+// redrawn from scratch whenever the tick changes
+fn draw_board(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    theme: Res<Theme>,
+    cell_query: Query<Entity, With<ReplayCell>>,
+) {
+    for entity in &cell_query {
+        commands.entity(entity).despawn();
+    }
+    let mut spawn_cell = |position: (i32, i32), size: Vec2, color: Color, z: f32| {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    position.0 as f32 * PIXEL_UNIT_SIZE,
+                    position.1 as f32 * PIXEL_UNIT_SIZE,
+                    z,
+                )),
+                ..default()
+            },
+            ReplayCell,
+        ));
+    };
+    let cell = Vec2::splat(PIXEL_UNIT_SIZE);
+    let board = Vec2::new(
+        PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE,
+        PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE,
+    );
+    let sim = &playback.sim;
+    spawn_cell((0, 0), board, theme.background, -0.1);
+    for position in sim.playfield_mask.walls() {
+        spawn_cell(position, cell, Color::BLACK, -0.05);
+    }
+    if let Some((footprint, definition)) = &sim.apple {
+        for position in footprint {
+            spawn_cell(
+                *position,
+                cell,
+                definition.color.unwrap_or(theme.apple),
+                0.0,
+            );
+        }
+    }
+    for (index, position) in sim.snake().enumerate() {
+        let color = if index == 0 {
+            theme.snake_head
+        } else {
+            theme.snake_body
+        };
+        spawn_cell(position, cell, color, 0.0);
+    }
+}
+
+fn update_timeline(
+    playback: Res<Playback>,
+    mut fill_query: Query<&mut Style, With<TimelineFill>>,
+    mut text_query: Query<&mut Text, With<PlaybackText>>,
+) {
+    let progress = playback.sim.tick as f32 / playback.length.max(1) as f32;
+    for mut style in &mut fill_query {
+        style.width = Val::Percent(progress * 100.0);
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = format!(
+            "Tick {} / {} - score {} - {}x{}",
+            playback.sim.tick,
+            playback.length,
+            playback.sim.score,
+            playback.speed,
+            if playback.paused { " - paused" } else { "" }
+        );
+    }
+}
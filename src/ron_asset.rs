@@ -0,0 +1,75 @@
+// RON assets
+// Loader for any asset type that can be deserialized from a RON file, plus a plain file helper for
+// files shipped next to the game, saved data goes through storage.rs
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RonAssetError {
+    #[error("could not access file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("could not write RON: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+const ASSET_DIRECTORY: &str = "assets"; // AssetPlugin's default
+
+// a file under assets/, looked up where the asset server finds it instead of in the working
+// directory, which a launcher or an installed game can have anywhere
+pub fn asset_file_path(path: impl AsRef<Path>) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join(ASSET_DIRECTORY)
+        .join(path)
+}
+
+pub fn read_ron_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, RonAssetError> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+pub struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        RonAssetLoader {
+            extensions,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, RonAssetError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -0,0 +1,175 @@
+// Route
+// Every cell the head entered during the run, recorded per tick, the analytics derived from it,
+// and with --show-path a polyline of it over the board after game over that fades toward the start
+use bevy::prelude::*;
+
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::playfield::PlayfieldMask;
+use crate::{Apple, AppleEaten, GameOver, Player, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const PATH_COLOR: Color = Color::CYAN;
+const OLDEST_ALPHA: f32 = 0.1;
+const NEWEST_ALPHA: f32 = 0.9;
+
+// player one's head in order, a respawn shows up as a jump between cells
+#[derive(Resource, Default)]
+pub struct RunRoute {
+    pub cells: Vec<(i32, i32)>,
+    legs: Vec<AppleLeg>,
+    leg_start: Option<(usize, (i32, i32))>, // route length and head cell when the apple appeared
+}
+
+// the way from an apple appearing to player one eating it
+struct AppleLeg {
+    travelled: usize,
+    optimal: u32, // grid distance from where the head was, around the edges on a wrapping board
+}
+
+pub struct RouteAnalytics {
+    pub apples_per_minute: f32,
+    pub cells_per_apple: f32,
+    pub optimal_cells_per_apple: f32,
+    pub turns: u32,
+    pub longest_straight: u32, // cells
+}
+
+impl RunRoute {
+    pub fn analytics(
+        &self,
+        elapsed_seconds: f32,
+        playfield_mask: &PlayfieldMask,
+    ) -> RouteAnalytics {
+        let apples = self.legs.len() as f32;
+        let per_apple = |total: f32| if apples > 0.0 { total / apples } else { 0.0 };
+
+        let mut turns = 0;
+        let mut longest_straight = 0;
+        let mut straight = 0;
+        let mut previous_step = None;
+        for pair in self.cells.windows(2) {
+            // a step across the edge of a wrapping board is still a move
+            let step = Direction::ALL
+                .into_iter()
+                .find(|direction| playfield_mask.wrapped(direction.step(pair[0])) == pair[1]);
+            let Some(step) = step else {
+                // a respawn, not a move
+                previous_step = None;
+                straight = 0;
+                continue;
+            };
+            if previous_step.is_some_and(|previous| previous != step) {
+                turns += 1;
+                straight = 0;
+            }
+            straight += 1;
+            longest_straight = longest_straight.max(straight);
+            previous_step = Some(step);
+        }
+
+        RouteAnalytics {
+            apples_per_minute: if elapsed_seconds > 0.0 {
+                apples / elapsed_seconds * 60.0
+            } else {
+                0.0
+            },
+            cells_per_apple: per_apple(self.legs.iter().map(|leg| leg.travelled as f32).sum()),
+            optimal_cells_per_apple: per_apple(
+                self.legs.iter().map(|leg| leg.optimal as f32).sum(),
+            ),
+            turns,
+            longest_straight,
+        }
+    }
+}
+
+// present once the path overlay is up
+#[derive(Resource)]
+struct PathShown;
+
+pub struct RoutePlugin;
+
+impl Plugin for RoutePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunRoute>().add_systems(
+            FixedUpdate,
+            (record_route, record_apple_legs)
+                .chain()
+                .after(SnakeSystemSet::Movement),
+        );
+        if Args::of(app).has("--show-path") {
+            app.add_systems(
+                Update,
+                (show_path, draw_path.run_if(resource_exists::<PathShown>())),
+            );
+        }
+    }
+}
+
+fn record_route(mut route: ResMut<RunRoute>, snake_head_query: Query<(&Player, &SnakeHead)>) {
+    for (_, snake_head) in snake_head_query
+        .iter()
+        .filter(|(player, _)| **player == Player::ONE)
+    {
+        route.cells.extend_from_slice(&snake_head.path);
+    }
+}
+
+fn record_apple_legs(
+    mut route: ResMut<RunRoute>,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    playfield_mask: Res<PlayfieldMask>,
+    new_apple_query: Query<(), Added<Apple>>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+) {
+    for event in apple_eaten_event.read() {
+        if event.player != Player::ONE {
+            continue;
+        }
+        if let Some((start_index, start)) = route.leg_start.take() {
+            let leg = AppleLeg {
+                travelled: route.cells.len() - start_index,
+                optimal: playfield_mask.distance(start, event.position),
+            };
+            route.legs.push(leg);
+        }
+    }
+    if !new_apple_query.is_empty() {
+        let head = snake_head_query
+            .iter()
+            .find(|(player, _)| **player == Player::ONE)
+            .map(|(_, snake_head)| snake_head.position);
+        route.leg_start = head.map(|head| (route.cells.len(), head));
+    }
+}
+
+fn show_path(mut commands: Commands, mut game_over_event: EventReader<GameOver>) {
+    if game_over_event.read().count() > 0 {
+        commands.insert_resource(PathShown);
+    }
+}
+
+// gizmos only last a frame, so this redraws every frame
+fn draw_path(route: Res<RunRoute>, mut gizmos: Gizmos) {
+    let count = route.cells.len().max(2) as f32;
+    let point = |index: usize, cell: (i32, i32)| {
+        let age = index as f32 / (count - 1.0);
+        (
+            Vec2::new(cell.0 as f32, cell.1 as f32) * PIXEL_UNIT_SIZE,
+            PATH_COLOR.with_a(OLDEST_ALPHA + (NEWEST_ALPHA - OLDEST_ALPHA) * age),
+        )
+    };
+    // one stroke per stretch of neighboring cells
+    let mut stroke: Vec<(Vec2, Color)> = Vec::new();
+    for (index, cell) in route.cells.iter().enumerate() {
+        let adjacent = index == 0 || {
+            let previous = route.cells[index - 1];
+            (cell.0 - previous.0).abs() + (cell.1 - previous.1).abs() <= 1
+        };
+        if !adjacent {
+            gizmos.linestrip_gradient_2d(std::mem::take(&mut stroke));
+        }
+        stroke.push(point(index, *cell));
+    }
+    gizmos.linestrip_gradient_2d(stroke);
+}
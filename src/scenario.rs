@@ -0,0 +1,178 @@
+// Scenarios
+// Practice boards for endgame technique (--scenarios): a menu at startup lists the files in
+// assets/scenarios, and picking one swaps player one's snake for the one laid out there. A snake
+// that doesn't fit this run's board, its shape and size included, leaves its scenario out
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::menu::{navigate_menu, spawn_button, MenuActivated};
+use crate::playfield::PlayfieldMask;
+use crate::ron_asset::{asset_file_path, read_ron_file};
+use crate::snake_layout::SnakeLayout;
+use crate::theme::Theme;
+use crate::tween::Tween;
+use crate::{spawn_snake_from_layout, Apple, PendingGrowth, Player, SnakeHead};
+
+const SCENARIO_DIRECTORY: &str = "scenarios"; // under assets/
+const SCENARIO_EXTENSION: &str = ".scenario.ron";
+
+#[derive(Deserialize)]
+struct Scenario {
+    name: String,
+    description: String,
+    snake: SnakeLayout, // head first
+}
+
+#[derive(Resource)]
+struct Scenarios(Vec<Scenario>);
+
+impl Scenarios {
+    // sorted by file name, unreadable files and broken snakes are left out
+    fn load(playfield_mask: &PlayfieldMask) -> Self {
+        let mut paths: Vec<_> = match std::fs::read_dir(asset_file_path(SCENARIO_DIRECTORY)) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.to_string_lossy().ends_with(SCENARIO_EXTENSION))
+                .collect(),
+            Err(error) => {
+                warn!("Could not list scenarios: {error}");
+                Vec::new()
+            }
+        };
+        paths.sort();
+        let scenarios = paths
+            .into_iter()
+            .filter_map(|path| match read_ron_file::<Scenario>(&path) {
+                Ok(scenario) => match scenario.snake.check_fits(playfield_mask) {
+                    Ok(()) => Some(scenario),
+                    Err(error) => {
+                        warn!(
+                            "Scenario {} does not fit the board: {error}",
+                            path.display()
+                        );
+                        None
+                    }
+                },
+                Err(error) => {
+                    warn!("Could not load scenario {}: {error}", path.display());
+                    None
+                }
+            })
+            .collect();
+        Scenarios(scenarios)
+    }
+}
+
+#[derive(Component)]
+struct ScenarioMenu;
+
+// None starts a normal game
+#[derive(Component)]
+struct ScenarioButton(Option<usize>);
+
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_scenario_menu)
+            .add_systems(Update, pick_scenario.after(navigate_menu));
+    }
+}
+
+// loaded here rather than with the plugin, once the board is set up
+fn setup_scenario_menu(
+    mut commands: Commands,
+    playfield_mask: Res<PlayfieldMask>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let scenarios = Scenarios::load(&playfield_mask);
+    time.pause();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ScenarioMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Scenarios",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            for (index, scenario) in scenarios.0.iter().enumerate() {
+                let label = format!("{} - {}", scenario.name, scenario.description);
+                spawn_button(parent, &label, ScenarioButton(Some(index)));
+            }
+            spawn_button(parent, "Free play", ScenarioButton(None));
+        });
+    commands.insert_resource(scenarios);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pick_scenario(
+    mut commands: Commands,
+    mut menu_activated_event: EventReader<MenuActivated>,
+    scenarios: Res<Scenarios>,
+    theme: Res<Theme>,
+    mut time: ResMut<Time<Virtual>>,
+    mut pending_growth: ResMut<PendingGrowth>,
+    button_query: Query<&ScenarioButton>,
+    menu_query: Query<Entity, With<ScenarioMenu>>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+    apple_query: Query<(Entity, &Apple, &Sprite)>,
+) {
+    let Some(choice) = menu_activated_event
+        .read()
+        .find_map(|event| button_query.get(event.0).ok().map(|button| button.0))
+    else {
+        return;
+    };
+    for menu in &menu_query {
+        commands.entity(menu).despawn_recursive();
+    }
+    time.unpause();
+    let Some(scenario) = choice.map(|index| &scenarios.0[index]) else {
+        return;
+    };
+    info!(scenario = scenario.name, "Scenario started");
+
+    for (entity, player, snake_head) in &snake_head_query {
+        if *player != Player::ONE {
+            continue;
+        }
+        for segment in snake_head.segments.iter().chain([&entity]) {
+            commands.entity(*segment).despawn();
+        }
+    }
+    pending_growth.0.remove(&Player::ONE);
+    // a fresh apple is spawned wherever the snake isn't
+    for (entity, apple, sprite) in &apple_query {
+        if apple
+            .footprint
+            .iter()
+            .any(|position| scenario.snake.cells().contains(position))
+        {
+            commands
+                .entity(entity)
+                .remove::<Apple>()
+                .insert(Tween::fade_out(sprite.color));
+        }
+    }
+    spawn_snake_from_layout(&mut commands, &theme, Player::ONE, &scenario.snake);
+}
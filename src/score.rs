@@ -0,0 +1,208 @@
+// Score
+// Points for eaten apples and the HUD showing them
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::playfield::PlayfieldMask;
+use crate::scoring::{Scoring, ScoringContext};
+use crate::tween::{Easing, Tween, TweenTarget};
+use crate::zones::{ScoreZone, ZoneRelocationTimer};
+use crate::{AppleEaten, Player, SnakeBody, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const COMBO_WINDOW_SECONDS: f32 = 3.0;
+const POPUP_SECONDS: f32 = 0.6;
+const POPUP_RISE: f32 = PIXEL_UNIT_SIZE * 1.5;
+
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+// apples eaten in quick succession
+#[derive(Resource)]
+pub struct Combo {
+    pub count: u32,
+    window: Timer,
+    since_last: Stopwatch, // for time-weighted scoring
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        let scoring = Scoring::from_args(&Args::of(app));
+        app.init_resource::<Score>()
+            .insert_resource(Combo {
+                count: 0,
+                window: Timer::from_seconds(COMBO_WINDOW_SECONDS, TimerMode::Once),
+                since_last: Stopwatch::new(),
+            })
+            .insert_resource(scoring)
+            .add_systems(Startup, setup_hud)
+            .add_systems(FixedUpdate, award_points.in_set(SnakeSystemSet::Collision))
+            .add_systems(Update, update_hud.in_set(SnakeSystemSet::Rendering));
+    }
+}
+
+fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::GOLD,
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        ScoreText,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn award_points(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    scoring: Res<Scoring>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    zone_query: Query<&ScoreZone>,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+) {
+    if combo.window.tick(time.delta()).just_finished() {
+        combo.count = 0;
+    }
+    combo.since_last.tick(time.delta());
+    // a CPU opponent's apples don't count for the player
+    for event in apple_eaten_event
+        .read()
+        .filter(|event| event.player == Player::ONE)
+    {
+        combo.count += 1;
+        combo.window.reset();
+        let multiplier = zone_query
+            .iter()
+            .filter(|zone| zone.contains(event.position))
+            .map(|zone| zone.multiplier)
+            .max()
+            .unwrap_or(1);
+        // the segment right behind the head is always next to it and doesn't count
+        let body: Vec<(i32, i32)> = snake_head_query
+            .iter()
+            .find(|(player, _)| **player == event.player)
+            .map(|(_, snake_head)| {
+                snake_head
+                    .segments
+                    .iter()
+                    .skip(1)
+                    .filter_map(|segment| snake_body_query.get(*segment).ok())
+                    .map(|snake_body| snake_body.position)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let risky = Direction::ALL.iter().any(|direction| {
+            let neighbour = direction.step(event.position);
+            playfield_mask.is_wall(neighbour) || body.contains(&neighbour)
+        });
+        let points = scoring.0.points(&ScoringContext {
+            points: event.points,
+            zone_multiplier: multiplier,
+            combo: combo.count,
+            seconds_since_last: combo.since_last.elapsed_secs(),
+            risky,
+        });
+        combo.since_last.reset();
+        score.0 += points;
+        spawn_score_popup(&mut commands, event.position, points);
+        debug!(
+            score = score.0,
+            combo = combo.count,
+            multiplier,
+            scoring = scoring.0.name(),
+            "Points awarded"
+        );
+    }
+}
+
+// a "+N" floating up from where the apple was eaten
+fn spawn_score_popup(commands: &mut Commands, position: (i32, i32), points: u32) {
+    let start = Vec3::new(
+        position.0 as f32 * PIXEL_UNIT_SIZE,
+        position.1 as f32 * PIXEL_UNIT_SIZE,
+        2.0,
+    );
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                format!("+{points}"),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::GOLD,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_translation(start),
+            ..default()
+        },
+        Tween::new(POPUP_SECONDS, Easing::OutQuad)
+            .with(TweenTarget::Translation {
+                from: start,
+                to: start + Vec3::Y * POPUP_RISE,
+            })
+            .with(TweenTarget::Color {
+                from: Color::GOLD,
+                to: Color::GOLD.with_a(0.0),
+            })
+            .despawn_when_done(),
+    ));
+}
+
+fn update_hud(
+    score: Res<Score>,
+    relocation_timer: Res<ZoneRelocationTimer>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    zone_query: Query<&ScoreZone>,
+    mut text_query: Query<&mut Text, With<ScoreText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = format!("Score: {}", score.0);
+
+    let zone_multiplier = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+        .and_then(|(_, snake_head)| {
+            zone_query
+                .iter()
+                .filter(|zone| zone.contains(snake_head.position))
+                .map(|zone| zone.multiplier)
+                .max()
+        });
+    let zones_move_in = relocation_timer.0.remaining_secs().ceil();
+    text.sections[1].value = match zone_multiplier {
+        Some(multiplier) => format!("   x{multiplier} ZONE ({zones_move_in}s)"),
+        None => format!("   zones move in {zones_move_in}s"),
+    };
+}
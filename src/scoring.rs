@@ -0,0 +1,112 @@
+// Scoring strategies
+// How many points an eaten apple is worth, one strategy per run: classic, time-weighted,
+// combo-based or a bonus for risky bites. Modes pick their own and --scoring <name> overrides it,
+// award_points in score.rs only gathers what a strategy looks at
+use bevy::prelude::*;
+
+use crate::args::Args;
+
+const TIME_WINDOW_SECONDS: f32 = 5.0; // apples eaten sooner than this after the last one earn more
+const RISK_BONUS: u32 = 2;
+
+// what is known about an apple when it is eaten
+pub struct ScoringContext {
+    pub points: u32, // the apple's own
+    pub zone_multiplier: u32,
+    pub combo: u32,              // apples in the current combo, this one included
+    pub seconds_since_last: f32, // since the previous apple, or since the start
+    pub risky: bool,             // eaten right next to a wall or the snake's own body
+}
+
+pub trait ScoringStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn points(&self, context: &ScoringContext) -> u32;
+}
+
+// the apple's points in any zone's multiplier
+pub struct Classic;
+
+impl ScoringStrategy for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    fn points(&self, context: &ScoringContext) -> u32 {
+        context.points * context.zone_multiplier
+    }
+}
+
+// up to double for an apple eaten straight after the last one
+pub struct TimeWeighted;
+
+impl ScoringStrategy for TimeWeighted {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn points(&self, context: &ScoringContext) -> u32 {
+        let base = Classic.points(context);
+        let quickness = (1.0 - context.seconds_since_last / TIME_WINDOW_SECONDS).max(0.0);
+        base + (base as f32 * quickness).round() as u32
+    }
+}
+
+// every apple in a combo is worth as many times more as there have been in it
+pub struct ComboBased;
+
+impl ScoringStrategy for ComboBased {
+    fn name(&self) -> &'static str {
+        "combo"
+    }
+
+    fn points(&self, context: &ScoringContext) -> u32 {
+        Classic.points(context) * context.combo.max(1)
+    }
+}
+
+// extra points for bites taken hugging a wall or the own body
+pub struct RiskBonus;
+
+impl ScoringStrategy for RiskBonus {
+    fn name(&self) -> &'static str {
+        "risk"
+    }
+
+    fn points(&self, context: &ScoringContext) -> u32 {
+        Classic.points(context) + if context.risky { RISK_BONUS } else { 0 }
+    }
+}
+
+#[derive(Resource)]
+pub struct Scoring(pub Box<dyn ScoringStrategy>);
+
+impl Scoring {
+    // --scoring <classic|time|combo|risk>, else what suits the mode. Both leave a run unranked, so
+    // ranked runs always score classic, the way verify_replay counts them
+    pub fn from_args(args: &Args) -> Self {
+        let strategy: Box<dyn ScoringStrategy> = match args.value("--scoring") {
+            Some("classic") => Box::new(Classic),
+            Some("time") => Box::new(TimeWeighted),
+            Some("combo") => Box::new(ComboBased),
+            Some("risk") => Box::new(RiskBonus),
+            Some(other) => {
+                args.problem(format!("Unknown scoring {other:?}, using the mode's"));
+                Scoring::for_mode(args)
+            }
+            None => Scoring::for_mode(args),
+        };
+        Scoring(strategy)
+    }
+
+    fn for_mode(args: &Args) -> Box<dyn ScoringStrategy> {
+        if args.has("--corridor") || args.has("--molt") {
+            Box::new(RiskBonus)
+        } else if args.has("--hunger") {
+            Box::new(TimeWeighted)
+        } else if args.has("--zen") {
+            Box::new(ComboBased)
+        } else {
+            Box::new(Classic)
+        }
+    }
+}
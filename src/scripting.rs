@@ -0,0 +1,266 @@
+// Scripting
+// Rhai gameplay mods loaded from mods/*.rhai at startup. A mod may define any of
+//   on_tick(tick, length)
+//   on_apple_eaten(points, growth, x, y)
+//   modify_spawn_table(apples) -> apples
+// and call add_score(points), grow(segments) and log(message) from them.
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bevy::prelude::*;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::items::{reload_item_table, AppleDefinition, ItemTable};
+use crate::score::Score;
+use crate::{AppleEaten, PendingGrowth, Player, SnakeBody, SnakeSystemSet};
+
+const MODS_DIRECTORY: &str = "mods";
+
+enum ModCommand {
+    AddScore(u32),
+    Grow(u32),
+    Log(String),
+}
+
+struct Mod {
+    name: String,
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+#[derive(Resource)]
+struct Mods {
+    engine: Engine,
+    mods: Vec<Mod>,
+    // filled by the functions mods call, applied after each hook; a panic while it is held only
+    // poisons it, the queue itself is still whole
+    commands: Arc<Mutex<Vec<ModCommand>>>,
+}
+
+impl Mods {
+    fn load(directory: &Path) -> Self {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        let queue = commands.clone();
+        engine.register_fn("add_score", move |points: i64| {
+            queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(ModCommand::AddScore(points.max(0) as u32));
+        });
+        let queue = commands.clone();
+        engine.register_fn("grow", move |segments: i64| {
+            queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(ModCommand::Grow(segments.max(0) as u32));
+        });
+        let queue = commands.clone();
+        engine.register_fn("log", move |message: &str| {
+            queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(ModCommand::Log(message.to_string()));
+        });
+
+        let mut paths: Vec<_> = std::fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|extension| extension == "rhai")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let mut mods = Vec::new();
+        for path in paths {
+            let name = path.display().to_string();
+            let ast = match engine.compile_file(path) {
+                Ok(ast) => ast,
+                Err(error) => {
+                    warn!("Could not load mod {name}: {error}");
+                    continue;
+                }
+            };
+            let mut scope = Scope::new();
+            if let Err(error) = engine.run_ast_with_scope(&mut scope, &ast) {
+                warn!("Could not run mod {name}: {error}");
+                continue;
+            }
+            info!("Loaded mod {name}");
+            mods.push(Mod { name, ast, scope });
+        }
+
+        Mods {
+            engine,
+            mods,
+            commands,
+        }
+    }
+
+    // calls a hook on every mod defining it, returning each mod's result
+    fn call(&mut self, hook: &str, args: impl rhai::FuncArgs + Clone) -> Vec<Dynamic> {
+        let mut results = Vec::new();
+        for script in &mut self.mods {
+            if !script
+                .ast
+                .iter_functions()
+                .any(|function| function.name == hook)
+            {
+                continue;
+            }
+            match self
+                .engine
+                .call_fn::<Dynamic>(&mut script.scope, &script.ast, hook, args.clone())
+            {
+                Ok(result) => results.push(result),
+                Err(error) => warn!("Mod {} failed in {hook}: {error}", script.name),
+            }
+        }
+        results
+    }
+}
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Mods::load(Path::new(MODS_DIRECTORY)))
+            .add_systems(
+                FixedUpdate,
+                (call_on_tick, call_on_apple_eaten, apply_mod_commands)
+                    .chain()
+                    .in_set(SnakeSystemSet::Collision),
+            )
+            .add_systems(
+                Update,
+                call_modify_spawn_table
+                    .after(reload_item_table)
+                    .run_if(resource_changed::<ItemTable>()),
+            );
+    }
+}
+
+fn call_on_tick(
+    mut mods: ResMut<Mods>,
+    mut tick: Local<i64>,
+    snake_body_query: Query<(), With<SnakeBody>>,
+) {
+    *tick += 1;
+    let length = snake_body_query.iter().count() as i64 + 1;
+    mods.call("on_tick", (*tick, length));
+}
+
+fn call_on_apple_eaten(mut mods: ResMut<Mods>, mut apple_eaten_event: EventReader<AppleEaten>) {
+    for event in apple_eaten_event.read() {
+        mods.call(
+            "on_apple_eaten",
+            (
+                event.points as i64,
+                event.growth as i64,
+                event.position.0 as i64,
+                event.position.1 as i64,
+            ),
+        );
+    }
+}
+
+fn apply_mod_commands(
+    mods: Res<Mods>,
+    mut score: ResMut<Score>,
+    mut pending_growth: ResMut<PendingGrowth>,
+) {
+    let mut commands = mods.commands.lock().unwrap_or_else(PoisonError::into_inner);
+    for command in commands.drain(..) {
+        match command {
+            ModCommand::AddScore(points) => score.0 += points,
+            ModCommand::Grow(segments) => {
+                *pending_growth.0.entry(Player::ONE).or_default() += segments
+            }
+            ModCommand::Log(message) => info!("[mod] {message}"),
+        }
+    }
+}
+
+fn apple_to_map(apple: &AppleDefinition) -> Map {
+    let mut map = Map::new();
+    map.insert("name".into(), apple.name.clone().into());
+    map.insert("points".into(), (apple.points as i64).into());
+    map.insert("growth".into(), (apple.growth as i64).into());
+    map.insert("size".into(), (apple.size as i64).into());
+    map.insert("spawn_weight".into(), (apple.spawn_weight as i64).into());
+    map
+}
+
+// fields missing from the map keep the value of the existing apple with the same name
+fn apple_from_map(map: &Map, existing: &[AppleDefinition]) -> Option<AppleDefinition> {
+    let name = map.get("name")?.clone().into_string().ok()?;
+    let mut apple = existing
+        .iter()
+        .find(|apple| apple.name == name)
+        .cloned()
+        .unwrap_or(AppleDefinition {
+            name,
+            color: None,
+            size: 1,
+            points: 1,
+            growth: 1,
+            effects: Vec::new(),
+            spawn_weight: 1,
+        });
+    let int = |key: &str| map.get(key).and_then(|value| value.as_int().ok());
+    if let Some(points) = int("points") {
+        apple.points = points.max(0) as u32;
+    }
+    if let Some(growth) = int("growth") {
+        apple.growth = growth.max(0) as u32;
+    }
+    // fitted to the board when an apple is rolled
+    if let Some(size) = int("size") {
+        apple.size = size.clamp(1, i32::MAX as i64) as i32;
+    }
+    if let Some(spawn_weight) = int("spawn_weight") {
+        apple.spawn_weight = spawn_weight.max(0) as u32;
+    }
+    Some(apple)
+}
+
+fn call_modify_spawn_table(mut mods: ResMut<Mods>, mut item_table: ResMut<ItemTable>) {
+    for script in 0..mods.mods.len() {
+        if !mods.mods[script]
+            .ast
+            .iter_functions()
+            .any(|function| function.name == "modify_spawn_table")
+        {
+            continue;
+        }
+        let apples: Array = item_table
+            .apples
+            .iter()
+            .map(|apple| Dynamic::from_map(apple_to_map(apple)))
+            .collect();
+        let Mods { engine, mods, .. } = &mut *mods;
+        let script = &mut mods[script];
+        match engine.call_fn::<Array>(
+            &mut script.scope,
+            &script.ast,
+            "modify_spawn_table",
+            (apples,),
+        ) {
+            Ok(modified) => {
+                let apples = modified
+                    .iter()
+                    .filter_map(|apple| apple.read_lock::<Map>())
+                    .filter_map(|map| apple_from_map(&map, &item_table.apples))
+                    .collect();
+                // bypassed so that this system does not trigger itself
+                item_table.bypass_change_detection().apples = apples;
+            }
+            Err(error) => warn!("Mod {} failed in modify_spawn_table: {error}", script.name),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Seasonal events
+// Around the dates listed in assets/events.ron the theme, the apple sprite and the soundtrack
+// switch to a holiday variant. The event is picked once at launch, --no-events keeps the defaults
+// and --event <name> plays one out of season
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::args::Args;
+use crate::high_score::civil_today;
+use crate::music::Soundtrack;
+use crate::ron_asset::{asset_file_path, read_ron_file};
+use crate::Apple;
+
+const EVENTS_PATH: &str = "events.ron"; // under assets/
+
+#[derive(Clone, Deserialize)]
+pub struct EventDefinition {
+    pub name: String,
+    start: (u32, u32), // month, day
+    end: (u32, u32),
+    #[serde(default)]
+    pub theme: Option<String>, // an asset path
+    #[serde(default)]
+    apple_image: Option<String>,
+    #[serde(default)]
+    pub soundtrack: Soundtrack,
+}
+
+impl EventDefinition {
+    // both ends included, an end before the start wraps over the new year
+    fn is_on(&self, date: (u32, u32)) -> bool {
+        if self.start <= self.end {
+            self.start <= date && date <= self.end
+        } else {
+            date >= self.start || date <= self.end
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EventCalendar {
+    events: Vec<EventDefinition>,
+}
+
+// the event on at launch, missing when there is none
+#[derive(Resource)]
+pub struct SeasonalEvent(pub EventDefinition);
+
+#[derive(Resource)]
+struct AppleImage(Handle<Image>);
+
+pub struct SeasonalPlugin;
+
+impl Plugin for SeasonalPlugin {
+    fn build(&self, app: &mut App) {
+        let args = Args::of(app);
+        if args.has("--no-events") {
+            return;
+        }
+        let forced = args.value("--event");
+        let events_path = asset_file_path(EVENTS_PATH);
+        let calendar: EventCalendar = match read_ron_file(&events_path) {
+            Ok(calendar) => calendar,
+            Err(error) => {
+                warn!("Could not read {}: {error}", events_path.display());
+                return;
+            }
+        };
+        let (_, month, day) = civil_today();
+        let event = calendar.events.into_iter().find(|event| match forced {
+            Some(name) => event.name.eq_ignore_ascii_case(name),
+            None => event.is_on((month, day)),
+        });
+        let Some(event) = event else {
+            if let Some(name) = forced {
+                warn!("No event named {name:?} in {EVENTS_PATH}");
+            }
+            return;
+        };
+        info!(event = event.name, "Seasonal event on");
+        app.insert_resource(SeasonalEvent(event))
+            .add_systems(Startup, load_apple_image)
+            .add_systems(Update, dress_apples);
+    }
+}
+
+fn load_apple_image(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    event: Res<SeasonalEvent>,
+) {
+    if let Some(path) = &event.0.apple_image {
+        commands.insert_resource(AppleImage(asset_server.load(path)));
+    }
+}
+
+// apples with a color of their own are other items and keep looking like them
+fn dress_apples(
+    apple_image: Option<Res<AppleImage>>,
+    mut apple_query: Query<(&Apple, &mut Handle<Image>), Added<Apple>>,
+) {
+    let Some(apple_image) = apple_image else {
+        return;
+    };
+    for (apple, mut image) in &mut apple_query {
+        if apple.color.is_none() {
+            *image = apple_image.0.clone();
+        }
+    }
+}
@@ -0,0 +1,260 @@
+// Run seed
+// Gameplay spawns draw from one seeded generator so a run's apple sequence can be shared and
+// replayed with --seed, the seed is shown at game over and C copies it to the clipboard. Play
+// seed... there asks for a seed, this run's to begin with, and starts the game again on it
+use std::process::Command;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::args::Args;
+use crate::menu::{navigate_menu, spawn_button, MenuActivated};
+use crate::widgets::{spawn_text_input, TextInput, TextSubmitted};
+use crate::GameOver;
+
+const SEED_LEN: usize = 20; // digits in the largest u64
+
+#[derive(Resource, Clone, Copy)]
+pub struct RunSeed(pub u64);
+
+impl RunSeed {
+    // --seed <number>, otherwise a fresh random one
+    pub fn from_args(args: &Args) -> Self {
+        let seed = match args.values("--seed") {
+            Some(values) => match values.first().map(|seed| seed.parse()) {
+                Some(Ok(seed)) => Some(seed),
+                _ => {
+                    args.problem("--seed expects a whole number".to_string());
+                    None
+                }
+            },
+            None => None,
+        };
+        RunSeed(seed.unwrap_or_else(rand::random))
+    }
+}
+
+// apple and power-up picks and positions, purely cosmetic randomness doesn't use it
+#[derive(Resource)]
+pub struct RunRng(pub StdRng);
+
+// the seed, the play seed button and its prompt, shown at game over
+#[derive(Component)]
+struct SeedScreen;
+
+#[derive(Component)]
+struct SeedText;
+
+// hidden while other text is being typed, Enter there would press it too
+#[derive(Component)]
+struct PlaySeedButton;
+
+#[derive(Component)]
+struct SeedPrompt;
+
+#[derive(Component)]
+struct SeedPromptLabel;
+
+#[derive(Component)]
+struct SeedInput;
+
+pub struct SeedPlugin;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = RunSeed::from_args(&Args::of(app));
+        app.insert_resource(seed)
+            .insert_resource(RunRng(StdRng::seed_from_u64(seed.0)))
+            .add_systems(Startup, (log_seed, setup_seed_screen))
+            .add_systems(
+                Update,
+                (
+                    show_seed,
+                    copy_seed,
+                    show_play_seed_button,
+                    open_seed_prompt.after(navigate_menu),
+                    play_seed,
+                ),
+            );
+    }
+}
+
+fn log_seed(seed: Res<RunSeed>) {
+    info!(seed = seed.0, "Run seed");
+}
+
+fn setup_seed_screen(mut commands: Commands, seed: Res<RunSeed>) {
+    let text_style = TextStyle {
+        font_size: 20.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(12.0),
+                    left: Val::Px(12.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            SeedScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    SeedPrompt,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("Seed:", text_style.clone()),
+                        SeedPromptLabel,
+                    ));
+                    spawn_text_input(parent, SEED_LEN, SeedInput);
+                });
+            spawn_button(parent, "Play seed...", PlaySeedButton);
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("Seed {} - [C] copy, replay with --seed {}", seed.0, seed.0),
+                    text_style,
+                ),
+                SeedText,
+            ));
+        });
+}
+
+fn show_seed(
+    mut game_over_event: EventReader<GameOver>,
+    mut screen_query: Query<&mut Visibility, With<SeedScreen>>,
+) {
+    if game_over_event.read().count() == 0 {
+        return;
+    }
+    for mut visibility in &mut screen_query {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+// like while entering high score initials
+fn show_play_seed_button(
+    text_input_query: Query<&InheritedVisibility, (With<TextInput>, Without<SeedInput>)>,
+    mut button_query: Query<&mut Visibility, With<PlaySeedButton>>,
+) {
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    for mut visibility in &mut button_query {
+        let wanted = if typing {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+fn open_seed_prompt(
+    mut menu_activated_event: EventReader<MenuActivated>,
+    seed: Res<RunSeed>,
+    button_query: Query<(), With<PlaySeedButton>>,
+    mut prompt_query: Query<&mut Visibility, With<SeedPrompt>>,
+    mut input_query: Query<&mut TextInput, With<SeedInput>>,
+) {
+    if !menu_activated_event
+        .read()
+        .any(|event| button_query.contains(event.0))
+    {
+        return;
+    }
+    for mut visibility in &mut prompt_query {
+        if *visibility == Visibility::Hidden {
+            *visibility = Visibility::Inherited;
+            // Enter right away plays this run's apples again
+            for mut input in &mut input_query {
+                input.value = seed.0.to_string();
+            }
+        }
+    }
+}
+
+// a new game on the same command line with --seed swapped in, this one ends
+fn play_seed(
+    mut text_submitted_event: EventReader<TextSubmitted>,
+    args: Res<Args>,
+    input_query: Query<(), With<SeedInput>>,
+    mut label_query: Query<&mut Text, With<SeedPromptLabel>>,
+    mut app_exit_event: EventWriter<AppExit>,
+) {
+    let Some(value) = text_submitted_event
+        .read()
+        .find(|event| input_query.contains(event.input))
+        .map(|event| event.value.trim().to_string())
+    else {
+        return;
+    };
+    let Ok(seed) = value.parse::<u64>() else {
+        for mut text in &mut label_query {
+            text.sections[0].value = "Seed (a whole number):".to_string();
+        }
+        return;
+    };
+    let started = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .args(args.with_value("--seed", &value))
+            .spawn()
+    });
+    match started {
+        Ok(_) => {
+            info!(seed, "Playing seed");
+            app_exit_event.send(AppExit);
+        }
+        Err(error) => warn!("Could not start a game with seed {seed}: {error}"),
+    }
+}
+
+// the clipboard is kept open, on some platforms its contents go away with it
+fn copy_seed(
+    keyboard_input: Res<Input<KeyCode>>,
+    seed: Res<RunSeed>,
+    text_query: Query<&InheritedVisibility, With<SeedText>>,
+    text_input_query: Query<&InheritedVisibility, With<TextInput>>,
+    mut clipboard: Local<Option<arboard::Clipboard>>,
+) {
+    let shown = text_query.iter().any(|visibility| visibility.get());
+    // C may be part of the initials being typed
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    if !shown || typing || !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    if clipboard.is_none() {
+        match arboard::Clipboard::new() {
+            Ok(opened) => *clipboard = Some(opened),
+            Err(error) => {
+                warn!("Could not open the clipboard: {error}");
+                return;
+            }
+        }
+    }
+    if let Some(clipboard) = clipboard.as_mut() {
+        match clipboard.set_text(seed.0.to_string()) {
+            Ok(()) => info!(seed = seed.0, "Seed copied"),
+            Err(error) => warn!("Could not copy the seed: {error}"),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// Shield
+// Item effect that absorbs the next fatal collision, shattering when used
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::items::{collect_power_ups, ItemEffect, PowerUpCollected};
+use crate::tween::{Easing, Tween, TweenTarget};
+use crate::{AppleEaten, Player, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE};
+
+const SHIELD_COLOR: Color = Color::CYAN;
+const SHARD_COUNT: usize = 8;
+const SHARD_SPEED: f32 = 160.0;
+const SHARD_LIFETIME_SECONDS: f32 = 0.5;
+const SHARD_SPIN: f32 = 4.0; // radians over the shard's lifetime
+
+// on the snake head while it carries a shield
+#[derive(Component)]
+pub struct Shield;
+
+#[derive(Component)]
+struct ShieldIcon;
+
+#[derive(Event)]
+pub struct ShieldBroken {
+    pub player: Player,
+    pub position: (i32, i32),
+}
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShieldBroken>()
+            .add_systems(Startup, setup_shield_icon)
+            .add_systems(
+                FixedUpdate,
+                (grant_shield.after(collect_power_ups), break_shield)
+                    .in_set(SnakeSystemSet::Collision),
+            )
+            .add_systems(Update, update_shield_icon);
+    }
+}
+
+fn setup_shield_icon(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                left: Val::Percent(50.0),
+                width: Val::Px(20.0),
+                height: Val::Px(20.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: SHIELD_COLOR.with_a(0.5).into(),
+            border_color: SHIELD_COLOR.into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ShieldIcon,
+    ));
+}
+
+fn grant_shield(
+    mut commands: Commands,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut power_up_collected_event: EventReader<PowerUpCollected>,
+    snake_head_query: Query<(Entity, &Player), With<SnakeHead>>,
+) {
+    let granted: Vec<Player> = apple_eaten_event
+        .read()
+        .map(|event| (event.player, &event.effects))
+        .chain(
+            power_up_collected_event
+                .read()
+                .map(|event| (event.player, &event.effects)),
+        )
+        .filter(|(_, effects)| effects.contains(&ItemEffect::Shield))
+        .map(|(player, _)| player)
+        .collect();
+    for (entity, player) in &snake_head_query {
+        if granted.contains(player) {
+            commands.entity(entity).insert(Shield);
+        }
+    }
+}
+
+fn break_shield(
+    mut commands: Commands,
+    mut shield_broken_event: EventReader<ShieldBroken>,
+    snake_head_query: Query<(Entity, &Player), With<Shield>>,
+) {
+    let mut rng = rand::thread_rng();
+    for event in shield_broken_event.read() {
+        info!(player = event.player.0, position = ?event.position, "Shield broken");
+        for (entity, player) in &snake_head_query {
+            if *player == event.player {
+                commands.entity(entity).remove::<Shield>();
+            }
+        }
+        spawn_shards(&mut commands, &mut rng, event.position);
+    }
+}
+
+fn spawn_shards(commands: &mut Commands, rng: &mut impl Rng, position: (i32, i32)) {
+    for i in 0..SHARD_COUNT {
+        let angle =
+            i as f32 / SHARD_COUNT as f32 * std::f32::consts::TAU + rng.gen_range(-0.3..0.3);
+        let start = Vec3::new(
+            position.0 as f32 * PIXEL_UNIT_SIZE,
+            position.1 as f32 * PIXEL_UNIT_SIZE,
+            1.0,
+        );
+        let travel = Vec2::from_angle(angle) * SHARD_SPEED * SHARD_LIFETIME_SECONDS;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: SHIELD_COLOR,
+                    custom_size: Some(Vec2::new(PIXEL_UNIT_SIZE * 0.3, PIXEL_UNIT_SIZE * 0.3)),
+                    ..default()
+                },
+                transform: Transform::from_translation(start),
+                ..default()
+            },
+            Tween::new(SHARD_LIFETIME_SECONDS, Easing::Linear)
+                .with(TweenTarget::Translation {
+                    from: start,
+                    to: start + travel.extend(0.0),
+                })
+                .with(TweenTarget::Rotation {
+                    from: 0.0,
+                    to: SHARD_SPIN,
+                })
+                .with(TweenTarget::Color {
+                    from: SHIELD_COLOR,
+                    to: SHIELD_COLOR.with_a(0.0),
+                })
+                .despawn_when_done(),
+        ));
+    }
+}
+
+fn update_shield_icon(
+    snake_head_query: Query<(&Player, Has<Shield>), With<SnakeHead>>,
+    mut icon_query: Query<&mut Visibility, With<ShieldIcon>>,
+) {
+    let shielded = snake_head_query
+        .iter()
+        .any(|(player, shielded)| *player == Player::ONE && shielded);
+    for mut visibility in &mut icon_query {
+        *visibility = if shielded {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,86 @@
+// Snake layouts
+// A snake given cell by cell, head first, checked to be joined up so it can be spawned as is from
+// scenario files or anything else that stores a snake. Whether it fits depends on the board it goes
+// on, check_fits tries it against one
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::playfield::PlayfieldMask;
+use crate::Player;
+
+#[derive(Debug, Error)]
+pub enum SnakeLayoutError {
+    #[error("a snake needs at least a head")]
+    Empty,
+    #[error("cell {0:?} is outside the playfield")]
+    OutOfBounds((i32, i32)),
+    #[error("cell {0:?} is in a wall")]
+    Wall((i32, i32)),
+    #[error("cells {0:?} and {1:?} are not next to each other")]
+    Gap((i32, i32), (i32, i32)),
+    #[error("cell {0:?} is used more than once")]
+    Overlap((i32, i32)),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "Vec<(i32, i32)>")]
+pub struct SnakeLayout {
+    cells: Vec<(i32, i32)>,
+}
+
+impl SnakeLayout {
+    pub fn new(cells: Vec<(i32, i32)>) -> Result<Self, SnakeLayoutError> {
+        if cells.is_empty() {
+            return Err(SnakeLayoutError::Empty);
+        }
+        if let Some(pair) = cells
+            .windows(2)
+            .find(|pair| pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1) != 1)
+        {
+            return Err(SnakeLayoutError::Gap(pair[0], pair[1]));
+        }
+        for (index, cell) in cells.iter().enumerate() {
+            if cells[..index].contains(cell) {
+                return Err(SnakeLayoutError::Overlap(*cell));
+            }
+        }
+        Ok(SnakeLayout { cells })
+    }
+
+    // facing right with its head in the given column of the player's row
+    pub fn starting(player: Player, length: i32, column: i32) -> Self {
+        SnakeLayout::in_row(player.spawn_row(), length, column)
+    }
+
+    // facing right with its head at the given column and row
+    pub fn in_row(row: i32, length: i32, column: i32) -> Self {
+        SnakeLayout {
+            cells: (0..length.max(1)).map(|x| (column - x, row)).collect(),
+        }
+    }
+
+    // every cell open on this board, off a smaller or inset one or in a shape's walls is not
+    pub fn check_fits(&self, playfield_mask: &PlayfieldMask) -> Result<(), SnakeLayoutError> {
+        for cell in &self.cells {
+            if playfield_mask.is_off_board(*cell) {
+                return Err(SnakeLayoutError::OutOfBounds(*cell));
+            }
+            if playfield_mask.is_wall(*cell) {
+                return Err(SnakeLayoutError::Wall(*cell));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn cells(&self) -> &[(i32, i32)] {
+        &self.cells
+    }
+}
+
+impl TryFrom<Vec<(i32, i32)>> for SnakeLayout {
+    type Error = SnakeLayoutError;
+
+    fn try_from(cells: Vec<(i32, i32)>) -> Result<Self, Self::Error> {
+        SnakeLayout::new(cells)
+    }
+}
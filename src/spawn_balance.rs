@@ -0,0 +1,94 @@
+// Spawn balance
+// Optional fair apple distribution: recent spawn quadrants are remembered and sampling favors
+// the ones that have gone without
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::items::pick_weighted;
+
+const RECENT_SPAWNS: usize = 8;
+
+// set from the game config
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum SpawnBalance {
+    #[default]
+    Uniform,
+    Balanced,
+}
+
+// the center row and column count toward the right and top quadrants
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Quadrant {
+    right: bool,
+    top: bool,
+}
+
+impl Quadrant {
+    const ALL: [Quadrant; 4] = [
+        Quadrant {
+            right: false,
+            top: false,
+        },
+        Quadrant {
+            right: true,
+            top: false,
+        },
+        Quadrant {
+            right: false,
+            top: true,
+        },
+        Quadrant {
+            right: true,
+            top: true,
+        },
+    ];
+
+    fn of(position: (i32, i32)) -> Quadrant {
+        Quadrant {
+            right: position.0 >= 0,
+            top: position.1 >= 0,
+        }
+    }
+
+    // footprint origins inside this quadrant that keep a size x size footprint on a board reaching
+    // this far from its center
+    pub fn origin_ranges(
+        self,
+        size: i32,
+        (half_width, half_height): (i32, i32),
+    ) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        let axis = |positive: bool, half: i32| {
+            if positive {
+                0..=half - size + 1
+            } else {
+                -half..=(-1).min(half - size + 1)
+            }
+        };
+        (axis(self.right, half_width), axis(self.top, half_height))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct RecentSpawns(VecDeque<Quadrant>);
+
+impl RecentSpawns {
+    // quadrants that saw fewer of the recent spawns are more likely
+    pub fn pick_quadrant(&self, rng: &mut impl Rng) -> Option<Quadrant> {
+        pick_weighted(rng, &Quadrant::ALL, |quadrant| {
+            let recent = self.0.iter().filter(|seen| *seen == quadrant).count();
+            (RECENT_SPAWNS + 1 - recent) as u32
+        })
+        .copied()
+    }
+
+    pub fn record(&mut self, position: (i32, i32)) {
+        if self.0.len() == RECENT_SPAWNS {
+            self.0.pop_front();
+        }
+        self.0.push_back(Quadrant::of(position));
+    }
+}
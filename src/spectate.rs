@@ -0,0 +1,608 @@
+// Spectating
+// A host (--spectator-port <port>) sends a snapshot of the board to every connected spectator after
+// each tick. A spectator (--spectate <host:port>) runs no game of its own and only draws the latest
+// snapshot it got, and with --remote-play it also gets a snake of its own on the host to steer,
+// drawn ahead of the snapshots by prediction. The host only listens on this machine unless given
+// --spectator-bind <address>, and takes remote players while their spawn rows fit on the board
+use std::collections::VecDeque;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
+
+use bevy::ecs::system::SystemParamItem;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy::tasks::Task;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::input::{resolve_direction, DiagonalPolicy, InputSettings};
+use crate::input_source::{AddInputSource, DirectionIntents, InputSource};
+use crate::items::PowerUp;
+use crate::net::{self, Connection};
+use crate::playfield::PlayfieldMask;
+use crate::prediction::Prediction;
+use crate::score::Score;
+use crate::snake_layout::SnakeLayout;
+use crate::theme::Theme;
+use crate::{
+    spawn_snake, Apple, Player, SnakeBody, SnakeDied, SnakeHead, SnakeSystemSet, PIXEL_UNIT_SIZE,
+    PLAYFIELD, START_LENGTH, TICKRATE,
+};
+
+const DEFAULT_PORT: u16 = 7878;
+pub const FIRST_REMOTE_PLAYER: u8 = 5; // past every local seat
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SnakeState {
+    pub player: u8,
+    pub cells: Vec<(i32, i32)>, // head first
+    pub direction: Direction,
+}
+
+// everything a spectator needs to draw one tick
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TickSnapshot {
+    pub tick: u64,
+    pub tickrate: f64, // seconds, for predicting at the host's pace
+    pub score: u32,
+    pub scroll: i32,
+    pub half_extents: (i32, i32),
+    pub wrap: bool,
+    pub background: Color,
+    pub walls: Vec<(i32, i32)>,
+    // snakes, apples and power-ups in the colors they are drawn in on the host
+    pub cells: Vec<((i32, i32), Color)>,
+    pub snakes: Vec<SnakeState>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum HostMessage {
+    Welcome {
+        player: u8,
+    },
+    Full, // no seat left on the board, only spectating
+    Tick {
+        snapshot: TickSnapshot,
+        acknowledged: u64, // the last of this spectator's inputs applied
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum SpectatorMessage {
+    Join,
+    Input { sequence: u64, direction: Direction },
+}
+
+// --spectate <host:port>, a bare host gets the default port
+pub fn host_from_args(args: &Args) -> Option<String> {
+    args.value("--spectate").map(|host| {
+        if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:{DEFAULT_PORT}")
+        }
+    })
+}
+
+struct Spectator {
+    connection: Connection,
+    player: Option<Player>, // once it joined as a remote player
+    // received but not applied yet, one per tick
+    inputs: VecDeque<(u64, Direction)>,
+    acknowledged: u64,
+}
+
+#[derive(Resource)]
+struct SpectatorBroadcast {
+    listener: TcpListener,
+    spectators: Vec<Spectator>,
+    next_player: u8,
+}
+
+pub struct SpectateHostPlugin;
+
+impl Plugin for SpectateHostPlugin {
+    fn build(&self, app: &mut App) {
+        let args = Args::of(app);
+        let port = match args.value("--spectator-port") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                args.problem(format!(
+                    "Invalid spectator port {value:?}, using {DEFAULT_PORT}"
+                ));
+                DEFAULT_PORT
+            }),
+            None => DEFAULT_PORT,
+        };
+        let address = match args.value("--spectator-bind") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                args.problem(format!(
+                    "Invalid spectator address {value:?}, only listening on this machine"
+                ));
+                IpAddr::V4(Ipv4Addr::LOCALHOST)
+            }),
+            None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        };
+        let listener = match TcpListener::bind((address, port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+        {
+            Ok(listener) => listener,
+            Err(error) => {
+                error!("Could not listen for spectators on {address} port {port}: {error}");
+                return;
+            }
+        };
+        app.add_input_source(SpectatorBroadcast {
+            listener,
+            spectators: Vec::new(),
+            next_player: FIRST_REMOTE_PLAYER,
+        })
+        .add_systems(
+            FixedUpdate,
+            broadcast_snapshot.in_set(SnakeSystemSet::Rendering),
+        )
+        .add_systems(Update, remove_remote_snakes);
+    }
+}
+
+// players over the network, read on the tick their inputs are sent for
+impl InputSource for SpectatorBroadcast {
+    type Param = (
+        Commands<'static, 'static>,
+        Res<'static, Theme>,
+        Res<'static, PlayfieldMask>,
+    );
+    const PER_TICK: bool = true;
+
+    fn poll(
+        &mut self,
+        (commands, theme, playfield_mask): &mut SystemParamItem<'_, '_, Self::Param>,
+        intents: &mut DirectionIntents,
+    ) {
+        let broadcast = self;
+        while let Ok((stream, address)) = broadcast.listener.accept() {
+            match Connection::new(stream) {
+                Ok(connection) => {
+                    info!(%address, "Spectator connected");
+                    broadcast.spectators.push(Spectator {
+                        connection,
+                        player: None,
+                        inputs: VecDeque::new(),
+                        acknowledged: 0,
+                    });
+                }
+                Err(error) => warn!("Could not accept a spectator: {error}"),
+            }
+        }
+
+        let next_player = &mut broadcast.next_player;
+        broadcast.spectators.retain_mut(|spectator| {
+            let messages = match spectator.connection.receive() {
+                Ok(messages) => messages,
+                Err(error) => {
+                    info!("Spectator left: {error}");
+                    return false;
+                }
+            };
+            for message in messages {
+                match message {
+                    SpectatorMessage::Join if spectator.player.is_none() => {
+                        let player = Player(*next_player);
+                        if !has_room(playfield_mask, player) {
+                            info!(player = player.0, "No room for another remote player");
+                            if spectator.connection.send(&HostMessage::Full).is_err() {
+                                return false;
+                            }
+                            continue;
+                        }
+                        *next_player += 1;
+                        spawn_snake(commands, theme, playfield_mask, player, START_LENGTH);
+                        spectator.player = Some(player);
+                        info!(player = player.0, "Remote player joined");
+                        if spectator
+                            .connection
+                            .send(&HostMessage::Welcome { player: player.0 })
+                            .is_err()
+                        {
+                            return false;
+                        }
+                    }
+                    SpectatorMessage::Join => {}
+                    SpectatorMessage::Input {
+                        sequence,
+                        direction,
+                    } => spectator.inputs.push_back((sequence, direction)),
+                }
+            }
+            let Some(player) = spectator.player else {
+                return true;
+            };
+            if let Some((sequence, direction)) = spectator.inputs.pop_front() {
+                spectator.acknowledged = sequence;
+                intents.push(player, direction);
+            }
+            true
+        });
+    }
+}
+
+// a player's starting row has to be open on the board for its snake, the rows go outwards from the
+// center so every later player is further out
+fn has_room(playfield_mask: &PlayfieldMask, player: Player) -> bool {
+    SnakeLayout::starting(player, START_LENGTH, playfield_mask.start_column())
+        .cells()
+        .iter()
+        .all(|cell| !playfield_mask.is_wall(*cell))
+}
+
+// a remote snake that dies is out, nobody respawns it, lives included
+fn remove_remote_snakes(
+    mut commands: Commands,
+    mut snake_died_event: EventReader<SnakeDied>,
+    snake_head_query: Query<(Entity, &Player, &SnakeHead)>,
+) {
+    // several collisions can report the same death in one frame
+    let died: Vec<Player> = snake_died_event
+        .read()
+        .map(|event| event.player)
+        .filter(|player| player.is_remote())
+        .collect();
+    for (entity, player, snake_head) in &snake_head_query {
+        if died.contains(player) {
+            for segment in snake_head.segments.iter().chain([&entity]) {
+                commands.entity(*segment).despawn();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn broadcast_snapshot(
+    mut tick: Local<u64>,
+    fixed_time: Res<Time<Fixed>>,
+    mut broadcast: ResMut<SpectatorBroadcast>,
+    score: Res<Score>,
+    theme: Res<Theme>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_query: Query<(Option<&SnakeHead>, Option<&SnakeBody>, &Sprite)>,
+    apple_query: Query<(&Apple, &Sprite)>,
+    power_up_query: Query<(&PowerUp, &Sprite)>,
+) {
+    *tick += 1;
+    if broadcast.spectators.is_empty() {
+        return;
+    }
+
+    let snakes = snake_query.iter().filter_map(|(head, body, sprite)| {
+        head.map(|head| head.position)
+            .or(body.map(|body| body.position))
+            .map(|position| (position, sprite.color))
+    });
+    let apples = apple_query.iter().flat_map(|(apple, sprite)| {
+        apple
+            .footprint
+            .iter()
+            .map(move |position| (*position, sprite.color))
+    });
+    let power_ups = power_up_query
+        .iter()
+        .map(|(power_up, sprite)| (power_up.position, sprite.color));
+    let snake_states = snake_head_query.iter().map(|(player, snake_head)| {
+        let body = snake_head.segments.iter().filter_map(|segment| {
+            snake_query
+                .get(*segment)
+                .ok()
+                .and_then(|(_, body, _)| body.map(|body| body.position))
+        });
+        SnakeState {
+            player: player.0,
+            cells: std::iter::once(snake_head.position).chain(body).collect(),
+            direction: snake_head.direction,
+        }
+    });
+    let snapshot = TickSnapshot {
+        tick: *tick,
+        tickrate: fixed_time.timestep().as_secs_f64(),
+        score: score.0,
+        scroll: playfield_mask.scroll(),
+        half_extents: playfield_mask.half_extents(),
+        wrap: playfield_mask.wrap,
+        background: theme.background,
+        walls: playfield_mask.walls().collect(),
+        cells: apples.chain(power_ups).chain(snakes).collect(),
+        snakes: snake_states.collect(),
+    };
+    // a spectator that can't keep up is dropped rather than holding up the game
+    broadcast.spectators.retain_mut(|spectator| {
+        let message = HostMessage::Tick {
+            snapshot: snapshot.clone(),
+            acknowledged: spectator.acknowledged,
+        };
+        match spectator.connection.send(&message) {
+            Ok(()) => true,
+            Err(error) => {
+                info!("Spectator dropped: {error}");
+                false
+            }
+        }
+    });
+}
+
+#[derive(Resource, Default)]
+struct HostConnection(Option<Connection>);
+
+#[derive(Resource)]
+struct ConnectingToHost {
+    host: String,
+    play: bool,
+    task: Option<Task<io::Result<Connection>>>,
+}
+
+#[derive(Resource, Default)]
+struct LatestSnapshot(Option<TickSnapshot>);
+
+// set once the host gave us a snake
+#[derive(Resource, Default)]
+struct RemotePlayer(Option<u8>);
+
+#[derive(Component)]
+struct SpectatorCell;
+
+#[derive(Component)]
+struct SpectatorText;
+
+pub struct SpectatorPlugin {
+    pub host: String,
+}
+
+impl Plugin for SpectatorPlugin {
+    // --remote-play to join the game instead of only watching it
+    fn build(&self, app: &mut App) {
+        let play = Args::of(app).has("--remote-play");
+        app.insert_resource(ConnectingToHost {
+            host: self.host.clone(),
+            play,
+            task: Some(Connection::connect(self.host.clone())),
+        })
+        .init_resource::<HostConnection>()
+        .init_resource::<LatestSnapshot>()
+        .init_resource::<RemotePlayer>()
+        .init_resource::<Prediction>()
+        .insert_resource(Time::<Fixed>::from_seconds(TICKRATE))
+        .add_systems(Startup, setup_spectator)
+        .add_systems(FixedUpdate, predict)
+        .add_systems(
+            Update,
+            (
+                finish_connecting,
+                receive_snapshots,
+                steer_remote_snake,
+                draw_snapshot,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn setup_spectator(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        TextBundle::from_section(
+            "Waiting for the host",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        SpectatorText,
+    ));
+}
+
+fn finish_connecting(
+    mut connecting: ResMut<ConnectingToHost>,
+    mut host_connection: ResMut<HostConnection>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+) {
+    let Some(connected) = net::finished(&mut connecting.task) else {
+        return;
+    };
+    let play = connecting.play;
+    let connection = connected.and_then(|mut connection| {
+        if play {
+            connection.send(&SpectatorMessage::Join)?;
+        }
+        Ok(connection)
+    });
+    match connection {
+        Ok(connection) => {
+            info!(host = connecting.host, play, "Spectating");
+            host_connection.0 = Some(connection);
+        }
+        Err(error) => {
+            error!("Could not connect to {}: {error}", connecting.host);
+            for mut text in &mut text_query {
+                text.sections[0].value.push_str(" (could not connect)");
+            }
+        }
+    }
+}
+
+fn receive_snapshots(
+    mut host_connection: ResMut<HostConnection>,
+    mut latest: ResMut<LatestSnapshot>,
+    mut remote_player: ResMut<RemotePlayer>,
+    mut prediction: ResMut<Prediction>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+) {
+    let Some(connection) = &mut host_connection.0 else {
+        return;
+    };
+    let messages = match connection.receive() {
+        Ok(messages) => messages,
+        Err(error) => {
+            info!("Host disconnected: {error}");
+            host_connection.0 = None;
+            for mut text in &mut text_query {
+                text.sections[0].value.push_str(" (disconnected)");
+            }
+            return;
+        }
+    };
+    for message in messages {
+        match message {
+            HostMessage::Welcome { player } => remote_player.0 = Some(player),
+            HostMessage::Full => info!("The host has no room for another player, spectating"),
+            HostMessage::Tick {
+                snapshot,
+                acknowledged,
+            } => {
+                let own_snake = snapshot
+                    .snakes
+                    .iter()
+                    .find(|snake| Some(snake.player) == remote_player.0);
+                match own_snake {
+                    Some(snake) => {
+                        let wrapping = snapshot
+                            .wrap
+                            .then_some((snapshot.scroll, snapshot.half_extents));
+                        prediction.reconcile(snake.clone(), acknowledged, wrapping);
+                    }
+                    // out of the game, nothing left to predict
+                    None if remote_player.0.is_some() => prediction.forget(),
+                    None => {}
+                }
+                if (fixed_time.timestep().as_secs_f64() - snapshot.tickrate).abs() > f64::EPSILON {
+                    fixed_time.set_timestep_seconds(snapshot.tickrate);
+                }
+                latest.0 = Some(snapshot);
+            }
+        }
+    }
+}
+
+// on the guest's own key bindings
+fn steer_remote_snake(
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    input_settings: Res<InputSettings>,
+    remote_player: Res<RemotePlayer>,
+    mut host_connection: ResMut<HostConnection>,
+    mut prediction: ResMut<Prediction>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+) {
+    let Some(direction) = resolve_direction(
+        &mut keyboard_input_events,
+        DiagonalPolicy::LatestPress,
+        &input_settings.actions,
+    ) else {
+        return;
+    };
+    let (Some(_), Some(connection)) = (remote_player.0, &mut host_connection.0) else {
+        return;
+    };
+    let sequence = prediction.push_input(direction);
+    // a line that didn't go out leaves the host out of step with us, there is no carrying on
+    if let Err(error) = connection.send(&SpectatorMessage::Input {
+        sequence,
+        direction,
+    }) {
+        warn!("Could not send input, disconnecting: {error}");
+        host_connection.0 = None;
+        for mut text in &mut text_query {
+            text.sections[0].value.push_str(" (disconnected)");
+        }
+    }
+}
+
+fn predict(mut prediction: ResMut<Prediction>) {
+    prediction.tick();
+}
+
+// redrawn from scratch for every new snapshot or prediction
+fn draw_snapshot(
+    mut commands: Commands,
+    latest: Res<LatestSnapshot>,
+    remote_player: Res<RemotePlayer>,
+    prediction: Res<Prediction>,
+    cell_query: Query<Entity, With<SpectatorCell>>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+) {
+    if !latest.is_changed() && !prediction.is_changed() {
+        return;
+    }
+    let Some(snapshot) = &latest.0 else {
+        return;
+    };
+    for entity in &cell_query {
+        commands.entity(entity).despawn();
+    }
+    let offset = snapshot.scroll as f32 * PIXEL_UNIT_SIZE;
+    for mut transform in &mut camera_query {
+        transform.translation.x = offset;
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = match remote_player.0 {
+            Some(player) => format!("Playing as P{player} - score {}", snapshot.score),
+            None => format!("Spectating - score {}", snapshot.score),
+        };
+    }
+
+    let mut spawn_cell = |position: (i32, i32), size: Vec2, color: Color, z: f32| {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    position.0 as f32 * PIXEL_UNIT_SIZE,
+                    position.1 as f32 * PIXEL_UNIT_SIZE,
+                    z,
+                )),
+                ..default()
+            },
+            SpectatorCell,
+        ));
+    };
+    let board = Vec2::new(
+        PLAYFIELD.0 as f32 * PIXEL_UNIT_SIZE,
+        PLAYFIELD.1 as f32 * PIXEL_UNIT_SIZE,
+    );
+    spawn_cell((snapshot.scroll, 0), board, snapshot.background, -0.1);
+    for position in &snapshot.walls {
+        spawn_cell(*position, Vec2::splat(PIXEL_UNIT_SIZE), Color::BLACK, -0.05);
+    }
+    // our own snake is drawn where the prediction has it, in the colors the host gave it
+    let own_snake = snapshot
+        .snakes
+        .iter()
+        .find(|snake| Some(snake.player) == remote_player.0);
+    let own_cells = own_snake.map_or(&[][..], |snake| &snake.cells[..]);
+    let mut own_colors = Vec::new();
+    for (position, color) in &snapshot.cells {
+        if let Some(index) = own_cells.iter().position(|cell| cell == position) {
+            own_colors.push((index, *color));
+            continue;
+        }
+        spawn_cell(*position, Vec2::splat(PIXEL_UNIT_SIZE), *color, 0.0);
+    }
+    own_colors.sort_by_key(|(index, _)| *index);
+    for (index, position) in prediction.cells().iter().enumerate() {
+        let color = own_colors
+            .iter()
+            .find(|(own_index, _)| *own_index == index)
+            .or(own_colors.last())
+            .map_or(Color::WHITE, |(_, color)| *color);
+        spawn_cell(*position, Vec2::splat(PIXEL_UNIT_SIZE), color, 0.0);
+    }
+}
@@ -0,0 +1,357 @@
+// Splits
+// Speedrun splits (--splits): every 10 apples player one eats is a split, timed on game time so
+// pauses don't count, and the HUD shows how far ahead of or behind the personal best the run is.
+// Between splits the delta goes live once the run falls behind the best's next split. Bests are
+// kept per category, the rules played under and whether the seed was set, in the profile's
+// splits.ron, and the best is the run that got furthest, or as far sooner. A run with cheats or
+// the demo bot is timed but never saved. The run starting, each split and game over also go out
+// as RunMilestone events, for an outside timer to follow along
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::cheats::Cheats;
+use crate::high_score::Ruleset;
+use crate::profile::Profile;
+use crate::seed::RunSeed;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::{AppleEaten, GameOver, Player, SnakeSystemSet};
+
+const SPLITS_FILE: &str = "splits.ron";
+const APPLES_PER_SPLIT: u32 = 10;
+const TIMER_COLOR: Color = Color::WHITE;
+const AHEAD_COLOR: Color = Color::LIME_GREEN;
+const BEHIND_COLOR: Color = Color::ORANGE_RED;
+const PB_COLOR: Color = Color::GOLD;
+
+// seconds into the run at each split
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct PersonalBest {
+    label: String,
+    splits: Vec<f32>,
+}
+
+impl PersonalBest {
+    fn beaten_by(&self, splits: &[f32]) -> bool {
+        match (self.splits.len().cmp(&splits.len()), splits.last()) {
+            (std::cmp::Ordering::Less, _) => true,
+            (std::cmp::Ordering::Equal, Some(time)) => self.splits.last() > Some(time),
+            _ => false,
+        }
+    }
+}
+
+// by category id
+#[derive(Default, Serialize, Deserialize)]
+struct PersonalBests {
+    categories: BTreeMap<String, PersonalBest>,
+}
+
+// the rules and whether the seed was set, a set seed being its own category
+struct Category {
+    id: String,
+    label: String,
+}
+
+impl Category {
+    fn from_args(args: &Args, seed: &RunSeed) -> Self {
+        let ruleset = Ruleset::from_args(args);
+        let seeded = args.has("--seed");
+        let (seed_id, seed_label) = if seeded {
+            (format!("seed-{}", seed.0), format!("seed {}", seed.0))
+        } else {
+            ("random".to_string(), "random seed".to_string())
+        };
+        Category {
+            id: format!("{}/{seed_id}", ruleset.id),
+            label: format!("{}, {seed_label}", ruleset.label),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct RunSplits {
+    category: Category,
+    file: Option<String>, // None until a profile is picked
+    personal_bests: PersonalBests,
+    comparison: Vec<f32>, // the best as it was when the run started
+    splits: Vec<f32>,
+    apples: u32,
+    clock: Stopwatch,
+    started: bool,
+    over: bool,
+    saved: bool, // not for the demo bot
+}
+
+impl RunSplits {
+    // against the best's split of the same number, negative is ahead
+    fn delta(&self, index: usize, seconds: f32) -> Option<f32> {
+        self.comparison.get(index).map(|best| seconds - best)
+    }
+
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Err(error) = write_ron_save(file, &self.personal_bests) {
+            warn!("Could not save splits: {error}");
+        }
+    }
+}
+
+// the run clock starts with the first unpaused frame, a split is counted from 0
+#[derive(Event)]
+pub enum RunMilestone {
+    Started,
+    Split {
+        index: usize,
+        seconds: f32,
+        delta: Option<f32>,
+    },
+    Ended {
+        seconds: f32,
+    },
+}
+
+#[derive(Component)]
+struct SplitsText;
+
+pub struct SplitsPlugin;
+
+impl Plugin for SplitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RunMilestone>()
+            .add_systems(Startup, (insert_run_splits, setup_splits_text))
+            .add_systems(
+                Update,
+                (
+                    load_splits.run_if(resource_added::<Profile>()),
+                    time_run,
+                    record_splits,
+                    log_milestones,
+                    update_splits_text.in_set(SnakeSystemSet::Rendering),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn insert_run_splits(mut commands: Commands, args: Res<Args>, seed: Res<RunSeed>) {
+    commands.insert_resource(RunSplits {
+        category: Category::from_args(&args, &seed),
+        file: None,
+        personal_bests: PersonalBests::default(),
+        comparison: Vec::new(),
+        splits: Vec::new(),
+        apples: 0,
+        clock: Stopwatch::new(),
+        started: false,
+        over: false,
+        saved: !args.has("--demo"),
+    });
+}
+
+fn setup_splits_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(48.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        SplitsText,
+    ));
+}
+
+fn load_splits(profile: Res<Profile>, mut run_splits: ResMut<RunSplits>) {
+    let file = profile.file(SPLITS_FILE);
+    run_splits.personal_bests = read_ron_save(&file).unwrap_or_default();
+    run_splits.file = Some(file);
+    run_splits.comparison = run_splits
+        .personal_bests
+        .categories
+        .get(&run_splits.category.id)
+        .map(|best| best.splits.clone())
+        .unwrap_or_default();
+    info!(
+        category = run_splits.category.label,
+        splits = run_splits.comparison.len(),
+        "Personal best loaded"
+    );
+}
+
+// virtual time, which stands still while the game is paused
+fn time_run(
+    time: Res<Time>,
+    mut game_over_event: EventReader<GameOver>,
+    mut run_splits: ResMut<RunSplits>,
+    mut run_milestone_event: EventWriter<RunMilestone>,
+) {
+    if run_splits.over {
+        return;
+    }
+    // a dead snake keeps reporting game over
+    if game_over_event.read().count() > 0 {
+        run_splits.over = true;
+        run_milestone_event.send(RunMilestone::Ended {
+            seconds: run_splits.clock.elapsed_secs(),
+        });
+        return;
+    }
+    if !run_splits.started && !time.delta().is_zero() {
+        run_splits.started = true;
+        run_milestone_event.send(RunMilestone::Started);
+    }
+    run_splits.clock.tick(time.delta());
+}
+
+// a run ahead of the best replaces it at each split, so quitting early keeps what was reached
+fn record_splits(
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    cheats: Option<Res<Cheats>>,
+    mut run_splits: ResMut<RunSplits>,
+    mut run_milestone_event: EventWriter<RunMilestone>,
+) {
+    let eaten = apple_eaten_event
+        .read()
+        .filter(|event| event.player == Player::ONE)
+        .count() as u32;
+    if eaten == 0 || run_splits.over {
+        return;
+    }
+    run_splits.apples += eaten;
+    let seconds = run_splits.clock.elapsed_secs();
+    while run_splits.splits.len() < (run_splits.apples / APPLES_PER_SPLIT) as usize {
+        let index = run_splits.splits.len();
+        run_splits.splits.push(seconds);
+        run_milestone_event.send(RunMilestone::Split {
+            index,
+            seconds,
+            delta: run_splits.delta(index, seconds),
+        });
+    }
+    let index = run_splits.splits.len();
+    let cheated = cheats.is_some_and(|cheats| cheats.used);
+    if cheated || !run_splits.saved || index == 0 {
+        return;
+    }
+    let category = run_splits.category.id.clone();
+    let best = run_splits
+        .personal_bests
+        .categories
+        .get(&category)
+        .cloned()
+        .unwrap_or_default();
+    if !best.beaten_by(&run_splits.splits) {
+        return;
+    }
+    let personal_best = PersonalBest {
+        label: run_splits.category.label.clone(),
+        splits: run_splits.splits.clone(),
+    };
+    run_splits
+        .personal_bests
+        .categories
+        .insert(category, personal_best);
+    run_splits.save();
+}
+
+fn log_milestones(mut run_milestone_event: EventReader<RunMilestone>) {
+    for milestone in run_milestone_event.read() {
+        match milestone {
+            RunMilestone::Started => info!("Run timer started"),
+            RunMilestone::Split {
+                index,
+                seconds,
+                delta,
+            } => info!(split = index + 1, seconds, delta = ?delta, "Split"),
+            RunMilestone::Ended { seconds } => info!(seconds, "Run timer stopped"),
+        }
+    }
+}
+
+// m:ss.s
+fn format_time(seconds: f32) -> String {
+    let tenths = (seconds.max(0.0) * 10.0).round() as u32;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+fn delta_section(delta: f32) -> TextSection {
+    TextSection::new(
+        format!(" {delta:+.1}"),
+        TextStyle {
+            font_size: 22.0,
+            color: if delta <= 0.0 {
+                AHEAD_COLOR
+            } else {
+                BEHIND_COLOR
+            },
+            ..default()
+        },
+    )
+}
+
+fn update_splits_text(
+    run_splits: Res<RunSplits>,
+    mut text_query: Query<&mut Text, With<SplitsText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let style = |color| TextStyle {
+        font_size: 22.0,
+        color,
+        ..default()
+    };
+    let elapsed = run_splits.clock.elapsed_secs();
+    let mut sections = vec![TextSection::new(
+        format!("{}\n", format_time(elapsed)),
+        TextStyle {
+            font_size: 28.0,
+            color: TIMER_COLOR,
+            ..default()
+        },
+    )];
+    if let Some(index) = run_splits.splits.len().checked_sub(1) {
+        let seconds = run_splits.splits[index];
+        sections.push(TextSection::new(
+            format!(
+                "{} apples {}",
+                (index as u32 + 1) * APPLES_PER_SPLIT,
+                format_time(seconds)
+            ),
+            style(TIMER_COLOR),
+        ));
+        if let Some(delta) = run_splits.delta(index, seconds) {
+            sections.push(delta_section(delta));
+        }
+        sections.push(TextSection::new("\n", style(TIMER_COLOR)));
+    }
+    let next = run_splits.splits.len();
+    match run_splits.comparison.get(next) {
+        Some(best) => {
+            sections.push(TextSection::new(
+                format!(
+                    "PB {} apples {}",
+                    (next as u32 + 1) * APPLES_PER_SPLIT,
+                    format_time(*best)
+                ),
+                style(PB_COLOR),
+            ));
+            // live once behind, ahead only shows at the split itself
+            if elapsed > *best && !run_splits.over {
+                sections.push(delta_section(elapsed - best));
+            }
+        }
+        None if run_splits.comparison.is_empty() => {
+            sections.push(TextSection::new("No personal best yet", style(PB_COLOR)));
+        }
+        None => {
+            sections.push(TextSection::new("Past the personal best", style(PB_COLOR)));
+        }
+    }
+    text.sections = sections;
+}
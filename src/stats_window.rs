@@ -0,0 +1,128 @@
+// Stats window
+// Optional second window with live run stats, for a clean capture of the main window, and route
+// analytics once the run is over
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Anchor;
+use bevy::window::WindowRef;
+
+use crate::playfield::PlayfieldMask;
+use crate::route::RunRoute;
+use crate::score::{Combo, Score};
+use crate::{speed_factor, AppleEaten, GameOver, MovementSettings, SnakeBody};
+
+const WINDOW_SIZE: (f32, f32) = (360.0, 420.0);
+const STATS_LAYER: u8 = 1; // keeps the stats text out of the main camera
+
+#[derive(Resource, Default)]
+struct RunStats {
+    apples_eaten: u32,
+    run_over: bool,
+}
+
+#[derive(Component)]
+struct StatsText;
+
+pub struct StatsWindowPlugin;
+
+impl Plugin for StatsWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(Startup, open_stats_window)
+            .add_systems(Update, (count_apples, end_run, update_stats_text).chain());
+    }
+}
+
+fn open_stats_window(mut commands: Commands) {
+    let window = commands
+        .spawn(Window {
+            title: "Snake stats".to_string(),
+            resolution: WINDOW_SIZE.into(),
+            ..default()
+        })
+        .id();
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Entity(window)),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(STATS_LAYER),
+        // the HUD belongs to the main window
+        UiCameraConfig { show_ui: false },
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 26.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            text_anchor: Anchor::TopLeft,
+            transform: Transform::from_translation(Vec3::new(
+                -WINDOW_SIZE.0 / 2.0 + 16.0,
+                WINDOW_SIZE.1 / 2.0 - 16.0,
+                0.0,
+            )),
+            ..default()
+        },
+        RenderLayers::layer(STATS_LAYER),
+        StatsText,
+    ));
+}
+
+fn count_apples(mut apple_eaten_event: EventReader<AppleEaten>, mut run_stats: ResMut<RunStats>) {
+    run_stats.apples_eaten += apple_eaten_event.read().count() as u32;
+}
+
+fn end_run(mut game_over_event: EventReader<GameOver>, mut run_stats: ResMut<RunStats>) {
+    if game_over_event.read().count() > 0 {
+        run_stats.run_over = true;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_stats_text(
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    score: Res<Score>,
+    combo: Res<Combo>,
+    run_stats: Res<RunStats>,
+    route: Res<RunRoute>,
+    playfield_mask: Res<PlayfieldMask>,
+    movement_settings: Res<MovementSettings>,
+    snake_body_query: Query<(), With<SnakeBody>>,
+    mut text_query: Query<&mut Text, With<StatsText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let elapsed = time.elapsed_seconds() as u32;
+    text.sections[0].value = format!(
+        "Score: {}\nLength: {}\nApples: {}\nCombo: {}\nSpeed: {:.1}x\nTime: {}:{:02}",
+        score.0,
+        snake_body_query.iter().count() + 1,
+        run_stats.apples_eaten,
+        combo.count,
+        speed_factor(&movement_settings, &fixed_time),
+        elapsed / 60,
+        elapsed % 60,
+    );
+    if run_stats.run_over {
+        let analytics = route.analytics(time.elapsed_seconds(), &playfield_mask);
+        text.sections[0].value += &format!(
+            "\n\nApples/min: {:.1}\nCells/apple: {:.1} (best {:.1})\nTurns: {}\nLongest straight: {}",
+            analytics.apples_per_minute,
+            analytics.cells_per_apple,
+            analytics.optimal_cells_per_apple,
+            analytics.turns,
+            analytics.longest_straight,
+        );
+    }
+}
@@ -0,0 +1,55 @@
+// Stone fruit
+// Apples with the Stone effect leave a wall behind: the cell they were eaten on turns to stone for
+// good once every snake has moved off it, so the tail always gets out first
+use bevy::prelude::*;
+
+use crate::items::ItemEffect;
+use crate::playfield::PlayfieldMask;
+use crate::{AppleEaten, SnakeBody, SnakeHead, SnakeSystemSet};
+
+// cells waiting to turn to stone
+#[derive(Resource, Default)]
+pub struct PendingStones(Vec<(i32, i32)>);
+
+pub struct StonePlugin;
+
+impl Plugin for StonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingStones>()
+            .add_systems(FixedUpdate, place_stones.in_set(SnakeSystemSet::Spawning));
+    }
+}
+
+pub fn place_stones(
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut pending_stones: ResMut<PendingStones>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    snake_head_query: Query<&SnakeHead>,
+    snake_body_query: Query<&SnakeBody>,
+) {
+    for event in apple_eaten_event.read() {
+        if event.effects.contains(&ItemEffect::Stone) {
+            pending_stones.0.push(event.position);
+        }
+    }
+    if pending_stones.0.is_empty() {
+        return;
+    }
+    let occupied = |position: (i32, i32)| {
+        snake_head_query
+            .iter()
+            .any(|snake_head| snake_head.position == position)
+            || snake_body_query
+                .iter()
+                .any(|snake_body| snake_body.position == position)
+    };
+    let (cleared, waiting): (Vec<_>, Vec<_>) = pending_stones
+        .0
+        .drain(..)
+        .partition(|position| !occupied(*position));
+    pending_stones.0 = waiting;
+    for position in cleared {
+        info!(?position, "Stone placed");
+        playfield_mask.add_wall(position);
+    }
+}
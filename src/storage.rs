@@ -0,0 +1,238 @@
+// Storage
+// Where saved data lives: the platform's per-user data directory (XDG on Linux, AppData on
+// Windows, Application Support on macOS) or localStorage in a browser. Files older versions left
+// in the working directory are moved over the first time they're asked for
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::ron_asset::RonAssetError;
+
+const APP_DIRECTORY: &str = "snake-rust";
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub struct StoragePaths {
+    // None when no home can be found, saves then stay in the working directory
+    data_directory: Option<PathBuf>,
+}
+
+impl StoragePaths {
+    pub fn get() -> &'static StoragePaths {
+        static PATHS: OnceLock<StoragePaths> = OnceLock::new();
+        PATHS.get_or_init(|| {
+            let paths = StoragePaths {
+                data_directory: platform_data_directory().map(|base| base.join(APP_DIRECTORY)),
+            };
+            info!("Saving to {}", paths.describe());
+            paths
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        if cfg!(target_arch = "wasm32") {
+            return "browser storage".to_string();
+        }
+        match &self.data_directory {
+            Some(directory) => directory.display().to_string(),
+            None => "the working directory".to_string(),
+        }
+    }
+
+    // the file a save goes to, created directories and migrated old copy included
+    pub fn path(&self, name: &str) -> PathBuf {
+        let Some(directory) = &self.data_directory else {
+            return PathBuf::from(name);
+        };
+        let path = directory.join(name);
+        let parent = path.parent().unwrap_or(directory);
+        if let Err(error) = std::fs::create_dir_all(parent) {
+            warn!("Could not create {}: {error}", parent.display());
+            return PathBuf::from(name);
+        }
+        let legacy = PathBuf::from(name);
+        if !path.exists() && legacy.is_file() {
+            match move_file(&legacy, &path) {
+                Ok(()) => info!("Moved {name} to {}", path.display()),
+                Err(error) => warn!("Could not move {name} to {}: {error}", path.display()),
+            }
+        }
+        path
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(&self, name: &str) -> io::Result<String> {
+        std::fs::read_to_string(self.path(name))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, name: &str, contents: &str) -> io::Result<()> {
+        std::fs::write(self.path(name), contents)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn append_line(&self, name: &str, line: &str) -> io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(name))?;
+        writeln!(file, "{line}")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_bytes(&self, name: &str, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(self.path(name), contents)
+    }
+
+    // a save under a new name, e.g. into a profile
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        move_file(&self.path(from), &self.path(to))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn read(&self, name: &str) -> io::Result<String> {
+        local_storage()?
+            .get_item(&storage_key(name))
+            .map_err(|_| io::Error::other("localStorage read failed"))?
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn write(&self, name: &str, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&storage_key(name), contents)
+            .map_err(|_| io::Error::other("localStorage is full or disabled"))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn append_line(&self, name: &str, line: &str) -> io::Result<()> {
+        let mut contents = match self.read(name) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        contents.push_str(line);
+        contents.push('\n');
+        self.write(name, &contents)
+    }
+
+    // localStorage only holds text
+    #[cfg(target_arch = "wasm32")]
+    pub fn write_bytes(&self, name: &str, contents: &[u8]) -> io::Result<()> {
+        self.write(name, &encode_base64(contents))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let contents = self.read(from)?;
+        self.write(to, &contents)?;
+        local_storage()?
+            .remove_item(&storage_key(from))
+            .map_err(|_| io::Error::other("localStorage remove failed"))
+    }
+}
+
+pub fn read_ron_save<T: DeserializeOwned>(name: &str) -> Result<T, RonAssetError> {
+    let contents = StoragePaths::get().read(name)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+pub fn write_ron_save<T: Serialize>(name: &str, value: &T) -> Result<(), RonAssetError> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    StoragePaths::get().write(name, &contents)?;
+    Ok(())
+}
+
+// URL-safe and unpadded, for binary saves kept as text
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+            group | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..=chunk.len() {
+            text.push(BASE64_ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+        }
+    }
+    text
+}
+
+// None for text encode_base64 couldn't have written
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut group = 0u32;
+        for (index, char) in chunk.iter().enumerate() {
+            let digit = BASE64_ALPHABET.iter().position(|c| c == char)? as u32;
+            group |= digit << (18 - 6 * index);
+        }
+        for index in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * index)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+// renaming fails across drives, copying doesn't
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::rename(from, to).or_else(|_| {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_arch = "wasm32")))]
+fn home_directory() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(target_os = "windows")]
+fn platform_data_directory() -> Option<PathBuf> {
+    std::env::var_os("APPDATA")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("USERPROFILE").map(|home| PathBuf::from(home).join("AppData/Roaming"))
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn platform_data_directory() -> Option<PathBuf> {
+    home_directory().map(|home| home.join("Library/Application Support"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn platform_data_directory() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_arch = "wasm32")))]
+fn platform_data_directory() -> Option<PathBuf> {
+    // an XDG_DATA_HOME that isn't absolute is to be ignored
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|directory| directory.is_absolute())
+        .or_else(|| home_directory().map(|home| home.join(".local/share")))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("{APP_DIRECTORY}/{name}")
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::other("localStorage is not available"))
+}
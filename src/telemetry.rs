@@ -0,0 +1,103 @@
+// Telemetry
+// Opt-in (--telemetry) summary of each run, appended as a JSON line to runs.jsonl in the save
+// directory
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::lives::Lives;
+use crate::score::Score;
+use crate::seed::RunSeed;
+use crate::storage::StoragePaths;
+use crate::{DeathCause, MovementSettings, Player, SnakeDied, SnakeHead};
+
+const TELEMETRY_FILE: &str = "runs.jsonl";
+
+#[derive(Resource, Default)]
+struct RunTelemetry {
+    inputs: u32,
+    death_cause: Option<DeathCause>, // None when the player quit
+}
+
+#[derive(Serialize)]
+struct RunSummary {
+    mode: &'static str,
+    seed: u64,
+    score: u32,
+    length: usize, // head included
+    duration_seconds: f32,
+    death_cause: Option<DeathCause>,
+    inputs: u32,
+}
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunTelemetry>()
+            .add_systems(Update, (count_inputs, record_death))
+            .add_systems(Last, write_run_summary);
+    }
+}
+
+fn count_inputs(keyboard_input: Res<Input<KeyCode>>, mut run_telemetry: ResMut<RunTelemetry>) {
+    run_telemetry.inputs += keyboard_input.get_just_pressed().count() as u32;
+}
+
+fn record_death(
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut run_telemetry: ResMut<RunTelemetry>,
+) {
+    if let Some(event) = snake_died_event
+        .read()
+        .filter(|event| event.player == Player::ONE)
+        .last()
+    {
+        run_telemetry.death_cause = Some(event.cause);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_run_summary(
+    mut app_exit_event: EventReader<AppExit>,
+    run_telemetry: Res<RunTelemetry>,
+    time: Res<Time<Virtual>>,
+    score: Res<Score>,
+    movement_settings: Res<MovementSettings>,
+    seed: Res<RunSeed>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    lives: Option<Res<Lives>>,
+) {
+    if app_exit_event.read().count() == 0 {
+        return;
+    }
+    let summary = RunSummary {
+        mode: movement_settings.mode_name(),
+        seed: seed.0,
+        score: score.0,
+        length: snake_head_query
+            .iter()
+            .find(|(player, _)| **player == Player::ONE)
+            .map_or(0, |(_, snake_head)| snake_head.segments.len() + 1),
+        duration_seconds: time.elapsed_seconds(),
+        // quitting with lives left ends the run without a death
+        death_cause: run_telemetry
+            .death_cause
+            .filter(|_| lives.is_none_or(|lives| lives.0 == 0)),
+        inputs: run_telemetry.inputs,
+    };
+    let result = serde_json::to_string(&summary)
+        .map_err(|error| error.to_string())
+        .and_then(|line| {
+            StoragePaths::get()
+                .append_line(TELEMETRY_FILE, &line)
+                .map_err(|error| error.to_string())
+        });
+    match result {
+        Ok(()) => info!(
+            "Run summary written to {TELEMETRY_FILE} in {}",
+            StoragePaths::get().describe()
+        ),
+        Err(error) => warn!("Could not write run summary: {error}"),
+    }
+}
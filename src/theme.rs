@@ -0,0 +1,114 @@
+// Theme
+// Colors of the playfield and everything on it, read from assets/default.theme.ron or a seasonal
+// event's theme, and reapplied whenever the file changes
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use serde::Deserialize;
+
+use crate::ron_asset::RonAssetLoader;
+use crate::seasonal::SeasonalEvent;
+use crate::{Apple, Background, SnakeBody, SnakeHead};
+
+pub const DEFAULT_THEME_PATH: &str = "default.theme.ron";
+
+#[derive(Resource, Asset, TypePath, Clone, PartialEq, Deserialize)]
+pub struct Theme {
+    pub snake_head: Color,
+    pub snake_body: Color,
+    pub apple: Color,
+    pub background: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            snake_head: Color::GREEN,
+            snake_body: Color::WHITE,
+            apple: Color::RED,
+            background: Color::GRAY,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ThemeHandle(pub Handle<Theme>);
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .init_asset::<Theme>()
+            .register_asset_loader(RonAssetLoader::<Theme>::new(&["theme.ron"]))
+            .add_systems(Startup, load_theme)
+            .add_systems(
+                Update,
+                (
+                    reload_theme,
+                    apply_theme
+                        .run_if(resource_changed::<Theme>())
+                        .after(reload_theme),
+                ),
+            );
+    }
+}
+
+fn load_theme(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    event: Option<Res<SeasonalEvent>>,
+) {
+    let path = event
+        .and_then(|event| event.0.theme.clone())
+        .unwrap_or_else(|| DEFAULT_THEME_PATH.to_string());
+    commands.insert_resource(ThemeHandle(asset_server.load(path)));
+}
+
+pub fn reload_theme(
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    theme_handle: Res<ThemeHandle>,
+    themes: Res<Assets<Theme>>,
+    mut theme: ResMut<Theme>,
+) {
+    for event in theme_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != theme_handle.0.id() {
+            continue;
+        }
+        if let Some(loaded_theme) = themes.get(*id) {
+            *theme = loaded_theme.clone();
+        }
+    }
+}
+
+// keeps the alpha, which other systems animate
+fn recolor(sprite: &mut Sprite, color: Color) {
+    sprite.color = color.with_a(sprite.color.a());
+}
+
+#[allow(clippy::type_complexity)]
+pub fn apply_theme(
+    theme: Res<Theme>,
+    mut sprite_query: Query<(
+        &mut Sprite,
+        Has<SnakeHead>,
+        Has<SnakeBody>,
+        Option<&Apple>,
+        Has<Background>,
+    )>,
+) {
+    for (mut sprite, snake_head, snake_body, apple, background) in &mut sprite_query {
+        if snake_head {
+            recolor(&mut sprite, theme.snake_head);
+        } else if snake_body {
+            recolor(&mut sprite, theme.snake_body);
+        } else if let Some(apple) = apple {
+            recolor(&mut sprite, apple.color.unwrap_or(theme.apple));
+        } else if background {
+            recolor(&mut sprite, theme.background);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// Tick pipeline
+// The snakes move on FixedUpdate ticks, as many in a frame as the time since the last one holds,
+// so a tick rate above the frame rate (240 a second for a bot arena, say, with --tick-rate 240 or
+// ticks_per_second in game.config.ron) still plays at full speed. A frame catches up at most
+// max_ticks_per_frame ticks; past that the game slows down rather than each slow frame queueing
+// more ticks for the next one, until no frame keeps up at all. A tick that kills a snake ends the
+// frame's moves, so the death is dealt with before anyone moves again
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::args::Args;
+use crate::{SnakeDied, SnakeSystemSet};
+
+const MAX_CATCH_UP: Duration = Duration::from_millis(250); // as Time<Virtual> allows by default
+
+#[derive(Resource, Clone, Deserialize)]
+#[serde(default)]
+pub struct TickPipeline {
+    pub max_ticks_per_frame: u32,
+    pub ticks_per_second: Option<f64>, // in place of the speed curve, boosts still apply
+    #[serde(skip)]
+    pub forced_ticks_per_second: Option<f64>, // --tick-rate, kept over the config's
+}
+
+impl Default for TickPipeline {
+    fn default() -> Self {
+        TickPipeline {
+            max_ticks_per_frame: 8,
+            ticks_per_second: None,
+            forced_ticks_per_second: None,
+        }
+    }
+}
+
+impl TickPipeline {
+    // seconds a tick when the rate is fixed, --tick-rate over the config
+    pub fn tickrate(&self) -> Option<f64> {
+        self.forced_ticks_per_second
+            .or(self.ticks_per_second)
+            .filter(|ticks_per_second| *ticks_per_second > 0.0)
+            .map(|ticks_per_second| 1.0 / ticks_per_second)
+    }
+}
+
+fn tick_rate_from_args(args: &Args) -> Option<f64> {
+    let tick_rate = args.values("--tick-rate")?.first()?.parse().ok();
+    if tick_rate.is_none() {
+        args.problem("--tick-rate expects ticks a second, keeping the speed curve".to_string());
+    }
+    tick_rate
+}
+
+// a snake died on a tick this frame
+#[derive(Resource, Default)]
+struct TicksHalted(bool);
+
+pub struct TickPipelinePlugin;
+
+impl Plugin for TickPipelinePlugin {
+    fn build(&self, app: &mut App) {
+        let forced_ticks_per_second = tick_rate_from_args(&Args::of(app));
+        app.insert_resource(TickPipeline {
+            forced_ticks_per_second,
+            ..default()
+        })
+        .init_resource::<TicksHalted>()
+        .configure_sets(FixedUpdate, SnakeSystemSet::Movement.run_if(ticks_running))
+        .add_systems(First, resume_ticks)
+        .add_systems(
+            FixedUpdate,
+            halt_on_death
+                .after(SnakeSystemSet::Collision)
+                .before(SnakeSystemSet::Spawning),
+        )
+        .add_systems(Update, bound_catch_up.after(SnakeSystemSet::Movement));
+    }
+}
+
+fn ticks_running(ticks_halted: Res<TicksHalted>) -> bool {
+    !ticks_halted.0
+}
+
+fn resume_ticks(mut ticks_halted: ResMut<TicksHalted>) {
+    ticks_halted.0 = false;
+}
+
+fn halt_on_death(
+    mut snake_died_event: EventReader<SnakeDied>,
+    mut ticks_halted: ResMut<TicksHalted>,
+) {
+    if snake_died_event.read().count() > 0 {
+        ticks_halted.0 = true;
+    }
+}
+
+// virtual time advances at most max_ticks_per_frame ticks a frame, the rest is dropped
+fn bound_catch_up(
+    tick_pipeline: Res<TickPipeline>,
+    fixed_time: Res<Time<Fixed>>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut warned: Local<bool>,
+) {
+    let max_delta =
+        (fixed_time.timestep() * tick_pipeline.max_ticks_per_frame.max(1)).min(MAX_CATCH_UP);
+    if virtual_time.max_delta() != max_delta {
+        virtual_time.set_max_delta(max_delta);
+    }
+    if real_time.delta() > max_delta && !virtual_time.is_paused() && !*warned {
+        *warned = true;
+        warn!(
+            max_ticks_per_frame = tick_pipeline.max_ticks_per_frame,
+            frame_ms = real_time.delta_seconds() * 1000.0,
+            "Frames can't keep up with the tick rate, the game is slowing down"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+// Bot training
+// --train-boards <count> [--train-ticks <ticks>] [--seed <n>] plays that many boards at once
+// without a window, each with its own seed and its own bot, spread over all cores, and prints how
+// fast they went and how well the bots did. The boards are the ranked rules' headless simulation,
+// so a learned bot can be trained far faster than real time
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::ai::{AiSkill, BotView, Greedy, Strategy};
+use crate::args::Args;
+use crate::occupancy::Occupancy;
+use crate::replay::ReplaySim;
+use crate::seed::RunSeed;
+
+const DEFAULT_TICKS: u32 = 5_000; // per board, a bot that only ever circles stops some time
+const RNG_SALT: u64 = 0x7261_696e; // keeps a bot's choices apart from its board's apples
+
+// how one board went
+struct BoardResult {
+    score: u32,
+    length: usize,
+    ticks: u32,
+}
+
+pub fn train_from_args(args: &Args) -> Option<Result<String, String>> {
+    let boards = args.values("--train-boards")?;
+    let Some(Ok(boards)) = boards.first().map(|count| count.parse::<u64>()) else {
+        return Some(Err("--train-boards expects a number of boards".to_string()));
+    };
+    let max_ticks = match args.values("--train-ticks") {
+        Some(values) => match values.first().map(|ticks| ticks.parse()) {
+            Some(Ok(ticks)) => ticks,
+            _ => return Some(Err("--train-ticks expects a number of ticks".to_string())),
+        },
+        None => DEFAULT_TICKS,
+    };
+    let first_seed = RunSeed::from_args(args).0;
+    Some(Ok(train(first_seed, boards, max_ticks)))
+}
+
+// boards get consecutive seeds, each thread plays every so many of them
+fn train(first_seed: u64, boards: u64, max_ticks: u32) -> String {
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |threads| threads.get() as u64)
+        .clamp(1, boards.max(1));
+    let started = Instant::now();
+    let results: Vec<BoardResult> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || {
+                    (worker..boards)
+                        .step_by(threads as usize)
+                        .map(|board| play_board(first_seed.wrapping_add(board), max_ticks))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+    let seconds = started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+    let ticks: u64 = results.iter().map(|result| result.ticks as u64).sum();
+    let played = results.len().max(1) as f64;
+    let mean_score = results
+        .iter()
+        .map(|result| result.score as f64)
+        .sum::<f64>()
+        / played;
+    let mean_length = results
+        .iter()
+        .map(|result| result.length as f64)
+        .sum::<f64>()
+        / played;
+    let best = results.iter().map(|result| result.score).max().unwrap_or(0);
+    format!(
+        "{} boards on {threads} threads: {ticks} ticks in {seconds:.2}s ({:.0} ticks/s)\n\
+         mean score {mean_score:.1}, best {best}, mean length {mean_length:.1}",
+        results.len(),
+        ticks as f64 / seconds,
+    )
+}
+
+fn play_board(seed: u64, max_ticks: u32) -> BoardResult {
+    let mut sim = ReplaySim::new(seed, Vec::new()).with_tick_limit(max_ticks);
+    let mut bot = Greedy::default();
+    let mut rng = StdRng::seed_from_u64(seed ^ RNG_SALT);
+    loop {
+        let apple = sim.apple();
+        let occupancy = Occupancy::from_board(&sim.playfield_mask, sim.occupants());
+        let view = BotView {
+            occupancy: &occupancy,
+            snake_head: sim.head(),
+            apple: apple.as_ref(),
+            skill: &AiSkill::NORMAL,
+        };
+        let direction = bot.choose_direction(&view, &mut rng);
+        sim.steer(direction);
+        if !sim.step() {
+            break;
+        }
+    }
+    BoardResult {
+        score: sim.score,
+        length: sim.length(),
+        ticks: sim.tick,
+    }
+}
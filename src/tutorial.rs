@@ -0,0 +1,244 @@
+// Tutorial
+// Guided first run: each prompt pauses the game and highlights the cells it talks about, moving
+// on once the player has done what it asks. Finishing or skipping it is remembered in tutorial.ron
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::args::Args;
+use crate::direction::Direction;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::tween::Tween;
+use crate::{Apple, AppleEaten, GameOver, Player, SnakeBody, SnakeHead, PIXEL_UNIT_SIZE};
+
+const TUTORIAL_FILE: &str = "tutorial.ron";
+const FINAL_APPLES: u32 = 2; // eaten after the last prompt to finish
+const HIGHLIGHT_COLOR: Color = Color::GOLD;
+const PROMPT_COLOR: Color = Color::WHITE;
+
+#[derive(Serialize, Deserialize, Default)]
+struct TutorialProgress {
+    completed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TutorialStep {
+    Turn,
+    EatApple,
+    AvoidSelf,
+}
+
+impl TutorialStep {
+    fn prompt(self) -> &'static str {
+        match self {
+            TutorialStep::Turn => "Press Up or Down to turn",
+            TutorialStep::EatApple => "Eat the apple to grow",
+            TutorialStep::AvoidSelf => "Don't hit yourself or the walls",
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Tutorial {
+    step: TutorialStep,
+    waiting: bool, // the game stays paused until the prompt is acted on
+    apples: u32,
+    paused_game: bool, // only a pause of its own is lifted, not one some other screen holds
+}
+
+impl Tutorial {
+    // leave time alone if something else paused it
+    fn pause(&mut self, time: &mut Time<Virtual>) {
+        if !time.is_paused() {
+            time.pause();
+            self.paused_game = true;
+        }
+    }
+
+    fn unpause(&mut self, time: &mut Time<Virtual>) {
+        if std::mem::take(&mut self.paused_game) {
+            time.unpause();
+        }
+    }
+}
+
+#[derive(Component)]
+struct TutorialText;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    // --tutorial plays it again after it was completed, the demo bot, practice runs and network
+    // games never get it
+    fn build(&self, app: &mut App) {
+        let progress: TutorialProgress = read_ron_save(TUTORIAL_FILE).unwrap_or_default();
+        let args = Args::of(app);
+        let replay = args.has("--tutorial");
+        let other_start = args.has("--demo") || args.has("--scenarios") || args.has("--lobby");
+        if (progress.completed && !replay) || other_start {
+            return;
+        }
+        app.insert_resource(Tutorial {
+            step: TutorialStep::Turn,
+            waiting: true,
+            apples: 0,
+            paused_game: false,
+        })
+        .add_systems(Startup, setup_tutorial)
+        .add_systems(
+            Update,
+            (run_tutorial, highlight_cells).run_if(resource_exists::<Tutorial>()),
+        );
+    }
+}
+
+fn setup_tutorial(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    info!("Tutorial started");
+    tutorial.pause(&mut time);
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 28.0,
+                color: PROMPT_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(48.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        TutorialText,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_tutorial(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut tutorial: ResMut<Tutorial>,
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    game_over_event: EventReader<GameOver>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    mut text_query: Query<(Entity, &mut Text), With<TutorialText>>,
+) {
+    let Ok((text_entity, mut text)) = text_query.get_single_mut() else {
+        return;
+    };
+    // dying ends it without counting as done, the next run starts it over
+    if !game_over_event.is_empty() {
+        commands.remove_resource::<Tutorial>();
+        commands.entity(text_entity).despawn();
+        return;
+    }
+    let skipped = keyboard_input.just_pressed(KeyCode::Tab);
+    let eaten = apple_eaten_event
+        .read()
+        .filter(|event| event.player == Player::ONE)
+        .count() as u32;
+
+    let step = tutorial.step;
+    if tutorial.waiting {
+        // another screen closing doesn't let the game run on
+        tutorial.pause(&mut time);
+        let acted = match step {
+            TutorialStep::Turn => snake_head_query.iter().any(|(player, snake_head)| {
+                *player == Player::ONE
+                    && snake_head.potential_direction != snake_head.direction
+                    && snake_head.potential_direction != snake_head.direction.opposite()
+            }),
+            _ => keyboard_input.just_pressed(KeyCode::Return),
+        };
+        if acted {
+            tutorial.waiting = false;
+            tutorial.unpause(&mut time);
+        }
+    } else {
+        tutorial.apples += eaten;
+    }
+
+    let next = match step {
+        TutorialStep::Turn if !tutorial.waiting => Some(TutorialStep::EatApple),
+        TutorialStep::EatApple if tutorial.apples > 0 => Some(TutorialStep::AvoidSelf),
+        _ => None,
+    };
+    if let Some(next) = next {
+        info!(step = ?next, "Tutorial step");
+        *tutorial = Tutorial {
+            step: next,
+            waiting: true,
+            apples: 0,
+            paused_game: tutorial.paused_game,
+        };
+        tutorial.pause(&mut time);
+    }
+
+    let finished = tutorial.step == TutorialStep::AvoidSelf && tutorial.apples >= FINAL_APPLES;
+    if skipped || finished {
+        info!(skipped, "Tutorial completed");
+        if let Err(error) = write_ron_save(TUTORIAL_FILE, &TutorialProgress { completed: true }) {
+            warn!("Could not save tutorial progress: {error}");
+        }
+        tutorial.unpause(&mut time);
+        commands.remove_resource::<Tutorial>();
+        text.sections[0].value = "Tutorial complete".to_string();
+        commands
+            .entity(text_entity)
+            .remove::<TutorialText>()
+            .insert(Tween::fade_out(PROMPT_COLOR));
+        return;
+    }
+
+    let hint = if !tutorial.waiting || tutorial.step == TutorialStep::Turn {
+        ""
+    } else {
+        " - [Enter] continue"
+    };
+    text.sections[0].value = format!("{}{hint}\n[Tab] skip tutorial", tutorial.step.prompt());
+}
+
+// gizmos only last a frame, so this redraws every frame
+fn highlight_cells(
+    tutorial: Res<Tutorial>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    snake_body_query: Query<&SnakeBody>,
+    apple_query: Query<&Apple>,
+    mut gizmos: Gizmos,
+) {
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let cells: Vec<(i32, i32)> = match tutorial.step {
+        TutorialStep::Turn => [Direction::Up, Direction::Down]
+            .into_iter()
+            .map(|direction| direction.step(snake_head.position))
+            .collect(),
+        TutorialStep::EatApple => apple_query
+            .iter()
+            .flat_map(|apple| apple.footprint.iter().copied())
+            .collect(),
+        TutorialStep::AvoidSelf => snake_head
+            .segments
+            .iter()
+            .filter_map(|segment| snake_body_query.get(*segment).ok())
+            .map(|snake_body| snake_body.position)
+            .collect(),
+    };
+    for (x, y) in cells {
+        gizmos.rect_2d(
+            Vec2::new(x as f32, y as f32) * PIXEL_UNIT_SIZE,
+            0.0,
+            Vec2::splat(PIXEL_UNIT_SIZE),
+            HIGHLIGHT_COLOR,
+        );
+    }
+}
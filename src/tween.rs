@@ -0,0 +1,156 @@
+// Tween
+// Generic animation component: eases translation, scale, rotation and color over a duration
+use bevy::prelude::*;
+
+use crate::SnakeSystemSet;
+
+const POP_IN_SECONDS: f32 = 0.25;
+const FADE_OUT_SECONDS: f32 = 0.3;
+
+#[derive(Clone, Copy)]
+pub enum Easing {
+    Linear,
+    InQuad,
+    OutQuad,
+    OutBack, // overshoots slightly before settling
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::OutBack => {
+                const OVERSHOOT: f32 = 1.70158;
+                let t = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * t * t * t + OVERSHOOT * t * t
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TweenTarget {
+    Translation { from: Vec3, to: Vec3 },
+    Scale { from: Vec3, to: Vec3 },
+    Rotation { from: f32, to: f32 },  // radians around z
+    Color { from: Color, to: Color }, // sprite, text or UI background, whichever the entity has
+}
+
+#[derive(Component)]
+pub struct Tween {
+    targets: Vec<TweenTarget>,
+    easing: Easing,
+    timer: Timer,
+    despawn_when_done: bool, // otherwise only the tween is removed
+}
+
+impl Tween {
+    pub fn new(seconds: f32, easing: Easing) -> Self {
+        Tween {
+            targets: Vec::new(),
+            easing,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            despawn_when_done: false,
+        }
+    }
+
+    pub fn with(mut self, target: TweenTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn despawn_when_done(mut self) -> Self {
+        self.despawn_when_done = true;
+        self
+    }
+
+    // scales a freshly spawned item up from nothing
+    pub fn pop_in() -> Self {
+        Tween::new(POP_IN_SECONDS, Easing::OutBack).with(TweenTarget::Scale {
+            from: Vec3::new(0.0, 0.0, 1.0),
+            to: Vec3::ONE,
+        })
+    }
+
+    // shrinks and fades an item away, whatever made it part of the game should be removed first
+    pub fn fade_out(color: Color) -> Self {
+        Tween::new(FADE_OUT_SECONDS, Easing::InQuad)
+            .with(TweenTarget::Scale {
+                from: Vec3::ONE,
+                to: Vec3::new(0.0, 0.0, 1.0),
+            })
+            .with(TweenTarget::Color {
+                from: color,
+                to: color.with_a(0.0),
+            })
+            .despawn_when_done()
+    }
+}
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, run_tweens.in_set(SnakeSystemSet::Rendering));
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    let [r, g, b, a] = from.lerp(to, t).to_array();
+    Color::rgba(r, g, b, a)
+}
+
+#[allow(clippy::type_complexity)]
+fn run_tweens(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tween_query: Query<(
+        Entity,
+        &mut Tween,
+        &mut Transform,
+        Option<&mut Sprite>,
+        Option<&mut Text>,
+        Option<&mut BackgroundColor>,
+    )>,
+) {
+    for (entity, mut tween, mut transform, mut sprite, mut text, mut background_color) in
+        &mut tween_query
+    {
+        let finished = tween.timer.tick(time.delta()).finished();
+        if finished && tween.despawn_when_done {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let t = tween.easing.apply(tween.timer.percent());
+        for target in &tween.targets {
+            match *target {
+                TweenTarget::Translation { from, to } => transform.translation = from.lerp(to, t),
+                TweenTarget::Scale { from, to } => transform.scale = from.lerp(to, t),
+                TweenTarget::Rotation { from, to } => {
+                    transform.rotation = Quat::from_rotation_z(from + (to - from) * t)
+                }
+                TweenTarget::Color { from, to } => {
+                    let color = lerp_color(from, to, t);
+                    if let Some(sprite) = sprite.as_mut() {
+                        sprite.color = color;
+                    }
+                    if let Some(text) = text.as_mut() {
+                        for section in &mut text.sections {
+                            section.style.color = color;
+                        }
+                    }
+                    if let Some(background_color) = background_color.as_mut() {
+                        background_color.0 = color;
+                    }
+                }
+            }
+        }
+        if finished {
+            commands.entity(entity).remove::<Tween>();
+        }
+    }
+}
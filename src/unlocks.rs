@@ -0,0 +1,511 @@
+// Unlocks
+// Cosmetics earned through achievements: snake skins, the rainbow one for the Konami code, board
+// palettes and a trail behind the tail. U opens a screen to equip them, the locked ones say what it takes. What a profile has
+// unlocked and equipped is kept in its unlocks.ron
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::{load_achievements, Achievement, AchievementEarned, Achievements};
+use crate::chat::{chat_closed, Chat};
+use crate::menu::{spawn_button, MenuActivated, MenuFocus};
+use crate::profile::Profile;
+use crate::storage::{read_ron_save, write_ron_save};
+use crate::theme::{apply_theme, reload_theme, Theme, ThemeHandle};
+use crate::tween::{Easing, Tween, TweenTarget};
+use crate::widgets::TextInput;
+use crate::{Player, SnakeHead, PIXEL_UNIT_SIZE};
+
+const UNLOCKS_FILE: &str = "unlocks.ron";
+const TRAIL_SECONDS: f32 = 0.8;
+const TRAIL_SIZE: f32 = 0.4; // of a cell
+const LOCKED_COLOR: Color = Color::GRAY;
+const RAINBOW_SECONDS: f32 = 3.0; // once around the hues
+const RAINBOW_BODY_OFFSET: f32 = 40.0; // degrees behind the head
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Slot {
+    Skin,
+    Palette,
+    Trail,
+}
+
+impl Slot {
+    const ALL: [Slot; 3] = [Slot::Skin, Slot::Palette, Slot::Trail];
+
+    fn name(self) -> &'static str {
+        match self {
+            Slot::Skin => "Skin",
+            Slot::Palette => "Palette",
+            Slot::Trail => "Trail",
+        }
+    }
+}
+
+// None keeps what the theme file says, or no trail
+#[derive(Clone, Copy)]
+enum Look {
+    Skin(Option<(Color, Color)>),    // head, body
+    Rainbow,                         // a skin cycling through the hues
+    Palette(Option<(Color, Color)>), // background, apple
+    Trail(Option<Color>),
+}
+
+impl Look {
+    fn slot(self) -> Slot {
+        match self {
+            Look::Skin(_) | Look::Rainbow => Slot::Skin,
+            Look::Palette(_) => Slot::Palette,
+            Look::Trail(_) => Slot::Trail,
+        }
+    }
+}
+
+struct Cosmetic {
+    name: &'static str,
+    look: Look,
+    requires: Option<Achievement>,
+}
+
+// the first of each slot is always there
+const COSMETICS: [Cosmetic; 11] = [
+    Cosmetic {
+        name: "Classic",
+        look: Look::Skin(None),
+        requires: None,
+    },
+    Cosmetic {
+        name: "Ember",
+        look: Look::Skin(Some((Color::ORANGE_RED, Color::GOLD))),
+        requires: Some(Achievement::Score50),
+    },
+    Cosmetic {
+        name: "Frost",
+        look: Look::Skin(Some((Color::CYAN, Color::ALICE_BLUE))),
+        requires: Some(Achievement::Combo5),
+    },
+    Cosmetic {
+        name: "Violet",
+        look: Look::Skin(Some((Color::PURPLE, Color::PINK))),
+        requires: Some(Achievement::Length30),
+    },
+    Cosmetic {
+        name: "Rainbow",
+        look: Look::Rainbow,
+        requires: Some(Achievement::Konami),
+    },
+    Cosmetic {
+        name: "Theme",
+        look: Look::Palette(None),
+        requires: None,
+    },
+    Cosmetic {
+        name: "Midnight",
+        look: Look::Palette(Some((Color::rgb(0.08, 0.08, 0.2), Color::ORANGE))),
+        requires: Some(Achievement::Score200),
+    },
+    Cosmetic {
+        name: "Meadow",
+        look: Look::Palette(Some((Color::rgb(0.25, 0.45, 0.25), Color::CRIMSON))),
+        requires: Some(Achievement::TenRuns),
+    },
+    Cosmetic {
+        name: "None",
+        look: Look::Trail(None),
+        requires: None,
+    },
+    Cosmetic {
+        name: "Sparks",
+        look: Look::Trail(Some(Color::GOLD)),
+        requires: Some(Achievement::FirstApple),
+    },
+    Cosmetic {
+        name: "Comet",
+        look: Look::Trail(Some(Color::CYAN)),
+        requires: Some(Achievement::Score200),
+    },
+];
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Unlocks {
+    unlocked: Vec<String>, // kept even if the achievements file goes
+    equipped: Vec<String>, // one per slot at most, a missing slot uses its first cosmetic
+    #[serde(skip)]
+    file: Option<String>, // None until a profile is picked
+}
+
+impl Unlocks {
+    fn is_unlocked(&self, cosmetic: &Cosmetic) -> bool {
+        cosmetic.requires.is_none() || self.unlocked.iter().any(|name| name == cosmetic.name)
+    }
+
+    fn equipped(&self, slot: Slot) -> &'static Cosmetic {
+        let mut in_slot = COSMETICS
+            .iter()
+            .filter(|cosmetic| cosmetic.look.slot() == slot);
+        let first = in_slot.next().unwrap_or(&COSMETICS[0]);
+        in_slot
+            .find(|cosmetic| self.equipped.iter().any(|name| name == cosmetic.name))
+            .filter(|cosmetic| self.is_unlocked(cosmetic))
+            .unwrap_or(first)
+    }
+
+    // the next unlocked cosmetic in the slot, wrapping around to the first
+    fn cycle(&mut self, slot: Slot) {
+        let current = self.equipped(slot).name;
+        let unlocked: Vec<&Cosmetic> = COSMETICS
+            .iter()
+            .filter(|cosmetic| cosmetic.look.slot() == slot && self.is_unlocked(cosmetic))
+            .collect();
+        let index = unlocked
+            .iter()
+            .position(|cosmetic| cosmetic.name == current)
+            .map_or(0, |index| (index + 1) % unlocked.len());
+        let next = unlocked[index].name;
+        self.equipped.retain(|name| {
+            COSMETICS
+                .iter()
+                .all(|cosmetic| cosmetic.name != name || cosmetic.look.slot() != slot)
+        });
+        self.equipped.push(next.to_string());
+        info!(?slot, cosmetic = next, "Cosmetic equipped");
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Err(error) = write_ron_save(file, self) {
+            warn!("Could not save unlocks: {error}");
+        }
+    }
+}
+
+#[derive(Component, Default)]
+struct UnlocksScreen {
+    paused_game: bool,
+}
+
+#[derive(Component, Clone, Copy)]
+enum UnlocksButton {
+    Cycle(Slot),
+    Close,
+}
+
+#[derive(Component)]
+struct UnlocksList;
+
+pub struct UnlocksPlugin;
+
+impl Plugin for UnlocksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Unlocks>()
+            .add_systems(Startup, setup_unlocks_screen)
+            .add_systems(
+                Update,
+                (
+                    load_unlocks
+                        .run_if(resource_added::<Profile>())
+                        .after(load_achievements),
+                    unlock_cosmetics,
+                    toggle_unlocks_screen,
+                    press_unlocks_buttons,
+                    update_unlocks_screen,
+                    apply_cosmetics.after(reload_theme).before(apply_theme),
+                    cycle_rainbow.before(apply_theme),
+                )
+                    .chain(),
+            )
+            .add_systems(PostUpdate, leave_trail);
+    }
+}
+
+fn load_unlocks(
+    profile: Res<Profile>,
+    achievements: Res<Achievements>,
+    mut unlocks: ResMut<Unlocks>,
+) {
+    let file = profile.file(UNLOCKS_FILE);
+    *unlocks = read_ron_save(&file).unwrap_or_default();
+    unlocks.file = Some(file);
+    // achievements earned before there were unlocks still count
+    for cosmetic in &COSMETICS {
+        if cosmetic
+            .requires
+            .is_some_and(|achievement| achievements.has(achievement))
+            && !unlocks.is_unlocked(cosmetic)
+        {
+            unlocks.unlocked.push(cosmetic.name.to_string());
+        }
+    }
+}
+
+fn unlock_cosmetics(
+    mut achievement_earned_event: EventReader<AchievementEarned>,
+    mut unlocks: ResMut<Unlocks>,
+) {
+    let mut changed = false;
+    for event in achievement_earned_event.read() {
+        for cosmetic in COSMETICS
+            .iter()
+            .filter(|cosmetic| cosmetic.requires == Some(event.0))
+        {
+            if !unlocks.is_unlocked(cosmetic) {
+                info!(cosmetic = cosmetic.name, "Cosmetic unlocked");
+                unlocks.unlocked.push(cosmetic.name.to_string());
+                changed = true;
+            }
+        }
+    }
+    if changed {
+        unlocks.save();
+    }
+}
+
+fn setup_unlocks_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            UnlocksScreen::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Unlocks",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            for slot in Slot::ALL {
+                spawn_button(parent, slot.name(), UnlocksButton::Cycle(slot));
+            }
+            parent.spawn((
+                TextBundle::default().with_style(Style {
+                    margin: UiRect::vertical(Val::Px(8.0)),
+                    ..default()
+                }),
+                UnlocksList,
+            ));
+            spawn_button(parent, "Close", UnlocksButton::Close);
+        });
+}
+
+// U opens it during play, Close or U again shuts it
+#[allow(clippy::too_many_arguments)]
+fn toggle_unlocks_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut menu_activated_event: EventReader<MenuActivated>,
+    menu_focus: Res<MenuFocus>,
+    chat: Option<Res<Chat>>,
+    mut time: ResMut<Time<Virtual>>,
+    button_query: Query<&UnlocksButton>,
+    text_input_query: Query<&InheritedVisibility, With<TextInput>>,
+    mut screen_query: Query<(&mut Visibility, &mut UnlocksScreen)>,
+) {
+    let Ok((mut visibility, mut screen)) = screen_query.get_single_mut() else {
+        return;
+    };
+    let open = *visibility != Visibility::Hidden;
+    let closed = menu_activated_event
+        .read()
+        .any(|event| matches!(button_query.get(event.0), Ok(UnlocksButton::Close)));
+    let typing = text_input_query.iter().any(|visibility| visibility.get());
+    let pressed = keyboard_input.just_pressed(KeyCode::U)
+        && !typing
+        && chat_closed(chat)
+        && (open || menu_focus.0.is_none());
+    if !(pressed || open && closed) {
+        return;
+    }
+    info!(open = !open, "Unlocks screen toggled");
+    // leave time alone if something else paused it
+    if open {
+        *visibility = Visibility::Hidden;
+        if std::mem::take(&mut screen.paused_game) {
+            time.unpause();
+        }
+    } else {
+        *visibility = Visibility::Inherited;
+        if !time.is_paused() {
+            time.pause();
+            screen.paused_game = true;
+        }
+    }
+}
+
+fn press_unlocks_buttons(
+    mut menu_activated_event: EventReader<MenuActivated>,
+    mut unlocks: ResMut<Unlocks>,
+    button_query: Query<&UnlocksButton>,
+) {
+    for event in menu_activated_event.read() {
+        if let Ok(UnlocksButton::Cycle(slot)) = button_query.get(event.0) {
+            unlocks.cycle(*slot);
+        }
+    }
+}
+
+fn update_unlocks_screen(
+    unlocks: Res<Unlocks>,
+    achievements: Res<Achievements>,
+    button_query: Query<(&UnlocksButton, &Children)>,
+    mut text_query: Query<&mut Text, Without<UnlocksList>>,
+    mut list_query: Query<&mut Text, With<UnlocksList>>,
+) {
+    if !unlocks.is_changed() && !achievements.is_changed() {
+        return;
+    }
+    for (button, children) in &button_query {
+        let UnlocksButton::Cycle(slot) = button else {
+            continue;
+        };
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value =
+                    format!("{}: {}", slot.name(), unlocks.equipped(*slot).name);
+            }
+        }
+    }
+    let earned = Achievement::ALL
+        .iter()
+        .filter(|achievement| achievements.has(**achievement))
+        .count();
+    let mut sections = vec![TextSection::new(
+        format!("{earned} of {} achievements\n", Achievement::ALL.len()),
+        TextStyle {
+            font_size: 24.0,
+            color: Color::GOLD,
+            ..default()
+        },
+    )];
+    for cosmetic in COSMETICS
+        .iter()
+        .filter(|cosmetic| cosmetic.requires.is_some())
+    {
+        let slot = cosmetic.look.slot().name().to_lowercase();
+        let (line, color) = match cosmetic.requires {
+            Some(achievement) if !unlocks.is_unlocked(cosmetic) => (
+                format!(
+                    "{} {slot} - locked, {}\n",
+                    cosmetic.name,
+                    achievement.hint()
+                ),
+                LOCKED_COLOR,
+            ),
+            _ => (format!("{} {slot}\n", cosmetic.name), Color::WHITE),
+        };
+        sections.push(TextSection::new(
+            line,
+            TextStyle {
+                font_size: 20.0,
+                color,
+                ..default()
+            },
+        ));
+    }
+    for mut text in &mut list_query {
+        text.sections = sections.clone();
+    }
+}
+
+// on top of the theme file's colors, again whenever the file is reloaded
+pub fn apply_cosmetics(
+    unlocks: Res<Unlocks>,
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    theme_handle: Option<Res<ThemeHandle>>,
+    themes: Res<Assets<Theme>>,
+    mut theme: ResMut<Theme>,
+) {
+    let reloaded = theme_events.read().count() > 0;
+    if !unlocks.is_changed() && !reloaded {
+        return;
+    }
+    let base = theme_handle
+        .and_then(|handle| themes.get(&handle.0).cloned())
+        .unwrap_or_default();
+    let (head, body) = match unlocks.equipped(Slot::Skin).look {
+        Look::Skin(Some(colors)) => colors,
+        _ => (base.snake_head, base.snake_body),
+    };
+    let (background, apple) = match unlocks.equipped(Slot::Palette).look {
+        Look::Palette(Some(colors)) => colors,
+        _ => (base.background, base.apple),
+    };
+    theme.snake_head = head;
+    theme.snake_body = body;
+    theme.background = background;
+    theme.apple = apple;
+}
+
+// real time, the colors keep going while the game is paused
+fn cycle_rainbow(time: Res<Time<Real>>, unlocks: Res<Unlocks>, mut theme: ResMut<Theme>) {
+    if !matches!(unlocks.equipped(Slot::Skin).look, Look::Rainbow) {
+        return;
+    }
+    let hue = (time.elapsed_seconds() / RAINBOW_SECONDS).fract() * 360.0;
+    theme.snake_head = Color::hsl(hue, 0.9, 0.55);
+    theme.snake_body = Color::hsl((hue + 360.0 - RAINBOW_BODY_OFFSET) % 360.0, 0.8, 0.65);
+}
+
+// each cell the tail leaves glows for a moment
+fn leave_trail(
+    mut commands: Commands,
+    unlocks: Res<Unlocks>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    mut last_tail: Local<Option<(i32, i32)>>,
+) {
+    let Look::Trail(Some(color)) = unlocks.equipped(Slot::Trail).look else {
+        return;
+    };
+    let Some((_, snake_head)) = snake_head_query
+        .iter()
+        .find(|(player, _)| **player == Player::ONE)
+    else {
+        return;
+    };
+    let tail = snake_head.tail_position;
+    if last_tail.replace(tail).is_none_or(|last| last == tail) {
+        return;
+    }
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(PIXEL_UNIT_SIZE * TRAIL_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                tail.0 as f32 * PIXEL_UNIT_SIZE,
+                tail.1 as f32 * PIXEL_UNIT_SIZE,
+                0.5,
+            ),
+            ..default()
+        },
+        Tween::new(TRAIL_SECONDS, Easing::OutQuad)
+            .with(TweenTarget::Color {
+                from: color,
+                to: color.with_a(0.0),
+            })
+            .with(TweenTarget::Scale {
+                from: Vec3::ONE,
+                to: Vec3::new(0.2, 0.2, 1.0),
+            })
+            .despawn_when_done(),
+    ));
+}
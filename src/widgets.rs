@@ -0,0 +1,193 @@
+// Widgets
+// Reusable screen pieces on top of the menu buttons: a yes/no confirmation dialog and a text field
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+
+use crate::menu::{navigate_menu, spawn_button, MenuActivated};
+
+const CARET_BLINK_SECONDS: f32 = 0.5;
+
+// full-screen overlay asking a question, toggle its visibility to show it
+#[derive(Component)]
+pub struct ConfirmDialog;
+
+#[derive(Component)]
+pub struct ConfirmButton {
+    dialog: Entity,
+    accept: bool,
+}
+
+// sent once per answer while the dialog is showing: Y, N or Escape, or one of its buttons
+#[derive(Event)]
+pub struct DialogAnswered {
+    pub dialog: Entity,
+    pub accepted: bool,
+}
+
+// captures typed characters while visible, Enter submits
+#[derive(Component)]
+pub struct TextInput {
+    pub value: String,
+    max_len: usize,
+}
+
+#[derive(Event)]
+pub struct TextSubmitted {
+    pub input: Entity,
+    pub value: String,
+}
+
+pub struct WidgetsPlugin;
+
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DialogAnswered>()
+            .add_event::<TextSubmitted>()
+            .add_systems(
+                Update,
+                (
+                    answer_dialogs.after(navigate_menu),
+                    (edit_text_inputs, show_text_inputs).chain(),
+                ),
+            );
+    }
+}
+
+// spawned hidden, the marker goes on the overlay root
+pub fn spawn_confirm_dialog(
+    commands: &mut Commands,
+    prompt: &str,
+    labels: [&str; 2], // accept, decline
+    marker: impl Bundle,
+) -> Entity {
+    let dialog = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ConfirmDialog,
+            marker,
+        ))
+        .id();
+    commands.entity(dialog).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            prompt,
+            TextStyle {
+                font_size: 32.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for (label, accept) in labels.into_iter().zip([true, false]) {
+                    spawn_button(parent, label, ConfirmButton { dialog, accept });
+                }
+            });
+    });
+    dialog
+}
+
+pub fn answer_dialogs(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut menu_activated_event: EventReader<MenuActivated>,
+    dialog_query: Query<(Entity, &InheritedVisibility), With<ConfirmDialog>>,
+    button_query: Query<&ConfirmButton>,
+    mut dialog_answered_event: EventWriter<DialogAnswered>,
+) {
+    let mut answered: Option<(Entity, bool)> = menu_activated_event
+        .read()
+        .find_map(|event| button_query.get(event.0).ok())
+        .map(|button| (button.dialog, button.accept));
+    if let Some((dialog, _)) = dialog_query.iter().find(|(_, visibility)| visibility.get()) {
+        if keyboard_input.just_pressed(KeyCode::Y) {
+            answered = Some((dialog, true));
+        } else if keyboard_input.any_just_pressed([KeyCode::N, KeyCode::Escape]) {
+            answered = Some((dialog, false));
+        }
+    }
+    if let Some((dialog, accepted)) = answered {
+        dialog_answered_event.send(DialogAnswered { dialog, accepted });
+    }
+}
+
+pub fn spawn_text_input(parent: &mut ChildBuilder, max_len: usize, marker: impl Bundle) -> Entity {
+    parent
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            TextInput {
+                value: String::new(),
+                max_len,
+            },
+            marker,
+        ))
+        .id()
+}
+
+fn edit_text_inputs(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut received_character_event: EventReader<ReceivedCharacter>,
+    mut input_query: Query<(Entity, &mut TextInput, &InheritedVisibility)>,
+    mut text_submitted_event: EventWriter<TextSubmitted>,
+) {
+    let typed: String = received_character_event
+        .read()
+        .map(|event| event.char)
+        .filter(|char| !char.is_control())
+        .collect();
+    for (entity, mut input, visibility) in &mut input_query {
+        if !visibility.get() {
+            continue;
+        }
+        for char in typed.chars() {
+            if input.value.chars().count() < input.max_len {
+                input.value.push(char);
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Back) {
+            input.value.pop();
+        }
+        if keyboard_input.just_pressed(KeyCode::Return) {
+            text_submitted_event.send(TextSubmitted {
+                input: entity,
+                value: input.value.clone(),
+            });
+        }
+    }
+}
+
+// real time so the caret keeps blinking while the game is paused
+fn show_text_inputs(time: Res<Time<Real>>, mut input_query: Query<(&TextInput, &mut Text)>) {
+    let caret_on = ((time.elapsed_seconds() / CARET_BLINK_SECONDS) as u32).is_multiple_of(2);
+    for (input, mut text) in &mut input_query {
+        let caret = if caret_on { "_" } else { " " };
+        text.sections[0].value = format!("{}{caret}", input.value);
+    }
+}
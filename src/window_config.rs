@@ -0,0 +1,124 @@
+// Window configuration
+// Title, icon and size constraints for the primary window, taken from the game config
+use bevy::prelude::*;
+use bevy::window::{EnabledButtons, PrimaryWindow, WindowResizeConstraints};
+use bevy::winit::WinitWindows;
+
+use crate::config::WindowConfig;
+use crate::score::Score;
+use crate::MovementSettings;
+
+// icon image, set on the native window once loaded
+#[derive(Resource)]
+struct WindowIcon {
+    handle: Handle<Image>,
+    applied: bool,
+}
+
+pub struct WindowConfigPlugin;
+
+impl Plugin for WindowConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_window_icon).add_systems(
+            Update,
+            (
+                apply_window_config.run_if(resource_changed::<WindowConfig>()),
+                update_window_title,
+                set_window_icon,
+            ),
+        );
+    }
+}
+
+fn load_window_icon(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window_config: Res<WindowConfig>,
+) {
+    commands.insert_resource(WindowIcon {
+        handle: asset_server.load(window_config.icon.clone()),
+        applied: false,
+    });
+}
+
+fn apply_window_config(
+    window_config: Res<WindowConfig>,
+    asset_server: Res<AssetServer>,
+    mut window_icon: ResMut<WindowIcon>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.resize_constraints = WindowResizeConstraints {
+            min_width: window_config.min_width,
+            min_height: window_config.min_height,
+            ..default()
+        };
+        window.enabled_buttons = EnabledButtons {
+            maximize: window_config.allow_maximize,
+            ..default()
+        };
+        if !window_config.allow_maximize {
+            window.set_maximized(false);
+        }
+    }
+    let handle = asset_server.load(window_config.icon.clone());
+    if handle != window_icon.handle {
+        *window_icon = WindowIcon {
+            handle,
+            applied: false,
+        };
+    }
+}
+
+fn update_window_title(
+    window_config: Res<WindowConfig>,
+    movement_settings: Res<MovementSettings>,
+    score: Res<Score>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !(window_config.is_changed() || movement_settings.is_changed() || score.is_changed()) {
+        return;
+    }
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.title = format!(
+            "{} - {} - Score: {}",
+            window_config.title,
+            movement_settings.mode_name(),
+            score.0
+        );
+    }
+}
+
+// bevy has no icon field on Window yet, so this goes through winit directly
+fn set_window_icon(
+    mut window_icon: ResMut<WindowIcon>,
+    images: Res<Assets<Image>>,
+    winit_windows: NonSend<WinitWindows>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+) {
+    if window_icon.applied {
+        return;
+    }
+    let (Some(image), Ok(entity)) = (images.get(&window_icon.handle), window_query.get_single())
+    else {
+        return;
+    };
+    let Some(window) = winit_windows.get_window(entity) else {
+        return;
+    };
+    window_icon.applied = true;
+    let icon = image
+        .clone()
+        .try_into_dynamic()
+        .map_err(|error| error.to_string())
+        .and_then(|dynamic_image| {
+            let rgba = dynamic_image.to_rgba8();
+            let (width, height) = rgba.dimensions();
+            winit::window::Icon::from_rgba(rgba.into_raw(), width, height)
+                .map_err(|error| error.to_string())
+        });
+    match icon {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(error) => warn!("Could not set the window icon: {error}"),
+    }
+}
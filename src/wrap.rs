@@ -0,0 +1,91 @@
+// Wrap-around portals
+// With --wrap the playfield mask sends snakes across the board at its edges, and a glowing marker
+// sits on the border at either end of each head's row and column, so wrap-around shots can be
+// lined up with where the snake will come back in
+use bevy::prelude::*;
+
+use crate::direction::Direction;
+use crate::playfield::PlayfieldMask;
+use crate::{Player, SnakeHead, PIXEL_UNIT_SIZE};
+
+const PORTAL_COLOR: Color = Color::CYAN;
+const PORTAL_THICKNESS: f32 = PIXEL_UNIT_SIZE * 0.3;
+const PULSE_SPEED: f32 = 4.0; // radians per second
+
+// one per head and edge
+#[derive(Component)]
+struct Portal {
+    player: Player,
+    edge: Direction,
+}
+
+pub struct WrapPlugin;
+
+impl Plugin for WrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, place_portals);
+    }
+}
+
+// followed every frame, the markers of snakes that are gone go with them
+fn place_portals(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield_mask: Res<PlayfieldMask>,
+    snake_head_query: Query<(&Player, &SnakeHead)>,
+    mut portal_query: Query<(Entity, &Portal, &mut Transform, &mut Sprite)>,
+) {
+    let (half_width, half_height) = playfield_mask.half_extents();
+    let scroll = playfield_mask.scroll();
+    // on the edge line itself, half a cell past the outermost open cells
+    let edge_translation = |edge: Direction, (x, y): (i32, i32)| match edge {
+        Direction::Left => Vec2::new(scroll as f32 - half_width as f32 - 0.5, y as f32),
+        Direction::Right => Vec2::new(scroll as f32 + half_width as f32 + 0.5, y as f32),
+        Direction::Up => Vec2::new(x as f32, half_height as f32 + 0.5),
+        Direction::Down => Vec2::new(x as f32, -half_height as f32 - 0.5),
+    } * PIXEL_UNIT_SIZE;
+    let glow = 0.6 + 0.4 * (time.elapsed_seconds() * PULSE_SPEED).sin();
+
+    for (entity, portal, mut transform, mut sprite) in &mut portal_query {
+        let Some((_, snake_head)) = snake_head_query
+            .iter()
+            .find(|(player, _)| **player == portal.player)
+        else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        transform.translation = edge_translation(portal.edge, snake_head.position).extend(0.5);
+        sprite.color = PORTAL_COLOR.with_a(glow);
+    }
+    for (player, snake_head) in &snake_head_query {
+        let placed = portal_query
+            .iter()
+            .any(|(_, portal, ..)| portal.player == *player);
+        if placed {
+            continue;
+        }
+        for edge in Direction::ALL {
+            let size = match edge {
+                Direction::Left | Direction::Right => Vec2::new(PORTAL_THICKNESS, PIXEL_UNIT_SIZE),
+                Direction::Up | Direction::Down => Vec2::new(PIXEL_UNIT_SIZE, PORTAL_THICKNESS),
+            };
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: PORTAL_COLOR.with_a(glow),
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        edge_translation(edge, snake_head.position).extend(0.5),
+                    ),
+                    ..default()
+                },
+                Portal {
+                    player: *player,
+                    edge,
+                },
+            ));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Zen mode
+// --zen starts on a small board that opens up by one ring around its edge every few apples, so the
+// early game stays tight and the late game has room, until it is back to the full playfield
+use bevy::prelude::*;
+
+use crate::event_log::EventLogged;
+use crate::playfield::PlayfieldMask;
+use crate::{AppleEaten, Background, Border, SnakeSystemSet};
+
+const START_INSET: i32 = 10; // a 13 x 13 board
+const APPLES_PER_RING: u32 = 3;
+
+pub struct ZenPlugin;
+
+impl Plugin for ZenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, shrink_board)
+            .add_systems(FixedUpdate, grow_board.in_set(SnakeSystemSet::Collision))
+            .add_systems(Update, resize_board);
+    }
+}
+
+fn shrink_board(mut playfield_mask: ResMut<PlayfieldMask>) {
+    playfield_mask.set_inset(START_INSET);
+}
+
+fn grow_board(
+    mut apple_eaten_event: EventReader<AppleEaten>,
+    mut playfield_mask: ResMut<PlayfieldMask>,
+    mut event_logged_event: EventWriter<EventLogged>,
+    mut apples: Local<u32>,
+) {
+    for _ in apple_eaten_event.read() {
+        *apples += 1;
+        if apples.is_multiple_of(APPLES_PER_RING) && playfield_mask.inset() > 0 {
+            let inset = playfield_mask.inset() - 1;
+            playfield_mask.set_inset(inset);
+            info!(size = ?playfield_mask.half_extents(), "Board grew");
+            event_logged_event.send(EventLogged("The board grew".to_string()));
+        }
+    }
+}
+
+// the border sprite is the board plus a pixel all around
+#[allow(clippy::type_complexity)]
+fn resize_board(
+    playfield_mask: Res<PlayfieldMask>,
+    mut board_query: Query<(&mut Sprite, Has<Border>), Or<(With<Background>, With<Border>)>>,
+) {
+    if !playfield_mask.is_changed() {
+        return;
+    }
+    for (mut sprite, border) in &mut board_query {
+        let size = playfield_mask.size();
+        sprite.custom_size = Some(if border { size + 2.0 } else { size });
+    }
+}
@@ -0,0 +1,138 @@
+// Score multiplier zones
+// Glowing areas of the board where apples are worth more, relocating periodically
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::args::Args;
+use crate::playfield::PlayfieldMask;
+use crate::seed::RunRng;
+use crate::PIXEL_UNIT_SIZE;
+
+const ZONE_SIZES: [(i32, i32); 2] = [(5, 5), (3, 7)]; // the level's zone layout
+const ZONE_MULTIPLIER: u32 = 2;
+const ZONE_RELOCATE_SECONDS: f32 = 30.0;
+
+#[derive(Component)]
+pub struct ScoreZone {
+    origin: (i32, i32), // bottom-left cell
+    size: (i32, i32),
+    pub multiplier: u32,
+}
+
+impl ScoreZone {
+    pub fn contains(&self, position: (i32, i32)) -> bool {
+        position.0 >= self.origin.0
+            && position.0 < self.origin.0 + self.size.0
+            && position.1 >= self.origin.1
+            && position.1 < self.origin.1 + self.size.1
+    }
+
+    fn translation(&self) -> Vec3 {
+        Vec3::new(
+            (self.origin.0 as f32 + (self.size.0 - 1) as f32 / 2.0) * PIXEL_UNIT_SIZE,
+            (self.origin.1 as f32 + (self.size.1 - 1) as f32 / 2.0) * PIXEL_UNIT_SIZE,
+            -0.05,
+        )
+    }
+}
+
+#[derive(Resource)]
+pub struct ZoneRelocationTimer(pub Timer);
+
+pub struct ScoreZonePlugin;
+
+impl Plugin for ScoreZonePlugin {
+    // zones draw from the run's generator, which the replay check doesn't model, so a ranked run
+    // plays without them
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ZoneRelocationTimer(Timer::from_seconds(
+            ZONE_RELOCATE_SECONDS,
+            TimerMode::Repeating,
+        )));
+        if Args::of(app).ranked() {
+            return;
+        }
+        app.add_systems(Startup, spawn_zones)
+            .add_systems(Update, (relocate_zones, pulse_zones));
+    }
+}
+
+// a zone lies only over open cells, None when the board has no room left for one this size
+fn random_origin(
+    rng: &mut impl Rng,
+    playfield_mask: &PlayfieldMask,
+    size: (i32, i32),
+) -> Option<(i32, i32)> {
+    let (half_width, half_height) = playfield_mask.half_extents();
+    let left = playfield_mask.scroll() - half_width;
+    let origins: Vec<(i32, i32)> = (left..=left + 2 * half_width - size.0 + 1)
+        .flat_map(|x| (-half_height..=half_height - size.1 + 1).map(move |y| (x, y)))
+        .filter(|origin| {
+            (0..size.0).all(|dx| {
+                (0..size.1).all(|dy| !playfield_mask.is_wall((origin.0 + dx, origin.1 + dy)))
+            })
+        })
+        .collect();
+    if origins.is_empty() {
+        return None;
+    }
+    Some(origins[rng.gen_range(0..origins.len())])
+}
+
+fn spawn_zones(
+    mut commands: Commands,
+    mut run_rng: ResMut<RunRng>,
+    playfield_mask: Res<PlayfieldMask>,
+) {
+    for size in ZONE_SIZES {
+        let Some(origin) = random_origin(&mut run_rng.0, &playfield_mask, size) else {
+            continue;
+        };
+        let zone = ScoreZone {
+            origin,
+            size,
+            multiplier: ZONE_MULTIPLIER,
+        };
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::GOLD.with_a(0.3),
+                    custom_size: Some(Vec2::new(
+                        size.0 as f32 * PIXEL_UNIT_SIZE,
+                        size.1 as f32 * PIXEL_UNIT_SIZE,
+                    )),
+                    ..default()
+                },
+                transform: Transform::from_translation(zone.translation()),
+                ..default()
+            },
+            zone,
+        ));
+    }
+}
+
+fn relocate_zones(
+    time: Res<Time>,
+    mut relocation_timer: ResMut<ZoneRelocationTimer>,
+    mut run_rng: ResMut<RunRng>,
+    playfield_mask: Res<PlayfieldMask>,
+    mut zone_query: Query<(&mut ScoreZone, &mut Transform)>,
+) {
+    if !relocation_timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    for (mut zone, mut transform) in &mut zone_query {
+        // a zone with nowhere left to go stays where it is
+        if let Some(origin) = random_origin(&mut run_rng.0, &playfield_mask, zone.size) {
+            zone.origin = origin;
+        }
+        transform.translation = zone.translation();
+    }
+}
+
+fn pulse_zones(time: Res<Time>, mut zone_query: Query<&mut Sprite, With<ScoreZone>>) {
+    let alpha = 0.3 + 0.1 * (time.elapsed_seconds() * 3.0).sin();
+    for mut sprite in &mut zone_query {
+        sprite.color.set_a(alpha);
+    }
+}